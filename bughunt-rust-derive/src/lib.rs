@@ -0,0 +1,470 @@
+//! Derive the fuzz input plumbing of `Op` enums
+//!
+//! Each `Op` type in `bughunt-rust` needs to be decoded from fuzz input,
+//! encoded back into it, shrunk, and written to and read from traces. Written
+//! by hand these all repeat the enum's variants, and the tag numbers in
+//! particular must agree everywhere. `#[derive(TaggedOp)]` generates them all
+//! from the enum definition and attributes on its variants:
+//!
+//! * `#[op(tag = N)]` -- required, the fixed tag of the variant in fuzz input
+//! * `#[op(weight = N)]` -- optional, the relative weight of the variant
+//! * `#[op(fields = "a b")]` -- required for tuple variants, the names of the
+//!   fields as written in traces
+//!
+//! The generated code refers to `bughunt-rust` by paths from its crate root,
+//! so the derive can only be used within that crate.
+#![recursion_limit = "256"]
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use std::collections::HashMap;
+use syn::{Data, DeriveInput, Fields, Generics, Ident, Lit, Meta, NestedMeta};
+
+/// Derive `TaggedOp`, `Arbitrary`, `Encode`, `Display` and `FromStr`
+#[proc_macro_derive(TaggedOp, attributes(op))]
+pub fn derive_tagged_op(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// The kinds of variant an `Op` may have
+enum Style {
+    Unit,
+    Named,
+    Tuple,
+}
+
+/// A single field of a variant
+struct OpField {
+    /// The name the field is bound to in generated code
+    binding: Ident,
+    /// The name of the field in a struct variant
+    member: Option<Ident>,
+    /// The name of the field in traces
+    name: String,
+}
+
+/// A single variant of an `Op`, with its attributes parsed
+struct OpVariant {
+    ident: Ident,
+    name: String,
+    tag: u8,
+    weight: u32,
+    style: Style,
+    fields: Vec<OpField>,
+}
+
+impl OpVariant {
+    /// A pattern matching the variant, binding each field by reference
+    fn pattern(&self, ty: &Ident) -> TokenStream2 {
+        let ident = &self.ident;
+        let bindings = self.fields.iter().map(|f| &f.binding);
+        match self.style {
+            Style::Unit => quote!(#ty::#ident),
+            Style::Named => {
+                let members = self.fields.iter().map(|f| &f.member);
+                quote!(#ty::#ident { #(#members: ref #bindings),* })
+            }
+            Style::Tuple => quote!(#ty::#ident( #(ref #bindings),* )),
+        }
+    }
+
+    /// An expression constructing the variant, with the given field values
+    fn construct<I>(&self, ty: &Ident, values: I) -> TokenStream2
+    where
+        I: IntoIterator<Item = TokenStream2>,
+    {
+        let ident = &self.ident;
+        let values = values.into_iter();
+        match self.style {
+            Style::Unit => quote!(#ty::#ident),
+            Style::Named => {
+                let members = self.fields.iter().map(|f| &f.member);
+                quote!(#ty::#ident { #(#members: #values),* })
+            }
+            Style::Tuple => quote!(#ty::#ident( #(#values),* )),
+        }
+    }
+}
+
+/// Convert a variant name like `SwapRemoveBack` to `swap_remove_back`
+fn snake_case(s: &str) -> String {
+    let mut out = String::new();
+    for (idx, c) in s.chars().enumerate() {
+        if c.is_uppercase() {
+            if idx > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn parse_variant(variant: &syn::Variant) -> syn::Result<OpVariant> {
+    let mut tag: Option<u8> = None;
+    let mut weight: u32 = 1;
+    let mut names: Option<Vec<String>> = None;
+    for attr in variant.attrs.iter().filter(|a| a.path.is_ident("op")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(syn::Error::new_spanned(meta, "expected #[op(...)]"));
+            }
+        };
+        for nested in list.nested {
+            let nv = match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) => nv,
+                nested => {
+                    return Err(syn::Error::new_spanned(nested, "expected key = value"));
+                }
+            };
+            match (nv.ident.to_string().as_str(), &nv.lit) {
+                ("tag", Lit::Int(i)) if i.value() <= u64::from(u8::MAX) => {
+                    tag = Some(i.value() as u8);
+                }
+                ("weight", Lit::Int(i)) if i.value() <= u64::from(u32::MAX) => {
+                    weight = i.value() as u32;
+                }
+                ("fields", Lit::Str(s)) => {
+                    names = Some(s.value().split_whitespace().map(String::from).collect());
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        nv,
+                        "expected tag = <u8>, weight = <u32> or fields = \"...\"",
+                    ));
+                }
+            }
+        }
+    }
+    let tag = tag.ok_or_else(|| {
+        syn::Error::new_spanned(&variant.ident, "missing #[op(tag = ...)]")
+    })?;
+
+    let binding = |idx: usize| Ident::new(&format!("__field{}", idx), Span::call_site());
+    let (style, fields) = match variant.fields {
+        Fields::Unit => (Style::Unit, Vec::new()),
+        Fields::Named(ref named) => {
+            if names.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &variant.ident,
+                    "fields = \"...\" is only for tuple variants",
+                ));
+            }
+            let fields = named
+                .named
+                .iter()
+                .enumerate()
+                .map(|(idx, f)| OpField {
+                    binding: binding(idx),
+                    member: f.ident.clone(),
+                    name: f.ident.as_ref().map(|i| i.to_string()).unwrap_or_default(),
+                })
+                .collect();
+            (Style::Named, fields)
+        }
+        Fields::Unnamed(ref unnamed) => {
+            let names = match names {
+                Some(ref names) if names.len() == unnamed.unnamed.len() => names,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &variant.ident,
+                        "tuple variants must name each field with fields = \"...\"",
+                    ));
+                }
+            };
+            let fields = names
+                .iter()
+                .enumerate()
+                .map(|(idx, name)| OpField {
+                    binding: binding(idx),
+                    member: None,
+                    name: name.clone(),
+                })
+                .collect();
+            (Style::Tuple, fields)
+        }
+    };
+
+    Ok(OpVariant {
+        ident: variant.ident.clone(),
+        name: snake_case(&variant.ident.to_string()),
+        tag,
+        weight,
+        style,
+        fields,
+    })
+}
+
+/// A copy of `generics` with every type parameter bound by `bound`
+fn bounded(generics: &Generics, bound: TokenStream2) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics.type_params().map(|p| p.ident.clone()).collect();
+    {
+        let where_clause = generics.make_where_clause();
+        for param in params {
+            where_clause
+                .predicates
+                .push(parse_quote!(#param: #bound));
+        }
+    }
+    generics
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let data = match input.data {
+        Data::Enum(ref data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "TaggedOp can only be derived for enums",
+            ));
+        }
+    };
+    let mut variants = Vec::new();
+    let mut tags: HashMap<u8, Ident> = HashMap::new();
+    for variant in &data.variants {
+        let variant = parse_variant(variant)?;
+        if let Some(other) = tags.insert(variant.tag, variant.ident.clone()) {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                format!("tag {} is already used by {}", variant.tag, other),
+            ));
+        }
+        variants.push(variant);
+    }
+    if variants.iter().all(|v| v.weight == 0) {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "at least one variant must have a non-zero weight",
+        ));
+    }
+
+    let ty = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let tagged_op = {
+        let generics = bounded(&input.generics, quote!(::arbitrary::Arbitrary));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let names = variants.iter().map(|v| &v.name);
+        let tags = variants.iter().map(|v| v.tag);
+        let weights = variants.iter().map(|v| v.weight);
+        let tag_arms = variants.iter().map(|v| {
+            let pattern = v.pattern(ty);
+            let tag = v.tag;
+            quote!(#pattern => #tag)
+        });
+        let decode_arms = variants.iter().map(|v| {
+            let tag = v.tag;
+            let bindings = v.fields.iter().map(|f| &f.binding);
+            let construct = v.construct(ty, v.fields.iter().map(|f| {
+                let binding = &f.binding;
+                quote!(#binding)
+            }));
+            quote! {
+                #tag => {
+                    #( let #bindings = ::arbitrary::Arbitrary::arbitrary(u)?; )*
+                    #construct
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics ::op::TaggedOp for #ty #ty_generics #where_clause {
+                const VARIANTS: &'static [::op::Variant] = &[
+                    #( ::op::Variant { name: #names, tag: #tags, weight: #weights } ),*
+                ];
+
+                #[allow(unused_variables)]
+                fn tag(&self) -> u8 {
+                    match *self {
+                        #( #tag_arms ),*
+                    }
+                }
+
+                fn decode_tagged<U>(tag: u8, u: &mut U) -> Result<Option<Self>, U::Error>
+                where
+                    U: ::arbitrary::Unstructured + ?Sized,
+                {
+                    let op = match tag {
+                        #( #decode_arms )*
+                        _ => return Ok(None),
+                    };
+                    Ok(Some(op))
+                }
+            }
+        }
+    };
+
+    let arbitrary = {
+        let generics = bounded(&input.generics, quote!(::arbitrary::Arbitrary + Clone));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let shrink_arms = variants.iter().map(|v| {
+            let pattern = v.pattern(ty);
+            let shrinks = v.fields.iter().map(|shrunk| {
+                let binding = &shrunk.binding;
+                let others: Vec<&Ident> = v
+                    .fields
+                    .iter()
+                    .map(|f| &f.binding)
+                    .filter(|b| *b != binding)
+                    .collect();
+                let sources = others.clone();
+                let construct = v.construct(
+                    ty,
+                    v.fields.iter().map(|f| {
+                        let b = &f.binding;
+                        if b == binding {
+                            quote!(#b)
+                        } else {
+                            quote!(#b.clone())
+                        }
+                    }),
+                );
+                quote! {
+                    {
+                        #( let #others = #sources.clone(); )*
+                        shrinks.push(Box::new(
+                            ::arbitrary::Arbitrary::shrink(#binding).map(move |#binding| #construct),
+                        ));
+                    }
+                }
+            });
+            quote! {
+                #pattern => {
+                    #( #shrinks )*
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics ::arbitrary::Arbitrary for #ty #ty_generics #where_clause {
+                fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+                where
+                    U: ::arbitrary::Unstructured + ?Sized,
+                {
+                    // Bytes that are not the tag of any op are skipped. Were
+                    // they instead mapped onto some op, adding a variant would
+                    // change the meaning of existing inputs.
+                    loop {
+                        let tag: u8 = ::arbitrary::Arbitrary::arbitrary(u)?;
+                        if let Some(op) = <Self as ::op::TaggedOp>::decode_tagged(tag, u)? {
+                            return Ok(op);
+                        }
+                    }
+                }
+
+                #[allow(unused_mut)]
+                fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+                    let mut shrinks: Vec<Box<dyn Iterator<Item = Self>>> = Vec::new();
+                    match *self {
+                        #( #shrink_arms )*
+                    }
+                    Box::new(shrinks.into_iter().flat_map(|s| s))
+                }
+            }
+        }
+    };
+
+    let encode = {
+        let generics = bounded(&input.generics, quote!(::encode::Encode));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let arms = variants.iter().map(|v| {
+            let pattern = v.pattern(ty);
+            let tag = v.tag;
+            let bindings = v.fields.iter().map(|f| &f.binding);
+            quote! {
+                #pattern => {
+                    ::encode::Encode::encode(&#tag, buf);
+                    #( ::encode::Encode::encode(#bindings, buf); )*
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics ::encode::Encode for #ty #ty_generics #where_clause {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    match *self {
+                        #( #arms )*
+                    }
+                }
+            }
+        }
+    };
+
+    let display = {
+        let generics = bounded(&input.generics, quote!(::std::fmt::Display));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let arms = variants.iter().map(|v| {
+            let pattern = v.pattern(ty);
+            let name = &v.name;
+            let writes = v.fields.iter().map(|f| {
+                let binding = &f.binding;
+                let format = format!(" {}={{}}", f.name);
+                quote!(write!(f, #format, #binding)?;)
+            });
+            quote! {
+                #pattern => {
+                    f.write_str(#name)?;
+                    #( #writes )*
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics ::std::fmt::Display for #ty #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                    match *self {
+                        #( #arms )*
+                    }
+                    Ok(())
+                }
+            }
+        }
+    };
+
+    let from_str = {
+        let generics = bounded(&input.generics, quote!(::std::str::FromStr));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let arms = variants.iter().map(|v| {
+            let name = &v.name;
+            let construct = v.construct(
+                ty,
+                v.fields.iter().map(|f| {
+                    let field = &f.name;
+                    quote!(line.get(#field)?)
+                }),
+            );
+            quote!(#name => Ok(#construct),)
+        });
+        quote! {
+            impl #impl_generics ::std::str::FromStr for #ty #ty_generics #where_clause {
+                type Err = ::trace::ParseError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    let line = ::trace::OpLine::parse(s)?;
+                    match line.name() {
+                        #( #arms )*
+                        _ => line.unknown(),
+                    }
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        #tagged_op
+        #arbitrary
+        #encode
+        #display
+        #from_str
+    })
+}
@@ -1 +1 @@
-TESTS="str_repeat vec_deque hash_map"
+TESTS="str_repeat vec_deque hash_map hash_map_hashbrown hash_map_indexmap vec_deque_smallvec vec_deque_arrayvec vec_deque_im vec_deque_three_way string str_search str_from_utf8 char_unicode char_utf16 num_int num_int_fmt num_float slice_sort slice_buffer iter cell rc sync_arc sync_atomic sync_mutex sync_rwlock sync_mpsc"
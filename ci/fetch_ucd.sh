@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+set -o errexit
+set -o pipefail
+set -o nounset
+
+# Download the files of the Unicode Character Database the `char_unicode`
+# target reads, to be checked in. They must be of the version `UCD_VERSION`
+# pins in src/stdlib/char/unicode.rs, so move that along with them.
+VERSION="${1:?usage: ci/fetch_ucd.sh <unicode version, such as 17.0.0>}"
+UCD_DIR=src/stdlib/char/ucd
+
+mkdir -p ${UCD_DIR}
+for FILE in UnicodeData.txt SpecialCasing.txt PropList.txt DerivedCoreProperties.txt
+do
+    curl --fail --silent --show-error --location \
+        --output ${UCD_DIR}/${FILE} \
+        https://www.unicode.org/Public/${VERSION}/ucd/${FILE}
+done
@@ -0,0 +1,18 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::char::unicode;
+use std::process;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = unicode::decode_input(data) {
+        // Without a UCD of std's version there is nothing to check. That is no
+        // bug of std, so say what is wrong and stop rather than crash.
+        if let Err(e) = unicode::replay(&trace) {
+            eprintln!("{} cannot run: {}", unicode::TARGET, e);
+            process::exit(1);
+        }
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::char::utf16;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = utf16::decode_input(data) {
+        utf16::replay(&trace);
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+extern crate hashbrown;
+
+use bughunt_rust::stdlib::collections::hash_map::{self, BuildTrulyAwfulHasher};
+use hashbrown::HashMap;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = hash_map::decode_input(data) {
+        hash_map::replay_map::<HashMap<u16, u16, BuildTrulyAwfulHasher>>(&trace);
+    }
+});
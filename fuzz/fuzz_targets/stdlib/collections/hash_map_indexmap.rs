@@ -0,0 +1,14 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+extern crate indexmap;
+
+use bughunt_rust::stdlib::collections::hash_map::{self, BuildTrulyAwfulHasher};
+use indexmap::IndexMap;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = hash_map::decode_input(data) {
+        hash_map::replay_map::<IndexMap<u16, u16, BuildTrulyAwfulHasher>>(&trace);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+extern crate im;
+
+use bughunt_rust::stdlib::collections::vec_deque;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = vec_deque::decode_input(data) {
+        vec_deque::replay_deque::<im::Vector<u8>>(&trace);
+    }
+});
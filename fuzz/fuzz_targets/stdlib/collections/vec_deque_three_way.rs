@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::collections::vec_deque::{self, SmallVecDeque};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = vec_deque::decode_input(data) {
+        vec_deque::replay_three_way::<SmallVecDeque<u8>>(&trace);
+    }
+});
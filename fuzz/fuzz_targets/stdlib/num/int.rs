@@ -0,0 +1,10 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::num::int;
+
+fuzz_target!(|data: &[u8]| {
+    int::run(data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::num::int_fmt;
+
+fuzz_target!(|data: &[u8]| {
+    int_fmt::run(data);
+});
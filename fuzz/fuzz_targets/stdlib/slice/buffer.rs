@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::slice::buffer;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = buffer::decode_input(data) {
+        buffer::replay(&trace);
+    }
+});
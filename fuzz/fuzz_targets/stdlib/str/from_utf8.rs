@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::str::utf8;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(trace) = utf8::decode_input(data) {
+        utf8::replay(&trace);
+    }
+});
@@ -0,0 +1,85 @@
+//! Rewrite legacy fuzz inputs into the current, versioned encoding
+//!
+//! Before inputs were versioned an `Op` variant was chosen by a byte modulo the
+//! number of variants, so adding a variant silently changed the meaning of
+//! every saved input. This program decodes such legacy inputs and writes them
+//! back out in the current encoding, in which every variant has a fixed tag.
+//!
+//! ```text
+//! > cargo run --bin migrate_corpus hash_map old/corpus/hash_map fuzz/corpus/hash_map
+//! ```
+//!
+//! Every file in the source directory is taken to be a legacy input. Migrated
+//! files are written to the destination directory under the same name.
+//!
+//! A migrated input decodes to the same ops as the legacy input did, with one
+//! exception. The version and profile bytes take up two bytes of a target's
+//! maximum input length, so a legacy input whose last ops only just fit loses
+//! them.
+extern crate bughunt_rust;
+
+use bughunt_rust::stdlib::collections::{hash_map, vec_deque};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: migrate_corpus <target> <from> <to>";
+
+/// Rewrite a single legacy input, or `None` if it holds no ops at all
+fn migrate(target: &str, data: &[u8]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    let mut buf = match target {
+        hash_map::TARGET => {
+            hash_map::decode_legacy_input(data).map(|t| hash_map::encode_input(&t))
+        }
+        vec_deque::TARGET => {
+            vec_deque::decode_legacy_input(data).map(|t| vec_deque::encode_input(&t))
+        }
+        _ => return Err(format!("unknown target '{}'", target).into()),
+    };
+    // Inputs are padded with zeros as they are decoded. Decoding a legacy
+    // input pulls that padding in as ops which, re-encoded, are zeros again.
+    // There's no sense writing them out.
+    if let Some(ref mut buf) = buf {
+        while buf.last() == Some(&0) {
+            buf.pop();
+        }
+    }
+    Ok(buf)
+}
+
+fn run(target: &str, from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(to)?;
+    let (mut migrated, mut skipped) = (0, 0);
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        match migrate(target, &fs::read(&path)?)? {
+            Some(buf) => {
+                fs::write(to.join(path.file_name().expect("files have names")), buf)?;
+                migrated += 1;
+            }
+            None => skipped += 1,
+        }
+    }
+    println!(
+        "{}: migrated {} inputs, skipped {} too short to decode",
+        target, migrated, skipped
+    );
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() != 3 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    if let Err(e) = run(&args[0], Path::new(&args[1]), Path::new(&args[2])) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
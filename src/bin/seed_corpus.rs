@@ -0,0 +1,1568 @@
+//! Write seed inputs for every fuzz target
+//!
+//! Starting libFuzzer from an empty corpus means spending the first hours of a
+//! run rediscovering basic shapes: a map that's grown past its first
+//! reallocation, a deque whose ring buffer has wrapped. This program writes a
+//! handful of inputs per target that get there immediately, built as traces of
+//! ops and encoded the same way the targets decode them.
+//!
+//! Run it from the root of the project:
+//!
+//! ```text
+//! > cargo run --bin seed_corpus
+//! ```
+//!
+//! Seeds are written to `fuzz/corpus/<target>/`, or `<dir>/<target>/` if a
+//! directory is passed as the first argument.
+extern crate bughunt_rust;
+
+use bughunt_rust::encode::Encode;
+use bughunt_rust::stdlib::cell;
+use bughunt_rust::stdlib::char::{unicode, utf16};
+use bughunt_rust::stdlib::collections::{hash_map, vec_deque};
+use bughunt_rust::stdlib::iter;
+use bughunt_rust::stdlib::num::big::Big;
+use bughunt_rust::stdlib::num::float::{self, Digits, Float};
+use bughunt_rust::stdlib::num::int::{self, Int};
+use bughunt_rust::stdlib::num::int_fmt::{self, Numeral, Spec};
+use bughunt_rust::stdlib::rc;
+use bughunt_rust::stdlib::slice::buffer;
+use bughunt_rust::stdlib::slice::sort;
+use bughunt_rust::stdlib::str::search::{self, Pattern, Word};
+use bughunt_rust::stdlib::str::utf8;
+use bughunt_rust::stdlib::string::{self, Char, Text};
+use bughunt_rust::stdlib::sync::{arc, atomic, mpsc, mutex, rwlock, THREADS};
+use bughunt_rust::stdlib::util::Bytes;
+use bughunt_rust::trace::{Header, Trace};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A named seed input for a single target
+struct Seed {
+    name: String,
+    bytes: Vec<u8>,
+}
+
+impl Seed {
+    fn new<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self::from_bytes(name, Vec::new())
+    }
+
+    fn from_bytes<S>(name: S, bytes: Vec<u8>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            bytes,
+        }
+    }
+
+    fn push<E>(&mut self, e: &E) -> &mut Self
+    where
+        E: Encode,
+    {
+        e.encode(&mut self.bytes);
+        self
+    }
+}
+
+/// The powers of two we'd like each growable collection to pass through
+fn powers_of_two() -> impl Iterator<Item = u16> {
+    (0..11).map(|p| 1 << p)
+}
+
+/// An empty trace of the `hash_map` target
+fn hash_map_trace(hash_seed: u8, capacity: u8) -> Trace<hash_map::Op<u16, u16>> {
+    Trace::new(Header {
+        target: hash_map::TARGET.to_string(),
+        types: vec!["u16".to_string(), "u16".to_string()],
+        capacity: capacity as usize,
+        hash_seed: Some(hash_seed),
+    })
+}
+
+/// An empty trace of the `vec_deque` target
+fn vec_deque_trace(capacity: u8) -> Trace<vec_deque::Op<u8>> {
+    Trace::new(Header {
+        target: vec_deque::TARGET.to_string(),
+        types: vec!["u8".to_string()],
+        capacity: capacity as usize,
+        hash_seed: None,
+    })
+}
+
+/// An empty trace of the `string` target
+fn string_trace(capacity: u8) -> Trace<string::Op> {
+    Trace::new(Header {
+        target: string::TARGET.to_string(),
+        types: vec!["char".to_string()],
+        capacity: capacity as usize,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `hash_map` target
+///
+/// After the encoding version and profile the target reads a hasher seed and
+/// an initial capacity, both `u8`, before reading `Op<u16, u16>` until its
+/// input runs out.
+fn hash_map_seeds() -> Vec<Seed> {
+    type Op = hash_map::Op<u16, u16>;
+    let mut seeds = Vec::new();
+
+    // Grow from nothing until we're just past a power-of-two capacity, then
+    // read every key back.
+    for n in powers_of_two() {
+        let name = format!("grow_past_{}", n);
+        let mut trace = hash_map_trace(0, 0);
+        for k in 0..=n {
+            trace.ops.push(Op::Insert { k, v: !k });
+        }
+        for k in 0..=n {
+            trace.ops.push(Op::Get { k });
+        }
+        seeds.push((name, trace));
+    }
+
+    // `TrulyAwfulHasher` only looks at the first byte written to it, which
+    // for a `u16` key is the low byte. Every key here has a low byte that's a
+    // multiple of 8 and so, with a hasher seed of zero, hashes to zero.
+    for &capacity in &[0u8, 255] {
+        let name = format!("single_bucket_cap_{}", capacity);
+        let mut trace = hash_map_trace(0, capacity);
+        for i in 0..512u16 {
+            trace.ops.push(Op::Insert { k: i * 8, v: i });
+        }
+        for i in (0..512u16).rev() {
+            trace.ops.push(Op::Remove { k: i * 8 });
+            trace.ops.push(Op::Get { k: i * 8 });
+        }
+        seeds.push((name, trace));
+    }
+
+    // Alternate between emptying the map and asking for room, with a few
+    // inserts in between so there's something to clear.
+    let name = "clear_reserve";
+    let mut trace = hash_map_trace(3, 16);
+    for n in powers_of_two() {
+        for k in 0..4 {
+            trace.ops.push(Op::Insert { k: n + k, v: k });
+        }
+        trace.ops.push(Op::Reserve { n });
+        trace.ops.push(Op::Clear);
+        trace.ops.push(Op::Reserve { n: n - 1 });
+    }
+    seeds.push((name.to_string(), trace));
+
+    // Fill, shrink and drain: the capacity should come down as we go.
+    let name = "shrink_while_draining";
+    let mut trace = hash_map_trace(7, 128);
+    for k in 0..256 {
+        trace.ops.push(Op::Insert { k, v: k });
+    }
+    for k in 0..256 {
+        trace.ops.push(Op::Remove { k });
+        if k % 32 == 0 {
+            trace.ops.push(Op::ShrinkToFit);
+        }
+    }
+    trace.ops.push(Op::ShrinkToFit);
+    seeds.push((name.to_string(), trace));
+
+    // Repeatedly insert and remove over a small set of keys, overwriting
+    // values as we go.
+    let name = "churn";
+    let mut trace = hash_map_trace(1, 8);
+    for round in 0..64 {
+        for k in 0..16 {
+            trace.ops.push(Op::Insert { k, v: round });
+        }
+        for k in (0..16).filter(|k| k % 3 == round % 3) {
+            trace.ops.push(Op::Remove { k });
+        }
+    }
+    seeds.push((name.to_string(), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, hash_map::encode_input(&trace)))
+        .collect()
+}
+
+/// Seeds for the `vec_deque` target
+///
+/// After the encoding version and profile the target reads an initial
+/// capacity, a `u8`, before reading `Op<u8>` until its input runs out.
+fn vec_deque_seeds() -> Vec<Seed> {
+    type Op = vec_deque::Op<u8>;
+    let mut seeds = Vec::new();
+
+    // Grow from nothing until we're just past a power-of-two capacity, then
+    // take everything back off the front.
+    for n in powers_of_two() {
+        let name = format!("grow_past_{}", n);
+        let mut trace = vec_deque_trace(0);
+        for t in 0..=n {
+            trace.ops.push(Op::PushBack(t as u8));
+        }
+        for _ in 0..=n {
+            trace.ops.push(Op::PopFront);
+        }
+        seeds.push((name, trace));
+    }
+
+    // Walk the ring buffer around so that its head sits past its tail in
+    // memory, then poke at the middle of the wrapped storage. The initial
+    // capacity is small enough that no reallocation happens along the way.
+    for &capacity in &[4u8, 8, 16, 32] {
+        let cap = u16::from(capacity);
+        let name = format!("wrap_cap_{}", capacity);
+        let mut trace = vec_deque_trace(capacity);
+        for t in 0..(cap / 2) {
+            trace.ops.push(Op::PushBack(t as u8));
+        }
+        for round in 0..cap {
+            trace.ops.push(Op::PopFront);
+            trace.ops.push(Op::PushBack(round as u8));
+        }
+        for idx in 0..(cap / 2) {
+            trace.ops.push(Op::Insert(idx as usize, idx as u8));
+            trace.ops.push(Op::Remove(idx as usize + 1));
+            trace.ops.push(Op::SwapRemoveBack(idx as usize));
+            trace.ops.push(Op::PushFront(idx as u8));
+        }
+        seeds.push((name, trace));
+    }
+
+    // Grow from both ends at once.
+    let name = "both_ends";
+    let mut trace = vec_deque_trace(1);
+    for t in 0..512u16 {
+        trace.ops.push(Op::PushFront(t as u8));
+        trace.ops.push(Op::PushBack(!t as u8));
+    }
+    for _ in 0..512 {
+        trace.ops.push(Op::PopBack);
+        trace.ops.push(Op::PopFront);
+    }
+    seeds.push((name.to_string(), trace));
+
+    // Alternate between emptying the deque and shrinking it back down.
+    let name = "clear_shrink";
+    let mut trace = vec_deque_trace(64);
+    for n in powers_of_two().take(8) {
+        for t in 0..n {
+            trace.ops.push(Op::PushBack(t as u8));
+        }
+        trace.ops.push(Op::ShrinkToFit);
+        trace.ops.push(Op::Clear);
+        trace.ops.push(Op::ShrinkToFit);
+    }
+    seeds.push((name.to_string(), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, vec_deque::encode_input(&trace)))
+        .collect()
+}
+
+/// Seeds for the `string` target
+///
+/// After the encoding version and profile the target reads an initial
+/// capacity, a `u8`, before reading `string::Op` until its input runs out.
+fn string_seeds() -> Vec<Seed> {
+    type Op = string::Op;
+    let mixed = Text("a\u{e9}\u{2603}\u{1f41b}".to_string());
+    let mut seeds = Vec::new();
+
+    // Build a string of chars of every width, then edit it at every byte
+    // offset, boundary or not.
+    let name = "every_offset";
+    let mut trace = string_trace(0);
+    for _ in 0..4 {
+        trace.ops.push(Op::PushStr { s: mixed.clone() });
+    }
+    for idx in 0..40 {
+        trace.ops.push(Op::Insert { idx, c: Char('\u{e9}') });
+        trace.ops.push(Op::Remove { idx });
+        trace.ops.push(Op::InsertStr { idx, s: mixed.clone() });
+        trace.ops.push(Op::Drain { start: idx, end: idx + 5 });
+    }
+    seeds.push((name.to_string(), trace));
+
+    // Grow one wide char at a time past each power of two bytes, then split
+    // and shrink.
+    for n in powers_of_two().take(9) {
+        let name = format!("grow_past_{}", n);
+        let mut trace = string_trace(0);
+        for _ in 0..=(n / 4) {
+            trace.ops.push(Op::Push { c: Char('\u{1f41b}') });
+        }
+        trace.ops.push(Op::SplitOff { at: n / 2 });
+        trace.ops.push(Op::ShrinkToFit);
+        trace.ops.push(Op::Truncate { len: n / 4 });
+        seeds.push((name, trace));
+    }
+
+    // Replace and retain over mixed widths.
+    let name = "replace_retain";
+    let mut trace = string_trace(16);
+    trace.ops.push(Op::PushStr { s: mixed.clone() });
+    for width in 0..4 {
+        trace.ops.push(Op::ReplaceRange { start: 1, end: 6, s: mixed.clone() });
+        trace.ops.push(Op::Retain { width });
+        trace.ops.push(Op::Reserve { n: 64 });
+        trace.ops.push(Op::Pop);
+    }
+    trace.ops.push(Op::Clear);
+    seeds.push((name.to_string(), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, string::encode_input(&trace)))
+        .collect()
+}
+
+/// An empty trace of the `str_search` target
+fn str_search_trace() -> Trace<search::Op> {
+    Trace::new(Header {
+        target: search::TARGET.to_string(),
+        types: vec!["char".to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Every op of the `str_search` target, searching `hay` for `pat`
+fn str_search_ops(hay: &Word, pat: &Pattern, dirs: u16) -> Vec<search::Op> {
+    type Op = search::Op;
+    let (hay, pat, to) = (hay.clone(), pat.clone(), Word("\u{e9}".to_string()));
+    vec![
+        Op::Find { hay: hay.clone(), pat: pat.clone() },
+        Op::Rfind { hay: hay.clone(), pat: pat.clone() },
+        Op::MatchIndices { hay: hay.clone(), pat: pat.clone(), dirs },
+        Op::RmatchIndices { hay: hay.clone(), pat: pat.clone(), dirs },
+        Op::Split { hay: hay.clone(), pat: pat.clone(), dirs },
+        Op::Rsplit { hay: hay.clone(), pat: pat.clone(), dirs },
+        Op::Splitn { hay: hay.clone(), pat: pat.clone(), n: 3 },
+        Op::Rsplitn { hay: hay.clone(), pat: pat.clone(), n: 3 },
+        Op::SplitTerminator { hay: hay.clone(), pat: pat.clone(), dirs },
+        Op::SplitOnce { hay: hay.clone(), pat: pat.clone() },
+        Op::RsplitOnce { hay: hay.clone(), pat: pat.clone() },
+        Op::Replace { hay: hay.clone(), pat: pat.clone(), to: to.clone() },
+        Op::Replacen { hay: hay.clone(), pat: pat.clone(), to, n: 2 },
+        Op::StripPrefix { hay: hay.clone(), pat: pat.clone() },
+        Op::StripSuffix { hay: hay.clone(), pat: pat.clone() },
+        Op::TrimMatches { hay, pat },
+    ]
+}
+
+/// Seeds for the `str_search` target
+///
+/// After the encoding version and profile the target reads
+/// `str::search::Op` until its input runs out.
+fn str_search_seeds() -> Vec<Seed> {
+    let word = |s: &str| Word(s.to_string());
+    let mut seeds = Vec::new();
+
+    // Periodic and overlapping needles, the hard cases for Two-Way, and the
+    // empty needle, which matches everywhere.
+    let cases: &[(&str, &str, &str)] = &[
+        ("periodic", "abababcabababab", "ababab"),
+        ("overlapping", "aaaaaaa", "aa"),
+        ("near_miss", "abcabcab\u{e9}abcab", "abcab\u{e9}"),
+        ("shared_bytes", "\u{e9}\u{a9}\u{20ac}\u{2603}\u{a9}\u{e9}", "\u{a9}\u{e9}"),
+        ("empty_needle", "a\u{e9}\u{1f41b}", ""),
+        ("empty_hay", "", "a"),
+    ];
+    for &(name, hay, pat) in cases {
+        let mut trace = str_search_trace();
+        trace.ops = str_search_ops(&word(hay), &Pattern::Str(word(pat)), 0);
+        seeds.push((name.to_string(), trace));
+    }
+
+    // Chars and closures, drained from the front, the back and alternately.
+    let hay = word("abca\u{e9}b\u{20ac}cab\u{1f41b}");
+    for &dirs in &[0, u16::MAX, 0b0101_0101_0101_0101] {
+        let name = format!("char_dirs_{:x}", dirs);
+        let mut trace = str_search_trace();
+        trace.ops = str_search_ops(&hay, &Pattern::Char('a'), dirs);
+        seeds.push((name, trace));
+        let name = format!("set_dirs_{:x}", dirs);
+        let mut trace = str_search_trace();
+        trace.ops = str_search_ops(&hay, &Pattern::Set(0b0010_1001), dirs);
+        seeds.push((name, trace));
+    }
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, search::encode_input(&trace)))
+        .collect()
+}
+
+/// An empty trace of the `str_from_utf8` target
+fn str_from_utf8_trace() -> Trace<utf8::Op> {
+    Trace::new(Header {
+        target: utf8::TARGET.to_string(),
+        types: vec!["u8".to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `str_from_utf8` target
+///
+/// After the encoding version and profile the target reads `str::utf8::Op`,
+/// each appending bytes to its input, until its input runs out.
+fn str_from_utf8_seeds() -> Vec<Seed> {
+    type Op = utf8::Op;
+    let widths = ['a', '\u{e9}', '\u{2603}', '\u{1f41b}'];
+    let mut seeds = Vec::new();
+
+    // Chars of every width, each followed by every way of going wrong.
+    let name = "every_error";
+    let mut trace = str_from_utf8_trace();
+    for &c in &widths {
+        let c = Char(c);
+        trace.ops.push(Op::Char { c });
+        for len in 0..3 {
+            trace.ops.push(Op::Truncated { c, len });
+            trace.ops.push(Op::Char { c });
+        }
+        for width in 0..3 {
+            trace.ops.push(Op::Overlong { c, width });
+        }
+        trace.ops.push(Op::Surrogate { n: 0 });
+        trace.ops.push(Op::Surrogate { n: 0x7FF });
+        trace.ops.push(Op::TooLarge { n: 0 });
+    }
+    seeds.push((name.to_string(), trace));
+
+    // The maximal subparts example of the Unicode Standard, table 3-8.
+    let name = "maximal_subparts";
+    let mut trace = str_from_utf8_trace();
+    for &b in &[0x61, 0xF1, 0x80, 0x80, 0xE1, 0x80, 0xC2, 0x62, 0x80, 0x63, 0x80, 0xBF, 0x64] {
+        trace.ops.push(Op::Byte { b });
+    }
+    seeds.push((name.to_string(), trace));
+
+    // A long valid run, ending in a truncated char of each width.
+    for &c in &widths[1..] {
+        let name = format!("valid_then_truncated_{}", c.len_utf8());
+        let mut trace = str_from_utf8_trace();
+        for _ in 0..64 {
+            for &w in &widths {
+                trace.ops.push(Op::Char { c: Char(w) });
+            }
+        }
+        trace.ops.push(Op::Truncated { c: Char(c), len: 1 });
+        seeds.push((name, trace));
+    }
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, utf8::encode_input(&trace)))
+        .collect()
+}
+
+/// An empty trace of the `char_unicode` target
+fn char_unicode_trace() -> Trace<unicode::Op> {
+    Trace::new(Header {
+        target: unicode::TARGET.to_string(),
+        types: vec!["char".to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `char_unicode` target
+///
+/// After the encoding version and profile the target reads
+/// `char::unicode::Op` until its input runs out.
+fn char_unicode_seeds() -> Vec<Seed> {
+    type Op = unicode::Op;
+    let text = |s: &str| Text(s.to_string());
+    let mut seeds = Vec::new();
+
+    // Sigma at the start, middle and end of words, with case-ignorable chars
+    // around it.
+    let name = "final_sigma";
+    let mut trace = char_unicode_trace();
+    for s in &[
+        "\u{3a3}",
+        "A\u{3a3}",
+        "A\u{3a3}B",
+        "\u{391}\u{3a3}'. \u{3a3}\u{391}",
+        "\u{391}\u{301}\u{3a3}\u{301}",
+        "\u{2b0}\u{3a3}",
+    ] {
+        trace.ops.push(Op::StrCase { s: text(s) });
+    }
+    seeds.push((name.to_string(), trace));
+
+    // The escapes of controls, quotes, separators and grapheme extenders, at
+    // the start of a string and within it.
+    let name = "escapes";
+    let mut trace = char_unicode_trace();
+    let escapes = "\0\t\r\n\\'\" \u{a0}\u{ad}\u{301}\u{2028}\u{e000}\u{10ffff}";
+    trace.ops.push(Op::StrEscapeDebug { s: text(escapes) });
+    trace.ops.push(Op::StrEscapeDebug { s: text(&escapes[1..]) });
+    for c in escapes.chars() {
+        trace.ops.push(Op::EscapeDebug { c: Char(c) });
+    }
+    seeds.push((name.to_string(), trace));
+
+    // Sweeps over the blocks where the tables are busiest.
+    let blocks: &[(&str, u32)] = &[
+        ("ascii", 0x0),
+        ("latin_1", 0x80),
+        ("latin_extended", 0x100),
+        ("combining", 0x300),
+        ("greek", 0x370),
+        ("cyrillic", 0x400),
+        ("armenian", 0x530),
+        ("georgian", 0x10a0),
+        ("cherokee", 0x13a0),
+        ("glagolitic", 0x2c00),
+        ("deseret", 0x1_0400),
+        ("adlam", 0x1_e900),
+        ("tags", 0xe_0000),
+    ];
+    for &(name, start) in blocks {
+        let mut trace = char_unicode_trace();
+        for i in 0..4 {
+            trace.ops.push(Op::Sweep { start: start + i * 0x100, len: u8::MAX });
+        }
+        seeds.push((format!("sweep_{}", name), trace));
+    }
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, unicode::encode_input(&trace)))
+        .collect()
+}
+
+/// An empty trace of the `char_utf16` target
+fn char_utf16_trace() -> Trace<utf16::Op> {
+    Trace::new(Header {
+        target: utf16::TARGET.to_string(),
+        types: vec!["u16".to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `char_utf16` target
+///
+/// After the encoding version and profile the target reads `char::utf16::Op`,
+/// each appending code units to its input, until its input runs out.
+fn char_utf16_seeds() -> Vec<Seed> {
+    type Op = utf16::Op;
+    let widths = ['a', '\u{e9}', '\u{ffff}', '\u{10000}', '\u{1f41b}', '\u{10ffff}'];
+    let mut seeds = Vec::new();
+
+    // Chars either side of the surrogates, then each surrogate out of place.
+    let name = "every_error";
+    let mut trace = char_utf16_trace();
+    for &c in &widths {
+        let c = Char(c);
+        trace.ops.push(Op::Char { c });
+        trace.ops.push(Op::Swapped { c });
+        trace.ops.push(Op::High { n: 0 });
+        trace.ops.push(Op::Char { c });
+        trace.ops.push(Op::Low { n: 0x3FF });
+    }
+    seeds.push((name.to_string(), trace));
+
+    // A long valid run, ending in a high surrogate waiting for its partner.
+    let name = "valid_then_high";
+    let mut trace = char_utf16_trace();
+    for _ in 0..64 {
+        for &c in &widths {
+            trace.ops.push(Op::Char { c: Char(c) });
+        }
+    }
+    trace.ops.push(Op::High { n: 0x3D });
+    seeds.push((name.to_string(), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, utf16::encode_input(&trace)))
+        .collect()
+}
+
+/// An empty trace of the `num_int` target, of ops on `T`
+fn num_int_trace<T: Int>() -> Trace<int::Op<T>> {
+    Trace::new(Header {
+        target: int::TARGET.to_string(),
+        types: vec![T::NAME.to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `num_int` target, of ops on `T`
+///
+/// Every op is run on the values at the edges of `T`, which is where the
+/// flavours of each method part ways. The binary ops take every pair of edges
+/// and get a seed of their own, lest a seed of `u128` outgrow the input.
+fn num_int_seeds_of<T: Int>() -> Vec<Seed> {
+    type Op<T> = int::Op<T>;
+    let one = || Big::from(1u128);
+    let edges: Vec<T> = [
+        int::min::<T>(),
+        int::min::<T>() + one(),
+        -one(),
+        Big::zero(),
+        one(),
+        Big::from(10u128),
+        int::max::<T>() - one(),
+        int::max::<T>(),
+    ]
+    .iter()
+    .map(int::wrap)
+    .collect();
+    let mut seeds = Vec::new();
+
+    let mut trace = num_int_trace::<T>();
+    for &lhs in &edges {
+        for &rhs in &edges {
+            trace.ops.push(Op::Add { lhs, rhs });
+            trace.ops.push(Op::Sub { lhs, rhs });
+            trace.ops.push(Op::Mul { lhs, rhs });
+            trace.ops.push(Op::Div { lhs, rhs });
+            trace.ops.push(Op::Rem { lhs, rhs });
+            trace.ops.push(Op::DivEuclid { lhs, rhs });
+            trace.ops.push(Op::RemEuclid { lhs, rhs });
+        }
+    }
+    seeds.push((format!("{}_pairs", T::NAME), trace));
+
+    let bits = T::BITS as u8;
+    let mut trace = num_int_trace::<T>();
+    for &x in &edges {
+        trace.ops.push(Op::Neg { x });
+        trace.ops.push(Op::SaturatingNeg { x });
+        trace.ops.push(Op::Abs { x });
+        for &n in &[0, 1, bits - 1, bits, u8::MAX] {
+            trace.ops.push(Op::Shl { x, n });
+            trace.ops.push(Op::Shr { x, n });
+            trace.ops.push(Op::Bits { x, n });
+        }
+        for &exp in &[0, 1, 2, bits - 1, bits] {
+            trace.ops.push(Op::Pow { x, exp });
+        }
+        trace.ops.push(Op::AbsDiff { lhs: x, rhs: edges[0] });
+        trace.ops.push(Op::Isqrt { x });
+        trace.ops.push(Op::CheckedIsqrt { x });
+        trace.ops.push(Op::Ilog { x, base: edges[4] });
+        trace.ops.push(Op::Ilog { x, base: edges[5] });
+        trace.ops.push(Op::PowerOfTwo { x });
+    }
+    seeds.push((format!("{}_edges", T::NAME), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, int::encode_input(&trace)))
+        .collect()
+}
+
+/// Seeds for the `num_int` target
+///
+/// After the encoding version and profile the target reads a byte choosing
+/// the integer type and then `num::int::Op` until its input runs out.
+fn num_int_seeds() -> Vec<Seed> {
+    let mut seeds = Vec::new();
+    seeds.extend(num_int_seeds_of::<u8>());
+    seeds.extend(num_int_seeds_of::<u16>());
+    seeds.extend(num_int_seeds_of::<u32>());
+    seeds.extend(num_int_seeds_of::<u64>());
+    seeds.extend(num_int_seeds_of::<u128>());
+    seeds.extend(num_int_seeds_of::<usize>());
+    seeds.extend(num_int_seeds_of::<i8>());
+    seeds.extend(num_int_seeds_of::<i16>());
+    seeds.extend(num_int_seeds_of::<i32>());
+    seeds.extend(num_int_seeds_of::<i64>());
+    seeds.extend(num_int_seeds_of::<i128>());
+    seeds.extend(num_int_seeds_of::<isize>());
+    seeds
+}
+
+/// An empty trace of the `num_int_fmt` target, of ops on `T`
+fn num_int_fmt_trace<T: Int>() -> Trace<int_fmt::Op<T>> {
+    Trace::new(Header {
+        target: int_fmt::TARGET.to_string(),
+        types: vec![T::NAME.to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `num_int_fmt` target, of ops on `T`
+///
+/// The strings are those where parsing fails or only just succeeds: signs on
+/// their own, digits past the radix and the bounds of `T`, written in the
+/// smallest, the usual and the largest radix with a digit more to overflow.
+/// The bounds are formatted by every base with and without each flag.
+fn num_int_fmt_seeds_of<T: Int>() -> Vec<Seed> {
+    type Op<T> = int_fmt::Op<T>;
+    let bounds: Vec<T> = [int::min::<T>(), Big::zero(), int::max::<T>()].iter().map(int::wrap).collect();
+    // `int_fmt::radix` takes a byte to `2 + byte % 35`.
+    let radices = [0u8, 8, 34];
+    let mut seeds = Vec::new();
+
+    let mut trace = num_int_fmt_trace::<T>();
+    for &s in &["", "+", "-", "-0", "+-1", "0_0", "z", "Z", "\u{e9}", "\u{663}"] {
+        for &radix in &radices {
+            trace.ops.push(Op::Parse { s: Numeral(s.to_string()), radix });
+        }
+    }
+    for &x in &bounds {
+        for &radix in &radices {
+            trace.ops.push(Op::Reparse { x, radix, zeros: 15 });
+            trace.ops.push(Op::Overflow { x, radix, digit: 0 });
+            trace.ops.push(Op::Overflow { x, radix, digit: 1 });
+        }
+    }
+    seeds.push((format!("{}_parse", T::NAME), trace));
+
+    let mut trace = num_int_fmt_trace::<T>();
+    for &x in &bounds {
+        for &base in &["", "b", "o", "x", "X"] {
+            for &flags in &["", "+", "#", "0", "<", "*^+#0", "\u{e9}>"] {
+                let spec: Spec = format!("{{:{}40.3{}}}", flags, base).parse().expect("a valid spec");
+                trace.ops.push(Op::Format { x, spec });
+            }
+        }
+    }
+    seeds.push((format!("{}_format", T::NAME), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, int_fmt::encode_input(&trace)))
+        .collect()
+}
+
+/// Seeds for the `num_int_fmt` target
+///
+/// The target reads the header of `num_int` and then `num::int_fmt::Op` until
+/// its input runs out.
+fn num_int_fmt_seeds() -> Vec<Seed> {
+    let mut seeds = Vec::new();
+    seeds.extend(num_int_fmt_seeds_of::<u8>());
+    seeds.extend(num_int_fmt_seeds_of::<u16>());
+    seeds.extend(num_int_fmt_seeds_of::<u32>());
+    seeds.extend(num_int_fmt_seeds_of::<u64>());
+    seeds.extend(num_int_fmt_seeds_of::<u128>());
+    seeds.extend(num_int_fmt_seeds_of::<usize>());
+    seeds.extend(num_int_fmt_seeds_of::<i8>());
+    seeds.extend(num_int_fmt_seeds_of::<i16>());
+    seeds.extend(num_int_fmt_seeds_of::<i32>());
+    seeds.extend(num_int_fmt_seeds_of::<i64>());
+    seeds.extend(num_int_fmt_seeds_of::<i128>());
+    seeds.extend(num_int_fmt_seeds_of::<isize>());
+    seeds
+}
+
+/// An empty trace of the `num_float` target, of ops on `T`
+fn num_float_trace<T: Float>() -> Trace<float::Op<T>> {
+    Trace::new(Header {
+        target: float::TARGET.to_string(),
+        types: vec![T::NAME.to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `num_float` target, of ops on `T`
+///
+/// The edges are the least and greatest subnormals and normals, one, the
+/// floats either side of where `Debug` switches to an exponent, and the
+/// special values. Each is formatted, and the midpoints to their neighbours
+/// and the decimals just past them are parsed. Apart from those there are
+/// the special strings, some that nearly parse and decimals of many digits or
+/// huge exponents.
+fn num_float_seeds_of<T: Float>() -> Vec<Seed> {
+    type Op<T> = float::Op<T>;
+    let mantissa = 1u64 << T::MANTISSA_BITS;
+    let top = (1u64 << (T::BITS - T::MANTISSA_BITS - 1)) - 1;
+    let one = float::parse::<T>("1").expect("one parses").to_bits();
+    let low = float::parse::<T>("1e-4").expect("1e-4 parses").to_bits();
+    let high = float::parse::<T>("1e16").expect("1e16 parses").to_bits();
+    let mut edges: Vec<T> = [
+        0,
+        1,
+        mantissa - 1,
+        mantissa,
+        one,
+        low - 1,
+        low,
+        high - 1,
+        high,
+        (top - 1) * mantissa + mantissa - 1,
+        top * mantissa,
+        top * mantissa + 1,
+    ]
+    .iter()
+    .map(|&bits| T::from_bits(bits))
+    .collect();
+    let negated: Vec<T> = edges.iter().map(|&x| T::from_bits(x.to_bits() | 1 << (T::BITS - 1))).collect();
+    edges.extend(negated);
+    let mut seeds = Vec::new();
+
+    let mut trace = num_float_trace::<T>();
+    for &s in &["", ".", "-", "inf", "-Infinity", "NaN", "+.5e+3", "5.", "1e", "1e+", "1_0", "0x1", "infinit"] {
+        trace.ops.push(Op::Parse { s: float::Numeral(s.to_string()) });
+    }
+    let long = |head: &str, fill: &str| Digits(format!("{}{}", head, fill.repeat(float::MAX_DIGITS - head.len())));
+    for &exp in &[0, -324, 308, i16::MIN, i16::MAX] {
+        trace.ops.push(Op::Digits { digits: Digits("9007199254740993".to_string()), point: u16::MAX, exp });
+        trace.ops.push(Op::Digits { digits: long("1", "0"), point: 1, exp });
+        trace.ops.push(Op::Digits { digits: long("0", "9"), point: 1, exp });
+        trace.ops.push(Op::Digits { digits: long("2470328229206232720", "0"), point: 1, exp });
+    }
+    seeds.push((format!("{}_parse", T::NAME), trace));
+
+    let mut trace = num_float_trace::<T>();
+    for &x in &edges {
+        for &nudge in &[0, 1, 2, 94, 95] {
+            trace.ops.push(Op::Halfway { x, nudge });
+        }
+    }
+    seeds.push((format!("{}_halfway", T::NAME), trace));
+
+    let mut trace = num_float_trace::<T>();
+    for &x in &edges {
+        for &precision in &[0, 1, 17, u8::MAX] {
+            trace.ops.push(Op::Format { x, precision });
+        }
+    }
+    seeds.push((format!("{}_format", T::NAME), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, float::encode_input(&trace)))
+        .collect()
+}
+
+/// Seeds for the `num_float` target
+///
+/// After the encoding version and profile the target reads a byte choosing
+/// the float type and then `num::float::Op` until its input runs out.
+fn num_float_seeds() -> Vec<Seed> {
+    let mut seeds = num_float_seeds_of::<f32>();
+    seeds.extend(num_float_seeds_of::<f64>());
+    seeds
+}
+
+/// An empty trace of the `slice_sort` target
+fn slice_sort_trace() -> Trace<sort::Op> {
+    Trace::new(Header {
+        target: sort::TARGET.to_string(),
+        types: vec!["u8".to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `slice_sort` target
+///
+/// After the encoding version and profile the target reads `slice::sort::Op`
+/// until its input runs out. The keys come in the patterns sorts look out
+/// for -- runs up and down, few distinct keys, a sawtooth -- at the lengths
+/// either side of where sorts switch from sorting small slices by insertion.
+/// The chaos seeds answer every comparison the same, or in short cycles.
+fn slice_sort_seeds() -> Vec<Seed> {
+    type Op = sort::Op;
+    let key = |pattern: &str, i: usize| match pattern {
+        "ascending" => i as u8,
+        "descending" => u8::MAX - i as u8,
+        "equal" => 7,
+        "few" => (i % 3) as u8,
+        "sawtooth" => (i % 16) as u8 * 16,
+        _ => (i * 167 + 13) as u8,
+    };
+    let keys = |pattern: &str, len: usize| Bytes((0..len).map(|i| key(pattern, i)).collect());
+    let mut seeds = Vec::new();
+
+    for &name in &["ascending", "descending", "equal", "few", "sawtooth", "scattered"] {
+        let mut trace = slice_sort_trace();
+        for &len in &[0, 1, 2, 20, 21, 33, 255] {
+            let keys = keys(name, len);
+            trace.ops.push(Op::Sort { keys: keys.clone() });
+            trace.ops.push(Op::SortByKey { keys: keys.clone(), shift: 4 });
+            trace.ops.push(Op::SortByCachedKey { keys: keys.clone(), shift: 7 });
+            trace.ops.push(Op::SortUnstable { keys: keys.clone() });
+            trace.ops.push(Op::SelectNth { keys: keys.clone(), index: (len / 2) as u8 });
+            trace.ops.push(Op::BinarySearch { keys, key: 7 });
+        }
+        seeds.push((name.to_string(), trace));
+    }
+
+    for &(name, answers) in &[
+        ("chaos_less", &[0][..]),
+        ("chaos_greater", &[2]),
+        ("chaos_alternate", &[0, 2]),
+        ("chaos_cycle", &[0, 1, 2]),
+        ("chaos_mostly_less", &[0, 0, 0, 0, 0, 0, 0, 2]),
+    ] {
+        let answers = Bytes(answers.to_vec());
+        let mut trace = slice_sort_trace();
+        for &len in &[2, 20, 33, 255] {
+            let keys = keys("scattered", len);
+            trace.ops.push(Op::ChaosSort { keys: keys.clone(), answers: answers.clone() });
+            trace.ops.push(Op::ChaosSortUnstable { keys: keys.clone(), answers: answers.clone() });
+            let index = (len / 3) as u8;
+            trace.ops.push(Op::ChaosSelectNth { keys: keys.clone(), answers: answers.clone(), index });
+            trace.ops.push(Op::ChaosBinarySearch { keys, answers: answers.clone() });
+        }
+        seeds.push((name.to_string(), trace));
+    }
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, sort::encode_input(&trace)))
+        .collect()
+}
+
+/// An empty trace of the `slice_buffer` target
+fn slice_buffer_trace() -> Trace<buffer::Op> {
+    Trace::new(Header {
+        target: buffer::TARGET.to_string(),
+        types: vec!["u8".to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `slice_buffer` target
+///
+/// After the encoding version and profile the target reads `slice::buffer::Op`
+/// until its input runs out. Each seed fills the buffer to a length that some
+/// chunk sizes divide and others don't, then either rearranges it or drives
+/// every view of it through the same steps: from the front, from the back,
+/// alternating, and skipping by `nth` and `nth_back`.
+fn slice_buffer_seeds() -> Vec<Seed> {
+    type Op = buffer::Op;
+    let fill = |len: usize| Op::Extend {
+        bytes: Bytes((0..len).map(|i| (i * 37 + 5) as u8).collect()),
+    };
+    let mut seeds = Vec::new();
+
+    for &len in &[0, 1, 12, 13, 200] {
+        let mut trace = slice_buffer_trace();
+        trace.ops.push(fill(len));
+        for &(a, b) in &[(0, 0), (1, 5), (3, 11), (7, 200), (u16::MAX, 2)] {
+            trace.ops.push(Op::RotateLeft { mid: a });
+            trace.ops.push(Op::RotateRight { k: b });
+            trace.ops.push(Op::Reverse { start: a, end: b });
+            trace.ops.push(Op::Swap { a, b });
+            trace.ops.push(Op::CopyWithin { start: a, end: b, dest: a ^ b });
+            trace.ops.push(Op::Fill { start: b, end: a.wrapping_add(2), value: a as u8 });
+            trace.ops.push(Op::SplitAtMut { mid: b });
+            trace.ops.push(Op::Truncate { len: u16::MAX - a });
+        }
+        seeds.push((format!("rearrange_{}", len), trace));
+    }
+
+    for &(name, steps) in &[
+        ("front", &[][..]),
+        ("back", &[1, 1, 1, 1]),
+        ("alternate", &[0, 1, 0, 1, 0, 1]),
+        ("nth", &[2, 6, 10, 14]),
+        ("nth_back", &[3, 7, 11, 15]),
+        ("overshoot", &[2 | 63 << 2, 3 | 63 << 2]),
+    ] {
+        let steps = Bytes(steps.to_vec());
+        let mut trace = slice_buffer_trace();
+        for &len in &[0, 1, 12, 13, 200] {
+            trace.ops.push(Op::Truncate { len: 0 });
+            trace.ops.push(fill(len));
+            for &size in &[0, 1, 2, 3, 5, 12, 13, 255] {
+                trace.ops.push(Op::Chunks { size, steps: steps.clone() });
+                trace.ops.push(Op::ChunksExact { size, steps: steps.clone() });
+                trace.ops.push(Op::Rchunks { size, steps: steps.clone() });
+                trace.ops.push(Op::Windows { size, steps: steps.clone() });
+            }
+            for &modulus in &[0, 1, 3, 7] {
+                trace.ops.push(Op::Split { modulus, steps: steps.clone() });
+                trace.ops.push(Op::Rsplit { modulus, steps: steps.clone() });
+                trace.ops.push(Op::Splitn { modulus, n: modulus });
+            }
+        }
+        seeds.push((format!("view_{}", name), trace));
+    }
+
+    let mut trace = slice_buffer_trace();
+    for &len in &[0, 1, 13] {
+        trace.ops.push(Op::Truncate { len: 0 });
+        trace.ops.push(fill(len));
+        trace.ops.push(Op::Join { size: 2, sep: Bytes(vec![]) });
+        trace.ops.push(Op::Join { size: 4, sep: Bytes(vec![0, 1]) });
+        for &x in &[0, 5, 42] {
+            trace.ops.push(Op::Contains { x });
+        }
+        for &(n, flip) in &[(0, 0), (1, 0), (5, 1), (13, 0), (14, 0), (u16::MAX, 3)] {
+            trace.ops.push(Op::StartsWith { len: n, flip });
+            trace.ops.push(Op::EndsWith { len: n, flip });
+        }
+    }
+    seeds.push(("query".to_string(), trace));
+
+    seeds
+        .into_iter()
+        .map(|(name, trace)| Seed::from_bytes(name, buffer::encode_input(&trace)))
+        .collect()
+}
+
+/// An empty trace of the `iter` target
+fn iter_trace() -> Trace<iter::Op> {
+    Trace::new(Header {
+        target: iter::TARGET.to_string(),
+        types: vec!["u8".to_string()],
+        capacity: 0,
+        hash_seed: None,
+    })
+}
+
+/// Seeds for the `iter` target
+///
+/// After the encoding version and profile the target reads `iter::Op` until
+/// its input runs out. Each seed builds pipelines that are exact in size,
+/// only double-ended, or neither, and drives each from the front, from the
+/// back, both ways in turn, by `nth` and `nth_back`, and by `fold` and `rfold`.
+fn iter_seeds() -> Vec<Seed> {
+    type Op = iter::Op;
+    let items = |len: usize| Bytes((0..len).map(|i| (i * 29 + 3) as u8).collect());
+    let pipelines: Vec<(&str, Vec<Op>)> = vec![
+        ("exact", vec![Op::Map { add: 7 }, Op::Skip { n: 3 }, Op::StepBy { step: 2 }, Op::Take { n: 40 }]),
+        ("zip", vec![Op::Zip { items: items(50) }, Op::Enumerate, Op::Rev, Op::Peekable { peeks: 1 }]),
+        ("peeked", vec![Op::Peekable { peeks: 2 }, Op::Skip { n: 1 }, Op::Peekable { peeks: 1 }, Op::Fuse]),
+        ("double", vec![Op::Filter { modulus: 1 }, Op::Chain { items: items(9) }, Op::Rev, Op::Flatten { modulus: 2 }]),
+        ("forward", vec![Op::SkipWhile { modulus: 0 }, Op::Take { n: 30 }, Op::StepBy { step: 3 }, Op::Enumerate]),
+        ("mixed", vec![Op::Chain { items: items(5) }, Op::Skip { n: 2 }, Op::Filter { modulus: 3 }, Op::Peekable { peeks: 1 }]),
+    ];
+    let drives: Vec<(&str, Vec<Op>)> = vec![
+        ("front", vec![Op::Next; 8]),
+        ("back", vec![Op::NextBack; 8]),
+        ("both", vec![Op::Next, Op::NextBack, Op::Next, Op::NextBack, Op::Nth { n: 1 }, Op::NthBack { n: 1 }]),
+        ("nth", vec![Op::Nth { n: 0 }, Op::Nth { n: 2 }, Op::NthBack { n: 3 }, Op::Nth { n: 200 }]),
+        ("fold", vec![Op::Next, Op::Fold]),
+        ("rfold", vec![Op::NextBack, Op::Rfold]),
+    ];
+    let mut seeds = Vec::new();
+    for (name, stages) in &pipelines {
+        for (drive, steps) in &drives {
+            let mut trace = iter_trace();
+            for &len in &[0, 1, 7, 64, 255] {
+                trace.ops.push(Op::Source { items: items(len) });
+                trace.ops.extend(stages.iter().cloned());
+                trace.ops.extend(steps.iter().cloned());
+                trace.ops.push(Op::Fold);
+            }
+            seeds.push(Seed::from_bytes(format!("{}_{}", name, drive), iter::encode_input(&trace)));
+        }
+    }
+    seeds
+}
+
+/// Seeds for the `cell` target
+///
+/// After the encoding version and profile the target reads `cell::Op` until
+/// its input runs out. Each seed walks the borrow flag through one of its
+/// shapes: many readers, one writer, writers split by `map_split`, and
+/// readers outliving the `Ref` they were cloned from, with `replace` and
+/// `take` tried while borrowed and after.
+fn cell_seeds() -> Vec<Seed> {
+    type Op = cell::Op;
+    let scenarios: Vec<(&str, Vec<Op>)> = vec![
+        (
+            "readers",
+            vec![
+                Op::TryBorrow,
+                Op::TryBorrow,
+                Op::TryBorrowMut,
+                Op::Take,
+                Op::Drop { idx: 0 },
+                Op::TryBorrowMut,
+                Op::Drop { idx: 0 },
+                Op::TryBorrowMut,
+            ],
+        ),
+        (
+            "writer",
+            vec![
+                Op::TryBorrowMut,
+                Op::Write { idx: 0, side: 0, value: 5 },
+                Op::Write { idx: 0, side: 1, value: 6 },
+                Op::TryBorrow,
+                Op::Replace { left: 1, right: 2 },
+                Op::Drop { idx: 0 },
+                Op::Replace { left: 1, right: 2 },
+                Op::TryBorrow,
+            ],
+        ),
+        (
+            "split_writers",
+            vec![
+                Op::Replace { left: 9, right: 8 },
+                Op::TryBorrowMut,
+                Op::MapSplit { idx: 0 },
+                Op::Write { idx: 0, side: 0, value: 1 },
+                Op::Write { idx: 1, side: 0, value: 2 },
+                Op::Drop { idx: 1 },
+                Op::TryBorrow,
+                Op::Map { idx: 0, side: 1 },
+                Op::Drop { idx: 0 },
+                Op::Take,
+            ],
+        ),
+        (
+            "split_readers",
+            vec![
+                Op::TryBorrow,
+                Op::MapSplit { idx: 0 },
+                Op::CloneRef { idx: 1 },
+                Op::Drop { idx: 0 },
+                Op::TryBorrowMut,
+                Op::Drop { idx: 0 },
+                Op::Drop { idx: 0 },
+                Op::TryBorrowMut,
+            ],
+        ),
+        (
+            "cloned_readers",
+            vec![
+                Op::Replace { left: 3, right: 4 },
+                Op::TryBorrow,
+                Op::Map { idx: 0, side: 1 },
+                Op::CloneRef { idx: 0 },
+                Op::CloneRef { idx: 1 },
+                Op::Drop { idx: 0 },
+                Op::TryBorrowMut,
+                Op::Drop { idx: 1 },
+                Op::Drop { idx: 0 },
+                Op::TryBorrowMut,
+            ],
+        ),
+    ];
+    scenarios
+        .into_iter()
+        .map(|(name, ops)| {
+            let mut trace = Trace::new(Header {
+                target: cell::TARGET.to_string(),
+                types: vec!["u8".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            Seed::from_bytes(name, cell::encode_input(&trace))
+        })
+        .collect()
+}
+
+/// Seeds for the `rc` target
+///
+/// After the encoding version and profile the target reads `rc::Op` until
+/// its input runs out. Each seed takes an allocation through one of the ways
+/// its value goes: dropped with the last `Rc` while `Weak`s linger, cloned
+/// or moved away by `make_mut`, or moved out by `try_unwrap` and
+/// `into_inner`.
+fn rc_seeds() -> Vec<Seed> {
+    type Op = rc::Op;
+    let scenarios: Vec<(&str, Vec<Op>)> = vec![
+        (
+            "lingering_weak",
+            vec![
+                Op::New { byte: 1 },
+                Op::Downgrade { idx: 0 },
+                Op::Clone { idx: 1 },
+                Op::Upgrade { idx: 1 },
+                Op::Drop { idx: 0 },
+                Op::Drop { idx: 2 },
+                Op::Upgrade { idx: 0 },
+                Op::PtrEq { a: 0, b: 1 },
+            ],
+        ),
+        (
+            "make_mut_clones",
+            vec![
+                Op::New { byte: 2 },
+                Op::Clone { idx: 0 },
+                Op::GetMut { idx: 0, byte: 3 },
+                Op::MakeMut { idx: 0, byte: 4 },
+                Op::PtrEq { a: 0, b: 1 },
+                Op::GetMut { idx: 0, byte: 5 },
+                Op::Drop { idx: 1 },
+            ],
+        ),
+        (
+            "make_mut_moves",
+            vec![
+                Op::New { byte: 6 },
+                Op::Downgrade { idx: 0 },
+                Op::MakeMut { idx: 0, byte: 7 },
+                Op::Upgrade { idx: 1 },
+                Op::PtrEq { a: 0, b: 1 },
+                Op::Drop { idx: 0 },
+            ],
+        ),
+        (
+            "unwrap",
+            vec![
+                Op::NewCyclic { byte: 8 },
+                Op::Clone { idx: 0 },
+                Op::TryUnwrap { idx: 0 },
+                Op::IntoInner { idx: 0 },
+                Op::TryUnwrap { idx: 1 },
+                Op::Upgrade { idx: 0 },
+                Op::New { byte: 9 },
+                Op::IntoInner { idx: 1 },
+            ],
+        ),
+    ];
+    scenarios
+        .into_iter()
+        .map(|(name, ops)| {
+            let mut trace = Trace::new(Header {
+                target: rc::TARGET.to_string(),
+                types: vec!["u8".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            Seed::from_bytes(name, rc::encode_input(&trace))
+        })
+        .collect()
+}
+
+/// Seeds for the `sync_arc` target
+///
+/// After the encoding version and profile the target reads `sync::arc::Op`
+/// until its input runs out. Each seed hands an allocation to several threads
+/// and then has them race: to upgrade while the last `Arc` is dropped, to
+/// `make_mut` a value others share, or to drop their handles one by one.
+fn sync_arc_seeds() -> Vec<Seed> {
+    type Op = arc::Op;
+    let share = |weak: bool| {
+        let mut ops = vec![Op::New { thread: 0, byte: 1 }];
+        for to in 1..THREADS as u8 {
+            ops.push(if weak {
+                Op::Downgrade { thread: 0, idx: 0 }
+            } else {
+                Op::Clone { thread: 0, idx: 0 }
+            });
+            ops.push(Op::Give { thread: 0, idx: 1, to });
+        }
+        ops.push(Op::Sync);
+        ops
+    };
+    let mut scenarios: Vec<(&str, Vec<Op>)> = Vec::new();
+    let mut ops = share(true);
+    for thread in 1..THREADS as u8 {
+        ops.push(Op::Upgrade { thread, idx: 0 });
+        ops.push(Op::MakeMut { thread, idx: 1, byte: thread });
+    }
+    ops.push(Op::Drop { thread: 0, idx: 0 });
+    scenarios.push(("upgrade_races_drop", ops));
+    let mut ops = share(false);
+    for thread in 0..THREADS as u8 {
+        ops.push(Op::MakeMut { thread, idx: 0, byte: thread });
+        ops.push(Op::Downgrade { thread, idx: 0 });
+        ops.push(Op::Drop { thread, idx: 0 });
+    }
+    ops.push(Op::Sync);
+    for thread in 0..THREADS as u8 {
+        ops.push(Op::Upgrade { thread, idx: 0 });
+    }
+    scenarios.push(("make_mut_races", ops));
+    let mut ops = share(false);
+    for thread in 0..THREADS as u8 {
+        ops.push(Op::Downgrade { thread, idx: 0 });
+        ops.push(Op::Give { thread, idx: 0, to: thread + 1 });
+        ops.push(Op::Drop { thread, idx: 0 });
+    }
+    scenarios.push(("pass_the_parcel", ops));
+    scenarios
+        .into_iter()
+        .map(|(name, ops)| {
+            let mut trace = Trace::new(Header {
+                target: arc::TARGET.to_string(),
+                types: vec!["u8".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            Seed::from_bytes(name, arc::encode_input(&trace))
+        })
+        .collect()
+}
+
+/// Seeds for the `sync_atomic` target
+///
+/// After the encoding version and profile the target reads
+/// `sync::atomic::Op` until its input runs out. Each seed has all threads
+/// run side by side, round after round, with ops that make the order they
+/// ran in show: `compare_exchange` on values the others store, or `swap` and
+/// `fetch_add`.
+fn sync_atomic_seeds() -> Vec<Seed> {
+    type Op = atomic::Op;
+    let interleave = |op: &dyn Fn(u8, u8) -> Op| {
+        let mut ops = Vec::new();
+        for round in 0..8 {
+            for thread in 0..THREADS as u8 {
+                ops.push(op(thread, round));
+                ops.push(Op::Load { thread });
+            }
+            ops.push(Op::Sync);
+        }
+        ops
+    };
+    let scenarios: Vec<(&str, Vec<Op>)> = vec![
+        (
+            "exchange_chain",
+            interleave(&|thread, round| Op::CompareExchange {
+                thread,
+                current: round.wrapping_add(thread),
+                new: round.wrapping_add(thread + 1),
+            }),
+        ),
+        ("swap", interleave(&|thread, round| Op::Swap { thread, val: round ^ thread })),
+        ("fetch_add", interleave(&|thread, _| Op::FetchAdd { thread, val: 1 << thread })),
+    ];
+    scenarios
+        .into_iter()
+        .map(|(name, ops)| {
+            let mut trace = Trace::new(Header {
+                target: atomic::TARGET.to_string(),
+                types: vec!["usize".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            Seed::from_bytes(name, atomic::encode_input(&trace))
+        })
+        .collect()
+}
+
+/// Seeds for the `sync_mutex` target
+///
+/// After the encoding version and profile the target reads
+/// `sync::mutex::Op` until its input runs out. Each seed has all threads
+/// run side by side, round after round, to pop the values the others push,
+/// at the other end or the same one.
+fn sync_mutex_seeds() -> Vec<Seed> {
+    type Op = mutex::Op;
+    let interleave = |ends: &dyn Fn(u8, u8) -> [Op; 2]| {
+        let mut ops = Vec::new();
+        for round in 0..8 {
+            for thread in 0..THREADS as u8 {
+                ops.extend(ends(thread, round).iter().cloned());
+                ops.push(Op::Len { thread });
+            }
+            ops.push(Op::Sync);
+        }
+        ops
+    };
+    let scenarios: Vec<(&str, Vec<Op>)> = vec![
+        (
+            "queue",
+            interleave(&|thread, round| {
+                [
+                    Op::PushBack { thread, value: round },
+                    Op::PopFront { thread: thread + 1 },
+                ]
+            }),
+        ),
+        (
+            "stack",
+            interleave(&|thread, round| {
+                [
+                    Op::PushFront { thread, value: round },
+                    Op::PopFront { thread: thread + 1 },
+                ]
+            }),
+        ),
+        (
+            "both_ends",
+            interleave(&|thread, round| {
+                [
+                    Op::PushFront { thread, value: round },
+                    Op::PopBack { thread },
+                ]
+            }),
+        ),
+    ];
+    scenarios
+        .into_iter()
+        .map(|(name, ops)| {
+            let mut trace = Trace::new(Header {
+                target: mutex::TARGET.to_string(),
+                types: vec!["u8".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            Seed::from_bytes(name, mutex::encode_input(&trace))
+        })
+        .collect()
+}
+
+/// Seeds for the `sync_rwlock` target
+///
+/// After the encoding version and profile the target reads
+/// `sync::rwlock::Op` until its input runs out. Each seed has all threads
+/// read a few keys, round after round, while one or all of them write.
+fn sync_rwlock_seeds() -> Vec<Seed> {
+    type Op = rwlock::Op;
+    let interleave = |writers: u8| {
+        let mut ops = Vec::new();
+        for round in 0..8 {
+            for thread in 0..THREADS as u8 {
+                let key = (round + thread) & 3;
+                if thread < writers {
+                    ops.push(Op::Insert { thread, key, value: round });
+                    ops.push(Op::Remove { thread, key: key ^ 1 });
+                }
+                ops.push(Op::Get { thread, key });
+                ops.push(Op::Get { thread, key: key ^ 1 });
+                ops.push(Op::Len { thread });
+            }
+            ops.push(Op::Sync);
+        }
+        ops
+    };
+    let scenarios: Vec<(&str, Vec<Op>)> = vec![
+        ("one_writer", interleave(1)),
+        ("all_write", interleave(THREADS as u8)),
+    ];
+    scenarios
+        .into_iter()
+        .map(|(name, ops)| {
+            let mut trace = Trace::new(Header {
+                target: rwlock::TARGET.to_string(),
+                types: vec!["u8".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            Seed::from_bytes(name, rwlock::encode_input(&trace))
+        })
+        .collect()
+}
+
+/// Seeds for the `sync_mpsc` target
+///
+/// After the encoding version and profile the target reads `sync::mpsc::Op`
+/// until its input runs out. Each seed has the threads send, round after
+/// round, while the first receives: keeping up with them, or leaving values
+/// in flight from round to round.
+fn sync_mpsc_seeds() -> Vec<Seed> {
+    type Op = mpsc::Op;
+    let interleave = |receives: usize| {
+        let mut ops = Vec::new();
+        for round in 0..8 {
+            for thread in 0..THREADS as u8 {
+                ops.push(Op::Send { thread, value: round });
+                ops.push(Op::Send { thread, value: thread });
+                ops.extend(vec![Op::TryRecv; receives]);
+            }
+            ops.push(Op::Sync);
+        }
+        ops
+    };
+    let scenarios: Vec<(&str, Vec<Op>)> = vec![
+        ("keep_up", interleave(3)),
+        ("fall_behind", interleave(1)),
+    ];
+    scenarios
+        .into_iter()
+        .map(|(name, ops)| {
+            let mut trace = Trace::new(Header {
+                target: mpsc::TARGET.to_string(),
+                types: vec!["u8".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            Seed::from_bytes(name, mpsc::encode_input(&trace))
+        })
+        .collect()
+}
+
+/// Seeds for the `str_repeat` target
+///
+/// The target reads a `Vec<u8>` -- which it skips unless it's valid UTF-8 --
+/// and a `u16` count of repeats.
+fn str_repeat_seeds() -> Vec<Seed> {
+    let strs: &[(&str, &str)] = &[
+        ("empty", ""),
+        ("ascii", "bughunt"),
+        ("two_byte", "\u{e9}t\u{e9}"),
+        ("three_byte", "\u{2603}\u{2604}"),
+        ("four_byte", "\u{1f41b}"),
+    ];
+    let mut seeds = Vec::new();
+    for &(name, s) in strs {
+        for &repeats in &[0u16, 1, 2, 255, 4096, u16::MAX] {
+            let mut seed = Seed::new(format!("{}_x{}", name, repeats));
+            seed.push(&s.as_bytes().to_vec()).push(&repeats);
+            seeds.push(seed);
+        }
+    }
+    seeds
+}
+
+fn write_seeds(root: &Path, target: &str, seeds: &[Seed]) -> io::Result<()> {
+    let dir = root.join(target);
+    fs::create_dir_all(&dir)?;
+    for seed in seeds {
+        fs::write(dir.join(&seed.name), &seed.bytes)?;
+    }
+    println!("{}: wrote {} seeds to {}", target, seeds.len(), dir.display());
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let root: PathBuf = env::args_os()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("fuzz/corpus"));
+
+    // The hashbrown and indexmap targets share the input of `hash_map`.
+    write_seeds(&root, "hash_map", &hash_map_seeds())?;
+    write_seeds(&root, "hash_map_hashbrown", &hash_map_seeds())?;
+    write_seeds(&root, "hash_map_indexmap", &hash_map_seeds())?;
+    // Likewise the other sequence targets share the input of `vec_deque`.
+    write_seeds(&root, "vec_deque", &vec_deque_seeds())?;
+    write_seeds(&root, "vec_deque_smallvec", &vec_deque_seeds())?;
+    write_seeds(&root, "vec_deque_arrayvec", &vec_deque_seeds())?;
+    write_seeds(&root, "vec_deque_im", &vec_deque_seeds())?;
+    write_seeds(&root, "vec_deque_three_way", &vec_deque_seeds())?;
+    write_seeds(&root, "string", &string_seeds())?;
+    write_seeds(&root, "str_repeat", &str_repeat_seeds())?;
+    write_seeds(&root, "str_search", &str_search_seeds())?;
+    write_seeds(&root, "str_from_utf8", &str_from_utf8_seeds())?;
+    write_seeds(&root, "char_unicode", &char_unicode_seeds())?;
+    write_seeds(&root, "char_utf16", &char_utf16_seeds())?;
+    write_seeds(&root, "num_int", &num_int_seeds())?;
+    write_seeds(&root, "num_int_fmt", &num_int_fmt_seeds())?;
+    write_seeds(&root, "num_float", &num_float_seeds())?;
+    write_seeds(&root, "slice_sort", &slice_sort_seeds())?;
+    write_seeds(&root, "slice_buffer", &slice_buffer_seeds())?;
+    write_seeds(&root, "iter", &iter_seeds())?;
+    write_seeds(&root, "cell", &cell_seeds())?;
+    write_seeds(&root, "rc", &rc_seeds())?;
+    write_seeds(&root, "sync_arc", &sync_arc_seeds())?;
+    write_seeds(&root, "sync_atomic", &sync_atomic_seeds())?;
+    write_seeds(&root, "sync_mutex", &sync_mutex_seeds())?;
+    write_seeds(&root, "sync_rwlock", &sync_rwlock_seeds())?;
+    write_seeds(&root, "sync_mpsc", &sync_mpsc_seeds())?;
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! Record and replay op histories as text traces
+//!
+//! A crash input found by `cargo fuzz` is a blob of bytes whose meaning
+//! depends on the exact decoding of the target's `Op` type. This program
+//! converts such an input into a text trace -- see the `trace` module for the
+//! format -- and replays traces against the model and the system under test.
+//!
+//! ```text
+//! > cargo run --bin trace record hash_map fuzz/artifacts/hash_map/crash-...
+//! > cargo run --bin trace replay reproducer.trace
+//! ```
+extern crate bughunt_rust;
+extern crate hashbrown;
+extern crate im;
+extern crate indexmap;
+
+use bughunt_rust::stdlib::cell;
+use bughunt_rust::stdlib::char::{unicode, utf16};
+use bughunt_rust::stdlib::collections::hash_map::{self, BuildTrulyAwfulHasher, Map};
+use bughunt_rust::stdlib::collections::vec_deque::{self, ArrayDeque, Deque, SmallVecDeque};
+use bughunt_rust::stdlib::iter;
+use bughunt_rust::stdlib::num::{float, int, int_fmt};
+use bughunt_rust::stdlib::rc;
+use bughunt_rust::stdlib::slice::{buffer, sort};
+use bughunt_rust::stdlib::str::{search, utf8};
+use bughunt_rust::stdlib::string;
+use bughunt_rust::stdlib::sync::{arc, atomic, mpsc, mutex, rwlock};
+use bughunt_rust::trace::{self, Header, Trace};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+
+type HashbrownMap = hashbrown::HashMap<u16, u16, BuildTrulyAwfulHasher>;
+type IndexMap = indexmap::IndexMap<u16, u16, BuildTrulyAwfulHasher>;
+type SmallVec = SmallVecDeque<u8>;
+type ArrayVec = ArrayDeque<u8>;
+type ImVector = im::Vector<u8>;
+
+const USAGE: &str = "usage:
+    trace record <target> <input>    print the trace of a fuzz input
+    trace replay <trace>             replay a trace against model and SUT";
+
+/// Fail unless the element types named in `header` are `expected`
+fn check_types(header: &Header, expected: &[&str]) -> Result<(), Box<dyn Error>> {
+    if header.types.iter().map(String::as_str).eq(expected.iter().cloned()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} traces must have types {}, not {}",
+            header.target,
+            expected.join(","),
+            header.types.join(",")
+        )
+        .into())
+    }
+}
+
+fn record(target: &str, input: &str) -> Result<(), Box<dyn Error>> {
+    let data = fs::read(input)?;
+    let trace = match target {
+        // Every map target reads the input of `hash_map`.
+        hash_map::TARGET | HashbrownMap::TARGET | IndexMap::TARGET => {
+            hash_map::decode_input(&data).map(|mut t| {
+                t.header.target = target.to_string();
+                t.to_string()
+            })
+        }
+        // Every sequence target reads the input of `vec_deque`.
+        vec_deque::TARGET
+        | vec_deque::THREE_WAY_TARGET
+        | SmallVec::TARGET
+        | ArrayVec::TARGET
+        | ImVector::TARGET => {
+            vec_deque::decode_input(&data).map(|mut t| {
+                t.header.target = target.to_string();
+                t.to_string()
+            })
+        }
+        string::TARGET => string::decode_input(&data).map(|t| t.to_string()),
+        search::TARGET => search::decode_input(&data).map(|t| t.to_string()),
+        utf8::TARGET => utf8::decode_input(&data).map(|t| t.to_string()),
+        unicode::TARGET => unicode::decode_input(&data).map(|t| t.to_string()),
+        utf16::TARGET => utf16::decode_input(&data).map(|t| t.to_string()),
+        int::TARGET => int::record(&data),
+        int_fmt::TARGET => int_fmt::record(&data),
+        float::TARGET => float::record(&data),
+        sort::TARGET => sort::decode_input(&data).map(|t| t.to_string()),
+        buffer::TARGET => buffer::decode_input(&data).map(|t| t.to_string()),
+        iter::TARGET => iter::decode_input(&data).map(|t| t.to_string()),
+        cell::TARGET => cell::decode_input(&data).map(|t| t.to_string()),
+        rc::TARGET => rc::decode_input(&data).map(|t| t.to_string()),
+        arc::TARGET => arc::decode_input(&data).map(|t| t.to_string()),
+        atomic::TARGET => atomic::decode_input(&data).map(|t| t.to_string()),
+        mutex::TARGET => mutex::decode_input(&data).map(|t| t.to_string()),
+        rwlock::TARGET => rwlock::decode_input(&data).map(|t| t.to_string()),
+        mpsc::TARGET => mpsc::decode_input(&data).map(|t| t.to_string()),
+        _ => return Err(format!("unknown target '{}'", target).into()),
+    };
+    match trace {
+        Some(trace) => {
+            print!("{}", trace);
+            Ok(())
+        }
+        None => Err(format!("input too short for target '{}'", target).into()),
+    }
+}
+
+fn replay(path: &str) -> Result<(), Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let header = trace::header(&text)?;
+    let ops = match header.target.as_str() {
+        target @ hash_map::TARGET
+        | target @ HashbrownMap::TARGET
+        | target @ IndexMap::TARGET => {
+            check_types(&header, &["u16", "u16"])?;
+            let trace: Trace<hash_map::Op<u16, u16>> = text.parse()?;
+            match target {
+                HashbrownMap::TARGET => hash_map::replay_map::<HashbrownMap>(&trace),
+                IndexMap::TARGET => hash_map::replay_map::<IndexMap>(&trace),
+                _ => hash_map::replay(&trace),
+            }
+            trace.ops.len()
+        }
+        target @ vec_deque::TARGET
+        | target @ vec_deque::THREE_WAY_TARGET
+        | target @ SmallVec::TARGET
+        | target @ ArrayVec::TARGET
+        | target @ ImVector::TARGET => {
+            check_types(&header, &["u8"])?;
+            let trace: Trace<vec_deque::Op<u8>> = text.parse()?;
+            match target {
+                SmallVec::TARGET => vec_deque::replay_deque::<SmallVec>(&trace),
+                ArrayVec::TARGET => vec_deque::replay_deque::<ArrayVec>(&trace),
+                ImVector::TARGET => vec_deque::replay_deque::<ImVector>(&trace),
+                vec_deque::THREE_WAY_TARGET => vec_deque::replay_three_way::<SmallVec>(&trace),
+                _ => vec_deque::replay(&trace),
+            }
+            trace.ops.len()
+        }
+        string::TARGET => {
+            check_types(&header, &["char"])?;
+            let trace: Trace<string::Op> = text.parse()?;
+            string::replay(&trace);
+            trace.ops.len()
+        }
+        search::TARGET => {
+            check_types(&header, &["char"])?;
+            let trace: Trace<search::Op> = text.parse()?;
+            search::replay(&trace);
+            trace.ops.len()
+        }
+        utf8::TARGET => {
+            check_types(&header, &["u8"])?;
+            let trace: Trace<utf8::Op> = text.parse()?;
+            utf8::replay(&trace);
+            trace.ops.len()
+        }
+        unicode::TARGET => {
+            check_types(&header, &["char"])?;
+            let trace: Trace<unicode::Op> = text.parse()?;
+            unicode::replay(&trace)?;
+            trace.ops.len()
+        }
+        utf16::TARGET => {
+            check_types(&header, &["u16"])?;
+            let trace: Trace<utf16::Op> = text.parse()?;
+            utf16::replay(&trace);
+            trace.ops.len()
+        }
+        // The type of a `num_int`, `num_int_fmt` or `num_float` trace is
+        // whichever its header names.
+        int::TARGET => int::replay_text(&text)?,
+        int_fmt::TARGET => int_fmt::replay_text(&text)?,
+        float::TARGET => float::replay_text(&text)?,
+        sort::TARGET => {
+            let trace: Trace<sort::Op> = text.parse()?;
+            sort::replay(&trace);
+            trace.ops.len()
+        }
+        buffer::TARGET => {
+            let trace: Trace<buffer::Op> = text.parse()?;
+            buffer::replay(&trace);
+            trace.ops.len()
+        }
+        iter::TARGET => {
+            let trace: Trace<iter::Op> = text.parse()?;
+            iter::replay(&trace);
+            trace.ops.len()
+        }
+        cell::TARGET => {
+            let trace: Trace<cell::Op> = text.parse()?;
+            cell::replay(&trace);
+            trace.ops.len()
+        }
+        rc::TARGET => {
+            let trace: Trace<rc::Op> = text.parse()?;
+            rc::replay(&trace);
+            trace.ops.len()
+        }
+        arc::TARGET => {
+            let trace: Trace<arc::Op> = text.parse()?;
+            arc::replay(&trace);
+            trace.ops.len()
+        }
+        atomic::TARGET => {
+            let trace: Trace<atomic::Op> = text.parse()?;
+            atomic::replay(&trace);
+            trace.ops.len()
+        }
+        mutex::TARGET => {
+            let trace: Trace<mutex::Op> = text.parse()?;
+            mutex::replay(&trace);
+            trace.ops.len()
+        }
+        rwlock::TARGET => {
+            let trace: Trace<rwlock::Op> = text.parse()?;
+            rwlock::replay(&trace);
+            trace.ops.len()
+        }
+        mpsc::TARGET => {
+            let trace: Trace<mpsc::Op> = text.parse()?;
+            mpsc::replay(&trace);
+            trace.ops.len()
+        }
+        target => return Err(format!("unknown target '{}'", target).into()),
+    };
+    println!("{}: replayed {} ops without divergence", header.target, ops);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let res = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["record", target, input] => record(target, input),
+        ["replay", path] => replay(path),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = res {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,109 @@
+//! Three-way differential checks
+//!
+//! When a model and the system under test disagree it's not obvious which of
+//! the two is wrong. Running a third, independent implementation alongside
+//! them settles it, most of the time: whichever side disagrees with the other
+//! two is the odd one out and the first place to look.
+//!
+//! Harnesses make three-way checks through the `check_agree!` macro, which
+//! counts the assertion site in [`stats`] like `check!` does.
+//!
+//! [`stats`]: ../stats/index.html
+use std::fmt::{self, Debug};
+
+/// Assert that model, system under test and alternative all agree
+///
+/// Takes the three values followed by a format string, and its arguments,
+/// describing what was compared. On disagreement this panics naming the odd
+/// one out.
+macro_rules! check_agree {
+    ($model:expr, $sut:expr, $alt:expr, $($what:tt)+) => {{
+        ::stats::assert_site(concat!(file!(), ":", line!()));
+        if let Some(report) = ::diff::disagreement(&$model, &$sut, &$alt) {
+            panic!("{}: {}", format!($($what)+), report);
+        }
+    }};
+}
+
+/// One of the three sides of a three-way check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// The model
+    Model,
+    /// The system under test
+    Sut,
+    /// The alternative implementation
+    Alt,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Side::Model => "the model",
+            Side::Sut => "the system under test",
+            Side::Alt => "the alternative implementation",
+        })
+    }
+}
+
+/// The outcome of comparing three values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// All three agree
+    Agree,
+    /// Two agree and the given side does not
+    Odd(Side),
+    /// No two agree
+    AllDiffer,
+}
+
+/// Compare the values of model, system under test and alternative
+pub fn judge<T>(model: &T, sut: &T, alt: &T) -> Verdict
+where
+    T: PartialEq + ?Sized,
+{
+    match (model == sut, model == alt, sut == alt) {
+        (true, true, _) => Verdict::Agree,
+        (true, false, _) => Verdict::Odd(Side::Alt),
+        (false, true, _) => Verdict::Odd(Side::Sut),
+        (false, false, true) => Verdict::Odd(Side::Model),
+        (false, false, false) => Verdict::AllDiffer,
+    }
+}
+
+/// Describe the disagreement of three values, or `None` if they agree
+pub fn disagreement<T>(model: &T, sut: &T, alt: &T) -> Option<String>
+where
+    T: PartialEq + Debug + ?Sized,
+{
+    let verdict = match judge(model, sut, alt) {
+        Verdict::Agree => return None,
+        Verdict::Odd(side) => format!("{} disagrees with the other two", side),
+        Verdict::AllDiffer => "all three disagree".to_string(),
+    };
+    Some(format!(
+        "{}\n  model: {:?}\n  sut:   {:?}\n  alt:   {:?}",
+        verdict, model, sut, alt
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn judge_finds_odd_one_out() {
+        assert_eq!(Verdict::Agree, judge(&1, &1, &1));
+        assert_eq!(Verdict::Odd(Side::Model), judge(&0, &1, &1));
+        assert_eq!(Verdict::Odd(Side::Sut), judge(&1, &0, &1));
+        assert_eq!(Verdict::Odd(Side::Alt), judge(&1, &1, &0));
+        assert_eq!(Verdict::AllDiffer, judge(&0, &1, &2));
+    }
+
+    #[test]
+    fn agreement_has_no_report() {
+        assert_eq!(None, disagreement(&Some(3), &Some(3), &Some(3)));
+        let report = disagreement(&Some(3), &None, &Some(3)).expect("sut disagrees");
+        assert!(report.starts_with("the system under test disagrees"));
+    }
+}
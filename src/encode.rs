@@ -0,0 +1,82 @@
+//! Encoding of values into fuzz input bytes
+//!
+//! Our fuzz targets decode their input with [`arbitrary`]. The [`Encode`] trait
+//! runs the other way: it writes out the bytes that, when decoded by the
+//! matching `Arbitrary` implementation, produce the original value again. This
+//! lets us build inputs -- seed corpora, mostly -- from a description of the
+//! operations we'd like a target to perform rather than by hand.
+use std::mem;
+
+/// The version of the input encoding, the first byte of every input
+///
+/// Inputs of the collection targets begin with this byte. Each `Op` variant is
+/// then identified by a fixed tag, so adding a variant leaves the meaning of
+/// existing inputs alone. Inputs from before versioning -- which have no
+/// version byte and chose a variant by a byte modulo the number of variants --
+/// can be rewritten with the `migrate_corpus` program.
+///
+/// Version 2 added a profile byte after the version, choosing how the target
+/// weighs its ops. See [`op::Profiles`]. The `hash_map*` and `vec_deque*`
+/// targets, whose inputs are older, still read version 1 inputs, with ops
+/// decoded by tag. All others read version 2 alone, see [`op::decode_input`].
+///
+/// [`op::Profiles`]: ../op/struct.Profiles.html
+/// [`op::decode_input`]: ../op/fn.decode_input.html
+pub const VERSION: u8 = 2;
+
+/// Encode a value into the bytes its `Arbitrary` implementation decodes
+///
+/// Implementations must agree exactly with the decoding done by the
+/// `Arbitrary` implementation of the same type. If they do not the seeds we
+/// generate will decode into something other than what we asked for, quietly.
+pub trait Encode {
+    /// Append the encoding of `self` to `buf`
+    fn encode(&self, buf: &mut Vec<u8>);
+}
+
+// `arbitrary` 0.2 builds integers by filling a buffer of `size_of` bytes and
+// reading them least significant byte first.
+macro_rules! impl_encode_for_integers {
+    ( $( $ty:ty ),* ) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    let x = *self as u128;
+                    for i in 0..mem::size_of::<$ty>() {
+                        buf.push((x >> (i * 8)) as u8);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_for_integers!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+// `arbitrary` 0.2 builds floats from the integer of their bits.
+impl Encode for f32 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.to_bits().encode(buf)
+    }
+}
+
+impl Encode for f64 {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.to_bits().encode(buf)
+    }
+}
+
+/// Containers are prefixed by their length, as decoded by
+/// `Unstructured::container_size`. For the `FiniteBuffer` our targets use this
+/// is a `usize`, reduced modulo the buffer's maximum length.
+impl<T> Encode for Vec<T>
+where
+    T: Encode,
+{
+    fn encode(&self, buf: &mut Vec<u8>) {
+        self.len().encode(buf);
+        for t in self {
+            t.encode(buf);
+        }
+    }
+}
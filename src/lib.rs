@@ -1,7 +1,25 @@
 extern crate arbitrary;
-extern crate strum;
+extern crate arrayvec;
 #[macro_use]
-extern crate strum_macros;
+extern crate bughunt_rust_derive;
+extern crate hashbrown;
+extern crate im;
+extern crate indexmap;
+#[macro_use]
+extern crate lazy_static;
+#[cfg(unix)]
+extern crate libc;
+extern crate smallvec;
+#[cfg(test)]
+#[macro_use]
+extern crate quickcheck;
+#[macro_use]
+pub mod stats;
+#[macro_use]
+pub mod diff;
+pub mod encode;
+pub mod op;
+pub mod trace;
 #[deny(warnings)]
 #[deny(bad_style)]
 // #[deny(missing_docs)]
@@ -0,0 +1,313 @@
+//! Operations decoded from fuzz input by fixed tag
+//!
+//! Every `Op` type in this crate derives [`TaggedOp`] -- see the
+//! `bughunt-rust-derive` crate -- from attributes on its variants:
+//!
+//! ```text
+//! #[derive(Clone, Debug, TaggedOp)]
+//! pub enum Op<T> {
+//!     #[op(tag = 0)]
+//!     Clear,
+//!     #[op(tag = 1, weight = 4, fields = "t")]
+//!     Push(T),
+//! }
+//! ```
+//!
+//! The derive generates the `TaggedOp`, `Arbitrary`, `Encode`, `Display` and
+//! `FromStr` implementations of the type, so that the tags live in exactly one
+//! place. Every variant must have a tag and no two variants may share one, else
+//! compilation fails. The `Display` and `FromStr` implementations speak the
+//! line format of [`trace`]: the variant name in snake case followed by its
+//! fields. Tuple variants name their fields with the `fields` attribute.
+//!
+//! Decoding by tag keeps inputs stable but makes every variant equally likely,
+//! which is rarely the workload we want. A [`Profile`] instead chooses among
+//! the variants by [`Weights`], and each target offers a handful of profiles --
+//! growth heavy, churn, and so on -- picked by a byte of its input.
+//!
+//! [`trace`]: ../trace/index.html
+use arbitrary::{Arbitrary, FiniteBuffer, Unstructured};
+use encode::{Encode, VERSION};
+use std::env;
+use trace::{Header, ParseError, Trace};
+
+/// The environment variable overriding the weights of the `default` profile
+///
+/// The value is a list of `name=weight` pairs, such as `insert=8 clear=0`. It
+/// is read once, when a target first decodes an input.
+pub const WEIGHTS_VAR: &str = "BUGHUNT_WEIGHTS";
+
+/// A single variant of a [`TaggedOp`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Variant {
+    /// The name of the variant, as written in traces
+    pub name: &'static str,
+    /// The fixed tag identifying the variant in fuzz input
+    pub tag: u8,
+    /// The relative weight of the variant, defaulting to 1
+    pub weight: u32,
+}
+
+/// An operation whose variants are identified by fixed tags
+///
+/// Tags are fixed: once a variant has a tag it keeps it for good and a new
+/// variant takes a tag that has never been used. Saved corpora and crash
+/// inputs depend on this.
+pub trait TaggedOp: Sized {
+    /// Every variant of the op, in declaration order
+    const VARIANTS: &'static [Variant];
+
+    /// The tag of this op's variant
+    fn tag(&self) -> u8;
+
+    /// Decode the op with the given `tag`, or `None` if no op has it
+    fn decode_tagged<U>(tag: u8, u: &mut U) -> Result<Option<Self>, U::Error>
+    where
+        U: Unstructured + ?Sized;
+}
+
+/// Relative weights over the variants of a [`TaggedOp`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Weights {
+    variants: Vec<Variant>,
+}
+
+impl Weights {
+    /// The weights given by the `#[op(weight = N)]` attributes of `O`
+    pub fn of<O>() -> Self
+    where
+        O: TaggedOp,
+    {
+        Self {
+            variants: O::VARIANTS.to_vec(),
+        }
+    }
+
+    /// Set the weight of the variant called `name`
+    pub fn set(&mut self, name: &str, weight: u32) -> Result<(), ParseError> {
+        match self.variants.iter_mut().find(|v| v.name == name) {
+            Some(variant) => {
+                variant.weight = weight;
+                Ok(())
+            }
+            None => Err(ParseError::new(format!("unknown op '{}'", name))),
+        }
+    }
+
+    /// Set weights from a list of `name=weight` pairs
+    ///
+    /// Pairs may be separated by whitespace or commas.
+    pub fn configure(&mut self, spec: &str) -> Result<(), ParseError> {
+        for pair in spec.split(|c: char| c == ',' || c.is_whitespace()) {
+            if pair.is_empty() {
+                continue;
+            }
+            let mut parts = pair.splitn(2, '=');
+            let name = parts.next().expect("split yields at least once");
+            let weight = parts
+                .next()
+                .and_then(|w| w.parse().ok())
+                .ok_or_else(|| ParseError::new(format!("expected name=weight, got '{}'", pair)))?;
+            self.set(name, weight)?;
+        }
+        if self.total() == 0 {
+            return Err(ParseError::new("weights must not all be zero"));
+        }
+        Ok(())
+    }
+
+    /// The sum of all weights
+    pub fn total(&self) -> u64 {
+        self.variants.iter().map(|v| u64::from(v.weight)).sum()
+    }
+
+    /// Decode an op, choosing its variant by weight
+    ///
+    /// A `u16` is read and scaled into the total weight to choose the variant,
+    /// whose fields are then decoded as usual.
+    pub fn decode<O, U>(&self, u: &mut U) -> Result<O, U::Error>
+    where
+        O: TaggedOp,
+        U: Unstructured + ?Sized,
+    {
+        let selector: u16 = Arbitrary::arbitrary(u)?;
+        let mut point = (u64::from(selector) * self.total()) >> 16;
+        let variant = self
+            .variants
+            .iter()
+            .find(|v| {
+                let weight = u64::from(v.weight);
+                if point < weight {
+                    true
+                } else {
+                    point -= weight;
+                    false
+                }
+            })
+            .expect("weights must not all be zero");
+        let op = O::decode_tagged(variant.tag, u)?;
+        Ok(op.expect("every variant has a tag"))
+    }
+}
+
+/// How a target chooses among its ops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// The name of the profile
+    pub name: &'static str,
+    /// The weights of the profile, or `None` if ops are decoded by tag
+    pub weights: Option<Weights>,
+}
+
+impl Profile {
+    /// Decode an op according to this profile
+    pub fn decode<O, U>(&self, u: &mut U) -> Result<O, U::Error>
+    where
+        O: TaggedOp + Arbitrary,
+        U: Unstructured + ?Sized,
+    {
+        match self.weights {
+            Some(ref weights) => weights.decode(u),
+            None => Arbitrary::arbitrary(u),
+        }
+    }
+}
+
+/// The profiles a target offers, chosen between by a byte of its input
+///
+/// The first profile is always `tagged`, decoding by tag, and the second
+/// `default`, the attribute weights overridden by [`WEIGHTS_VAR`]. Then come
+/// the target's own profiles.
+///
+/// Only inputs in the `tagged` profile are stable. Changing the weights of a
+/// profile, or the number of profiles, changes the meaning of every input in
+/// the others. Convert inputs you mean to keep to traces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    /// Build the profiles of `O` from the target's own weight overrides
+    ///
+    /// # Panics
+    ///
+    /// Panics if a profile names an op `O` does not have or weighs every op
+    /// at zero, or if [`WEIGHTS_VAR`] is set but cannot be parsed.
+    pub fn new<O>(shapes: &[(&'static str, &[(&str, u32)])]) -> Self
+    where
+        O: TaggedOp,
+    {
+        let mut default = Weights::of::<O>();
+        if let Ok(spec) = env::var(WEIGHTS_VAR) {
+            if let Err(e) = default.configure(&spec) {
+                panic!("{}: {}", WEIGHTS_VAR, e);
+            }
+        }
+        let mut profiles = vec![
+            Profile {
+                name: "tagged",
+                weights: None,
+            },
+            Profile {
+                name: "default",
+                weights: Some(default),
+            },
+        ];
+        for &(name, overrides) in shapes {
+            let mut weights = Weights::of::<O>();
+            for &(op, weight) in overrides {
+                if let Err(e) = weights.set(op, weight) {
+                    panic!("profile {}: {}", name, e);
+                }
+            }
+            assert!(weights.total() > 0, "profile {}: all weights zero", name);
+            profiles.push(Profile {
+                name,
+                weights: Some(weights),
+            });
+        }
+        Self { profiles }
+    }
+
+    /// The profile chosen by `byte`
+    pub fn choose(&self, byte: u8) -> &Profile {
+        &self.profiles[byte as usize % self.profiles.len()]
+    }
+
+    /// The `tagged` profile, which decodes ops by tag
+    pub fn tagged(&self) -> &Profile {
+        &self.profiles[0]
+    }
+}
+
+/// The mask of the ops of `O` that `owner` has, one bit per op at the op's
+/// tag, given the names of the ops it `lacks`
+///
+/// # Panics
+///
+/// Panics if `lacks` names an op that doesn't exist.
+pub fn capabilities<O: TaggedOp>(owner: &str, lacks: &[&str]) -> u32 {
+    let mut mask = !0;
+    for name in lacks {
+        let variant = O::VARIANTS
+            .iter()
+            .find(|v| v.name == *name)
+            .unwrap_or_else(|| panic!("{} lacks unknown op '{}'", owner, name));
+        mask &= !(1 << variant.tag);
+    }
+    mask
+}
+
+/// Decode the input of a fuzz target into a [`Trace`]
+///
+/// The input holds the encoding version and a byte choosing one of `profiles`,
+/// then whatever else of its header the target reads with `header`, then ops
+/// until it is exhausted. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input<O, F>(
+    data: &[u8],
+    max_len: usize,
+    profiles: &Profiles,
+    header: F,
+) -> Option<Trace<O>>
+where
+    O: TaggedOp + Arbitrary,
+    F: FnOnce(&mut FiniteBuffer) -> Option<Header>,
+{
+    let mut ring = FiniteBuffer::new(data, max_len).ok()?;
+    let version: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    if version != VERSION {
+        return None;
+    }
+    let byte: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    let profile = profiles.choose(byte);
+
+    let mut trace = Trace::new(header(&mut ring)?);
+    loop {
+        match profile.decode(&mut ring) {
+            Ok(op) => trace.ops.push(op),
+            Err(_) => return Some(trace),
+        }
+    }
+}
+
+/// Encode a [`Trace`] as input for a fuzz target
+///
+/// This is the inverse of [`decode_input`], with `header` writing what the
+/// target reads of its header after the profile byte. Ops are encoded by tag,
+/// under the `tagged` profile.
+pub fn encode_input<O, F>(trace: &Trace<O>, header: F) -> Vec<u8>
+where
+    O: Encode,
+    F: FnOnce(&Header, &mut Vec<u8>),
+{
+    let mut buf = Vec::new();
+    VERSION.encode(&mut buf);
+    0u8.encode(&mut buf);
+    header(&trace.header, &mut buf);
+    for op in &trace.ops {
+        op.encode(&mut buf);
+    }
+    buf
+}
@@ -0,0 +1,311 @@
+//! Coverage statistics for fuzz campaigns
+//!
+//! A fuzzer tells us about the code it reached but not about the states our
+//! models reached. Did a `hash_map` run ever hold a thousand entries? Did it
+//! ever call `reserve` just before a resize? This module collects the answers:
+//! ops run per variant, a histogram of model sizes, the capacity transitions
+//! seen and how many times each assertion site ran.
+//!
+//! Collection is off unless the [`STATS_VAR`] environment variable is set, to
+//! `-` for a report on stderr or else to the path of a file to write the report
+//! to. The report is written when the process exits normally -- as libFuzzer
+//! does at the end of `-runs` or `-max_total_time` -- and whenever the process
+//! receives `SIGUSR1`, which is handy for a campaign that runs forever. Any
+//! handler of `SIGUSR1` installed before ours, as libFuzzer's is, still runs.
+//! A process that dies of a crash does not write a report.
+//!
+//! Targets call [`input`], [`op`], [`size`] and [`capacity`] as they replay and
+//! make their assertions through the `check!` and `check_eq!` macros, which
+//! count each site before asserting.
+use op::TaggedOp;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::{self, Write as FmtWrite};
+use std::fs;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// The environment variable enabling collection, see the module docs
+pub const STATS_VAR: &str = "BUGHUNT_STATS";
+
+/// Assert a condition, counting the assertion site
+///
+/// This is like to `assert!` but records the site with `stats::assert_site`
+/// first.
+macro_rules! check {
+    ($($arg:tt)*) => {{
+        ::stats::assert_site(concat!(file!(), ":", line!()));
+        assert!($($arg)*);
+    }};
+}
+
+/// Assert two expressions are equal, counting the assertion site
+///
+/// This is like to `assert_eq!` but records the site with `stats::assert_site`
+/// first.
+macro_rules! check_eq {
+    ($($arg:tt)*) => {{
+        ::stats::assert_site(concat!(file!(), ":", line!()));
+        assert_eq!($($arg)*);
+    }};
+}
+
+/// Where the report goes
+enum Sink {
+    Stderr,
+    File(String),
+}
+
+/// The statistics of a single target
+#[derive(Default)]
+struct TargetStats {
+    inputs: u64,
+    ops: BTreeMap<&'static str, u64>,
+    /// Model sizes, bucketed by bit length: bucket 0 holds size 0, bucket `i`
+    /// sizes in `2^(i-1)..2^i`.
+    sizes: BTreeMap<u32, u64>,
+    max_size: usize,
+    transitions: BTreeMap<(usize, usize), u64>,
+}
+
+struct Collector {
+    sink: Sink,
+    targets: BTreeMap<&'static str, TargetStats>,
+    asserts: BTreeMap<&'static str, u64>,
+}
+
+impl Collector {
+    fn target(&mut self, target: &'static str) -> &mut TargetStats {
+        self.targets.entry(target).or_default()
+    }
+}
+
+lazy_static! {
+    static ref COLLECTOR: Option<Mutex<Collector>> = {
+        let sink = match env::var(STATS_VAR) {
+            Ok(ref v) if v == "-" => Sink::Stderr,
+            Ok(v) => Sink::File(v),
+            Err(_) => return None,
+        };
+        hooks::install();
+        Some(Mutex::new(Collector {
+            sink,
+            targets: BTreeMap::new(),
+            asserts: BTreeMap::new(),
+        }))
+    };
+}
+
+/// Set by the signal handler, acted on by the next call to [`op`]
+static DUMP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+fn with<F>(f: F)
+where
+    F: FnOnce(&mut Collector),
+{
+    if let Some(ref collector) = *COLLECTOR {
+        // No assertion runs while the lock is held, so even a poisoned lock
+        // guards whole statistics.
+        let mut collector = collector.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut collector)
+    }
+}
+
+/// Whether statistics are being collected
+pub fn enabled() -> bool {
+    COLLECTOR.is_some()
+}
+
+/// Record the start of an input to `target`
+pub fn input(target: &'static str) {
+    with(|c| c.target(target).inputs += 1)
+}
+
+/// Record that `target` ran `op`
+///
+/// If a report was asked for by signal since the last op, it is written now.
+pub fn op<O>(target: &'static str, op: &O)
+where
+    O: TaggedOp,
+{
+    if !enabled() {
+        return;
+    }
+    let tag = op.tag();
+    let name = O::VARIANTS
+        .iter()
+        .find(|v| v.tag == tag)
+        .map_or("unknown", |v| v.name);
+    with(|c| *c.target(target).ops.entry(name).or_insert(0) += 1);
+    if DUMP_REQUESTED.swap(false, Ordering::SeqCst) {
+        dump();
+    }
+}
+
+/// Record that the model of `target` holds `len` elements
+pub fn size(target: &'static str, len: usize) {
+    with(|c| {
+        let stats = c.target(target);
+        let bucket = 0usize.leading_zeros() - len.leading_zeros();
+        *stats.sizes.entry(bucket).or_insert(0) += 1;
+        stats.max_size = stats.max_size.max(len);
+    })
+}
+
+/// Record the capacity of the system under test of `target` across an op
+///
+/// Only changes of capacity are recorded.
+pub fn capacity(target: &'static str, before: usize, after: usize) {
+    if before == after {
+        return;
+    }
+    with(|c| {
+        *c.target(target)
+            .transitions
+            .entry((before, after))
+            .or_insert(0) += 1
+    })
+}
+
+/// Record that the assertion at `site` ran
+///
+/// Called by the `check!` and `check_eq!` macros, `site` being a file and
+/// line.
+pub fn assert_site(site: &'static str) {
+    with(|c| *c.asserts.entry(site).or_insert(0) += 1)
+}
+
+/// Render the report of everything collected so far
+///
+/// Returns `None` if statistics are not being collected.
+pub fn report() -> Option<String> {
+    let mut out = None;
+    with(|c| {
+        let mut s = String::new();
+        render(c, &mut s).expect("writing to a String cannot fail");
+        out = Some(s);
+    });
+    out
+}
+
+fn render(c: &Collector, s: &mut String) -> fmt::Result {
+    for (target, stats) in &c.targets {
+        let total: u64 = stats.ops.values().sum();
+        writeln!(s, "{}: {} inputs, {} ops", target, stats.inputs, total)?;
+        writeln!(s, "  ops per variant:")?;
+        for (name, n) in &stats.ops {
+            let pct = 100.0 * *n as f64 / total as f64;
+            writeln!(s, "    {:<20} {:>12} {:>6.2}%", name, n, pct)?;
+        }
+        writeln!(s, "  model size, max {}:", stats.max_size)?;
+        for (bucket, n) in &stats.sizes {
+            let range = match *bucket {
+                0 => "0".to_string(),
+                1 => "1".to_string(),
+                b => format!("{}..={}", 1usize << (b - 1), (1usize << b) - 1),
+            };
+            writeln!(s, "    {:<20} {:>12}", range, n)?;
+        }
+        writeln!(s, "  capacity transitions:")?;
+        for (&(before, after), n) in &stats.transitions {
+            writeln!(s, "    {:<20} {:>12}", format!("{} -> {}", before, after), n)?;
+        }
+    }
+    writeln!(s, "assertion sites:")?;
+    for (site, n) in &c.asserts {
+        writeln!(s, "  {:<48} {:>12}", site, n)?;
+    }
+    Ok(())
+}
+
+/// Write the report to wherever [`STATS_VAR`] points
+///
+/// A report file is overwritten, so it always holds the latest report.
+pub fn dump() {
+    with(|c| {
+        let mut s = String::new();
+        render(c, &mut s).expect("writing to a String cannot fail");
+        let res = match c.sink {
+            Sink::Stderr => io::stderr().write_all(s.as_bytes()),
+            Sink::File(ref path) => fs::write(path, s),
+        };
+        if let Err(e) = res {
+            eprintln!("{}: could not write report: {}", STATS_VAR, e);
+        }
+    })
+}
+
+#[cfg(unix)]
+mod hooks {
+    use super::{dump, DUMP_REQUESTED};
+    use libc;
+    use std::mem;
+    use std::ptr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// The handler of `SIGUSR1` before ours, see [`install`]
+    static PREVIOUS_HANDLER: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+    /// The `sa_flags` the previous handler was installed with
+    static PREVIOUS_FLAGS: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn at_exit() {
+        dump();
+    }
+
+    // Very little is safe to do in a signal handler. We only raise a flag,
+    // which the next op notices, and pass the signal on.
+    extern "C" fn on_signal(
+        signum: libc::c_int,
+        info: *mut libc::siginfo_t,
+        context: *mut libc::c_void,
+    ) {
+        DUMP_REQUESTED.store(true, Ordering::SeqCst);
+        let handler = PREVIOUS_HANDLER.load(Ordering::SeqCst);
+        if handler == libc::SIG_DFL || handler == libc::SIG_IGN {
+            return;
+        }
+        let flags = PREVIOUS_FLAGS.load(Ordering::SeqCst) as libc::c_int;
+        unsafe {
+            if flags & libc::SA_SIGINFO != 0 {
+                let handler: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                    mem::transmute(handler);
+                handler(signum, info, context);
+            } else {
+                let handler: extern "C" fn(libc::c_int) = mem::transmute(handler);
+                handler(signum);
+            }
+        }
+    }
+
+    /// Install the exit hook and the `SIGUSR1` handler
+    ///
+    /// libFuzzer handles `SIGUSR1` too, exiting gracefully, unless it is run
+    /// with `-handle_usr1=0`. `SIGUSR2` is no better, libFuzzer handles it the
+    /// same. So rather than take the signal from it, our handler chains to
+    /// whatever handler was installed before: under libFuzzer the signal
+    /// still ends the run, and the report is written on the way out.
+    pub fn install() {
+        unsafe {
+            libc::atexit(at_exit);
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_signal
+                as extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void)
+                as libc::sighandler_t;
+            action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            let mut previous: libc::sigaction = mem::zeroed();
+            if libc::sigaction(libc::SIGUSR1, ptr::null(), &mut previous) != 0 {
+                return;
+            }
+            PREVIOUS_HANDLER.store(previous.sa_sigaction, Ordering::SeqCst);
+            PREVIOUS_FLAGS.store(previous.sa_flags as usize, Ordering::SeqCst);
+            libc::sigaction(libc::SIGUSR1, &action, ptr::null_mut());
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod hooks {
+    pub fn install() {}
+}
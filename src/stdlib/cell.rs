@@ -0,0 +1,621 @@
+//! Tests for `std::cell::RefCell`
+//!
+//! Ops borrow a `RefCell<(u8, u8)>` and hold on to the guards they get --
+//! whole or narrowed to one side of the pair by `Ref::map` and the
+//! `map_split`s -- until an op drops them. The model, a [`PropRefCell`], keeps
+//! a list of the guards alike, and the state of the borrow flag follows from
+//! it: the cell may be read while no guard is exclusive and written while
+//! there are no guards at all. After every op the cell must agree, whether
+//! borrowed with `try_borrow` and `try_borrow_mut` or through every held
+//! guard.
+use op::{self, Profiles};
+use stats;
+use std::cell::{Ref, RefCell, RefMut};
+use stdlib::util::panics;
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `RefCell`
+pub const TARGET: &str = "cell";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most guards held at once; ops that would hold more do nothing
+pub const MAX_GUARDS: usize = 64;
+
+lazy_static! {
+    /// The workload profiles of the `cell` target, see [`Profiles`]
+    ///
+    /// `churn` drops guards about as often as it borrows, so the cell is
+    /// often free to borrow mutably, or to `replace` and `take`.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "churn",
+        &[("drop", 6), ("try_borrow_mut", 3)],
+    )]);
+}
+
+/// How a guard borrows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Borrow {
+    /// A `Ref`, reading
+    Shared,
+    /// A `RefMut`, writing
+    Exclusive,
+}
+
+/// What of the pair a guard sees
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum View {
+    /// The whole pair
+    Whole,
+    /// The first of the pair
+    Left,
+    /// The second of the pair
+    Right,
+}
+
+impl View {
+    /// The side of a pair `side` picks, by whether it is even
+    pub fn side(side: u8) -> View {
+        if side & 1 == 0 {
+            View::Left
+        } else {
+            View::Right
+        }
+    }
+}
+
+/// A model of a guard of a `RefCell`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PropGuard {
+    /// How the guard borrows
+    pub borrow: Borrow,
+    /// What the guard sees
+    pub view: View,
+}
+
+/// A `RefCell<(u8, u8)>` model
+///
+/// The model holds the value and a list of the guards borrowing it, in the
+/// order the system under test holds them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PropRefCell {
+    value: (u8, u8),
+    guards: Vec<PropGuard>,
+}
+
+impl PropRefCell {
+    /// Construct a new `PropRefCell` of `(0, 0)`, unborrowed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The value
+    pub fn value(&self) -> (u8, u8) {
+        self.value
+    }
+
+    /// The guards held
+    pub fn guards(&self) -> &[PropGuard] {
+        &self.guards
+    }
+
+    /// The number of `Ref`s held
+    pub fn readers(&self) -> usize {
+        self.guards
+            .iter()
+            .filter(|g| g.borrow == Borrow::Shared)
+            .count()
+    }
+
+    /// The number of `RefMut`s held
+    ///
+    /// There can be more than one, each of a side of the pair, after a
+    /// `RefMut::map_split`.
+    pub fn writers(&self) -> usize {
+        self.guards
+            .iter()
+            .filter(|g| g.borrow == Borrow::Exclusive)
+            .count()
+    }
+
+    /// Whether the value may be read
+    pub fn can_borrow(&self) -> bool {
+        self.writers() == 0
+    }
+
+    /// Whether the value may be written
+    pub fn can_borrow_mut(&self) -> bool {
+        self.guards.is_empty()
+    }
+
+    /// The part of the value `view` sees, the other side being zero
+    pub fn seen(&self, view: View) -> (u8, u8) {
+        match view {
+            View::Whole => self.value,
+            View::Left => (self.value.0, 0),
+            View::Right => (0, self.value.1),
+        }
+    }
+
+    /// Borrow the whole value, holding the guard last
+    ///
+    /// This is like to [`RefCell::try_borrow`]. Returns whether the borrow
+    /// succeeded.
+    pub fn try_borrow(&mut self) -> bool {
+        self.hold(Borrow::Shared, self.can_borrow())
+    }
+
+    /// Borrow the whole value mutably, holding the guard last
+    ///
+    /// This is like to [`RefCell::try_borrow_mut`]. Returns whether the
+    /// borrow succeeded.
+    pub fn try_borrow_mut(&mut self) -> bool {
+        self.hold(Borrow::Exclusive, self.can_borrow_mut())
+    }
+
+    fn hold(&mut self, borrow: Borrow, ok: bool) -> bool {
+        if ok {
+            self.guards.push(PropGuard {
+                borrow,
+                view: View::Whole,
+            });
+        }
+        ok
+    }
+
+    /// Drop the guard at `idx`
+    pub fn drop_guard(&mut self, idx: usize) {
+        self.guards.remove(idx);
+    }
+
+    /// Narrow the guard at `idx` to `side`, if it sees the whole pair
+    ///
+    /// This is like to [`Ref::map`] and [`RefMut::map`]. A guard of one side
+    /// is mapped to the same side again.
+    pub fn map(&mut self, idx: usize, side: View) {
+        let guard = &mut self.guards[idx];
+        if guard.view == View::Whole {
+            guard.view = side;
+        }
+    }
+
+    /// Hold a second `Ref` like the one at `idx`, if it is a `Ref`, after it
+    ///
+    /// This is like to [`Ref::clone`]
+    pub fn clone_guard(&mut self, idx: usize) {
+        let guard = self.guards[idx];
+        if guard.borrow == Borrow::Shared {
+            self.guards.insert(idx + 1, guard);
+        }
+    }
+
+    /// Split the guard at `idx` into a guard of each side, if it sees the
+    /// whole pair
+    ///
+    /// This is like to [`Ref::map_split`] and [`RefMut::map_split`]. The
+    /// left guard takes the place of the split one and the right follows it.
+    pub fn map_split(&mut self, idx: usize) {
+        let guard = self.guards[idx];
+        if guard.view == View::Whole {
+            self.guards[idx].view = View::Left;
+            self.guards.insert(
+                idx + 1,
+                PropGuard {
+                    borrow: guard.borrow,
+                    view: View::Right,
+                },
+            );
+        }
+    }
+
+    /// Write `value` through the guard at `idx`, if it is a `RefMut`
+    ///
+    /// A guard of the whole pair writes the side `side` picks.
+    pub fn write(&mut self, idx: usize, side: View, value: u8) {
+        let guard = self.guards[idx];
+        if guard.borrow == Borrow::Exclusive {
+            let side = if guard.view == View::Whole {
+                side
+            } else {
+                guard.view
+            };
+            match side {
+                View::Right => self.value.1 = value,
+                _ => self.value.0 = value,
+            }
+        }
+    }
+
+    /// Replace the value with `value`, giving the old one, or `None` if the
+    /// value is borrowed
+    ///
+    /// This is like to [`RefCell::replace`], which panics where this gives
+    /// `None`.
+    pub fn replace(&mut self, value: (u8, u8)) -> Option<(u8, u8)> {
+        if self.can_borrow_mut() {
+            let old = self.value;
+            self.value = value;
+            Some(old)
+        } else {
+            None
+        }
+    }
+}
+
+/// A guard of a `RefCell<(u8, u8)>`
+pub enum Guard<'a> {
+    /// A `Ref` of the whole pair
+    Shared(Ref<'a, (u8, u8)>),
+    /// A `Ref` of one side of the pair
+    SharedPart(Ref<'a, u8>),
+    /// A `RefMut` of the whole pair
+    Exclusive(RefMut<'a, (u8, u8)>),
+    /// A `RefMut` of one side of the pair
+    ExclusivePart(RefMut<'a, u8>),
+}
+
+impl<'a> Guard<'a> {
+    /// What of the value the guard sees, as [`PropRefCell::seen`] has it of
+    /// `view`
+    fn seen(&self, view: View) -> (u8, u8) {
+        let part = match *self {
+            Guard::Shared(ref guard) => return **guard,
+            Guard::Exclusive(ref guard) => return **guard,
+            Guard::SharedPart(ref guard) => **guard,
+            Guard::ExclusivePart(ref guard) => **guard,
+        };
+        match view {
+            View::Right => (0, part),
+            _ => (part, 0),
+        }
+    }
+}
+
+/// Narrow `guard` to `side`, as [`PropRefCell::map`] does
+fn map(guard: Guard, side: View) -> Guard {
+    match guard {
+        Guard::Shared(guard) => Guard::SharedPart(Ref::map(guard, |pair| match side {
+            View::Right => &pair.1,
+            _ => &pair.0,
+        })),
+        Guard::Exclusive(guard) => Guard::ExclusivePart(RefMut::map(guard, |pair| match side {
+            View::Right => &mut pair.1,
+            _ => &mut pair.0,
+        })),
+        Guard::SharedPart(guard) => Guard::SharedPart(Ref::map(guard, |part| part)),
+        Guard::ExclusivePart(guard) => Guard::ExclusivePart(RefMut::map(guard, |part| part)),
+    }
+}
+
+/// Split `guard` into a guard of each side, as [`PropRefCell::map_split`]
+/// does, or give it back if it is of one side already
+fn map_split(guard: Guard) -> Result<(Guard, Guard), Guard> {
+    match guard {
+        Guard::Shared(guard) => {
+            let (left, right) = Ref::map_split(guard, |pair| (&pair.0, &pair.1));
+            Ok((Guard::SharedPart(left), Guard::SharedPart(right)))
+        }
+        Guard::Exclusive(guard) => {
+            let (left, right) = RefMut::map_split(guard, |pair| (&mut pair.0, &mut pair.1));
+            Ok((Guard::ExclusivePart(left), Guard::ExclusivePart(right)))
+        }
+        guard => Err(guard),
+    }
+}
+
+/// The `Op` defines the ways the `RefCell` of the `cell` target is borrowed
+/// and its guards used.
+///
+/// Guards are picked by `idx` modulo the number held, and ops on a guard do
+/// nothing while none are held. Ops that don't apply to the guard picked --
+/// writing through a `Ref`, say -- do nothing either. A `side` picks the
+/// first of the pair if even and the second if odd.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `RefCell::try_borrow`
+    #[op(tag = 0, weight = 3)]
+    TryBorrow,
+    /// This operation triggers `RefCell::try_borrow_mut`
+    #[op(tag = 1, weight = 2)]
+    TryBorrowMut,
+    /// This operation drops a guard
+    #[op(tag = 2, weight = 3)]
+    Drop {
+        /// The guard to drop
+        idx: u8,
+    },
+    /// This operation triggers `Ref::map` or `RefMut::map`
+    #[op(tag = 3)]
+    Map {
+        /// The guard to map
+        idx: u8,
+        /// The side of the pair to map a guard of the whole pair to
+        side: u8,
+    },
+    /// This operation triggers `Ref::clone`
+    #[op(tag = 4)]
+    CloneRef {
+        /// The guard to clone
+        idx: u8,
+    },
+    /// This operation triggers `Ref::map_split` or `RefMut::map_split`
+    #[op(tag = 5)]
+    MapSplit {
+        /// The guard to split
+        idx: u8,
+    },
+    /// This operation writes through a `RefMut`
+    #[op(tag = 6, weight = 2)]
+    Write {
+        /// The guard to write through
+        idx: u8,
+        /// The side of the pair to write through a guard of the whole pair
+        side: u8,
+        /// The byte to write
+        value: u8,
+    },
+    /// This operation triggers `RefCell::replace`
+    #[op(tag = 7)]
+    Replace {
+        /// The first of the new pair
+        left: u8,
+        /// The second of the new pair
+        right: u8,
+    },
+    /// This operation triggers `RefCell::take`
+    #[op(tag = 8)]
+    Take,
+}
+
+/// Check `cell` and `guards` against `model`
+///
+/// Probing borrows must succeed as the model says, and every guard must see
+/// what its model guard does.
+fn check_state(cell: &RefCell<(u8, u8)>, guards: &[Guard], model: &PropRefCell) {
+    check_eq!(guards.len(), model.guards().len());
+    check_eq!(cell.try_borrow().is_ok(), model.can_borrow());
+    check_eq!(cell.try_borrow_mut().is_ok(), model.can_borrow_mut());
+    if let Ok(value) = cell.try_borrow() {
+        check_eq!(*value, model.value());
+    }
+    for (guard, prop) in guards.iter().zip(model.guards()) {
+        check_eq!(guard.seen(prop.view), model.seen(prop.view));
+    }
+}
+
+/// Run `op` against `cell`, holding its guards in `guards`, and `model`,
+/// panicking if they disagree
+pub fn check_op<'a>(
+    op: &Op,
+    cell: &'a RefCell<(u8, u8)>,
+    guards: &mut Vec<Guard<'a>>,
+    model: &mut PropRefCell,
+) {
+    let held = guards.len();
+    match *op {
+        Op::TryBorrow | Op::TryBorrowMut | Op::CloneRef { .. } | Op::MapSplit { .. }
+            if held >= MAX_GUARDS => {}
+        Op::TryBorrow => {
+            let sut = cell.try_borrow();
+            check_eq!(sut.is_ok(), model.try_borrow());
+            if let Ok(guard) = sut {
+                guards.push(Guard::Shared(guard));
+            }
+        }
+        Op::TryBorrowMut => {
+            let sut = cell.try_borrow_mut();
+            check_eq!(sut.is_ok(), model.try_borrow_mut());
+            if let Ok(guard) = sut {
+                guards.push(Guard::Exclusive(guard));
+            }
+        }
+        Op::Replace { left, right } => {
+            let expected = model.replace((left, right));
+            match expected {
+                Some(old) => check_eq!(cell.replace((left, right)), old),
+                None => check!(panics(|| {
+                    cell.replace((left, right));
+                })),
+            }
+        }
+        Op::Take => {
+            let expected = model.replace((0, 0));
+            match expected {
+                Some(old) => check_eq!(cell.take(), old),
+                None => check!(panics(|| {
+                    cell.take();
+                })),
+            }
+        }
+        _ if held == 0 => {}
+        Op::Drop { idx } => {
+            let idx = idx as usize % held;
+            drop(guards.remove(idx));
+            model.drop_guard(idx);
+        }
+        Op::Map { idx, side } => {
+            let idx = idx as usize % held;
+            let side = View::side(side);
+            let guard = guards.remove(idx);
+            guards.insert(idx, map(guard, side));
+            model.map(idx, side);
+        }
+        Op::CloneRef { idx } => {
+            let idx = idx as usize % held;
+            let clone = match guards[idx] {
+                Guard::Shared(ref guard) => Some(Guard::Shared(Ref::clone(guard))),
+                Guard::SharedPart(ref guard) => Some(Guard::SharedPart(Ref::clone(guard))),
+                _ => None,
+            };
+            if let Some(clone) = clone {
+                guards.insert(idx + 1, clone);
+            }
+            model.clone_guard(idx);
+        }
+        Op::MapSplit { idx } => {
+            let idx = idx as usize % held;
+            match map_split(guards.remove(idx)) {
+                Ok((left, right)) => {
+                    guards.insert(idx, right);
+                    guards.insert(idx, left);
+                }
+                Err(guard) => guards.insert(idx, guard),
+            }
+            model.map_split(idx);
+        }
+        Op::Write { idx, side, value } => {
+            let idx = idx as usize % held;
+            let side = View::side(side);
+            match guards[idx] {
+                Guard::Exclusive(ref mut guard) => match side {
+                    View::Right => guard.1 = value,
+                    _ => guard.0 = value,
+                },
+                Guard::ExclusivePart(ref mut guard) => **guard = value,
+                _ => {}
+            }
+            model.write(idx, side, value);
+        }
+    }
+    check_state(cell, guards, model);
+}
+
+/// Decode the input of the `cell` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `cell` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against `RefCell<(u8, u8)>` and [`PropRefCell`]
+///
+/// Every op is applied to both the model and the system under test, panicking
+/// as soon as the two disagree. The run is counted in [`stats`], if
+/// collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let cell = RefCell::new((0, 0));
+    let mut guards = Vec::new();
+    let mut model = PropRefCell::new();
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        check_op(op, &cell, &mut guards, &mut model);
+        stats::size(TARGET, guards.len());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(ops: &[Op]) -> PropRefCell {
+        let cell = RefCell::new((0, 0));
+        let mut guards = Vec::new();
+        let mut model = PropRefCell::new();
+        for op in ops {
+            check_op(op, &cell, &mut guards, &mut model);
+        }
+        model
+    }
+
+    #[test]
+    fn readers_shut_out_writers() {
+        let mut model = PropRefCell::new();
+        assert!(model.try_borrow());
+        assert!(model.try_borrow());
+        assert!(!model.try_borrow_mut());
+        assert_eq!(2, model.readers());
+        model.drop_guard(0);
+        model.drop_guard(0);
+        assert!(model.try_borrow_mut());
+        assert!(!model.try_borrow());
+        assert_eq!((0, 1), (model.readers(), model.writers()));
+    }
+
+    #[test]
+    fn split_writers_hold_the_cell_until_both_drop() {
+        let model = run(&[
+            Op::TryBorrowMut,
+            Op::MapSplit { idx: 0 },
+            Op::Write {
+                idx: 1,
+                side: 0,
+                value: 7,
+            },
+            Op::Drop { idx: 0 },
+            Op::TryBorrow,
+            Op::Replace { left: 1, right: 2 },
+        ]);
+        assert_eq!(1, model.writers());
+        assert_eq!((0, 7), model.value());
+        let model = run(&[
+            Op::TryBorrowMut,
+            Op::MapSplit { idx: 0 },
+            Op::Drop { idx: 0 },
+            Op::Drop { idx: 0 },
+            Op::Take,
+        ]);
+        assert!(model.can_borrow_mut());
+    }
+
+    #[test]
+    fn cloned_refs_outlive_the_original() {
+        let model = run(&[
+            Op::Replace { left: 3, right: 4 },
+            Op::TryBorrow,
+            Op::Map { idx: 0, side: 1 },
+            Op::CloneRef { idx: 0 },
+            Op::Drop { idx: 0 },
+            Op::TryBorrowMut,
+        ]);
+        assert_eq!(
+            vec![PropGuard {
+                borrow: Borrow::Shared,
+                view: View::Right
+            }],
+            model.guards()
+        );
+    }
+
+    quickcheck! {
+        fn cell_agrees(ops: Vec<(u8, u8, u8)>) -> bool {
+            let ops: Vec<Op> = ops
+                .into_iter()
+                .map(|(tag, idx, value)| match tag % 9 {
+                    0 => Op::TryBorrow,
+                    1 => Op::TryBorrowMut,
+                    2 => Op::Drop { idx },
+                    3 => Op::Map { idx, side: value },
+                    4 => Op::CloneRef { idx },
+                    5 => Op::MapSplit { idx },
+                    6 => Op::Write { idx, side: value, value },
+                    7 => Op::Replace { left: idx, right: value },
+                    _ => Op::Take,
+                })
+                .collect();
+            run(&ops);
+            true
+        }
+    }
+}
@@ -0,0 +1,3 @@
+//! Tests for `std::char`
+pub mod unicode;
+pub mod utf16;
@@ -0,0 +1,748 @@
+//! Tests for the Unicode tables of `char` and `str`
+//!
+//! Case mapping and classification in std run off tables generated from the
+//! Unicode Character Database. The tables are regenerated for every new
+//! version of Unicode and one that is stale, or was generated wrong, gives
+//! wrong answers for whole ranges of chars. We check them against a model
+//! that reads the UCD files themselves, from `src/stdlib/char/ucd`:
+//!
+//! * `UnicodeData.txt`, for general categories and simple case mappings
+//! * `SpecialCasing.txt`, for full case mappings and the `Final_Sigma` rule
+//! * `DerivedCoreProperties.txt`, for `Alphabetic`, `Lowercase`, `Uppercase`,
+//!   `Cased`, `Case_Ignorable` and `Grapheme_Extend`
+//! * `PropList.txt`, for `White_Space`
+//!
+//! The files are checked in, of the version of Unicode [`UCD_VERSION`] pins,
+//! and `ci/fetch_ucd.sh` replaces them when that moves. A toolchain whose
+//! `char::UNICODE_VERSION` is another has nothing to be checked against:
+//! [`replay`] gives an error the target reports, and the tests are skipped.
+use op::{self, Profiles};
+use stats;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use stdlib::string::{Char, Text};
+use trace::{Header, ParseError, Trace};
+
+/// The name of the fuzz target driving the Unicode tables of `char`
+pub const TARGET: &str = "char_unicode";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The version of Unicode of the UCD files checked in
+pub const UCD_VERSION: (u8, u8, u8) = (17, 0, 0);
+
+/// The directory the UCD files are read from
+pub const UCD_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/stdlib/char/ucd");
+
+lazy_static! {
+    /// The Unicode Character Database, read from [`UCD_DIR`], or why it
+    /// could not be, see [`Ucd::load`]
+    pub static ref UCD: Result<Ucd, ParseError> = Ucd::load(Path::new(UCD_DIR));
+
+    /// The workload profiles of the `char_unicode` target, see [`Profiles`]
+    ///
+    /// `strings` only converts and escapes strings, where the context of a
+    /// char matters.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "strings",
+        &[("char_case", 0), ("classify", 0), ("escape_debug", 0), ("sweep", 0)],
+    )]);
+}
+
+/// The Unicode Character Database, or the parts of it std's tables are made of
+#[derive(Debug, Clone, Default)]
+pub struct Ucd {
+    version: (u8, u8, u8),
+    /// General categories by inclusive range of code points, sorted
+    categories: Vec<(u32, u32, String)>,
+    /// Simple uppercase mappings
+    upper: HashMap<char, char>,
+    /// Simple lowercase mappings
+    lower: HashMap<char, char>,
+    /// Unconditional full mappings, lowercase then uppercase
+    special: HashMap<char, (String, String)>,
+    /// Lowercase mappings under the `Final_Sigma` condition
+    final_sigma: HashMap<char, String>,
+    /// Binary properties by inclusive range of code points, sorted
+    properties: HashMap<String, Vec<(u32, u32)>>,
+}
+
+/// The file names of the UCD files read, in the order `Ucd::parse` takes them
+const UCD_FILES: [&str; 4] = [
+    "UnicodeData.txt",
+    "SpecialCasing.txt",
+    "PropList.txt",
+    "DerivedCoreProperties.txt",
+];
+
+fn parse_point(s: &str) -> Result<u32, ParseError> {
+    u32::from_str_radix(s.trim(), 16)
+        .map_err(|_| ParseError::new(format!("'{}' is not a code point in hex", s)))
+}
+
+fn parse_char(s: &str) -> Result<char, ParseError> {
+    let point = parse_point(s)?;
+    ::std::char::from_u32(point).ok_or_else(|| ParseError::new(format!("{:X} is not a char", point)))
+}
+
+/// Parse a space separated sequence of code points, as in `SpecialCasing.txt`
+fn parse_chars(s: &str) -> Result<String, ParseError> {
+    s.split_whitespace().map(parse_char).collect()
+}
+
+/// The line of a UCD file with any comment removed, or `None` if that's empty
+fn strip_comment(line: &str) -> Option<&str> {
+    let line = line.split('#').next().expect("split yields at least once").trim();
+    if line.is_empty() {
+        None
+    } else {
+        Some(line)
+    }
+}
+
+/// The version of Unicode named by the header line of a UCD file
+///
+/// Every file but `UnicodeData.txt` starts with a line like
+/// `# PropList-17.0.0.txt`.
+fn parse_version(file: &str) -> Result<(u8, u8, u8), ParseError> {
+    let header = file.lines().next().unwrap_or("");
+    let version = header
+        .rsplit('-')
+        .next()
+        .and_then(|v| v.trim().split(".txt").next())
+        .unwrap_or("");
+    let parts: Vec<u8> = version.split('.').filter_map(|p| p.parse().ok()).collect();
+    match parts[..] {
+        [major, minor, update] => Ok((major, minor, update)),
+        _ => Err(ParseError::new(format!("no version in header '{}'", header))),
+    }
+}
+
+/// Look `point` up in sorted, disjoint, inclusive `ranges`
+fn find_range<T>(ranges: &[(u32, u32, T)], point: u32) -> Option<&T> {
+    ranges
+        .binary_search_by(|&(lo, hi, _)| {
+            if hi < point {
+                Ordering::Less
+            } else if lo > point {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|idx| &ranges[idx].2)
+}
+
+impl Ucd {
+    /// Read the UCD files from `dir`
+    ///
+    /// Fails if a file is missing or cannot be parsed, or if the files are of
+    /// a version of Unicode other than [`UCD_VERSION`].
+    pub fn load(dir: &Path) -> Result<Self, ParseError> {
+        let (major, minor, update) = UCD_VERSION;
+        let fetch = format!(
+            "fetch the UCD with `ci/fetch_ucd.sh {}.{}.{}`",
+            major, minor, update
+        );
+        let mut files = Vec::new();
+        for name in &UCD_FILES {
+            let path = dir.join(name);
+            let file = fs::read_to_string(&path).map_err(|e| {
+                ParseError::new(format!("could not read {}: {}; {}", path.display(), e, fetch))
+            })?;
+            files.push(file);
+        }
+        let ucd = Self::parse(&files[0], &files[1], &files[2], &files[3])?;
+        if ucd.version != (major, minor, update) {
+            let (a, b, c) = ucd.version;
+            return Err(ParseError::new(format!(
+                "the UCD in {} is version {}.{}.{} but {}.{}.{} is pinned; {}",
+                dir.display(),
+                a,
+                b,
+                c,
+                major,
+                minor,
+                update,
+                fetch
+            )));
+        }
+        Ok(ucd)
+    }
+
+    /// Parse the UCD from the contents of its files
+    ///
+    /// The files of the UCD disagreeing on its version is an error.
+    pub fn parse(
+        unicode_data: &str,
+        special_casing: &str,
+        prop_list: &str,
+        derived_core_properties: &str,
+    ) -> Result<Self, ParseError> {
+        let version = parse_version(derived_core_properties)?;
+        for (name, file) in UCD_FILES[1..3].iter().zip(&[special_casing, prop_list]) {
+            if parse_version(file)? != version {
+                return Err(ParseError::new(format!(
+                    "{} is not of the version of DerivedCoreProperties.txt",
+                    name
+                )));
+            }
+        }
+        let mut ucd = Ucd {
+            version,
+            ..Ucd::default()
+        };
+        ucd.parse_unicode_data(unicode_data)?;
+        ucd.parse_special_casing(special_casing)?;
+        ucd.parse_properties(prop_list)?;
+        ucd.parse_properties(derived_core_properties)?;
+        Ok(ucd)
+    }
+
+    fn parse_unicode_data(&mut self, file: &str) -> Result<(), ParseError> {
+        let mut first = None;
+        for (idx, line) in file.lines().enumerate() {
+            let err = |e: ParseError| e.at_line(idx + 1);
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(';').collect();
+            if fields.len() < 15 {
+                return Err(err(ParseError::new("expected 15 fields")));
+            }
+            let point = parse_point(fields[0]).map_err(err)?;
+            let (name, category) = (fields[1], fields[2]);
+            // Large blocks of chars with the same properties are given as the
+            // first and last of them.
+            if name.ends_with(", First>") {
+                first = Some(point);
+                continue;
+            }
+            let start = if name.ends_with(", Last>") {
+                first
+                    .take()
+                    .ok_or_else(|| err(ParseError::new("range has no first code point")))?
+            } else {
+                point
+            };
+            self.categories.push((start, point, category.to_string()));
+            if let Some(c) = ::std::char::from_u32(point) {
+                if !fields[12].is_empty() {
+                    self.upper.insert(c, parse_char(fields[12]).map_err(err)?);
+                }
+                if !fields[13].is_empty() {
+                    self.lower.insert(c, parse_char(fields[13]).map_err(err)?);
+                }
+            }
+        }
+        self.categories.sort();
+        Ok(())
+    }
+
+    fn parse_special_casing(&mut self, file: &str) -> Result<(), ParseError> {
+        for (idx, line) in file.lines().enumerate() {
+            let err = |e: ParseError| e.at_line(idx + 1);
+            let line = match strip_comment(line) {
+                Some(line) => line,
+                None => continue,
+            };
+            // Each line is `code; lower; title; upper; (condition_list;)?`,
+            // leaving an empty field after the last semicolon.
+            let fields: Vec<&str> = line.split(';').map(str::trim).collect();
+            if fields.len() < 4 {
+                return Err(err(ParseError::new("expected at least 4 fields")));
+            }
+            let c = parse_char(fields[0]).map_err(err)?;
+            let lower = parse_chars(fields[1]).map_err(err)?;
+            let upper = parse_chars(fields[3]).map_err(err)?;
+            match fields.get(4).cloned().unwrap_or("") {
+                "" => {
+                    self.special.insert(c, (lower, upper));
+                }
+                "Final_Sigma" => {
+                    self.final_sigma.insert(c, lower);
+                }
+                // The conditions of languages, which std does not apply.
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_properties(&mut self, file: &str) -> Result<(), ParseError> {
+        for (idx, line) in file.lines().enumerate() {
+            let err = |e: ParseError| e.at_line(idx + 1);
+            let line = match strip_comment(line) {
+                Some(line) => line,
+                None => continue,
+            };
+            let fields: Vec<&str> = line.split(';').map(str::trim).collect();
+            if fields.len() < 2 {
+                return Err(err(ParseError::new("expected at least 2 fields")));
+            }
+            let mut range = fields[0].splitn(2, "..");
+            let lo = parse_point(range.next().expect("split yields at least once")).map_err(err)?;
+            let hi = match range.next() {
+                Some(hi) => parse_point(hi).map_err(err)?,
+                None => lo,
+            };
+            self.properties
+                .entry(fields[1].to_string())
+                .or_default()
+                .push((lo, hi));
+        }
+        for ranges in self.properties.values_mut() {
+            ranges.sort();
+        }
+        Ok(())
+    }
+
+    /// The version of Unicode of the UCD
+    pub fn version(&self) -> (u8, u8, u8) {
+        self.version
+    }
+
+    /// The general category of `c`, `Cn` if it is unassigned
+    pub fn category(&self, c: char) -> &str {
+        find_range(&self.categories, c as u32).map_or("Cn", |gc| gc.as_str())
+    }
+
+    /// Determine if `c` has the binary property `property`
+    pub fn has(&self, property: &str, c: char) -> bool {
+        let point = c as u32;
+        match self.properties.get(property) {
+            Some(ranges) => ranges
+                .binary_search_by(|&(lo, hi)| {
+                    if hi < point {
+                        Ordering::Less
+                    } else if lo > point {
+                        Ordering::Greater
+                    } else {
+                        Ordering::Equal
+                    }
+                })
+                .is_ok(),
+            None => false,
+        }
+    }
+
+    /// The full lowercase mapping of `c`
+    ///
+    /// This is like to [`char::to_lowercase`]
+    pub fn to_lowercase(&self, c: char) -> String {
+        match self.special.get(&c) {
+            Some((lower, _)) => lower.clone(),
+            None => self.lower.get(&c).cloned().unwrap_or(c).to_string(),
+        }
+    }
+
+    /// The full uppercase mapping of `c`
+    ///
+    /// This is like to [`char::to_uppercase`]
+    pub fn to_uppercase(&self, c: char) -> String {
+        match self.special.get(&c) {
+            Some((_, upper)) => upper.clone(),
+            None => self.upper.get(&c).cloned().unwrap_or(c).to_string(),
+        }
+    }
+
+    /// Determine if the first char of `chars` not `Case_Ignorable` is `Cased`
+    ///
+    /// Like std, and ICU, we take a char that is both to be case-ignorable.
+    fn case_ignorable_then_cased<I>(&self, mut chars: I) -> bool
+    where
+        I: Iterator<Item = char>,
+    {
+        match chars.find(|&c| !self.has("Case_Ignorable", c)) {
+            Some(c) => self.has("Cased", c),
+            None => false,
+        }
+    }
+
+    /// The lowercase mapping of `s`
+    ///
+    /// This is like to [`str::to_lowercase`]. A char with a mapping under the
+    /// `Final_Sigma` condition -- only capital sigma has one -- takes it when
+    /// it ends a word, as the Unicode Standard, chapter 3, table 3-17 has it.
+    pub fn str_to_lowercase(&self, s: &str) -> String {
+        let chars: Vec<char> = s.chars().collect();
+        let mut lower = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            match self.final_sigma.get(&c) {
+                Some(final_sigma)
+                    if self.case_ignorable_then_cased(chars[..i].iter().rev().cloned())
+                        && !self.case_ignorable_then_cased(chars[i + 1..].iter().cloned()) =>
+                {
+                    lower.push_str(final_sigma)
+                }
+                _ => lower.push_str(&self.to_lowercase(c)),
+            }
+        }
+        lower
+    }
+
+    /// The uppercase mapping of `s`
+    ///
+    /// This is like to [`str::to_uppercase`]
+    pub fn str_to_uppercase(&self, s: &str) -> String {
+        s.chars().map(|c| self.to_uppercase(c)).collect()
+    }
+
+    /// This is like to [`char::is_alphabetic`]
+    pub fn is_alphabetic(&self, c: char) -> bool {
+        self.has("Alphabetic", c)
+    }
+
+    /// This is like to [`char::is_numeric`]
+    pub fn is_numeric(&self, c: char) -> bool {
+        matches!(self.category(c), "Nd" | "Nl" | "No")
+    }
+
+    /// This is like to [`char::is_alphanumeric`]
+    pub fn is_alphanumeric(&self, c: char) -> bool {
+        self.is_alphabetic(c) || self.is_numeric(c)
+    }
+
+    /// This is like to [`char::is_whitespace`]
+    pub fn is_whitespace(&self, c: char) -> bool {
+        self.has("White_Space", c)
+    }
+
+    /// This is like to [`char::is_lowercase`]
+    pub fn is_lowercase(&self, c: char) -> bool {
+        self.has("Lowercase", c)
+    }
+
+    /// This is like to [`char::is_uppercase`]
+    pub fn is_uppercase(&self, c: char) -> bool {
+        self.has("Uppercase", c)
+    }
+
+    /// This is like to [`char::is_control`]
+    pub fn is_control(&self, c: char) -> bool {
+        self.category(c) == "Cc"
+    }
+
+    /// Determine if `c` is printed as is by `escape_debug`
+    ///
+    /// Every char is but those in the categories of controls, format chars,
+    /// surrogates, private use, unassigned chars and separators -- bar the
+    /// space.
+    pub fn is_printable(&self, c: char) -> bool {
+        match self.category(c) {
+            "Cc" | "Cf" | "Cs" | "Co" | "Cn" | "Zl" | "Zp" => false,
+            "Zs" => c == ' ',
+            _ => true,
+        }
+    }
+
+    /// Escape `c` as `escape_debug` does, grapheme extenders included or not
+    fn escape(&self, c: char, escape_grapheme_extended: bool, escaped: &mut String) {
+        match c {
+            '\0' => escaped.push_str("\\0"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\n' => escaped.push_str("\\n"),
+            '\\' | '\'' | '"' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ if escape_grapheme_extended && self.has("Grapheme_Extend", c) => {
+                escaped.push_str(&format!("\\u{{{:x}}}", c as u32))
+            }
+            _ if self.is_printable(c) => escaped.push(c),
+            _ => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        }
+    }
+
+    /// This is like to [`char::escape_debug`]
+    pub fn escape_debug(&self, c: char) -> String {
+        let mut escaped = String::new();
+        self.escape(c, true, &mut escaped);
+        escaped
+    }
+
+    /// Escape `s` as [`str::escape_debug`] does
+    ///
+    /// A grapheme extender is escaped only at the start of `s`. Anywhere else
+    /// it extends the char before it.
+    pub fn str_escape_debug(&self, s: &str) -> String {
+        let mut escaped = String::new();
+        for (i, c) in s.chars().enumerate() {
+            self.escape(c, i == 0, &mut escaped);
+        }
+        escaped
+    }
+}
+
+/// The `Op` defines the set of operations checked against the Unicode tables
+/// of `char` and `str` and against [`Ucd`].
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `char::to_uppercase`, `char::to_lowercase`,
+    /// `char::is_uppercase` and `char::is_lowercase`
+    #[op(tag = 0, weight = 2)]
+    CharCase {
+        /// The char to check
+        c: Char,
+    },
+    /// This operation triggers `char::is_alphabetic`, `char::is_numeric`,
+    /// `char::is_alphanumeric`, `char::is_whitespace` and `char::is_control`
+    #[op(tag = 1, weight = 2)]
+    Classify {
+        /// The char to check
+        c: Char,
+    },
+    /// This operation triggers `char::escape_debug`
+    #[op(tag = 2, weight = 2)]
+    EscapeDebug {
+        /// The char to escape
+        c: Char,
+    },
+    /// This operation triggers `str::to_uppercase` and `str::to_lowercase`
+    #[op(tag = 3, weight = 2)]
+    StrCase {
+        /// The string to convert
+        s: Text,
+    },
+    /// This operation triggers `str::escape_debug`
+    #[op(tag = 4)]
+    StrEscapeDebug {
+        /// The string to escape
+        s: Text,
+    },
+    /// This operation checks every char of a range as the char ops do
+    #[op(tag = 5)]
+    Sweep {
+        /// The first code point of the range, modulo `0x110000`
+        start: u32,
+        /// The length of the range, in code points
+        len: u8,
+    },
+}
+
+/// Decode the input of the `char_unicode` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["char".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `char_unicode` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+fn check_case(ucd: &Ucd, c: char) {
+    let upper = c.to_uppercase();
+    check_eq!(upper.len(), upper.clone().count());
+    check_eq!(upper.collect::<String>(), ucd.to_uppercase(c), "{:?}.to_uppercase()", c);
+    let lower = c.to_lowercase();
+    check_eq!(lower.len(), lower.clone().count());
+    check_eq!(lower.collect::<String>(), ucd.to_lowercase(c), "{:?}.to_lowercase()", c);
+    check_eq!(c.is_uppercase(), ucd.is_uppercase(c), "{:?}.is_uppercase()", c);
+    check_eq!(c.is_lowercase(), ucd.is_lowercase(c), "{:?}.is_lowercase()", c);
+}
+
+fn check_classes(ucd: &Ucd, c: char) {
+    check_eq!(c.is_alphabetic(), ucd.is_alphabetic(c), "{:?}.is_alphabetic()", c);
+    check_eq!(c.is_numeric(), ucd.is_numeric(c), "{:?}.is_numeric()", c);
+    check_eq!(c.is_alphanumeric(), ucd.is_alphanumeric(c), "{:?}.is_alphanumeric()", c);
+    check_eq!(c.is_whitespace(), ucd.is_whitespace(c), "{:?}.is_whitespace()", c);
+    check_eq!(c.is_control(), ucd.is_control(c), "{:?}.is_control()", c);
+}
+
+fn check_escape(ucd: &Ucd, c: char) {
+    let escaped = c.escape_debug();
+    check_eq!(escaped.len(), escaped.clone().count());
+    check_eq!(escaped.to_string(), ucd.escape_debug(c), "{:?}.escape_debug()", c);
+}
+
+/// The Unicode Character Database of [`UCD`], if std is of its version
+///
+/// Without the UCD, or with one of another version than std's tables, there
+/// is nothing to check std against. That is no bug of std.
+pub fn ucd() -> Result<&'static Ucd, ParseError> {
+    let ucd = UCD.as_ref().map_err(Clone::clone)?;
+    let (major, minor, update) = ::std::char::UNICODE_VERSION;
+    if (major, minor, update) != ucd.version() {
+        let (a, b, c) = ucd.version();
+        return Err(ParseError::new(format!(
+            "std is of Unicode {}.{}.{} but the UCD checked in is {}.{}.{}",
+            major, minor, update, a, b, c
+        )));
+    }
+    Ok(ucd)
+}
+
+/// Replay a [`Trace`] against std and [`UCD`]
+///
+/// Every op is checked against both, panicking as soon as the two disagree.
+/// Fails, checking nothing, if there is no UCD to check against, see
+/// [`ucd`]. The run is counted in [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op>) -> Result<(), ParseError> {
+    let ucd = ucd()?;
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        match *op {
+            Op::CharCase { c } => check_case(ucd, c.0),
+            Op::Classify { c } => check_classes(ucd, c.0),
+            Op::EscapeDebug { c } => check_escape(ucd, c.0),
+            Op::StrCase { ref s } => {
+                let s = &s.0;
+                check_eq!(s.to_uppercase(), ucd.str_to_uppercase(s), "{:?}.to_uppercase()", s);
+                check_eq!(s.to_lowercase(), ucd.str_to_lowercase(s), "{:?}.to_lowercase()", s);
+                stats::size(TARGET, s.len());
+            }
+            Op::StrEscapeDebug { ref s } => {
+                let s = &s.0;
+                check_eq!(s.escape_debug().to_string(), ucd.str_escape_debug(s), "{:?}.escape_debug()", s);
+                stats::size(TARGET, s.len());
+            }
+            Op::Sweep { start, len } => {
+                let start = start % 0x11_0000;
+                for c in (start..start + u32::from(len)).filter_map(::std::char::from_u32) {
+                    check_case(ucd, c);
+                    check_classes(ucd, c);
+                    check_escape(ucd, c);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Excerpts of the UCD files, enough for the tests below.
+    const UNICODE_DATA: &str = "\
+0020;SPACE;Zs;0;WS;;;;;N;;;;;
+0027;APOSTROPHE;Po;0;ON;;;;;N;APOSTROPHE-QUOTE;;;;
+002E;FULL STOP;Po;0;CS;;;;;N;PERIOD;;;;
+0041;LATIN CAPITAL LETTER A;Lu;0;L;;;;;N;;;;0061;
+0061;LATIN SMALL LETTER A;Ll;0;L;;;;;N;;;0041;;0041
+00AD;SOFT HYPHEN;Cf;0;BN;;;;;N;;;;;
+00DF;LATIN SMALL LETTER SHARP S;Ll;0;L;;;;;N;;;;;
+0301;COMBINING ACUTE ACCENT;Mn;230;NSM;;;;;N;NON-SPACING ACUTE;;;;
+03A3;GREEK CAPITAL LETTER SIGMA;Lu;0;L;;;;;N;;;;03C3;
+03C2;GREEK SMALL LETTER FINAL SIGMA;Ll;0;L;;;;;N;;;03A3;;03A3
+03C3;GREEK SMALL LETTER SIGMA;Ll;0;L;;;;;N;;;03A3;;03A3
+3400;<CJK Ideograph Extension A, First>;Lo;0;L;;;;;N;;;;;
+4DBF;<CJK Ideograph Extension A, Last>;Lo;0;L;;;;;N;;;;;
+";
+
+    const SPECIAL_CASING: &str = "\
+# SpecialCasing-17.0.0.txt
+00DF; 00DF; 0053 0073; 0053 0053; # LATIN SMALL LETTER SHARP S
+03A3; 03C2; 03A3; 03A3; Final_Sigma; # GREEK CAPITAL LETTER SIGMA
+0049; 0131; 0049; 0049; tr; # LATIN CAPITAL LETTER I
+";
+
+    const PROP_LIST: &str = "\
+# PropList-17.0.0.txt
+0020          ; White_Space # Zs       SPACE
+";
+
+    const DERIVED_CORE_PROPERTIES: &str = "\
+# DerivedCoreProperties-17.0.0.txt
+0041..005A    ; Alphabetic # L&  [26] LATIN CAPITAL LETTER A..LATIN CAPITAL LETTER Z
+0061..007A    ; Alphabetic # L&  [26] LATIN SMALL LETTER A..LATIN SMALL LETTER Z
+03A3          ; Alphabetic # L&       GREEK CAPITAL LETTER SIGMA
+0041..005A    ; Cased # L&  [26] LATIN CAPITAL LETTER A..LATIN CAPITAL LETTER Z
+0061..007A    ; Cased # L&  [26] LATIN SMALL LETTER A..LATIN SMALL LETTER Z
+00DF          ; Cased # L&       LATIN SMALL LETTER SHARP S
+03A3          ; Cased # L&       GREEK CAPITAL LETTER SIGMA
+03C2..03C3    ; Cased # L&   [2] GREEK SMALL LETTER FINAL SIGMA..GREEK SMALL LETTER SIGMA
+0027          ; Case_Ignorable # Po       APOSTROPHE
+002E          ; Case_Ignorable # Po       FULL STOP
+0301          ; Case_Ignorable # Mn       COMBINING ACUTE ACCENT
+0301          ; Grapheme_Extend # Mn       COMBINING ACUTE ACCENT
+0300..036F    ; InCB; Extend # Mn [112] COMBINING GRAVE ACCENT..COMBINING LATIN SMALL LETTER X
+";
+
+    fn ucd() -> Ucd {
+        Ucd::parse(UNICODE_DATA, SPECIAL_CASING, PROP_LIST, DERIVED_CORE_PROPERTIES)
+            .expect("the excerpts parse")
+    }
+
+    #[test]
+    fn categories_cover_ranges() {
+        let ucd = ucd();
+        assert_eq!((17, 0, 0), ucd.version());
+        assert_eq!("Lu", ucd.category('A'));
+        assert_eq!("Lo", ucd.category('\u{4000}'));
+        assert_eq!("Cn", ucd.category('\u{4DC0}'));
+    }
+
+    #[test]
+    fn full_mappings_override_simple_ones() {
+        let ucd = ucd();
+        assert_eq!("SS", ucd.to_uppercase('\u{df}'));
+        assert_eq!("\u{df}", ucd.to_lowercase('\u{df}'));
+        assert_eq!("a", ucd.to_lowercase('A'));
+        // Language conditions are not applied.
+        assert_eq!("I", ucd.to_uppercase('I'));
+        assert_eq!("I", ucd.to_lowercase('I'));
+    }
+
+    #[test]
+    fn sigma_is_final_at_the_end_of_a_word() {
+        let ucd = ucd();
+        assert_eq!("a\u{3c2}", ucd.str_to_lowercase("A\u{3a3}"));
+        assert_eq!("a'\u{3c2}. a", ucd.str_to_lowercase("A'\u{3a3}. A"));
+        assert_eq!("a\u{3c3}a", ucd.str_to_lowercase("A\u{3a3}A"));
+        assert_eq!("\u{3c3}", ucd.str_to_lowercase("\u{3a3}"));
+        assert_eq!("\u{3c3}", ucd.to_lowercase('\u{3a3}'));
+    }
+
+    #[test]
+    fn grapheme_extenders_are_escaped_first() {
+        let ucd = ucd();
+        assert_eq!("\\u{301}", ucd.escape_debug('\u{301}'));
+        assert_eq!("\\u{301}\u{301}\\u{ad}", ucd.str_escape_debug("\u{301}\u{301}\u{ad}"));
+        assert_eq!("\\0\\'\\\" \\u{3000}", ucd.str_escape_debug("\0'\" \u{3000}"));
+    }
+
+    #[test]
+    fn versions_must_agree() {
+        let old = PROP_LIST.replace("17.0.0", "16.0.0");
+        assert!(Ucd::parse(UNICODE_DATA, SPECIAL_CASING, &old, DERIVED_CORE_PROPERTIES).is_err());
+    }
+    #[test]
+    fn std_agrees_with_the_ucd() {
+        let ucd = match super::ucd() {
+            Ok(ucd) => ucd,
+            Err(e) => {
+                eprintln!("skipped, {}", e);
+                return;
+            }
+        };
+        for c in (0..0x11_0000).filter_map(::std::char::from_u32) {
+            check_case(ucd, c);
+            check_classes(ucd, c);
+            check_escape(ucd, c);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Tests for the UTF-16 encoding of `char`
+//!
+//! `char::encode_utf16` and `str::encode_utf16` split chars past `U+FFFF` into
+//! surrogate pairs. `String::from_utf16`, `String::from_utf16_lossy` and
+//! `char::decode_utf16` put them back together and decide what to make of a
+//! surrogate with no partner. We check all of them against [`encode`] and
+//! [`decode`], a surrogate pair codec written from the Unicode Standard,
+//! chapter 3, definitions D91 and D75.
+use op::{self, Profiles};
+use stats;
+use std::char;
+use stdlib::string::Char;
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `char::decode_utf16`
+pub const TARGET: &str = "char_utf16";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 4_096;
+
+/// The replacement char `String::from_utf16_lossy` puts in for bad units
+pub const REPLACEMENT: char = '\u{fffd}';
+
+/// The first high, or leading, surrogate
+pub const HIGH_SURROGATES: u16 = 0xD800;
+
+/// The first low, or trailing, surrogate
+pub const LOW_SURROGATES: u16 = 0xDC00;
+
+lazy_static! {
+    /// The workload profiles of the `char_utf16` target, see [`Profiles`]
+    ///
+    /// `valid` only ever appends chars. `broken` appends lone surrogates as
+    /// often as chars.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[
+        ("valid", &[("unit", 0), ("high", 0), ("low", 0), ("swapped", 0)]),
+        ("broken", &[("char", 1)]),
+    ]);
+}
+
+/// Whether `unit` is a high surrogate, `D800..=DBFF`
+pub fn is_high(unit: u16) -> bool {
+    unit & 0xFC00 == HIGH_SURROGATES
+}
+
+/// Whether `unit` is a low surrogate, `DC00..=DFFF`
+pub fn is_low(unit: u16) -> bool {
+    unit & 0xFC00 == LOW_SURROGATES
+}
+
+/// Encode `c` in one code unit, or two if it is past `U+FFFF`
+///
+/// This is like to [`char::encode_utf16`]
+pub fn encode(c: char) -> Vec<u16> {
+    let point = c as u32;
+    if point < 0x1_0000 {
+        return vec![point as u16];
+    }
+    let bits = point - 0x1_0000;
+    vec![
+        HIGH_SURROGATES | (bits >> 10) as u16,
+        LOW_SURROGATES | (bits & 0x3FF) as u16,
+    ]
+}
+
+/// A piece of decoded code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Piece {
+    /// A char and the unit offset it starts at
+    Char(usize, char),
+    /// A surrogate with no partner and the unit offset it is at
+    Unpaired(usize, u16),
+}
+
+/// Decode `units` into chars and unpaired surrogates
+///
+/// A high surrogate pairs with a low surrogate right after it. Every other
+/// surrogate is unpaired, and a high surrogate followed by anything else
+/// leaves that to be decoded on its own.
+pub fn decode(units: &[u16]) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut idx = 0;
+    while idx < units.len() {
+        let unit = units[idx];
+        if is_high(unit) && idx + 1 < units.len() && is_low(units[idx + 1]) {
+            let high = u32::from(unit - HIGH_SURROGATES);
+            let low = u32::from(units[idx + 1] - LOW_SURROGATES);
+            let c = char::from_u32(0x1_0000 + (high << 10 | low)).expect("surrogate pairs encode chars");
+            pieces.push(Piece::Char(idx, c));
+            idx += 2;
+        } else if is_high(unit) || is_low(unit) {
+            pieces.push(Piece::Unpaired(idx, unit));
+            idx += 1;
+        } else {
+            let c = char::from_u32(u32::from(unit)).expect("units outside the surrogates are chars");
+            pieces.push(Piece::Char(idx, c));
+            idx += 1;
+        }
+    }
+    pieces
+}
+
+/// Decode `units`, or give the offset of the first unpaired surrogate
+///
+/// This is like to [`String::from_utf16`], which only tells that there is
+/// some unpaired surrogate and not where.
+pub fn from_utf16(units: &[u16]) -> Result<String, usize> {
+    decode(units)
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Char(_, c) => Ok(c),
+            Piece::Unpaired(offset, _) => Err(offset),
+        })
+        .collect()
+}
+
+/// Decode `units`, replacing each unpaired surrogate with [`REPLACEMENT`]
+///
+/// This is like to [`String::from_utf16_lossy`]
+pub fn from_utf16_lossy(units: &[u16]) -> String {
+    decode(units)
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Char(_, c) => c,
+            Piece::Unpaired(..) => REPLACEMENT,
+        })
+        .collect()
+}
+
+/// The `Op` defines the ways code units are appended to the input of
+/// `char::decode_utf16` and [`decode`].
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation appends the encoding of a char
+    #[op(tag = 0, weight = 4)]
+    Char {
+        /// The char to append
+        c: Char,
+    },
+    /// This operation appends a code unit
+    #[op(tag = 1)]
+    Unit {
+        /// The unit to append
+        u: u16,
+    },
+    /// This operation appends a high surrogate
+    #[op(tag = 2)]
+    High {
+        /// Append `D800` plus `n % 0x400`
+        n: u16,
+    },
+    /// This operation appends a low surrogate
+    #[op(tag = 3)]
+    Low {
+        /// Append `DC00` plus `n % 0x400`
+        n: u16,
+    },
+    /// This operation appends the encoding of a char, units reversed
+    #[op(tag = 4)]
+    Swapped {
+        /// The char to append, which is appended as is if it takes 1 unit
+        c: Char,
+    },
+}
+
+impl Op {
+    /// The code units this op appends
+    pub fn units(&self) -> Vec<u16> {
+        match *self {
+            Op::Char { c } => encode(c.0),
+            Op::Unit { u } => vec![u],
+            Op::High { n } => vec![HIGH_SURROGATES + n % 0x400],
+            Op::Low { n } => vec![LOW_SURROGATES + n % 0x400],
+            Op::Swapped { c } => {
+                let mut units = encode(c.0);
+                units.reverse();
+                units
+            }
+        }
+    }
+}
+
+/// Decode the input of the `char_utf16` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u16".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `char_utf16` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against `char::decode_utf16` and [`decode`]
+///
+/// Each op appends to a buffer of code units which is decoded afresh, by
+/// both, and checked, panicking as soon as the two disagree. Whenever the
+/// units decode to a string it is encoded again and must give them back. The
+/// run is counted in [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let mut units = Vec::new();
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        let mut buf = [0; 2];
+        if let Op::Char { c } = *op {
+            check_eq!(c.0.encode_utf16(&mut buf), &encode(c.0)[..]);
+            check_eq!(c.0.len_utf16(), encode(c.0).len());
+        }
+        units.extend(op.units());
+        let pieces = decode(&units);
+        stats::size(TARGET, units.len());
+
+        // `decode_utf16` yields a piece at a time, and the offset of each
+        // follows from the units taken up by those before it.
+        let (lower, upper) = char::decode_utf16(units.iter().cloned()).size_hint();
+        let mut offset = 0;
+        let mut sut = Vec::new();
+        for item in char::decode_utf16(units.iter().cloned()) {
+            match item {
+                Ok(c) => {
+                    sut.push(Piece::Char(offset, c));
+                    offset += c.len_utf16();
+                }
+                Err(e) => {
+                    sut.push(Piece::Unpaired(offset, e.unpaired_surrogate()));
+                    offset += 1;
+                }
+            }
+        }
+        check_eq!(sut, pieces);
+        check!(lower <= pieces.len());
+        if let Some(upper) = upper {
+            check!(pieces.len() <= upper);
+        }
+
+        let model = from_utf16(&units);
+        check_eq!(String::from_utf16(&units).ok(), model.clone().ok());
+        check_eq!(String::from_utf16_lossy(&units), from_utf16_lossy(&units));
+        match model {
+            Ok(s) => {
+                check_eq!(s.encode_utf16().collect::<Vec<_>>(), units);
+                check_eq!(s.encode_utf16().count(), units.len());
+                let len: usize = s.chars().map(|c| encode(c).len()).sum();
+                check_eq!(len, units.len());
+            }
+            Err(offset) => {
+                check!(String::from_utf16(&units[..offset]).is_ok());
+                check!(String::from_utf16(&units[..=offset]).is_err());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pairs_split_at_ten_bits() {
+        assert_eq!(vec![0xD83D, 0xDC1B], encode('\u{1f41b}'));
+        assert_eq!(vec![0xDBFF, 0xDFFF], encode('\u{10ffff}'));
+        assert_eq!(vec![0xD800, 0xDC00], encode('\u{10000}'));
+        assert_eq!(vec![0xFFFF], encode('\u{ffff}'));
+    }
+
+    #[test]
+    fn unpaired_surrogates_are_found() {
+        assert_eq!(Err(1), from_utf16(&[0x61, 0xDC00, 0xD800]));
+        assert_eq!(Err(1), from_utf16(&[0x61, 0xD800, 0x61]));
+        assert_eq!(Err(2), from_utf16(&[0xD800, 0xDC00, 0xD800]));
+        assert_eq!(Ok("a\u{10000}".to_string()), from_utf16(&[0x61, 0xD800, 0xDC00]));
+    }
+
+    #[test]
+    fn a_high_surrogate_leaves_the_next_unit_alone() {
+        let pieces = vec![
+            Piece::Unpaired(0, 0xD800),
+            Piece::Char(1, '\u{10000}'),
+            Piece::Unpaired(3, 0xDC00),
+        ];
+        assert_eq!(pieces, decode(&[0xD800, 0xD800, 0xDC00, 0xDC00]));
+    }
+
+    quickcheck! {
+        fn strings_round_trip(s: String) -> bool {
+            let units: Vec<u16> = s.chars().flat_map(encode).collect();
+            from_utf16(&units) == Ok(s)
+        }
+
+        fn pieces_cover_the_units(units: Vec<u16>) -> bool {
+            let mut end = 0;
+            for piece in decode(&units) {
+                let (offset, len) = match piece {
+                    Piece::Char(offset, c) => (offset, encode(c).len()),
+                    Piece::Unpaired(offset, _) => (offset, 1),
+                };
+                if offset != end {
+                    return false;
+                }
+                end = offset + len;
+            }
+            end == units.len()
+        }
+
+        fn lossy_is_lossless_on_valid_units(s: String) -> bool {
+            let units: Vec<u16> = s.chars().flat_map(encode).collect();
+            from_utf16_lossy(&units) == s
+        }
+    }
+}
@@ -1,7 +1,43 @@
 //! Tests for `std::collections::HashMap`
 use arbitrary::*;
+use encode::{Encode, VERSION};
+use hashbrown::HashMap as HashbrownMap;
+use indexmap::IndexMap;
+use op::{Profile, Profiles, TaggedOp};
+use stats;
+use std::collections::HashMap;
+use std::fmt::Debug;
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::mem;
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `HashMap`
+pub const TARGET: &str = "hash_map";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+lazy_static! {
+    /// The workload profiles of the `hash_map` target, see [`Profiles`]
+    ///
+    /// `grow` mostly inserts, driving the map through many reallocations.
+    /// `churn` inserts and removes in equal measure, leaving tombstones about
+    /// at a steady size. `shrink` mostly removes and shrinks.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op<u16, u16>>(&[
+        (
+            "grow",
+            &[("insert", 16), ("get", 4), ("remove", 1), ("reserve", 2), ("clear", 0)],
+        ),
+        (
+            "churn",
+            &[("insert", 4), ("remove", 4), ("get", 2), ("shrink_to_fit", 1), ("reserve", 1), ("clear", 1)],
+        ),
+        (
+            "shrink",
+            &[("insert", 4), ("remove", 6), ("get", 1), ("shrink_to_fit", 4), ("reserve", 1), ("clear", 2)],
+        ),
+    ]);
+}
 
 /// Build a [`TrulyAwfulHasher`]
 ///
@@ -75,6 +111,7 @@ impl Hasher for TrulyAwfulHasher {
 /// In actuality, `PropHashMap<K, V>` is a vector of `(K, V)`. The pairs are not
 /// held in order so the operations against the map are extremely
 /// inefficient. But, they are simple to implement and verify.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PropHashMap<K, V>
 where
     K: Eq + Hash,
@@ -140,6 +177,15 @@ where
         None
     }
 
+    /// The entries of `PropHashMap<K, V>`, in order
+    ///
+    /// Entries are kept in the order they were first inserted, except that
+    /// removing an entry moves the last entry into its place. This is exactly
+    /// the order of an `IndexMap` whose entries are removed by `swap_remove`.
+    pub fn entries(&self) -> &[(K, V)] {
+        &self.data
+    }
+
     /// Remove a value from `PropHashMap<K, V>` at the given key, returning the
     /// previous value if one existed
     ///
@@ -157,18 +203,24 @@ where
 /// `HashMap<K, V>` and `PropHashMap<K, V>`. Some map directly to functions
 /// available on the types, others require a more elaborate interpretation
 /// step.
-#[derive(Clone, EnumCount, Debug)]
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
 pub enum Op<K, V> {
     /// This operation triggers `std::collections::HashMap::shrink_to_fit`
+    #[op(tag = 3)]
     ShrinkToFit,
     /// This operation triggers `std::collections::HashMap::clear`
+    #[op(tag = 4)]
     Clear,
     /// This operation triggers `std::collections::HashMap::reserve`
+    #[op(tag = 5)]
     Reserve {
         /// Reserve `n` capacity elements
         n: u16,
     },
     /// This operation triggers `std::collections::HashMap::insert`
+    #[op(tag = 0, weight = 4)]
     Insert {
         /// The key to be inserted
         k: K,
@@ -176,50 +228,463 @@ pub enum Op<K, V> {
         v: V,
     },
     /// This operation triggers `std::collections::HashMap::remove`
+    #[op(tag = 1, weight = 2)]
     Remove {
         /// The key to be removed
         k: K,
     },
     /// This operation triggers `std::collections::HashMap::get`
+    #[op(tag = 2, weight = 2)]
     Get {
         /// The key to be removed
         k: K,
     },
 }
 
-impl<K, V> Arbitrary for Op<K, V>
+/// The number of `Op<K, V>` variants before each was given a fixed tag
+///
+/// Legacy inputs chose a variant by a byte modulo this number. It must never
+/// change, else legacy inputs can no longer be migrated.
+const LEGACY_OP_COUNT: u8 = 6;
+
+impl<K, V> Op<K, V>
 where
-    K: Clone + Send + Arbitrary,
-    V: Clone + Send + Arbitrary,
+    K: Arbitrary,
+    V: Arbitrary,
 {
-    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    /// Decode an op as legacy, unversioned inputs did
+    ///
+    /// The tags were assigned to match the legacy decoding, so the two differ
+    /// only for bytes at or past [`LEGACY_OP_COUNT`].
+    fn arbitrary_legacy<U>(u: &mut U) -> Result<Self, U::Error>
     where
         U: Unstructured + ?Sized,
     {
-        let total_enum_fields = OP_COUNT as u8;
         let variant: u8 = Arbitrary::arbitrary(u)?;
-        let op = match variant % total_enum_fields {
-            0 => {
-                let k: K = Arbitrary::arbitrary(u)?;
-                let v: V = Arbitrary::arbitrary(u)?;
-                Op::Insert { k, v }
-            }
-            1 => {
-                let k: K = Arbitrary::arbitrary(u)?;
-                Op::Remove { k }
-            }
-            2 => {
-                let k: K = Arbitrary::arbitrary(u)?;
-                Op::Get { k }
-            }
-            3 => Op::ShrinkToFit,
-            4 => Op::Clear,
-            5 => {
-                let n: u16 = Arbitrary::arbitrary(u)?;
-                Op::Reserve { n }
-            }
-            _ => unreachable!(),
+        let op = <Self as TaggedOp>::decode_tagged(variant % LEGACY_OP_COUNT, u)?;
+        Ok(op.expect("every legacy variant has a tag"))
+    }
+}
+
+/// Decode the input of the `hash_map` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version, a profile byte, a hasher seed and an
+/// initial capacity before reading ops until the input is exhausted. The
+/// profile byte chooses from [`PROFILES`] how ops are decoded. Inputs of
+/// version 1, which have no profile byte, decode ops by tag. Returns `None` if
+/// the input is of some other version or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op<u16, u16>>> {
+    decode(data, false)
+}
+
+/// Decode a legacy, unversioned input of the `hash_map` fuzz target
+///
+/// This is for migrating old corpora only, see [`decode_input`] for the
+/// current encoding.
+pub fn decode_legacy_input(data: &[u8]) -> Option<Trace<Op<u16, u16>>> {
+    decode(data, true)
+}
+
+fn decode(data: &[u8], legacy: bool) -> Option<Trace<Op<u16, u16>>> {
+    let mut ring = FiniteBuffer::new(data, MAX_INPUT_LEN).ok()?;
+    let profile: Option<&Profile> = if legacy {
+        None
+    } else {
+        let version: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+        match version {
+            1 => Some(PROFILES.tagged()),
+            VERSION => {
+                let byte: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+                Some(PROFILES.choose(byte))
+            }
+            _ => return None,
+        }
+    };
+    let hash_seed: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    // Why is capacity not usize? We're very likely to request a capacity so
+    // large that the HashMap cannot allocate enough slots to store them,
+    // resulting in a panic when we call `with_capacity_and_hasher`. This is a
+    // crash, but an uninteresting one.
+    //
+    // We also request a low-ish capacity, all but guaranteeing we'll force
+    // reallocation during execution.
+    //
+    // See note on [`Op::Reserve`] for details
+    let capacity: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+
+    let mut trace = Trace::new(Header {
+        target: TARGET.to_string(),
+        types: vec!["u16".to_string(), "u16".to_string()],
+        capacity: capacity as usize,
+        hash_seed: Some(hash_seed),
+    });
+    loop {
+        let op = match profile {
+            Some(profile) => profile.decode(&mut ring),
+            None => Op::arbitrary_legacy(&mut ring),
         };
-        Ok(op)
+        match op {
+            Ok(op) => trace.ops.push(op),
+            Err(_) => return Some(trace),
+        }
+    }
+}
+
+/// Encode a [`Trace`] as input for the `hash_map` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile. The capacity is truncated to a `u8` and a missing hasher
+/// seed is taken to be zero.
+pub fn encode_input(trace: &Trace<Op<u16, u16>>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    VERSION.encode(&mut buf);
+    0u8.encode(&mut buf);
+    trace.header.hash_seed.unwrap_or(0).encode(&mut buf);
+    (trace.header.capacity as u8).encode(&mut buf);
+    for op in &trace.ops {
+        op.encode(&mut buf);
+    }
+    buf
+}
+
+/// A map that can stand in as the system under test of the `hash_map` target
+///
+/// The `hash_map` ops are run against any type implementing this trait, each
+/// method being like to its namesake on `std::collections::HashMap`. Every
+/// implementation gets its own fuzz target, named by [`Map::TARGET`].
+pub trait Map<K, V> {
+    /// The name of the fuzz target running ops against this map
+    const TARGET: &'static str;
+
+    /// Construct the map with room for `capacity` elements, hashing with
+    /// `hasher`
+    fn with_capacity_and_hasher(capacity: usize, hasher: BuildTrulyAwfulHasher) -> Self;
+    /// Get a value from the map, if one exists
+    fn get(&self, k: &K) -> Option<&V>;
+    /// Insert a value, returning the previous value if one existed
+    fn insert(&mut self, k: K, v: V) -> Option<V>;
+    /// Remove the value at the given key, returning it if one existed
+    fn remove(&mut self, k: &K) -> Option<V>;
+    /// Remove all elements, keeping the allocated memory
+    fn clear(&mut self);
+    /// Shrink the capacity of the map as much as possible
+    fn shrink_to_fit(&mut self);
+    /// Reserve capacity for at least `n` more elements
+    fn reserve(&mut self, n: usize);
+    /// The number of elements the map can hold without reallocating
+    fn capacity(&self) -> usize;
+    /// The number of elements in the map
+    fn len(&self) -> usize;
+    /// Whether the map holds no elements
+    fn is_empty(&self) -> bool;
+
+    /// Check invariants particular to this map against the model
+    ///
+    /// This runs after every op, after the checks common to all maps. It does
+    /// nothing by default.
+    fn check_model(&self, _model: &PropHashMap<K, V>)
+    where
+        K: Eq + Hash,
+    {
+    }
+}
+
+// The implementations for `HashMap` and `hashbrown::HashMap` are identical
+// but for the type. They share no trait we could implement `Map` through.
+macro_rules! impl_map_for_hash_map {
+    ( $ty:ident, $target:expr ) => {
+        impl<K, V> Map<K, V> for $ty<K, V, BuildTrulyAwfulHasher>
+        where
+            K: Eq + Hash,
+        {
+            const TARGET: &'static str = $target;
+
+            fn with_capacity_and_hasher(capacity: usize, hasher: BuildTrulyAwfulHasher) -> Self {
+                $ty::with_capacity_and_hasher(capacity, hasher)
+            }
+            fn get(&self, k: &K) -> Option<&V> {
+                $ty::get(self, k)
+            }
+            fn insert(&mut self, k: K, v: V) -> Option<V> {
+                $ty::insert(self, k, v)
+            }
+            fn remove(&mut self, k: &K) -> Option<V> {
+                $ty::remove(self, k)
+            }
+            fn clear(&mut self) {
+                $ty::clear(self)
+            }
+            fn shrink_to_fit(&mut self) {
+                $ty::shrink_to_fit(self)
+            }
+            fn reserve(&mut self, n: usize) {
+                $ty::reserve(self, n)
+            }
+            fn capacity(&self) -> usize {
+                $ty::capacity(self)
+            }
+            fn len(&self) -> usize {
+                $ty::len(self)
+            }
+            fn is_empty(&self) -> bool {
+                $ty::is_empty(self)
+            }
+        }
+    };
+}
+
+impl_map_for_hash_map!(HashMap, TARGET);
+
+impl_map_for_hash_map!(HashbrownMap, "hash_map_hashbrown");
+
+/// `IndexMap` removes by `swap_remove`, which keeps its entries in the order of
+/// [`PropHashMap::entries`]. We check that order after every op.
+impl<K, V> Map<K, V> for IndexMap<K, V, BuildTrulyAwfulHasher>
+where
+    K: Eq + Hash + Debug,
+    V: PartialEq + Debug,
+{
+    const TARGET: &'static str = "hash_map_indexmap";
+
+    fn with_capacity_and_hasher(capacity: usize, hasher: BuildTrulyAwfulHasher) -> Self {
+        IndexMap::with_capacity_and_hasher(capacity, hasher)
+    }
+    fn get(&self, k: &K) -> Option<&V> {
+        IndexMap::get(self, k)
+    }
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        IndexMap::insert(self, k, v)
+    }
+    fn remove(&mut self, k: &K) -> Option<V> {
+        IndexMap::swap_remove(self, k)
+    }
+    fn clear(&mut self) {
+        IndexMap::clear(self)
+    }
+    fn shrink_to_fit(&mut self) {
+        IndexMap::shrink_to_fit(self)
+    }
+    fn reserve(&mut self, n: usize) {
+        IndexMap::reserve(self, n)
+    }
+    fn capacity(&self) -> usize {
+        IndexMap::capacity(self)
+    }
+    fn len(&self) -> usize {
+        IndexMap::len(self)
+    }
+    fn is_empty(&self) -> bool {
+        IndexMap::is_empty(self)
+    }
+
+    fn check_model(&self, model: &PropHashMap<K, V>) {
+        // Every entry must sit at the same index in the SUT as in the model.
+        for (idx, (k, v)) in model.entries().iter().enumerate() {
+            check_eq!(self.get_index(idx), Some((k, v)));
+        }
+    }
+}
+
+/// Replay a [`Trace`] against `HashMap` and [`PropHashMap`]
+///
+/// Every op is applied to both the model and the system under test, panicking
+/// as soon as the two disagree. A missing hasher seed is taken to be zero. The
+/// run is counted in [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op<u16, u16>>) {
+    replay_map::<HashMap<u16, u16, BuildTrulyAwfulHasher>>(trace)
+}
+
+/// Replay a [`Trace`] against any [`Map`] and [`PropHashMap`]
+///
+/// This is [`replay`] for maps other than `HashMap`. The run is counted in
+/// [`stats`] under [`Map::TARGET`].
+pub fn replay_map<M>(trace: &Trace<Op<u16, u16>>)
+where
+    M: Map<u16, u16>,
+{
+    let mut model: PropHashMap<u16, u16> = PropHashMap::new();
+    let mut sut = M::with_capacity_and_hasher(
+        trace.header.capacity,
+        BuildTrulyAwfulHasher::new(trace.header.hash_seed.unwrap_or(0)),
+    );
+
+    stats::input(M::TARGET);
+    for op in &trace.ops {
+        stats::op(M::TARGET, op);
+        let cap_before = sut.capacity();
+        match *op {
+            Op::Clear => {
+                // Clearing a HashMap removes all elements but keeps
+                // the memory around for reuse. That is, the length
+                // should drop to zero but the capacity will not shrink.
+                //
+                // The capacity may grow, though. Maps built on a
+                // SwissTable -- `HashMap`, hashbrown and indexmap all
+                // are -- leave tombstones behind on removal which count
+                // against capacity until a clear sweeps them away.
+                let prev_cap = sut.capacity();
+                sut.clear();
+                model.clear();
+                check_eq!(0, sut.len());
+                check_eq!(sut.len(), model.len());
+                check!(sut.capacity() >= prev_cap);
+            }
+            Op::ShrinkToFit => {
+                // NOTE There is no model behaviour here
+                //
+                // After a shrink the capacity may or may not shift from
+                // the passed arg `capacity`. But, the capacity of the
+                // HashMap should never grow after a shrink.
+                //
+                // Similarly, the length of the HashMap prior to a
+                // shrink should match the length after a shrink.
+                let prev_len = sut.len();
+                let prev_cap = sut.capacity();
+                sut.shrink_to_fit();
+                check_eq!(prev_len, sut.len());
+                check!(sut.capacity() <= prev_cap);
+            }
+            Op::Get { k } => {
+                let model_res = model.get(&k);
+                let sut_res = sut.get(&k);
+                check_eq!(model_res, sut_res);
+            }
+            Op::Insert { k, v } => {
+                let model_res = model.insert(k, v);
+                let sut_res = sut.insert(k, v);
+                check_eq!(model_res, sut_res);
+            }
+            Op::Remove { k } => {
+                let model_res = model.remove(&k);
+                let sut_res = sut.remove(&k);
+                check_eq!(model_res, sut_res);
+            }
+            Op::Reserve { n } => {
+                // NOTE There is no model behaviour here
+                if sut.capacity().checked_add(n as usize).is_some() {
+                    sut.reserve(n as usize);
+                } // else { assert!(sut.try_reserve(*n).is_err()); }
+            }
+        }
+        stats::size(M::TARGET, model.len());
+        stats::capacity(M::TARGET, cap_before, sut.capacity());
+        // Check invariants
+        //
+        // `HashMap<K, V>` defines the return of `capacity` as
+        // being "the number of elements the map can hold
+        // without reallocating", noting that the number is a
+        // "lower bound". This implies that:
+        //
+        //  * the HashMap capacity must always be at least the
+        //    length of the model
+        check!(sut.capacity() >= model.len());
+        // If the SUT is empty then the model must be.
+        check_eq!(model.is_empty(), sut.is_empty());
+        // The length of the SUT must always be exactly the length of
+        // the model.
+        check_eq!(model.len(), sut.len());
+        sut.check_model(&model);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn empty_model_has_nothing() {
+        let mut model: PropHashMap<u8, u8> = PropHashMap::new();
+        assert!(model.is_empty());
+        assert_eq!(0, model.len());
+        assert_eq!(None, model.get(&0));
+        assert_eq!(None, model.remove(&0));
+    }
+
+    #[test]
+    fn insert_replaces_previous_value() {
+        let mut model = PropHashMap::new();
+        assert_eq!(None, model.insert(1u8, 10u8));
+        assert_eq!(Some(10), model.insert(1, 11));
+        assert_eq!(Some(&11), model.get(&1));
+        assert_eq!(1, model.len());
+    }
+
+    #[test]
+    fn clear_after_removals_may_grow_capacity() {
+        for size in 1..200u16 {
+            let mut trace = Trace::new(Header {
+                target: TARGET.to_string(),
+                types: vec!["u16".to_string(), "u16".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops.extend((0..size).map(|k| Op::Insert { k, v: k }));
+            trace.ops.extend((0..size).step_by(2).map(|k| Op::Remove { k }));
+            trace.ops.push(Op::Clear);
+            replay(&trace);
+            replay_map::<HashbrownMap<u16, u16, BuildTrulyAwfulHasher>>(&trace);
+            replay_map::<IndexMap<u16, u16, BuildTrulyAwfulHasher>>(&trace);
+        }
+    }
+
+    #[test]
+    fn clear_empties_model() {
+        let mut model = PropHashMap::new();
+        model.insert(1u8, 10u8);
+        model.insert(2, 20);
+        model.clear();
+        assert!(model.is_empty());
+        assert_eq!(None, model.get(&1));
+    }
+
+    quickcheck! {
+        fn insert_then_get(prefix: Vec<(u8, u8)>, k: u8, v: u8) -> bool {
+            let mut model = PropHashMap::new();
+            for (pk, pv) in prefix {
+                model.insert(pk, pv);
+            }
+            model.insert(k, v);
+            model.get(&k) == Some(&v)
+        }
+
+        fn remove_after_insert(prefix: Vec<(u8, u8)>, k: u8, v: u8) -> bool {
+            let mut model = PropHashMap::new();
+            for (pk, pv) in prefix {
+                model.insert(pk, pv);
+            }
+            model.insert(k, v);
+            let len = model.len();
+            model.remove(&k) == Some(v) && model.get(&k).is_none() && model.len() == len - 1
+        }
+
+        fn remove_absent_key_changes_nothing(prefix: Vec<(u8, u8)>, k: u8) -> bool {
+            let mut model = PropHashMap::new();
+            for (pk, pv) in prefix {
+                if pk != k {
+                    model.insert(pk, pv);
+                }
+            }
+            let len = model.len();
+            model.remove(&k).is_none() && model.len() == len
+        }
+
+        // A `BTreeMap` shares no code with `HashMap`, so agreement with it
+        // says something about the model alone.
+        fn agrees_with_btree_map(ops: Vec<(bool, u8, u8)>) -> bool {
+            let mut model = PropHashMap::new();
+            let mut reference = BTreeMap::new();
+            for (insert, k, v) in ops {
+                let agree = if insert {
+                    model.insert(k, v) == reference.insert(k, v)
+                } else {
+                    model.remove(&k) == reference.remove(&k)
+                };
+                if !agree || model.len() != reference.len() {
+                    return false;
+                }
+            }
+            reference.iter().all(|(k, v)| model.get(k) == Some(v))
+        }
     }
 }
@@ -1,5 +1,45 @@
 //! Tests for `std::collections::VecDeque`
 use arbitrary::*;
+use arrayvec::ArrayVec;
+use encode::{Encode, VERSION};
+use im;
+use op::{self, Profile, Profiles, TaggedOp};
+use smallvec::SmallVec;
+use stats;
+use std::collections::VecDeque;
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `VecDeque`
+pub const TARGET: &str = "vec_deque";
+
+/// The name of the fuzz target driving `VecDeque` three ways, see
+/// [`replay_three_way`]
+pub const THREE_WAY_TARGET: &str = "vec_deque_three_way";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 65_563;
+
+lazy_static! {
+    /// The workload profiles of the `vec_deque` target, see [`Profiles`]
+    ///
+    /// `grow` mostly pushes, at both ends, so that the ring buffer wraps and
+    /// reallocates. `churn` pushes and pops in equal measure at a steady
+    /// length. `shrink` mostly pops, removes and shrinks.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op<u8>>(&[
+        (
+            "grow",
+            &[("push_back", 8), ("push_front", 8), ("insert", 4), ("clear", 0)],
+        ),
+        (
+            "churn",
+            &[("push_back", 4), ("push_front", 4), ("pop_back", 4), ("pop_front", 4), ("insert", 2), ("remove", 2)],
+        ),
+        (
+            "shrink",
+            &[("pop_back", 4), ("pop_front", 4), ("remove", 4), ("swap_remove_back", 2), ("shrink_to_fit", 4), ("push_back", 3), ("push_front", 3)],
+        ),
+    ]);
+}
 
 /// A `VecDeque<T>` model
 ///
@@ -8,6 +48,7 @@ use arbitrary::*;
 /// it's a queue that supports efficient push/pop from both the back and front
 /// of the queue. Efficiency is of no interest to us and we'll just abuse a Vec,
 /// much like with [`PropHashMap`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct PropVecDeque<T> {
     data: Vec<T>,
 }
@@ -112,11 +153,7 @@ impl<T> PropVecDeque<T> {
     ///
     /// This is like to [`std::collections::VecDeque::swap_remove_back`]
     pub fn swap_remove_back(&mut self, index: usize) -> Option<T> {
-        if self.data.is_empty() {
-            None
-        } else if self.data.len() == 1 {
-            self.pop_back()
-        } else if index < self.data.len() {
+        if index < self.data.len() {
             let back = self.data.len() - 1;
             self.data.swap(index, back);
             self.pop_back()
@@ -143,66 +180,772 @@ impl<T> PropVecDeque<T> {
 /// The `Op<T>` defines the set of operations that are available against
 /// `VecDeque<K, V>` and `PropVecDeque<T>`. Some map directly to functions
 /// available on the types, others require a more elaborate interpretation step.
-#[derive(Clone, EnumCount, Debug)]
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
 pub enum Op<T> {
     /// This operation triggers `std::collections::VecDeque::shrink_to_fit`
+    #[op(tag = 5)]
     ShrinkToFit,
     /// This operation triggers `std::collections::VecDeque::clear`
+    #[op(tag = 4)]
     Clear,
     /// This operation triggers `std::collections::VecDeque::push_back`
+    #[op(tag = 0, weight = 2, fields = "t")]
     PushBack(T),
     /// This operation triggers `std::collections::VecDeque::pop_back`
+    #[op(tag = 1)]
     PopBack,
     /// This operation triggers `std::collections::VecDeque::push_front`
+    #[op(tag = 2, weight = 2, fields = "t")]
     PushFront(T),
     /// This operation triggers `std::collections::VecDeque::pop_front`
+    #[op(tag = 3)]
     PopFront,
     /// This operation triggers `std::collections::VecDeque::insert`
+    #[op(tag = 6, fields = "idx t")]
     Insert(usize, T),
     /// This operation triggers `std::collections::VecDeque::remove`
+    #[op(tag = 7, fields = "idx")]
     Remove(usize),
     /// This operation triggers `std::collections::VecDeque::swap_remove_back`
+    #[op(tag = 8, fields = "idx")]
     SwapRemoveBack(usize),
 }
 
-impl<T> Arbitrary for Op<T>
+/// The number of `Op<T>` variants before each was given a fixed tag
+///
+/// Legacy inputs chose a variant by a byte modulo this number. It must never
+/// change, else legacy inputs can no longer be migrated.
+const LEGACY_OP_COUNT: u8 = 9;
+
+impl<T> Op<T>
 where
-    T: Clone + Send + Arbitrary,
+    T: Arbitrary,
 {
-    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    /// Decode an op as legacy, unversioned inputs did
+    ///
+    /// The tags were assigned to match the legacy decoding, so the two differ
+    /// only for bytes at or past [`LEGACY_OP_COUNT`].
+    fn arbitrary_legacy<U>(u: &mut U) -> Result<Self, U::Error>
     where
         U: Unstructured + ?Sized,
     {
-        let total_enum_fields = OP_COUNT as u8;
         let variant: u8 = Arbitrary::arbitrary(u)?;
-        let op = match variant % total_enum_fields {
-            0 => {
-                let t: T = Arbitrary::arbitrary(u)?;
-                Op::PushBack(t)
-            }
-            1 => Op::PopBack,
-            2 => {
-                let t: T = Arbitrary::arbitrary(u)?;
-                Op::PushFront(t)
-            }
-            3 => Op::PopFront,
-            4 => Op::Clear,
-            5 => Op::ShrinkToFit,
-            6 => {
-                let idx: usize = Arbitrary::arbitrary(u)?;
-                let t: T = Arbitrary::arbitrary(u)?;
-                Op::Insert(idx, t)
-            }
-            7 => {
-                let idx: usize = Arbitrary::arbitrary(u)?;
-                Op::Remove(idx)
-            }
-            8 => {
-                let idx: usize = Arbitrary::arbitrary(u)?;
-                Op::SwapRemoveBack(idx)
-            }
-            _ => unreachable!(),
+        let op = <Self as TaggedOp>::decode_tagged(variant % LEGACY_OP_COUNT, u)?;
+        Ok(op.expect("every legacy variant has a tag"))
+    }
+}
+
+/// Decode the input of the `vec_deque` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version, a profile byte and an initial
+/// capacity before reading ops until the input is exhausted. The profile byte
+/// chooses from [`PROFILES`] how ops are decoded. Inputs of version 1, which
+/// have no profile byte, decode ops by tag. Returns `None` if the input is of
+/// some other version or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op<u8>>> {
+    decode(data, false)
+}
+
+/// Decode a legacy, unversioned input of the `vec_deque` fuzz target
+///
+/// This is for migrating old corpora only, see [`decode_input`] for the
+/// current encoding.
+pub fn decode_legacy_input(data: &[u8]) -> Option<Trace<Op<u8>>> {
+    decode(data, true)
+}
+
+fn decode(data: &[u8], legacy: bool) -> Option<Trace<Op<u8>>> {
+    let mut ring = FiniteBuffer::new(data, MAX_INPUT_LEN).ok()?;
+    let profile: Option<&Profile> = if legacy {
+        None
+    } else {
+        let version: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+        match version {
+            1 => Some(PROFILES.tagged()),
+            VERSION => {
+                let byte: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+                Some(PROFILES.choose(byte))
+            }
+            _ => return None,
+        }
+    };
+    let capacity: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+
+    let mut trace = Trace::new(Header {
+        target: TARGET.to_string(),
+        types: vec!["u8".to_string()],
+        capacity: capacity as usize,
+        hash_seed: None,
+    });
+    loop {
+        let op = match profile {
+            Some(profile) => profile.decode(&mut ring),
+            None => Op::arbitrary_legacy(&mut ring),
         };
-        Ok(op)
+        match op {
+            Ok(op) => trace.ops.push(op),
+            Err(_) => return Some(trace),
+        }
+    }
+}
+
+/// Encode a [`Trace`] as input for the `vec_deque` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile. The capacity is truncated to a `u8`.
+pub fn encode_input(trace: &Trace<Op<u8>>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    VERSION.encode(&mut buf);
+    0u8.encode(&mut buf);
+    (trace.header.capacity as u8).encode(&mut buf);
+    for op in &trace.ops {
+        op.encode(&mut buf);
+    }
+    buf
+}
+
+/// A deque- or vector-like container that can stand in as the system under
+/// test of the `vec_deque` target
+///
+/// The `vec_deque` ops are run against any type implementing this trait, each
+/// method being like to its namesake on `std::collections::VecDeque`. Every
+/// implementation gets its own fuzz target, named by [`Deque::TARGET`].
+///
+/// Not every container has every op. Those a container lacks are named in
+/// [`Deque::LACKS`] and skipped -- by model and container both -- when they
+/// come up. Containers of bounded capacity refuse to grow past it by returning
+/// the rejected element as an error, which is checked against the model.
+pub trait Deque<T> {
+    /// The name of the fuzz target running ops against this container
+    const TARGET: &'static str;
+    /// The names of the ops this container lacks, as written in traces
+    const LACKS: &'static [&'static str] = &[];
+
+    /// Construct the container with room for `capacity` elements
+    fn with_capacity(capacity: usize) -> Self;
+    /// Push a value onto the back, or return it if the container is full
+    fn push_back(&mut self, t: T) -> Result<(), T>;
+    /// Push a value onto the front, or return it if the container is full
+    fn push_front(&mut self, t: T) -> Result<(), T>;
+    /// Insert a value at `idx`, no greater than the length, or return it if
+    /// the container is full
+    fn insert(&mut self, idx: usize, t: T) -> Result<(), T>;
+    /// Pop a value from the back, if one exists
+    fn pop_back(&mut self) -> Option<T>;
+    /// Pop a value from the front, if one exists
+    fn pop_front(&mut self) -> Option<T>;
+    /// Remove the value at `idx`, if one exists
+    fn remove(&mut self, idx: usize) -> Option<T>;
+    /// Remove the value at `idx`, if one exists, moving the back element into
+    /// its place
+    fn swap_remove_back(&mut self, idx: usize) -> Option<T>;
+    /// Remove all elements, keeping the allocated memory
+    fn clear(&mut self);
+    /// Shrink the capacity of the container as much as possible
+    fn shrink_to_fit(&mut self);
+    /// The number of elements the container can hold without reallocating,
+    /// or `None` if the container has no such notion
+    fn capacity(&self) -> Option<usize>;
+    /// The number of elements in the container
+    fn len(&self) -> usize;
+    /// Whether the container holds no elements
+    fn is_empty(&self) -> bool;
+    /// The front element, if one exists
+    fn front(&self) -> Option<&T>;
+    /// The back element, if one exists
+    fn back(&self) -> Option<&T>;
+
+    /// Check invariants particular to this container against the model
+    ///
+    /// This runs after every op, after the checks common to all containers.
+    /// It does nothing by default.
+    fn check_model(&self, _model: &PropVecDeque<T>) {}
+}
+
+impl<T> Deque<T> for VecDeque<T> {
+    const TARGET: &'static str = TARGET;
+
+    fn with_capacity(capacity: usize) -> Self {
+        VecDeque::with_capacity(capacity)
+    }
+    fn push_back(&mut self, t: T) -> Result<(), T> {
+        VecDeque::push_back(self, t);
+        Ok(())
+    }
+    fn push_front(&mut self, t: T) -> Result<(), T> {
+        VecDeque::push_front(self, t);
+        Ok(())
+    }
+    fn insert(&mut self, idx: usize, t: T) -> Result<(), T> {
+        VecDeque::insert(self, idx, t);
+        Ok(())
+    }
+    fn pop_back(&mut self) -> Option<T> {
+        VecDeque::pop_back(self)
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        VecDeque::pop_front(self)
+    }
+    fn remove(&mut self, idx: usize) -> Option<T> {
+        VecDeque::remove(self, idx)
+    }
+    fn swap_remove_back(&mut self, idx: usize) -> Option<T> {
+        VecDeque::swap_remove_back(self, idx)
+    }
+    fn clear(&mut self) {
+        VecDeque::clear(self)
+    }
+    fn shrink_to_fit(&mut self) {
+        VecDeque::shrink_to_fit(self)
+    }
+    fn capacity(&self) -> Option<usize> {
+        Some(VecDeque::capacity(self))
+    }
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+    fn is_empty(&self) -> bool {
+        VecDeque::is_empty(self)
+    }
+    fn front(&self) -> Option<&T> {
+        VecDeque::front(self)
+    }
+    fn back(&self) -> Option<&T> {
+        VecDeque::back(self)
+    }
+}
+
+/// The number of elements a [`SmallVecDeque`] holds before spilling to the
+/// heap
+pub const SMALLVEC_INLINE: usize = 8;
+
+/// The `SmallVec` driven by the `vec_deque_smallvec` target
+pub type SmallVecDeque<T> = SmallVec<[T; SMALLVEC_INLINE]>;
+
+/// `SmallVec` has no front ops. They are done by inserting and removing at
+/// index zero.
+impl<T> Deque<T> for SmallVecDeque<T> {
+    const TARGET: &'static str = "vec_deque_smallvec";
+
+    fn with_capacity(capacity: usize) -> Self {
+        SmallVec::with_capacity(capacity)
+    }
+    fn push_back(&mut self, t: T) -> Result<(), T> {
+        SmallVec::push(self, t);
+        Ok(())
+    }
+    fn push_front(&mut self, t: T) -> Result<(), T> {
+        SmallVec::insert(self, 0, t);
+        Ok(())
+    }
+    fn insert(&mut self, idx: usize, t: T) -> Result<(), T> {
+        SmallVec::insert(self, idx, t);
+        Ok(())
+    }
+    fn pop_back(&mut self) -> Option<T> {
+        SmallVec::pop(self)
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        Deque::remove(self, 0)
+    }
+    fn remove(&mut self, idx: usize) -> Option<T> {
+        if idx < SmallVec::len(self) {
+            Some(SmallVec::remove(self, idx))
+        } else {
+            None
+        }
+    }
+    fn swap_remove_back(&mut self, idx: usize) -> Option<T> {
+        if idx < SmallVec::len(self) {
+            Some(SmallVec::swap_remove(self, idx))
+        } else {
+            None
+        }
+    }
+    fn clear(&mut self) {
+        SmallVec::clear(self)
+    }
+    fn shrink_to_fit(&mut self) {
+        SmallVec::shrink_to_fit(self)
+    }
+    fn capacity(&self) -> Option<usize> {
+        Some(SmallVec::capacity(self))
+    }
+    fn len(&self) -> usize {
+        SmallVec::len(self)
+    }
+    fn is_empty(&self) -> bool {
+        SmallVec::is_empty(self)
+    }
+    fn front(&self) -> Option<&T> {
+        self.first()
+    }
+    fn back(&self) -> Option<&T> {
+        self.last()
+    }
+
+    fn check_model(&self, _model: &PropVecDeque<T>) {
+        // Until it spills a SmallVec holds exactly its inline capacity.
+        if !self.spilled() {
+            check_eq!(SMALLVEC_INLINE, SmallVec::capacity(self));
+        }
+    }
+}
+
+/// The capacity of an [`ArrayDeque`]
+pub const ARRAYVEC_CAPACITY: usize = 32;
+
+/// The `ArrayVec` driven by the `vec_deque_arrayvec` target
+pub type ArrayDeque<T> = ArrayVec<[T; ARRAYVEC_CAPACITY]>;
+
+/// `ArrayVec` cannot grow, so its pushes and inserts fail once it's full. It
+/// cannot shrink either.
+impl<T> Deque<T> for ArrayDeque<T> {
+    const TARGET: &'static str = "vec_deque_arrayvec";
+    const LACKS: &'static [&'static str] = &["shrink_to_fit"];
+
+    fn with_capacity(_capacity: usize) -> Self {
+        ArrayVec::new()
+    }
+    fn push_back(&mut self, t: T) -> Result<(), T> {
+        self.try_push(t).map_err(|e| e.element())
+    }
+    fn push_front(&mut self, t: T) -> Result<(), T> {
+        self.try_insert(0, t).map_err(|e| e.element())
+    }
+    fn insert(&mut self, idx: usize, t: T) -> Result<(), T> {
+        self.try_insert(idx, t).map_err(|e| e.element())
+    }
+    fn pop_back(&mut self) -> Option<T> {
+        ArrayVec::pop(self)
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        self.pop_at(0)
+    }
+    fn remove(&mut self, idx: usize) -> Option<T> {
+        self.pop_at(idx)
+    }
+    fn swap_remove_back(&mut self, idx: usize) -> Option<T> {
+        self.swap_pop(idx)
+    }
+    fn clear(&mut self) {
+        ArrayVec::clear(self)
+    }
+    fn shrink_to_fit(&mut self) {
+        unreachable!("ArrayVec lacks shrink_to_fit")
+    }
+    fn capacity(&self) -> Option<usize> {
+        Some(ArrayVec::capacity(self))
+    }
+    fn len(&self) -> usize {
+        ArrayVec::len(self)
+    }
+    fn is_empty(&self) -> bool {
+        ArrayVec::len(self) == 0
+    }
+    fn front(&self) -> Option<&T> {
+        self.first()
+    }
+    fn back(&self) -> Option<&T> {
+        self.last()
+    }
+}
+
+/// `im::Vector` is a persistent RRB tree. It has no notion of capacity and no
+/// `swap_remove_back`.
+impl<T> Deque<T> for im::Vector<T>
+where
+    T: Clone,
+{
+    const TARGET: &'static str = "vec_deque_im";
+    const LACKS: &'static [&'static str] = &["shrink_to_fit", "swap_remove_back"];
+
+    fn with_capacity(_capacity: usize) -> Self {
+        im::Vector::new()
+    }
+    fn push_back(&mut self, t: T) -> Result<(), T> {
+        im::Vector::push_back(self, t);
+        Ok(())
+    }
+    fn push_front(&mut self, t: T) -> Result<(), T> {
+        im::Vector::push_front(self, t);
+        Ok(())
+    }
+    fn insert(&mut self, idx: usize, t: T) -> Result<(), T> {
+        im::Vector::insert(self, idx, t);
+        Ok(())
+    }
+    fn pop_back(&mut self) -> Option<T> {
+        im::Vector::pop_back(self)
+    }
+    fn pop_front(&mut self) -> Option<T> {
+        im::Vector::pop_front(self)
+    }
+    fn remove(&mut self, idx: usize) -> Option<T> {
+        if idx < im::Vector::len(self) {
+            Some(im::Vector::remove(self, idx))
+        } else {
+            None
+        }
+    }
+    fn swap_remove_back(&mut self, _idx: usize) -> Option<T> {
+        unreachable!("im::Vector lacks swap_remove_back")
+    }
+    fn clear(&mut self) {
+        im::Vector::clear(self)
+    }
+    fn shrink_to_fit(&mut self) {
+        unreachable!("im::Vector lacks shrink_to_fit")
+    }
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+    fn len(&self) -> usize {
+        im::Vector::len(self)
+    }
+    fn is_empty(&self) -> bool {
+        im::Vector::is_empty(self)
+    }
+    fn front(&self) -> Option<&T> {
+        im::Vector::front(self)
+    }
+    fn back(&self) -> Option<&T> {
+        im::Vector::back(self)
+    }
+}
+
+/// Replay a [`Trace`] against `VecDeque` and [`PropVecDeque`]
+///
+/// Every op is applied to both the model and the system under test, panicking
+/// as soon as the two disagree. The run is counted in [`stats`], if collection
+/// is enabled.
+pub fn replay(trace: &Trace<Op<u8>>) {
+    replay_deque::<VecDeque<u8>>(trace)
+}
+
+/// Replay a [`Trace`] against any [`Deque`] and [`PropVecDeque`]
+///
+/// This is [`replay`] for containers other than `VecDeque`. Ops the container
+/// lacks are skipped. The run is counted in [`stats`] under [`Deque::TARGET`].
+pub fn replay_deque<D>(trace: &Trace<Op<u8>>)
+where
+    D: Deque<u8>,
+{
+    let caps = op::capabilities::<Op<u8>>(D::TARGET, D::LACKS);
+    let mut model: PropVecDeque<u8> = PropVecDeque::new();
+    let mut sut = D::with_capacity(trace.header.capacity);
+    stats::input(D::TARGET);
+    for op in &trace.ops {
+        if caps & (1 << op.tag()) == 0 {
+            continue;
+        }
+        stats::op(D::TARGET, op);
+        let cap_before = sut.capacity();
+        match *op {
+            Op::Clear => {
+                // Clearing a VecDeque removes all elements but keeps
+                // the memory around for reuse. That is, the length
+                // should drop to zero but the capacity will remain the
+                // same.
+                let prev_cap = sut.capacity();
+                sut.clear();
+                model.clear();
+                check_eq!(0, sut.len());
+                check_eq!(sut.len(), model.len());
+                check_eq!(prev_cap, sut.capacity());
+            }
+            Op::ShrinkToFit => {
+                // NOTE There is no model behaviour here
+                //
+                // After a shrink the capacity may or may not shift from
+                // the passed arg `capacity`. But, the capacity of the
+                // VecDeque should never grow after a shrink.
+                //
+                // Similarly, the length of the VecDeque prior to a
+                // shrink should match the length after a shrink.
+                let prev_len = sut.len();
+                let prev_cap = sut.capacity();
+                sut.shrink_to_fit();
+                check_eq!(prev_len, sut.len());
+                check!(sut.capacity() <= prev_cap);
+            }
+            Op::PushBack(t) => match sut.push_back(t) {
+                Ok(()) => model.push_back(t),
+                Err(rejected) => check_full(&sut, &model, t, rejected),
+            },
+            Op::PushFront(t) => match sut.push_front(t) {
+                Ok(()) => model.push_front(t),
+                Err(rejected) => check_full(&sut, &model, t, rejected),
+            },
+            Op::PopFront => {
+                let sut_res = sut.pop_front();
+                let model_res = model.pop_front();
+                check_eq!(sut_res, model_res);
+            }
+            Op::PopBack => {
+                let sut_res = sut.pop_back();
+                let model_res = model.pop_back();
+                check_eq!(sut_res, model_res);
+            }
+            Op::Insert(idx, t) => {
+                let scaled_idx = if !model.is_empty() {
+                    idx % model.len()
+                } else {
+                    0
+                };
+                match sut.insert(scaled_idx, t) {
+                    Ok(()) => model.insert(scaled_idx, t),
+                    Err(rejected) => check_full(&sut, &model, t, rejected),
+                }
+            }
+            Op::Remove(idx) => {
+                let sut_res = sut.remove(idx);
+                let model_res = model.remove(idx);
+                check_eq!(sut_res, model_res);
+            }
+            Op::SwapRemoveBack(idx) => {
+                let sut_res = sut.swap_remove_back(idx);
+                let model_res = model.swap_remove_back(idx);
+                check_eq!(sut_res, model_res);
+            }
+        }
+        stats::size(D::TARGET, model.len());
+        if let (Some(before), Some(after)) = (cap_before, sut.capacity()) {
+            stats::capacity(D::TARGET, before, after);
+        }
+        // Check invariants
+        //
+        // `VecDeque<T>` defines the return of `capacity` as being
+        // "the number of elements the map can hold without
+        // reallocating". Unlike `HashMap<K, V>` there is no
+        // discussion of bounds. This implies that:
+        //
+        // * the VecDeque capacity must always be at least the
+        // length of the model
+        if let Some(cap) = sut.capacity() {
+            check!(cap >= model.len());
+        }
+        // The length of the SUT must always be exactly the length
+        // of the model.
+        check_eq!(sut.len(), model.len());
+        // If the SUT is empty then the model must also be.
+        check_eq!(sut.is_empty(), model.is_empty());
+        // The front of the SUT must always be equivalent to the
+        // front of the model.
+        check_eq!(sut.front(), model.front());
+        // The back of the SUT must always be equivalent to the
+        // back of the model.
+        check_eq!(sut.back(), model.back());
+        sut.check_model(&model);
+    }
+}
+
+/// Replay a [`Trace`] against `VecDeque`, [`PropVecDeque`] and an alternative
+/// [`Deque`]
+///
+/// This is the three-way mode of [`replay`]. Every op is applied to all three
+/// and on the first disagreement we panic, naming the side that disagrees with
+/// the other two. The alternative should be an independent, unbounded
+/// implementation -- [`SmallVecDeque`], say -- and ops it lacks are skipped.
+/// The run is counted in [`stats`] under [`THREE_WAY_TARGET`].
+pub fn replay_three_way<A>(trace: &Trace<Op<u8>>)
+where
+    A: Deque<u8>,
+{
+    let caps = op::capabilities::<Op<u8>>(A::TARGET, A::LACKS);
+    let mut model: PropVecDeque<u8> = PropVecDeque::new();
+    let mut sut: VecDeque<u8> = VecDeque::with_capacity(trace.header.capacity);
+    let mut alt = A::with_capacity(trace.header.capacity);
+    stats::input(THREE_WAY_TARGET);
+    for (i, op) in trace.ops.iter().enumerate() {
+        if caps & (1 << op.tag()) == 0 {
+            continue;
+        }
+        stats::op(THREE_WAY_TARGET, op);
+        match *op {
+            Op::Clear => {
+                // As in `replay_deque`, clearing keeps the capacity of the
+                // `VecDeque` the same.
+                let prev_cap = sut.capacity();
+                model.clear();
+                sut.clear();
+                alt.clear();
+                check_eq!(prev_cap, sut.capacity());
+            }
+            Op::ShrinkToFit => {
+                // NOTE There is no model behaviour here, nor any result to
+                // compare. The common checks below still run, and as in
+                // `replay_deque` the capacity of the `VecDeque` must not
+                // grow.
+                let prev_cap = sut.capacity();
+                sut.shrink_to_fit();
+                alt.shrink_to_fit();
+                check!(sut.capacity() <= prev_cap);
+            }
+            Op::PushBack(t) => {
+                model.push_back(t);
+                let (sut_res, alt_res) = (Deque::push_back(&mut sut, t), alt.push_back(t));
+                check_agree!(Ok(()), sut_res, alt_res, "op {} `{}`", i, op);
+            }
+            Op::PushFront(t) => {
+                model.push_front(t);
+                let (sut_res, alt_res) = (Deque::push_front(&mut sut, t), alt.push_front(t));
+                check_agree!(Ok(()), sut_res, alt_res, "op {} `{}`", i, op);
+            }
+            Op::PopFront => {
+                let res = (model.pop_front(), sut.pop_front(), alt.pop_front());
+                check_agree!(res.0, res.1, res.2, "op {} `{}`", i, op);
+            }
+            Op::PopBack => {
+                let res = (model.pop_back(), sut.pop_back(), alt.pop_back());
+                check_agree!(res.0, res.1, res.2, "op {} `{}`", i, op);
+            }
+            Op::Insert(idx, t) => {
+                let scaled_idx = if !model.is_empty() {
+                    idx % model.len()
+                } else {
+                    0
+                };
+                model.insert(scaled_idx, t);
+                let sut_res = Deque::insert(&mut sut, scaled_idx, t);
+                let alt_res = alt.insert(scaled_idx, t);
+                check_agree!(Ok(()), sut_res, alt_res, "op {} `{}`", i, op);
+            }
+            Op::Remove(idx) => {
+                let res = (model.remove(idx), sut.remove(idx), alt.remove(idx));
+                check_agree!(res.0, res.1, res.2, "op {} `{}`", i, op);
+            }
+            Op::SwapRemoveBack(idx) => {
+                let res = (
+                    model.swap_remove_back(idx),
+                    sut.swap_remove_back(idx),
+                    alt.swap_remove_back(idx),
+                );
+                check_agree!(res.0, res.1, res.2, "op {} `{}`", i, op);
+            }
+        }
+        stats::size(THREE_WAY_TARGET, model.len());
+        // Check invariants, as in `replay_deque`, but three ways.
+        check!(sut.capacity() >= model.len());
+        if let Some(cap) = alt.capacity() {
+            check!(cap >= model.len());
+        }
+        check_agree!(model.len(), sut.len(), alt.len(), "len after op {} `{}`", i, op);
+        check_agree!(
+            model.front().cloned(),
+            sut.front().cloned(),
+            alt.front().cloned(),
+            "front after op {} `{}`",
+            i,
+            op
+        );
+        check_agree!(
+            model.back().cloned(),
+            sut.back().cloned(),
+            alt.back().cloned(),
+            "back after op {} `{}`",
+            i,
+            op
+        );
+        alt.check_model(&model);
+    }
+}
+
+/// Check that `sut` was right to reject `t`, returned as `rejected`
+///
+/// A container may only refuse an element when it is full, that is, when the
+/// model holds as many elements as the container's capacity.
+fn check_full<D>(sut: &D, model: &PropVecDeque<u8>, t: u8, rejected: u8)
+where
+    D: Deque<u8>,
+{
+    check_eq!(t, rejected);
+    check_eq!(sut.capacity(), Some(model.len()));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn from_vec(xs: Vec<u8>) -> PropVecDeque<u8> {
+        let mut model = PropVecDeque::new();
+        for x in xs {
+            model.push_back(x);
+        }
+        model
+    }
+
+    #[test]
+    fn empty_model_has_nothing() {
+        let mut model: PropVecDeque<u8> = PropVecDeque::new();
+        assert!(model.is_empty());
+        assert_eq!(None, model.front());
+        assert_eq!(None, model.back());
+        assert_eq!(None, model.pop_front());
+        assert_eq!(None, model.pop_back());
+        assert_eq!(None, model.remove(0));
+        assert_eq!(None, model.swap_remove_back(0));
+    }
+
+    #[test]
+    fn swap_remove_back_moves_back_into_hole() {
+        let mut model = from_vec(vec![1, 2, 3, 4]);
+        assert_eq!(Some(2), model.swap_remove_back(1));
+        assert_eq!(vec![1, 4, 3], model.data);
+        assert_eq!(Some(3), model.swap_remove_back(2));
+        assert_eq!(vec![1, 4], model.data);
+        assert_eq!(None, model.swap_remove_back(2));
+        assert_eq!(vec![1, 4], model.data);
+    }
+
+    quickcheck! {
+        fn push_front_pop_front_is_lifo(prefix: Vec<u8>, xs: Vec<u8>) -> bool {
+            let mut model = from_vec(prefix);
+            for &x in &xs {
+                model.push_front(x);
+            }
+            xs.iter().rev().all(|&x| model.pop_front() == Some(x))
+        }
+
+        fn push_back_pop_back_is_lifo(prefix: Vec<u8>, xs: Vec<u8>) -> bool {
+            let mut model = from_vec(prefix);
+            for &x in &xs {
+                model.push_back(x);
+            }
+            xs.iter().rev().all(|&x| model.pop_back() == Some(x))
+        }
+
+        fn push_back_pop_front_is_fifo(xs: Vec<u8>) -> bool {
+            let mut model = from_vec(xs.clone());
+            xs.iter().all(|&x| model.pop_front() == Some(x)) && model.is_empty()
+        }
+
+        fn front_and_back_are_ends(xs: Vec<u8>) -> bool {
+            let mut model = from_vec(xs.clone());
+            model.front() == xs.first() && model.back() == xs.last()
+        }
+
+        fn insert_then_remove(xs: Vec<u8>, idx: usize, x: u8) -> bool {
+            let idx = idx % (xs.len() + 1);
+            let mut model = from_vec(xs.clone());
+            model.insert(idx, x);
+            model.len() == xs.len() + 1 && model.remove(idx) == Some(x) && model.data == xs
+        }
+
+        fn swap_remove_back_semantics(xs: Vec<u8>, idx: usize) -> bool {
+            let mut model = from_vec(xs.clone());
+            let res = model.swap_remove_back(idx);
+            if idx >= xs.len() {
+                return res.is_none() && model.data == xs;
+            }
+            let mut expected = xs.clone();
+            let back = expected.pop().expect("idx is in bounds");
+            if idx < expected.len() {
+                expected[idx] = back;
+            }
+            res == Some(xs[idx]) && model.data == expected
+        }
     }
 }
@@ -0,0 +1,951 @@
+//! Tests for the adaptors of `std::iter`
+//!
+//! Ops build a pipeline of adaptors -- `map`, `filter`, `skip`, `zip`,
+//! `flatten` and the rest -- over a `Vec<u8>` one stage at a time, and drive
+//! it with `next`, `next_back`, `nth`, `nth_back`, `fold` and `rfold`. The
+//! model, a [`PropIter`], is the list of the items the pipeline has yet to
+//! yield, worked out anew by every stage. However far a pipeline has been
+//! driven, an adaptor on it yields the same as the adaptor on that list.
+//!
+//! Each stage is boxed, hiding its type, so a pipeline is only as
+//! double-ended or exact in size as its adaptors make it, see [`Pipeline`].
+//! After every op the size hint of the pipeline must bound the items left,
+//! and if it is an `ExactSizeIterator` its `len` must be their number.
+use op::{self, Profiles};
+use stats;
+use std::collections::VecDeque;
+use std::mem;
+use stdlib::util::Bytes;
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving the adaptors of iterators
+pub const TARGET: &str = "iter";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most stages a pipeline may have; further stages are ignored
+pub const MAX_DEPTH: usize = 32;
+
+/// The most items a pipeline may yield; `chain` and `flatten` stages that
+/// would yield more are ignored
+pub const MAX_ITEMS: usize = 4096;
+
+lazy_static! {
+    /// The workload profiles of the `iter` target, see [`Profiles`]
+    ///
+    /// `exact` leaves out the adaptors that lose the size of a pipeline, so
+    /// that it stays an `ExactSizeIterator` and double-ended.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "exact",
+        &[("filter", 0), ("chain", 0), ("flatten", 0), ("skip_while", 0)],
+    )]);
+}
+
+/// A predicate holding for bytes that aren't multiples of `modulus % 8 + 2`
+fn keep(modulus: u8) -> impl Fn(&u8) -> bool {
+    move |&byte| byte % (modulus % 8 + 2) != 0
+}
+
+/// The number of times a `flatten` stage repeats `byte`, one of `0..4`
+fn repeats(modulus: u8, byte: u8) -> usize {
+    (byte % (modulus % 3 + 2)) as usize
+}
+
+/// The step of a `step_by` stage, one of `1..=8`
+fn step(step: u8) -> usize {
+    step as usize % 8 + 1
+}
+
+/// A model of a pipeline of iterator adaptors
+///
+/// The model is the list of items left to yield. Every adaptor is a loop
+/// building the list it yields from that list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PropIter {
+    items: VecDeque<u8>,
+}
+
+impl PropIter {
+    /// Construct a new `PropIter` with nothing to yield
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of items left to yield
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether there are no items left to yield
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The next item to yield, if any
+    pub fn front(&self) -> Option<u8> {
+        self.items.front().cloned()
+    }
+
+    /// Start again from `items`
+    ///
+    /// This is like to `Vec::into_iter`
+    pub fn source(&mut self, items: &[u8]) {
+        self.items = items.iter().cloned().collect();
+    }
+
+    /// Add `add` to every item
+    ///
+    /// This is like to [`Iterator::map`]
+    pub fn map(&mut self, add: u8) {
+        for item in self.items.iter_mut() {
+            *item = item.wrapping_add(add);
+        }
+    }
+
+    /// Keep the items `pred` holds for
+    ///
+    /// This is like to [`Iterator::filter`]
+    pub fn filter<F: Fn(&u8) -> bool>(&mut self, pred: F) {
+        let mut kept = VecDeque::new();
+        for &item in &self.items {
+            if pred(&item) {
+                kept.push_back(item);
+            }
+        }
+        self.items = kept;
+    }
+
+    /// Drop the first `n` items
+    ///
+    /// This is like to [`Iterator::skip`]
+    pub fn skip(&mut self, n: usize) {
+        for _ in 0..n {
+            self.items.pop_front();
+        }
+    }
+
+    /// Keep the first `n` items
+    ///
+    /// This is like to [`Iterator::take`]
+    pub fn take(&mut self, n: usize) {
+        while self.items.len() > n {
+            self.items.pop_back();
+        }
+    }
+
+    /// Keep the first item and every `step`th after it
+    ///
+    /// This is like to [`Iterator::step_by`]
+    pub fn step_by(&mut self, step: usize) {
+        let mut kept = VecDeque::new();
+        for i in 0..self.items.len() {
+            if i % step == 0 {
+                kept.push_back(self.items[i]);
+            }
+        }
+        self.items = kept;
+    }
+
+    /// Yield `items` after the rest
+    ///
+    /// This is like to [`Iterator::chain`]
+    pub fn chain(&mut self, items: &[u8]) {
+        for &item in items {
+            self.items.push_back(item);
+        }
+    }
+
+    /// Pair items with `items`, as far as both go, and xor each pair
+    ///
+    /// This is like to [`Iterator::zip`]
+    pub fn zip(&mut self, items: &[u8]) {
+        let mut zipped = VecDeque::new();
+        let len = self.items.len().min(items.len());
+        for (i, &other) in items[..len].iter().enumerate() {
+            zipped.push_back(self.items[i] ^ other);
+        }
+        self.items = zipped;
+    }
+
+    /// Yield the items last first
+    ///
+    /// This is like to [`Iterator::rev`]
+    pub fn rev(&mut self) {
+        let mut reversed = VecDeque::new();
+        for &item in &self.items {
+            reversed.push_front(item);
+        }
+        self.items = reversed;
+    }
+
+    /// Yield each item `repeats(modulus, item)` times
+    ///
+    /// This is like to [`Iterator::flatten`] over a `Vec` for each item
+    pub fn flatten(&mut self, modulus: u8) {
+        let mut flat = VecDeque::new();
+        for &item in &self.items {
+            for _ in 0..repeats(modulus, item) {
+                flat.push_back(item);
+            }
+        }
+        self.items = flat;
+    }
+
+    /// Drop the items before the first that `pred` does not hold for
+    ///
+    /// This is like to [`Iterator::skip_while`]
+    pub fn skip_while<F: Fn(&u8) -> bool>(&mut self, pred: F) {
+        while self.items.front().map(&pred) == Some(true) {
+            self.items.pop_front();
+        }
+    }
+
+    /// Add its index, wrapping, to every item
+    ///
+    /// This is like to [`Iterator::enumerate`]
+    pub fn enumerate(&mut self) {
+        for (i, item) in self.items.iter_mut().enumerate() {
+            *item = item.wrapping_add(i as u8);
+        }
+    }
+
+    /// Yield the first item
+    ///
+    /// This is like to [`Iterator::next`]
+    pub fn pop_front(&mut self) -> Option<u8> {
+        self.items.pop_front()
+    }
+
+    /// Yield the last item
+    ///
+    /// This is like to [`DoubleEndedIterator::next_back`]
+    pub fn pop_back(&mut self) -> Option<u8> {
+        self.items.pop_back()
+    }
+
+    /// Drop `n` items and yield the next
+    ///
+    /// This is like to [`Iterator::nth`]
+    pub fn nth(&mut self, n: usize) -> Option<u8> {
+        self.skip(n);
+        self.pop_front()
+    }
+
+    /// Drop `n` items from the back and yield the next from there
+    ///
+    /// This is like to [`DoubleEndedIterator::nth_back`]
+    pub fn nth_back(&mut self, n: usize) -> Option<u8> {
+        for _ in 0..n {
+            self.items.pop_back();
+        }
+        self.pop_back()
+    }
+
+    /// Yield every item
+    ///
+    /// This is like to [`Iterator::fold`], collecting the items
+    pub fn fold(&mut self) -> Vec<u8> {
+        let mut items = Vec::new();
+        while let Some(item) = self.pop_front() {
+            items.push(item);
+        }
+        items
+    }
+
+    /// Yield every item, last first
+    ///
+    /// This is like to [`DoubleEndedIterator::rfold`], collecting the items
+    pub fn rfold(&mut self) -> Vec<u8> {
+        let mut items = Vec::new();
+        while let Some(item) = self.pop_back() {
+            items.push(item);
+        }
+        items
+    }
+}
+
+/// An iterator of bytes, with its type hidden
+///
+/// The methods are named apart from those of `Iterator`, which every
+/// `Forward` also is, so calls are never ambiguous.
+trait Forward {
+    fn pull(&mut self) -> Option<u8>;
+    fn pull_nth(&mut self, n: usize) -> Option<u8>;
+    fn hint(&self) -> (usize, Option<usize>);
+    /// Collect every item with the `fold` of the iterator itself
+    fn drain(self: Box<Self>) -> Vec<u8>;
+}
+
+/// A double-ended iterator of bytes, with its type hidden
+trait Double: Forward {
+    fn pull_back(&mut self) -> Option<u8>;
+    fn pull_nth_back(&mut self, n: usize) -> Option<u8>;
+    /// Collect every item with the `rfold` of the iterator itself
+    fn drain_back(self: Box<Self>) -> Vec<u8>;
+}
+
+/// A double-ended iterator of bytes of known size, with its type hidden
+trait Exact: Double {
+    fn exact_len(&self) -> usize;
+}
+
+impl<I: Iterator<Item = u8>> Forward for I {
+    fn pull(&mut self) -> Option<u8> {
+        self.next()
+    }
+
+    fn pull_nth(&mut self, n: usize) -> Option<u8> {
+        self.nth(n)
+    }
+
+    fn hint(&self) -> (usize, Option<usize>) {
+        self.size_hint()
+    }
+
+    fn drain(self: Box<Self>) -> Vec<u8> {
+        (*self).fold(Vec::new(), |mut items, item| {
+            items.push(item);
+            items
+        })
+    }
+}
+
+impl<I: DoubleEndedIterator<Item = u8>> Double for I {
+    fn pull_back(&mut self) -> Option<u8> {
+        self.next_back()
+    }
+
+    fn pull_nth_back(&mut self, n: usize) -> Option<u8> {
+        self.nth_back(n)
+    }
+
+    fn drain_back(self: Box<Self>) -> Vec<u8> {
+        (*self).rfold(Vec::new(), |mut items, item| {
+            items.push(item);
+            items
+        })
+    }
+}
+
+impl<I: DoubleEndedIterator<Item = u8> + ExactSizeIterator> Exact for I {
+    fn exact_len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// A boxed stage, an iterator again so the next stage can adapt it
+///
+/// Every method the stage overrides is passed through to it, `fold` and
+/// `rfold` included, so that each adaptor is driven through its own methods
+/// by the adaptor after it.
+struct Dyn<T: ?Sized>(Box<T>);
+
+macro_rules! impl_dyn_iterator {
+    ($stage:ident) => {
+        impl Iterator for Dyn<dyn $stage> {
+            type Item = u8;
+
+            fn next(&mut self) -> Option<u8> {
+                self.0.pull()
+            }
+
+            fn nth(&mut self, n: usize) -> Option<u8> {
+                self.0.pull_nth(n)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.hint()
+            }
+
+            fn fold<B, F>(self, init: B, f: F) -> B
+            where
+                F: FnMut(B, u8) -> B,
+            {
+                self.0.drain().into_iter().fold(init, f)
+            }
+        }
+    };
+}
+
+macro_rules! impl_dyn_double_ended_iterator {
+    ($stage:ident) => {
+        impl DoubleEndedIterator for Dyn<dyn $stage> {
+            fn next_back(&mut self) -> Option<u8> {
+                self.0.pull_back()
+            }
+
+            fn nth_back(&mut self, n: usize) -> Option<u8> {
+                self.0.pull_nth_back(n)
+            }
+
+            fn rfold<B, F>(self, init: B, f: F) -> B
+            where
+                F: FnMut(B, u8) -> B,
+            {
+                self.0.drain_back().into_iter().fold(init, f)
+            }
+        }
+    };
+}
+
+impl_dyn_iterator!(Forward);
+impl_dyn_iterator!(Double);
+impl_dyn_iterator!(Exact);
+impl_dyn_double_ended_iterator!(Double);
+impl_dyn_double_ended_iterator!(Exact);
+
+impl ExactSizeIterator for Dyn<dyn Exact> {
+    fn len(&self) -> usize {
+        self.0.exact_len()
+    }
+}
+
+/// A pipeline by the most it can do
+enum Shape {
+    Forward(Box<dyn Forward>),
+    Double(Box<dyn Double>),
+    Exact(Box<dyn Exact>),
+}
+
+/// Adapt the stage of `$shape` as `$it` by `$adapted`, into the shape each
+/// of `[$forward, $double, $exact]` says
+macro_rules! adapt {
+    ($shape:expr, |$it:ident| $adapted:expr, [$forward:ident, $double:ident, $exact:ident]) => {
+        match $shape {
+            Shape::Forward(stage) => {
+                let $it = Dyn(stage);
+                Shape::$forward(Box::new($adapted))
+            }
+            Shape::Double(stage) => {
+                let $it = Dyn(stage);
+                Shape::$double(Box::new($adapted))
+            }
+            Shape::Exact(stage) => {
+                let $it = Dyn(stage);
+                Shape::$exact(Box::new($adapted))
+            }
+        }
+    };
+}
+
+/// A pipeline of iterator adaptors over a `Vec<u8>`
+///
+/// Each stage is boxed, and the box is as double-ended or exact in size as
+/// std makes the adaptor in it: `filter` loses the size of a pipeline, for
+/// instance, and `skip_while` loses both. Ops that the pipeline can't do are
+/// ignored.
+pub struct Pipeline {
+    shape: Shape,
+    depth: usize,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pipeline {
+    /// Construct a new `Pipeline` over no items
+    pub fn new() -> Self {
+        Pipeline {
+            shape: Shape::Exact(Box::new(Vec::new().into_iter())),
+            depth: 0,
+        }
+    }
+
+    /// The number of stages after the source
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Whether the pipeline is a `DoubleEndedIterator`
+    pub fn is_double_ended(&self) -> bool {
+        match self.shape {
+            Shape::Forward(_) => false,
+            Shape::Double(_) | Shape::Exact(_) => true,
+        }
+    }
+
+    /// The `len` of the pipeline, if it is an `ExactSizeIterator`
+    pub fn exact_len(&self) -> Option<usize> {
+        match self.shape {
+            Shape::Exact(ref stage) => Some(stage.exact_len()),
+            _ => None,
+        }
+    }
+
+    /// The `size_hint` of the pipeline
+    pub fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.shape {
+            Shape::Forward(ref stage) => stage.hint(),
+            Shape::Double(ref stage) => stage.hint(),
+            Shape::Exact(ref stage) => stage.hint(),
+        }
+    }
+
+    /// Start again from `items`
+    pub fn source(&mut self, items: &[u8]) {
+        self.shape = Shape::Exact(Box::new(Vec::from(items).into_iter()));
+        self.depth = 0;
+    }
+
+    /// Add a stage by the adaptor of `op`, which must be one
+    ///
+    /// A `Peekable` stage is peeked at as often as `op` says, and each time
+    /// must show `front`.
+    pub fn adapt(&mut self, op: &Op, front: Option<u8>) {
+        let shape = mem::replace(
+            &mut self.shape,
+            Shape::Exact(Box::new(Vec::new().into_iter())),
+        );
+        self.shape = match *op {
+            Op::Map { add } => adapt!(
+                shape,
+                |it| it.map(move |x| x.wrapping_add(add)),
+                [Forward, Double, Exact]
+            ),
+            Op::Filter { modulus } => adapt!(
+                shape,
+                |it| it.filter(keep(modulus)),
+                [Forward, Double, Double]
+            ),
+            Op::Skip { n } => adapt!(shape, |it| it.skip(n as usize), [Forward, Forward, Exact]),
+            Op::Take { n } => adapt!(shape, |it| it.take(n as usize), [Forward, Forward, Exact]),
+            Op::StepBy { step: n } => {
+                adapt!(shape, |it| it.step_by(step(n)), [Forward, Forward, Exact])
+            }
+            Op::Chain { ref items } => {
+                let items = items.0.clone();
+                adapt!(shape, |it| it.chain(items), [Forward, Double, Double])
+            }
+            Op::Zip { ref items } => {
+                let items = items.0.clone();
+                adapt!(
+                    shape,
+                    |it| it.zip(items).map(|(a, b)| a ^ b),
+                    [Forward, Forward, Exact]
+                )
+            }
+            Op::Rev => match shape {
+                Shape::Double(stage) => Shape::Double(Box::new(Dyn(stage).rev())),
+                Shape::Exact(stage) => Shape::Exact(Box::new(Dyn(stage).rev())),
+                Shape::Forward(_) => panic!("cannot reverse a pipeline that isn't double-ended"),
+            },
+            Op::Flatten { modulus } => adapt!(
+                shape,
+                |it| Iterator::flatten(it.map(move |x| vec![x; repeats(modulus, x)])),
+                [Forward, Double, Double]
+            ),
+            Op::Peekable { peeks } => adapt!(
+                shape,
+                |it| {
+                    let mut it = it.peekable();
+                    for _ in 0..peeks % 3 {
+                        check_eq!(it.peek().cloned(), front);
+                    }
+                    it
+                },
+                [Forward, Double, Exact]
+            ),
+            Op::Fuse => adapt!(shape, |it| it.fuse(), [Forward, Double, Exact]),
+            Op::SkipWhile { modulus } => adapt!(
+                shape,
+                |it| it.skip_while(keep(modulus)),
+                [Forward, Forward, Forward]
+            ),
+            Op::Enumerate => adapt!(
+                shape,
+                |it| it.enumerate().map(|(i, x)| x.wrapping_add(i as u8)),
+                [Forward, Forward, Exact]
+            ),
+            _ => panic!("{} is not an adaptor", op),
+        };
+        self.depth += 1;
+    }
+
+    /// Yield the next item
+    pub fn pop_front(&mut self) -> Option<u8> {
+        match self.shape {
+            Shape::Forward(ref mut stage) => stage.pull(),
+            Shape::Double(ref mut stage) => stage.pull(),
+            Shape::Exact(ref mut stage) => stage.pull(),
+        }
+    }
+
+    /// Drop `n` items and yield the next
+    pub fn nth(&mut self, n: usize) -> Option<u8> {
+        match self.shape {
+            Shape::Forward(ref mut stage) => stage.pull_nth(n),
+            Shape::Double(ref mut stage) => stage.pull_nth(n),
+            Shape::Exact(ref mut stage) => stage.pull_nth(n),
+        }
+    }
+
+    /// Yield the last item, which the pipeline must be double-ended for
+    pub fn pop_back(&mut self) -> Option<u8> {
+        match self.shape {
+            Shape::Double(ref mut stage) => stage.pull_back(),
+            Shape::Exact(ref mut stage) => stage.pull_back(),
+            Shape::Forward(_) => panic!("the pipeline isn't double-ended"),
+        }
+    }
+
+    /// Drop `n` items from the back and yield the next from there, which the
+    /// pipeline must be double-ended for
+    pub fn nth_back(&mut self, n: usize) -> Option<u8> {
+        match self.shape {
+            Shape::Double(ref mut stage) => stage.pull_nth_back(n),
+            Shape::Exact(ref mut stage) => stage.pull_nth_back(n),
+            Shape::Forward(_) => panic!("the pipeline isn't double-ended"),
+        }
+    }
+
+    /// Yield every item by `fold`, leaving an empty pipeline
+    pub fn fold(&mut self) -> Vec<u8> {
+        match mem::take(self).shape {
+            Shape::Forward(stage) => stage.drain(),
+            Shape::Double(stage) => stage.drain(),
+            Shape::Exact(stage) => stage.drain(),
+        }
+    }
+
+    /// Yield every item by `rfold`, leaving an empty pipeline, which the
+    /// pipeline must be double-ended for
+    pub fn rfold(&mut self) -> Vec<u8> {
+        match mem::take(self).shape {
+            Shape::Double(stage) => stage.drain_back(),
+            Shape::Exact(stage) => stage.drain_back(),
+            Shape::Forward(_) => panic!("the pipeline isn't double-ended"),
+        }
+    }
+}
+
+/// The `Op` defines the stages a pipeline of the `iter` target is built
+/// from and the ways it is driven.
+///
+/// `Source` starts a new pipeline, and the adaptors from `Map` to
+/// `Enumerate` add a stage to it. The rest drive it.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `Vec::into_iter`, starting a new pipeline
+    #[op(tag = 0)]
+    Source {
+        /// The items of the new pipeline
+        items: Bytes,
+    },
+    /// This operation triggers `Iterator::map`
+    #[op(tag = 1)]
+    Map {
+        /// The byte to add, wrapping, to every item
+        add: u8,
+    },
+    /// This operation triggers `Iterator::filter`
+    #[op(tag = 2)]
+    Filter {
+        /// Keep the items that aren't multiples of `modulus % 8 + 2`
+        modulus: u8,
+    },
+    /// This operation triggers `Iterator::skip`
+    #[op(tag = 3)]
+    Skip {
+        /// The number of items to skip
+        n: u8,
+    },
+    /// This operation triggers `Iterator::take`
+    #[op(tag = 4)]
+    Take {
+        /// The number of items to take
+        n: u8,
+    },
+    /// This operation triggers `Iterator::step_by`
+    #[op(tag = 5)]
+    StepBy {
+        /// Step by `step % 8 + 1`
+        step: u8,
+    },
+    /// This operation triggers `Iterator::chain`
+    #[op(tag = 6)]
+    Chain {
+        /// The items to yield after the pipeline's
+        items: Bytes,
+    },
+    /// This operation triggers `Iterator::zip`, xoring each pair
+    #[op(tag = 7)]
+    Zip {
+        /// The items to pair the pipeline's with
+        items: Bytes,
+    },
+    /// This operation triggers `Iterator::rev`
+    #[op(tag = 8)]
+    Rev,
+    /// This operation triggers `Iterator::flatten`
+    #[op(tag = 9)]
+    Flatten {
+        /// Repeat each item its remainder by `modulus % 3 + 2` times
+        modulus: u8,
+    },
+    /// This operation triggers `Iterator::peekable` and `Peekable::peek`
+    #[op(tag = 10)]
+    Peekable {
+        /// Peek `peeks % 3` times
+        peeks: u8,
+    },
+    /// This operation triggers `Iterator::fuse`
+    #[op(tag = 11)]
+    Fuse,
+    /// This operation triggers `Iterator::skip_while`
+    #[op(tag = 12)]
+    SkipWhile {
+        /// Skip while items aren't multiples of `modulus % 8 + 2`
+        modulus: u8,
+    },
+    /// This operation triggers `Iterator::enumerate`, adding each index to
+    /// its item
+    #[op(tag = 13)]
+    Enumerate,
+    /// This operation triggers `Iterator::next`
+    #[op(tag = 14, weight = 4)]
+    Next,
+    /// This operation triggers `DoubleEndedIterator::next_back`
+    #[op(tag = 15, weight = 4)]
+    NextBack,
+    /// This operation triggers `Iterator::nth`
+    #[op(tag = 16, weight = 2)]
+    Nth {
+        /// The number of items to drop first
+        n: u8,
+    },
+    /// This operation triggers `DoubleEndedIterator::nth_back`
+    #[op(tag = 17, weight = 2)]
+    NthBack {
+        /// The number of items to drop first
+        n: u8,
+    },
+    /// This operation triggers `Iterator::fold`
+    #[op(tag = 18)]
+    Fold,
+    /// This operation triggers `DoubleEndedIterator::rfold`
+    #[op(tag = 19)]
+    Rfold,
+}
+
+/// Check that the size hint of `sut`, and its `len` if it has one, are
+/// honest about the items `model` has left
+fn check_size(sut: &Pipeline, model: &PropIter) {
+    let left = model.len();
+    let (lower, upper) = sut.size_hint();
+    check!(
+        lower <= left && upper.into_iter().all(|upper| left <= upper),
+        "size_hint {:?} does not bound {}",
+        (lower, upper),
+        left
+    );
+    if let Some(len) = sut.exact_len() {
+        check_eq!((lower, upper), (left, Some(left)));
+        check_eq!(len, left);
+    }
+}
+
+/// Run `op` against `sut` and `model`, panicking if they disagree
+///
+/// Stages past [`MAX_DEPTH`], `chain` and `flatten` stages past
+/// [`MAX_ITEMS`], and the ops from the back of a pipeline that isn't
+/// double-ended are ignored.
+pub fn check_op(op: &Op, sut: &mut Pipeline, model: &mut PropIter) {
+    match *op {
+        Op::Source { ref items } => {
+            sut.source(&items.0);
+            model.source(&items.0);
+        }
+        Op::Next => check_eq!(sut.pop_front(), model.pop_front()),
+        Op::Nth { n } => check_eq!(sut.nth(n as usize), model.nth(n as usize)),
+        Op::Fold => check_eq!(sut.fold(), model.fold()),
+        Op::NextBack | Op::NthBack { .. } | Op::Rfold | Op::Rev if !sut.is_double_ended() => {}
+        Op::NextBack => check_eq!(sut.pop_back(), model.pop_back()),
+        Op::NthBack { n } => check_eq!(sut.nth_back(n as usize), model.nth_back(n as usize)),
+        Op::Rfold => check_eq!(sut.rfold(), model.rfold()),
+        _ if sut.depth() >= MAX_DEPTH => {}
+        Op::Chain { ref items } if model.len() + items.0.len() > MAX_ITEMS => {}
+        Op::Flatten { modulus }
+            if model
+                .items
+                .iter()
+                .map(|&x| repeats(modulus, x))
+                .sum::<usize>()
+                > MAX_ITEMS => {}
+        _ => {
+            sut.adapt(op, model.front());
+            match *op {
+                Op::Map { add } => model.map(add),
+                Op::Filter { modulus } => model.filter(keep(modulus)),
+                Op::Skip { n } => model.skip(n as usize),
+                Op::Take { n } => model.take(n as usize),
+                Op::StepBy { step: n } => model.step_by(step(n)),
+                Op::Chain { ref items } => model.chain(&items.0),
+                Op::Zip { ref items } => model.zip(&items.0),
+                Op::Rev => model.rev(),
+                Op::Flatten { modulus } => model.flatten(modulus),
+                Op::SkipWhile { modulus } => model.skip_while(keep(modulus)),
+                Op::Enumerate => model.enumerate(),
+                _ => {}
+            }
+        }
+    }
+    check_size(sut, model);
+}
+
+/// Decode the input of the `iter` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `iter` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against a [`Pipeline`] and [`PropIter`]
+///
+/// Every op is applied to both the model and the system under test, panicking
+/// as soon as the two disagree. The run is counted in [`stats`], if
+/// collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let mut model = PropIter::new();
+    let mut sut = Pipeline::new();
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        check_op(op, &mut sut, &mut model);
+        stats::size(TARGET, model.len());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(ops: &[Op]) -> (Pipeline, PropIter) {
+        let mut sut = Pipeline::new();
+        let mut model = PropIter::new();
+        for op in ops {
+            check_op(op, &mut sut, &mut model);
+        }
+        (sut, model)
+    }
+
+    fn source(items: &[u8]) -> Op {
+        Op::Source {
+            items: Bytes(items.to_vec()),
+        }
+    }
+
+    #[test]
+    fn adaptors_keep_what_they_can() {
+        let (sut, _) = run(&[source(&[1, 2, 3]), Op::Map { add: 1 }, Op::Skip { n: 1 }]);
+        assert_eq!(Some(2), sut.exact_len());
+        let (sut, _) = run(&[source(&[1, 2, 3]), Op::Filter { modulus: 0 }]);
+        assert_eq!(None, sut.exact_len());
+        assert!(sut.is_double_ended());
+        let (sut, _) = run(&[
+            source(&[1, 2, 3]),
+            Op::Filter { modulus: 0 },
+            Op::Take { n: 2 },
+        ]);
+        assert!(!sut.is_double_ended());
+    }
+
+    #[test]
+    fn ops_from_the_back_wait_for_a_double_ended_pipeline() {
+        let (mut sut, mut model) = run(&[
+            source(&[1, 2, 3]),
+            Op::SkipWhile { modulus: 0 },
+            Op::NextBack,
+            Op::Rev,
+        ]);
+        assert_eq!(vec![2, 3], model.fold());
+        assert_eq!(vec![2, 3], sut.fold());
+    }
+
+    #[test]
+    fn model_steps_from_the_first_item() {
+        let mut model = PropIter::new();
+        model.source(&[0, 1, 2, 3, 4, 5, 6]);
+        model.step_by(3);
+        assert_eq!(vec![0, 3, 6], model.fold());
+    }
+
+    #[test]
+    fn pipelines_are_capped() {
+        let mut ops = vec![source(&[1])];
+        for _ in 0..MAX_DEPTH + 1 {
+            ops.push(Op::Fuse);
+        }
+        let (sut, _) = run(&ops);
+        assert_eq!(MAX_DEPTH, sut.depth());
+        let flatten = Op::Flatten { modulus: 2 };
+        let (_, model) = run(&[
+            source(&[0xff; 255]),
+            flatten.clone(),
+            flatten.clone(),
+            flatten,
+        ]);
+        assert_eq!(255 * 3 * 3, model.len());
+    }
+
+    quickcheck! {
+        fn pipelines_agree(items: Vec<u8>, stages: Vec<(u8, u8)>, steps: Vec<(u8, u8)>) -> bool {
+            let mut sut = Pipeline::new();
+            let mut model = PropIter::new();
+            check_op(&source(&items), &mut sut, &mut model);
+            for &(tag, x) in &stages {
+                let op = match tag % 13 {
+                    0 => Op::Map { add: x },
+                    1 => Op::Filter { modulus: x },
+                    2 => Op::Skip { n: x },
+                    3 => Op::Take { n: x },
+                    4 => Op::StepBy { step: x },
+                    5 => Op::Chain { items: Bytes(vec![x; x as usize % 5]) },
+                    6 => Op::Zip { items: Bytes(items.iter().map(|&i| i ^ x).collect()) },
+                    7 => Op::Rev,
+                    8 => Op::Flatten { modulus: x },
+                    9 => Op::Peekable { peeks: x },
+                    10 => Op::Fuse,
+                    11 => Op::SkipWhile { modulus: x },
+                    _ => Op::Enumerate,
+                };
+                check_op(&op, &mut sut, &mut model);
+            }
+            for &(tag, n) in &steps {
+                let op = match tag % 6 {
+                    0 => Op::Next,
+                    1 => Op::NextBack,
+                    2 => Op::Nth { n: n % 4 },
+                    3 => Op::NthBack { n: n % 4 },
+                    4 => Op::Fold,
+                    _ => Op::Rfold,
+                };
+                check_op(&op, &mut sut, &mut model);
+            }
+            true
+        }
+    }
+}
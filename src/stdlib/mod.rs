@@ -1,2 +1,12 @@
 //! Tests for `std`
+pub mod cell;
+pub mod char;
 pub mod collections;
+pub mod iter;
+pub mod num;
+pub mod rc;
+pub mod slice;
+pub mod str;
+pub mod string;
+pub mod sync;
+pub mod util;
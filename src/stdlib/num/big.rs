@@ -0,0 +1,463 @@
+//! A small arbitrary-precision integer
+//!
+//! The models of `std::num` work out the exact result of an operation and only
+//! then ask whether it fits the type at hand. Exact results outgrow even
+//! `i128` -- the product of two `u128`, or an `i8` raised to the 255th power --
+//! so they are kept in a [`Big`]. Nothing here is quick. It is long
+//! multiplication and long division, a bit at a time or, by a single digit, a
+//! digit at a time, which is hard to get wrong in the same way as the code
+//! under test.
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// An integer of any size
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Big {
+    /// Whether the integer is negative, which zero never is
+    neg: bool,
+    /// The magnitude in base 2^32 digits, least significant first and with no
+    /// zero digits at the top
+    mag: Vec<u32>,
+}
+
+/// Drop the zero digits at the top of `mag`
+fn trim(mut mag: Vec<u32>) -> Vec<u32> {
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    mag
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut sum = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let digit = u64::from(*a.get(i).unwrap_or(&0)) + u64::from(*b.get(i).unwrap_or(&0)) + carry;
+        sum.push(digit as u32);
+        carry = digit >> 32;
+    }
+    sum.push(carry as u32);
+    trim(sum)
+}
+
+/// Subtract `b` from `a`, which must be no less than it
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    debug_assert!(cmp_mag(a, b) != Ordering::Less);
+    let mut diff = Vec::with_capacity(a.len());
+    let mut borrow = 0u64;
+    for (i, &digit) in a.iter().enumerate() {
+        let sub = u64::from(*b.get(i).unwrap_or(&0)) + borrow;
+        let digit = u64::from(digit);
+        if digit >= sub {
+            diff.push((digit - sub) as u32);
+            borrow = 0;
+        } else {
+            diff.push((digit + (1 << 32) - sub) as u32);
+            borrow = 1;
+        }
+    }
+    trim(diff)
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut prod = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            // (2^32 - 1)^2 + 2 (2^32 - 1) is 2^64 - 1, so this never overflows.
+            let digit = u64::from(x) * u64::from(y) + u64::from(prod[i + j]) + carry;
+            prod[i + j] = digit as u32;
+            carry = digit >> 32;
+        }
+        prod[i + b.len()] = carry as u32;
+    }
+    trim(prod)
+}
+
+fn bit_len_mag(mag: &[u32]) -> u32 {
+    let mut bits = 32 * mag.len().saturating_sub(1) as u32;
+    let mut top = mag.last().cloned().unwrap_or(0);
+    while top != 0 {
+        bits += 1;
+        top >>= 1;
+    }
+    bits
+}
+
+fn bit_mag(mag: &[u32], bit: u32) -> bool {
+    match mag.get((bit / 32) as usize) {
+        Some(&digit) => digit >> (bit % 32) & 1 == 1,
+        None => false,
+    }
+}
+
+/// Divide `a` by `b`, which must not be zero, giving quotient and remainder
+fn div_rem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    assert!(!b.is_empty(), "division by zero");
+    if b.len() == 1 {
+        // Dividing by a single limb, as when writing out digits, goes a limb
+        // at a time rather than a bit at a time.
+        let divisor = u64::from(b[0]);
+        let mut quot = vec![0u32; a.len()];
+        let mut rem = 0u64;
+        for idx in (0..a.len()).rev() {
+            let cur = rem << 32 | u64::from(a[idx]);
+            quot[idx] = (cur / divisor) as u32;
+            rem = cur % divisor;
+        }
+        return (trim(quot), trim(vec![rem as u32]));
+    }
+    let mut quot = vec![0u32; a.len()];
+    let mut rem: Vec<u32> = Vec::new();
+    for bit in (0..bit_len_mag(a)).rev() {
+        // rem = rem * 2 + the next bit of a
+        let mut carry = u32::from(bit_mag(a, bit));
+        for digit in &mut rem {
+            let next = *digit >> 31;
+            *digit = *digit << 1 | carry;
+            carry = next;
+        }
+        rem.push(carry);
+        rem = trim(rem);
+        if cmp_mag(&rem, b) != Ordering::Less {
+            rem = sub_mag(&rem, b);
+            quot[(bit / 32) as usize] |= 1 << (bit % 32);
+        }
+    }
+    (trim(quot), rem)
+}
+
+impl Big {
+    fn from_parts(neg: bool, mag: Vec<u32>) -> Self {
+        let mag = trim(mag);
+        Big {
+            neg: neg && !mag.is_empty(),
+            mag,
+        }
+    }
+
+    /// Construct a `Big` of zero
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    /// Construct a `Big` of `2^exp`
+    pub fn pow2(exp: u32) -> Self {
+        let mut mag = vec![0u32; (exp / 32) as usize + 1];
+        mag[(exp / 32) as usize] = 1 << (exp % 32);
+        Self::from_parts(false, mag)
+    }
+
+    /// Whether the integer is zero
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    /// Whether the integer is less than zero
+    pub fn is_negative(&self) -> bool {
+        self.neg
+    }
+
+    /// The absolute value of the integer
+    pub fn abs(&self) -> Self {
+        Self::from_parts(false, self.mag.clone())
+    }
+
+    /// The number of bits in the magnitude of the integer, without leading
+    /// zeros
+    pub fn bit_len(&self) -> u32 {
+        bit_len_mag(&self.mag)
+    }
+
+    /// Raise the integer to the power `exp`
+    pub fn pow(&self, exp: u32) -> Self {
+        let mut acc = Big::from(1u128);
+        for _ in 0..exp {
+            acc = acc * self.clone();
+        }
+        acc
+    }
+
+    /// Divide by `rhs`, rounding the quotient towards zero
+    ///
+    /// The remainder takes the sign of `self`, as the `/` and `%` operators of
+    /// the primitive integers do.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` is zero.
+    pub fn div_rem(&self, rhs: &Big) -> (Big, Big) {
+        let (quot, rem) = div_rem_mag(&self.mag, &rhs.mag);
+        (
+            Self::from_parts(self.neg != rhs.neg, quot),
+            Self::from_parts(self.neg, rem),
+        )
+    }
+
+    /// Divide by `rhs`, leaving a remainder no less than zero
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rhs` is zero.
+    pub fn div_rem_euclid(&self, rhs: &Big) -> (Big, Big) {
+        let (quot, rem) = self.div_rem(rhs);
+        if !rem.is_negative() {
+            return (quot, rem);
+        }
+        let one = Big::from(1u128);
+        if rhs.is_negative() {
+            (quot + one, rem - rhs.clone())
+        } else {
+            (quot - one, rem + rhs.clone())
+        }
+    }
+
+    /// The greatest integer whose square is no greater than `self`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the integer is negative.
+    pub fn isqrt(&self) -> Self {
+        assert!(!self.neg, "square root of a negative integer");
+        let mut root = Big::zero();
+        for bit in (0..=self.bit_len() / 2).rev() {
+            let next = root.clone() + Big::pow2(bit);
+            if next.clone() * next.clone() <= *self {
+                root = next;
+            }
+        }
+        root
+    }
+
+    /// The greatest `k` such that `base^k` is no greater than `self`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the integer is not positive or `base` is less than two.
+    pub fn ilog(&self, base: &Big) -> u32 {
+        assert!(!self.neg && !self.is_zero(), "logarithm of a non-positive integer");
+        assert!(*base > Big::from(1u128), "logarithm to a base less than two");
+        let mut k = 0;
+        let mut power = base.clone();
+        while power <= *self {
+            k += 1;
+            power = power * base.clone();
+        }
+        k
+    }
+
+    /// The lowest `bits` bits of the integer in two's complement, at most 128
+    ///
+    /// This is the integer modulo `2^bits`.
+    pub fn low_bits(&self, bits: u32) -> u128 {
+        assert!(bits <= 128, "no more than 128 bits fit a u128");
+        let full = bits / 32;
+        let mut mag: Vec<u32> = self.mag.iter().take(full as usize).cloned().collect();
+        let partial = bits - 32 * full;
+        if let (true, Some(&digit)) = (partial > 0, self.mag.get(full as usize)) {
+            mag.push(digit & ((1 << partial) - 1));
+        }
+        let low = Self::from_parts(false, mag);
+        // A negative integer is the modulus less its magnitude, bar zero.
+        let low = if self.neg && !low.is_zero() {
+            Big::pow2(bits) - low
+        } else {
+            low
+        };
+        low.mag
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &digit)| acc | u128::from(digit) << (32 * i))
+    }
+}
+
+/// The digits of the bases up to 36, in order
+pub const DIGITS: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+impl Big {
+    /// Write the integer in base `radix`, with a `-` if it is negative
+    ///
+    /// The digits past nine are the lowercase letters, as in [`DIGITS`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in `2..=36`.
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        assert!((2..=36).contains(&radix), "radix {} is not in 2..=36", radix);
+        let radix = Big::from(u128::from(radix));
+        let mut digits = Vec::new();
+        let mut rest = self.abs();
+        loop {
+            let (quot, rem) = rest.div_rem(&radix);
+            digits.push(DIGITS.as_bytes()[rem.low_bits(8) as usize] as char);
+            rest = quot;
+            if rest.is_zero() {
+                break;
+            }
+        }
+        if self.neg {
+            digits.push('-');
+        }
+        digits.into_iter().rev().collect()
+    }
+}
+
+impl fmt::Display for Big {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_str_radix(10))
+    }
+}
+
+impl From<u128> for Big {
+    fn from(x: u128) -> Self {
+        let mag = (0..4).map(|i| (x >> (32 * i)) as u32).collect();
+        Self::from_parts(false, mag)
+    }
+}
+
+impl From<i128> for Big {
+    fn from(x: i128) -> Self {
+        if x >= 0 {
+            return Big::from(x as u128);
+        }
+        // The magnitude of i128::MIN doesn't fit an i128, so the two's
+        // complement is undone on the bits instead.
+        let mag = Big::from(!(x as u128)) + Big::from(1u128);
+        Self::from_parts(true, mag.mag)
+    }
+}
+
+impl Ord for Big {
+    fn cmp(&self, other: &Big) -> Ordering {
+        match (self.neg, other.neg) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &other.mag),
+            (true, true) => cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for Big {
+    fn partial_cmp(&self, other: &Big) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for Big {
+    type Output = Big;
+
+    fn neg(self) -> Big {
+        let neg = !self.neg;
+        Self::from_parts(neg, self.mag)
+    }
+}
+
+impl Add for Big {
+    type Output = Big;
+
+    fn add(self, rhs: Big) -> Big {
+        if self.neg == rhs.neg {
+            return Self::from_parts(self.neg, add_mag(&self.mag, &rhs.mag));
+        }
+        match cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => Self::from_parts(rhs.neg, sub_mag(&rhs.mag, &self.mag)),
+            _ => Self::from_parts(self.neg, sub_mag(&self.mag, &rhs.mag)),
+        }
+    }
+}
+
+impl Sub for Big {
+    type Output = Big;
+
+    fn sub(self, rhs: Big) -> Big {
+        self + -rhs
+    }
+}
+
+impl Mul for Big {
+    type Output = Big;
+
+    fn mul(self, rhs: Big) -> Big {
+        Self::from_parts(self.neg != rhs.neg, mul_mag(&self.mag, &rhs.mag))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn big(x: i64) -> Big {
+        Big::from(i128::from(x))
+    }
+
+    #[test]
+    fn products_outgrow_u128() {
+        let max = Big::from(u128::MAX);
+        let square = max.clone() * max.clone();
+        assert_eq!(256, square.bit_len());
+        assert_eq!((max.clone(), Big::zero()), square.div_rem(&max));
+        assert_eq!(1, square.low_bits(128));
+    }
+
+    #[test]
+    fn i128_min_is_exact() {
+        let min = Big::from(i128::MIN);
+        assert_eq!(-Big::pow2(127), min);
+        assert_eq!(1 << 127, min.low_bits(128));
+    }
+
+    #[test]
+    fn remainders_take_the_sign_of_the_dividend() {
+        assert_eq!((big(-2), big(-1)), big(-7).div_rem(&big(3)));
+        assert_eq!((big(-3), big(2)), big(-7).div_rem_euclid(&big(3)));
+        assert_eq!((big(3), big(2)), big(-7).div_rem_euclid(&big(-3)));
+    }
+
+    #[test]
+    fn digits_are_written_most_significant_first() {
+        assert_eq!("-1010", big(-10).to_str_radix(2));
+        assert_eq!("0", Big::zero().to_str_radix(36));
+        assert_eq!("zz", big(35 * 36 + 35).to_str_radix(36));
+        assert_eq!(u128::MAX.to_string(), Big::from(u128::MAX).to_string());
+    }
+
+    quickcheck! {
+        fn arithmetic_agrees_with_i128(a: i64, b: i64) -> bool {
+            let (x, y) = (i128::from(a), i128::from(b));
+            big(a) + big(b) == Big::from(x + y)
+                && big(a) - big(b) == Big::from(x - y)
+                && big(a) * big(b) == Big::from(x * y)
+                && big(a).cmp(&big(b)) == a.cmp(&b)
+        }
+
+        fn division_agrees_with_i128(a: i64, b: i64) -> bool {
+            let (x, y) = (i128::from(a), i128::from(b));
+            b == 0 || (big(a).div_rem(&big(b)) == (Big::from(x / y), Big::from(x % y))
+                && big(a).div_rem_euclid(&big(b))
+                    == (Big::from(x.div_euclid(y)), Big::from(x.rem_euclid(y))))
+        }
+
+        fn division_by_one_digit_agrees_with_u128(hi: u64, lo: u64, b: u32) -> bool {
+            let x = u128::from(hi) << 64 | u128::from(lo);
+            let y = u128::from(b);
+            b == 0 || Big::from(x).div_rem(&Big::from(y)) == (Big::from(x / y), Big::from(x % y))
+        }
+
+        fn isqrt_brackets_the_root(a: u64) -> bool {
+            let x = Big::from(u128::from(a));
+            let root = x.isqrt();
+            let next = root.clone() + Big::from(1u128);
+            root.clone() * root <= x && x < next.clone() * next
+        }
+
+        fn low_bits_are_twos_complement(a: i64) -> bool {
+            big(a).low_bits(64) == u128::from(a as u64) && big(a).low_bits(8) == u128::from(a as u8)
+        }
+    }
+}
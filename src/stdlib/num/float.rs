@@ -0,0 +1,925 @@
+//! Tests for the parsing and formatting of `f32` and `f64`
+//!
+//! A decimal string names an exact rational number, and parsing it must give
+//! the float nearest to that number, ties going to the float with the even
+//! significand. Writing a float out must give the fewest digits that parse
+//! back to it or, with a precision, its exact value rounded to that many
+//! places. We check parsing against [`parse`], which works the rational number
+//! out as a pair of [`Big`]s and rounds it by long division, and formatting
+//! against [`shortest`] and [`fixed`], which hold the candidate decimals up to
+//! the exact bounds of the float.
+use arbitrary::*;
+use encode::{Encode, VERSION};
+use op::{self, Profiles};
+use stats;
+use std::cmp::Ordering;
+use std::fmt;
+use std::num::ParseFloatError;
+use std::str::FromStr;
+use stdlib::num::big::Big;
+use trace::{self, Header, ParseError, Trace};
+
+/// The name of the fuzz target driving the float types
+pub const TARGET: &str = "num_float";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The float types, in the order the type byte of an input chooses them
+pub const TYPES: [&str; 2] = ["f32", "f64"];
+
+/// The most chars in a [`Numeral`]
+pub const MAX_NUMERAL_LEN: usize = 64;
+
+/// The most digits in [`Digits`]
+///
+/// This is past the 767 significant digits of the longest exact `f64`, so
+/// that every digit of a long mantissa may matter to its rounding.
+pub const MAX_DIGITS: usize = 800;
+
+/// The chars a [`Numeral`] is made of
+///
+/// These are the chars of decimal floats, those of the special values and a
+/// few that are never part of a float.
+pub const NUMERAL_CHARS: &str = "0123456789.eE+-infatyINFATY_x";
+
+lazy_static! {
+    /// The workload profiles of the `num_float` target, see [`Profiles`]
+    ///
+    /// `parse` sticks to parsing and `format` to formatting.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op<f64>>(&[
+        ("parse", &[("format", 0)]),
+        ("format", &[("parse", 0), ("digits", 0), ("halfway", 0)]),
+    ]);
+}
+
+/// A primitive float type, the system under test of the `num_float` target
+pub trait Float:
+    Copy
+    + fmt::Debug
+    + fmt::Display
+    + fmt::LowerExp
+    + fmt::UpperExp
+    + FromStr<Err = ParseFloatError>
+    + PartialEq
+    + Arbitrary
+    + Encode
+{
+    /// The name of the type, as written in trace headers
+    const NAME: &'static str;
+    /// The number of bits of the type
+    const BITS: u32;
+    /// The number of bits of the significand that are stored, which is all
+    /// but the leading one
+    const MANTISSA_BITS: u32;
+
+    /// The bits of the float, see `to_bits`
+    fn to_bits(self) -> u64;
+    /// The float of the given bits, see `from_bits`
+    fn from_bits(bits: u64) -> Self;
+}
+
+macro_rules! impl_float {
+    ($($ty:ident => $bits:ident, $mantissa:expr;)*) => {
+        $(
+            impl Float for $ty {
+                const NAME: &'static str = stringify!($ty);
+                const BITS: u32 = $bits::BITS;
+                const MANTISSA_BITS: u32 = $mantissa;
+
+                fn to_bits(self) -> u64 {
+                    u64::from($ty::to_bits(self))
+                }
+                fn from_bits(bits: u64) -> Self {
+                    $ty::from_bits(bits as $bits)
+                }
+            }
+        )*
+    };
+}
+
+impl_float! {
+    f32 => u32, 23;
+    f64 => u64, 52;
+}
+
+/// The number of bits of the exponent of `T`
+fn exponent_bits<T: Float>() -> u32 {
+    T::BITS - T::MANTISSA_BITS - 1
+}
+
+/// The bias of the exponent of `T`
+fn bias<T: Float>() -> i64 {
+    (1 << (exponent_bits::<T>() - 1)) - 1
+}
+
+/// The exponent of the last bit of the subnormals of `T`, the least there is
+pub fn min_exp<T: Float>() -> i64 {
+    1 - bias::<T>() - i64::from(T::MANTISSA_BITS)
+}
+
+/// The exponent of the last bit of the greatest finite `T`
+pub fn max_exp<T: Float>() -> i64 {
+    bias::<T>() - i64::from(T::MANTISSA_BITS)
+}
+
+/// What a float is, once its sign is taken off
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+    /// Not a number
+    Nan,
+    /// Infinity
+    Infinite,
+    /// The significand `m` times two to the `e`
+    Finite {
+        /// The significand, with the leading bit if the float is normal
+        m: u64,
+        /// The exponent of the last bit of the significand
+        e: i64,
+    },
+}
+
+/// Take `x` apart into its sign and its [`Value`]
+pub fn parts<T: Float>(x: T) -> (bool, Value) {
+    let bits = x.to_bits();
+    let neg = bits >> (T::BITS - 1) == 1;
+    let frac = bits & ((1 << T::MANTISSA_BITS) - 1);
+    let top = (1 << exponent_bits::<T>()) - 1;
+    let biased = (bits >> T::MANTISSA_BITS) & top;
+    let value = if biased == top {
+        if frac == 0 {
+            Value::Infinite
+        } else {
+            Value::Nan
+        }
+    } else if biased == 0 {
+        Value::Finite { m: frac, e: min_exp::<T>() }
+    } else {
+        Value::Finite { m: frac | 1 << T::MANTISSA_BITS, e: biased as i64 + min_exp::<T>() - 1 }
+    };
+    (neg, value)
+}
+
+/// The `T` of the given sign and value
+///
+/// The significand of a finite value must be less than two to the
+/// `MANTISSA_BITS + 1`, and if it is less than two to the `MANTISSA_BITS` the
+/// exponent must be [`min_exp`], as it is in [`parts`]. Past [`max_exp`] the
+/// value is infinite.
+pub fn assemble<T: Float>(neg: bool, value: Value) -> T {
+    let top: u64 = (1 << exponent_bits::<T>()) - 1;
+    let bits = match value {
+        Value::Nan => top << T::MANTISSA_BITS | 1 << (T::MANTISSA_BITS - 1),
+        Value::Infinite => top << T::MANTISSA_BITS,
+        Value::Finite { e, .. } if e > max_exp::<T>() => top << T::MANTISSA_BITS,
+        Value::Finite { m, .. } if m >> T::MANTISSA_BITS == 0 => m,
+        Value::Finite { m, e } => {
+            let biased = (e - min_exp::<T>() + 1) as u64;
+            biased << T::MANTISSA_BITS | (m & ((1 << T::MANTISSA_BITS) - 1))
+        }
+    };
+    T::from_bits(bits | (neg as u64) << (T::BITS - 1))
+}
+
+/// How a string fails to parse as a float
+///
+/// `ParseFloatError` keeps its kind to itself, so the kinds are told apart by
+/// comparing with the error of a string known to fail that way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloatErrorKind {
+    /// The string is empty
+    Empty,
+    /// The string is not a float
+    Invalid,
+}
+
+/// Parse `src` as a `T` with `FromStr`, keeping only the kind of error
+fn std_parse<T: Float>(src: &str) -> Result<T, FloatErrorKind> {
+    src.parse::<T>().map_err(|e| {
+        if e == "".parse::<T>().unwrap_err() {
+            FloatErrorKind::Empty
+        } else {
+            FloatErrorKind::Invalid
+        }
+    })
+}
+
+/// The two to the `exp`
+fn pow2(exp: i64) -> Big {
+    Big::pow2(exp as u32)
+}
+
+/// The ten to the `exp`
+fn pow10(exp: i64) -> Big {
+    Big::from(10u128).pow(exp as u32)
+}
+
+/// Compare `a` times ten to the `a10` with `b` times two to the `b2`
+fn cmp_scaled(a: &Big, a10: i64, b: &Big, b2: i64) -> Ordering {
+    let lhs = a.clone() * pow10(a10.max(0)) * pow2((-b2).max(0));
+    let rhs = b.clone() * pow2(b2.max(0)) * pow10((-a10).max(0));
+    lhs.cmp(&rhs)
+}
+
+/// The integer the decimal `digits` spell
+fn from_digits(digits: &str) -> Big {
+    let ten = Big::from(10u128);
+    digits.bytes().fold(Big::zero(), |acc, b| acc * ten.clone() + Big::from(u128::from(b - b'0')))
+}
+
+/// The `T` nearest to `digits` times ten to the `exp`, ties to even
+///
+/// The value is the fraction `num / den` of two [`Big`]s. It lies between two
+/// powers of two, which fix the exponent of the last bit of its significand,
+/// and the significand is then the quotient of a long division rounded by its
+/// remainder.
+fn nearest<T: Float>(neg: bool, digits: &str, exp: i64) -> T {
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return assemble(neg, Value::Finite { m: 0, e: min_exp::<T>() });
+    }
+    // The value is at least ten to the `magnitude - 1` and less than ten to
+    // the `magnitude`, and far past the range of `f64` either way there is
+    // nothing to work out.
+    let magnitude = exp + digits.len() as i64;
+    if magnitude > 400 {
+        return assemble(neg, Value::Infinite);
+    }
+    if magnitude < -400 {
+        return assemble(neg, Value::Finite { m: 0, e: min_exp::<T>() });
+    }
+    let (num, den) = if exp >= 0 {
+        (from_digits(digits) * pow10(exp), Big::from(1u128))
+    } else {
+        (from_digits(digits), pow10(-exp))
+    };
+    // The greatest power of two no greater than the value
+    let mut log2 = i64::from(num.bit_len()) - i64::from(den.bit_len());
+    if cmp_scaled(&num, 0, &den, log2) == Ordering::Less {
+        log2 -= 1;
+    }
+    let mut e = (log2 - i64::from(T::MANTISSA_BITS)).max(min_exp::<T>());
+    let (num, den) = if e >= 0 { (num, den * pow2(e)) } else { (num * pow2(-e), den) };
+    let (quot, rem) = num.div_rem(&den);
+    let mut m = quot.low_bits(64) as u64;
+    match (rem.clone() + rem).cmp(&den) {
+        Ordering::Greater => m += 1,
+        Ordering::Equal if m & 1 == 1 => m += 1,
+        _ => {}
+    }
+    if m >> (T::MANTISSA_BITS + 1) == 1 {
+        // Rounding up carried into a new bit.
+        m >>= 1;
+        e += 1;
+    }
+    assemble(neg, Value::Finite { m, e })
+}
+
+/// Parse `src` as a `T`
+///
+/// This is like to `FromStr` for the float types. A sign may lead, then comes
+/// `inf`, `infinity` or `nan` in any case, or else digits with at most one
+/// point among them and at least one digit. An exponent of `e` or `E`, a sign
+/// and at least one digit may follow the digits.
+pub fn parse<T: Float>(src: &str) -> Result<T, FloatErrorKind> {
+    let (neg, rest) = match src.bytes().next() {
+        None => return Err(FloatErrorKind::Empty),
+        Some(b'+') => (false, &src[1..]),
+        Some(b'-') => (true, &src[1..]),
+        Some(_) => (false, src),
+    };
+    match rest.to_ascii_lowercase().as_str() {
+        "inf" | "infinity" => return Ok(assemble(neg, Value::Infinite)),
+        "nan" => return Ok(assemble(neg, Value::Nan)),
+        _ => {}
+    }
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let (mantissa, exp) = match rest.find(&['e', 'E'][..]) {
+        Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+        None => (rest, None),
+    };
+    let (int, frac) = match mantissa.find('.') {
+        Some(idx) => (&mantissa[..idx], &mantissa[idx + 1..]),
+        None => (mantissa, ""),
+    };
+    if int.len() + frac.len() == 0 || !is_digits(int) || !is_digits(frac) {
+        return Err(FloatErrorKind::Invalid);
+    }
+    let exp = match exp {
+        None => 0,
+        Some(exp) => {
+            let (neg_exp, digits) = match exp.bytes().next() {
+                Some(b'+') => (false, &exp[1..]),
+                Some(b'-') => (true, &exp[1..]),
+                _ => (false, exp),
+            };
+            if digits.is_empty() || !is_digits(digits) {
+                return Err(FloatErrorKind::Invalid);
+            }
+            // Exponents only need to be big enough to be past every float.
+            let exp = digits.bytes().fold(0i64, |acc, b| (acc * 10 + i64::from(b - b'0')).min(1 << 40));
+            if neg_exp {
+                -exp
+            } else {
+                exp
+            }
+        }
+    };
+    Ok(nearest(neg, &format!("{}{}", int, frac), exp - frac.len() as i64))
+}
+
+/// A decimal `d.ddd` times ten to the `exp`, with no zeros at either end of
+/// its digits unless it is zero
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decimal {
+    /// The digits, the first of which is the ones digit
+    pub digits: String,
+    /// The power of ten of the first digit
+    pub exp: i64,
+}
+
+impl Decimal {
+    /// The decimal `int` times ten to the `exp`, `int` being decimal digits
+    fn new(int: &str, exp: i64) -> Self {
+        let digits = int.trim_start_matches('0');
+        if digits.is_empty() {
+            return Decimal { digits: "0".to_string(), exp: 0 };
+        }
+        Decimal {
+            digits: digits.trim_end_matches('0').to_string(),
+            exp: exp + digits.len() as i64 - 1,
+        }
+    }
+
+    /// Write the decimal out in full, with at least `min_frac` digits after
+    /// the point
+    fn positional(&self, min_frac: usize) -> String {
+        let (int, mut frac) = if self.exp >= 0 {
+            let split = self.digits.len().min(self.exp as usize + 1);
+            let mut int = self.digits[..split].to_string();
+            int.push_str(&"0".repeat(self.exp as usize + 1 - split));
+            (int, self.digits[split..].to_string())
+        } else {
+            ("0".to_string(), "0".repeat(-self.exp as usize - 1) + &self.digits)
+        };
+        if frac.len() < min_frac {
+            frac.push_str(&"0".repeat(min_frac - frac.len()));
+        }
+        if frac.is_empty() {
+            int
+        } else {
+            format!("{}.{}", int, frac)
+        }
+    }
+
+    /// Write the decimal out as `d.ddde<exp>`, with `e` being `marker`
+    fn exponential(&self, marker: char) -> String {
+        let (first, rest) = self.digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        format!("{}{}{}{}{}", first, point, rest, marker, self.exp)
+    }
+}
+
+/// The digits of `m` times two to the `e`, and the power of ten of the last
+fn exact_digits(m: u64, e: i64) -> (String, i64) {
+    let m = Big::from(u128::from(m));
+    if e >= 0 {
+        ((m * pow2(e)).to_string(), 0)
+    } else {
+        // Halving is multiplying by five and dividing by ten.
+        ((m * Big::from(5u128).pow(-e as u32)).to_string(), e)
+    }
+}
+
+/// The decimals of the fewest digits that parse back to the `T` of
+/// significand `m` and exponent `e`, as from [`parts`], nearest first
+///
+/// What parses back to a float is whatever lies between the midpoints to the
+/// floats either side of it, and the midpoints themselves if the significand
+/// is even. Each length of digits has two decimals bracketing the float, and
+/// the first length for which one of them is in bounds is the shortest. If
+/// both are, only the nearer will do, unless they are as near.
+pub fn shortest<T: Float>(m: u64, e: i64) -> Vec<Decimal> {
+    if m == 0 {
+        return vec![Decimal::new("0", 0)];
+    }
+    // The bounds in units of two to the `e - 2`. Below the least significand
+    // of a binade the floats are half as far apart.
+    let m = Big::from(u128::from(m));
+    let four = Big::from(4u128);
+    let high = four.clone() * m.clone() + Big::from(2u128);
+    let low = if m == Big::pow2(T::MANTISSA_BITS) && e > min_exp::<T>() {
+        four * m.clone() - Big::from(1u128)
+    } else {
+        four * m.clone() - Big::from(2u128)
+    };
+    let inclusive = m.low_bits(1) == 0;
+    let above_low = |c: &Big, exp: i64| match cmp_scaled(c, exp, &low, e - 2) {
+        Ordering::Greater => true,
+        Ordering::Equal => inclusive,
+        Ordering::Less => false,
+    };
+    let below_high = |c: &Big, exp: i64| match cmp_scaled(c, exp, &high, e - 2) {
+        Ordering::Less => true,
+        Ordering::Equal => inclusive,
+        Ordering::Greater => false,
+    };
+
+    let (digits, last) = exact_digits(m.low_bits(64) as u64, e);
+    for len in 1..=digits.len() {
+        let (head, rest) = digits.split_at(len);
+        let exp = last + rest.len() as i64;
+        let lo = from_digits(head);
+        if rest.bytes().all(|b| b == b'0') {
+            return vec![Decimal::new(head, exp)];
+        }
+        let hi = lo.clone() + Big::from(1u128);
+        let lo_in = above_low(&lo, exp);
+        let hi_in = below_high(&hi, exp);
+        let half = format!("5{}", "0".repeat(rest.len() - 1));
+        let (lo, hi) = (Decimal::new(head, exp), Decimal::new(&hi.to_string(), exp));
+        match (lo_in, hi_in) {
+            (true, true) => match rest.cmp(&half) {
+                Ordering::Less => return vec![lo],
+                Ordering::Greater => return vec![hi],
+                Ordering::Equal => return vec![lo, hi],
+            },
+            (true, false) => return vec![lo],
+            (false, true) => return vec![hi],
+            (false, false) => {}
+        }
+    }
+    unreachable!("the float itself is in bounds")
+}
+
+/// Write `m` times two to the `e` with `precision` digits after the point,
+/// rounded half to even
+///
+/// This is like to `{:.N}` on the absolute value of a float.
+pub fn fixed(m: u64, e: i64, precision: usize) -> String {
+    let m = Big::from(u128::from(m)) * pow10(precision as i64);
+    let (num, den) = if e >= 0 { (m * pow2(e), Big::from(1u128)) } else { (m, pow2(-e)) };
+    let (mut quot, rem) = num.div_rem(&den);
+    match (rem.clone() + rem).cmp(&den) {
+        Ordering::Greater => quot = quot + Big::from(1u128),
+        Ordering::Equal if quot.low_bits(1) == 1 => quot = quot + Big::from(1u128),
+        _ => {}
+    }
+    let mut digits = quot.to_string();
+    if digits.len() <= precision {
+        digits = "0".repeat(precision + 1 - digits.len()) + &digits;
+    }
+    let split = digits.len() - precision;
+    if precision == 0 {
+        digits
+    } else {
+        format!("{}.{}", &digits[..split], &digits[split..])
+    }
+}
+
+/// Whether `a` and `b` are the same float, or both not a number
+fn same<T: Float>(a: T, b: T) -> bool {
+    a.to_bits() == b.to_bits() || (parts(a).1 == Value::Nan && parts(b).1 == Value::Nan)
+}
+
+/// A string to parse, decoded from fuzz input
+///
+/// A `u8` length, taken modulo one more than [`MAX_NUMERAL_LEN`], is followed
+/// by one byte per char, taken modulo the number of [`NUMERAL_CHARS`]. In
+/// traces a `Numeral` is written out as itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Numeral(pub String);
+
+impl Arbitrary for Numeral {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let chars = NUMERAL_CHARS.as_bytes();
+        let len: u8 = Arbitrary::arbitrary(u)?;
+        let mut s = String::new();
+        for _ in 0..(len as usize % (MAX_NUMERAL_LEN + 1)) {
+            let c: u8 = Arbitrary::arbitrary(u)?;
+            s.push(chars[c as usize % chars.len()] as char);
+        }
+        Ok(Numeral(s))
+    }
+}
+
+impl Encode for Numeral {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        // Decoding reads at most `MAX_NUMERAL_LEN` chars, so we write no more.
+        let len = self.0.len().min(MAX_NUMERAL_LEN);
+        (len as u8).encode(buf);
+        for c in self.0.bytes().take(len) {
+            let idx = NUMERAL_CHARS.bytes().position(|n| n == c).expect("a Numeral is made of NUMERAL_CHARS");
+            (idx as u8).encode(buf);
+        }
+    }
+}
+
+impl fmt::Display for Numeral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Numeral {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.chars().find(|&c| !NUMERAL_CHARS.contains(c)) {
+            Some(c) => Err(ParseError::new(format!("'{}' is not a numeral char", c))),
+            None => Ok(Numeral(s.to_string())),
+        }
+    }
+}
+
+/// A run of decimal digits, decoded from fuzz input
+///
+/// A `u16` length, taken modulo one more than [`MAX_DIGITS`], is followed by
+/// one byte per digit, taken modulo ten. In traces `Digits` are written out as
+/// themselves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Digits(pub String);
+
+impl Arbitrary for Digits {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let len: u16 = Arbitrary::arbitrary(u)?;
+        let mut s = String::new();
+        for _ in 0..(len as usize % (MAX_DIGITS + 1)) {
+            let d: u8 = Arbitrary::arbitrary(u)?;
+            s.push((b'0' + d % 10) as char);
+        }
+        Ok(Digits(s))
+    }
+}
+
+impl Encode for Digits {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        // Decoding reads at most `MAX_DIGITS` digits, so we write no more.
+        let len = self.0.len().min(MAX_DIGITS);
+        (len as u16).encode(buf);
+        for d in self.0.bytes().take(len) {
+            (d - b'0').encode(buf);
+        }
+    }
+}
+
+impl fmt::Display for Digits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Digits {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.chars().find(|c| !c.is_ascii_digit()) {
+            Some(c) => Err(ParseError::new(format!("'{}' is not a digit", c))),
+            None => Ok(Digits(s.to_string())),
+        }
+    }
+}
+
+/// The `Op` defines the ways floats of the `num_float` target are parsed and
+/// formatted.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op<T> {
+    /// This operation parses a string, see `FromStr`
+    #[op(tag = 0)]
+    Parse {
+        /// The string to parse
+        s: Numeral,
+    },
+    /// This operation parses a decimal of many digits, see `FromStr`
+    #[op(tag = 1, weight = 2)]
+    Digits {
+        /// The digits
+        digits: Digits,
+        /// Put a point before digit `point % (len + 2)`, which is none at all
+        /// past the last
+        point: u16,
+        /// The exponent, which is left off if zero
+        exp: i16,
+    },
+    /// This operation parses the exact midpoint between a float and the one
+    /// after it, or a decimal just to either side, see `FromStr`
+    #[op(tag = 2, weight = 2)]
+    Halfway {
+        /// The float
+        x: T,
+        /// Parse the midpoint if `nudge % 3` is 0. Otherwise add `nudge / 3 %
+        /// 32` digits and a last one to land just above it if `nudge % 3` is
+        /// 1, or just below if it is 2.
+        nudge: u8,
+    },
+    /// This operation formats a float, see `Display`, `Debug`, `LowerExp`,
+    /// `UpperExp` and `{:.N}`
+    #[op(tag = 3, weight = 3)]
+    Format {
+        /// The float
+        x: T,
+        /// The precision of `{:.N}`
+        precision: u8,
+    },
+}
+
+impl<T: Float> Op<T> {
+    /// The string a parsing op parses, `None` if the op formats
+    pub fn numeral(&self) -> Option<String> {
+        match *self {
+            Op::Parse { ref s } => Some(s.0.clone()),
+            Op::Digits { ref digits, point, exp } => {
+                let mut s = digits.0.clone();
+                let point = point as usize % (s.len() + 2);
+                if point <= s.len() {
+                    s.insert(point, '.');
+                }
+                if exp != 0 {
+                    s.push_str(&format!("e{}", exp));
+                }
+                Some(s)
+            }
+            Op::Halfway { x, nudge } => {
+                let (neg, value) = parts(x);
+                let (m, e) = match value {
+                    Value::Finite { m, e } => (m, e),
+                    _ => return Some(x.to_string()),
+                };
+                let (digits, exp) = exact_digits(2 * m + 1, e - 1);
+                let extra = (nudge / 3 % 32) as usize;
+                let (digits, exp) = match nudge % 3 {
+                    0 => (digits, exp),
+                    1 => (format!("{}{}1", digits, "0".repeat(extra)), exp - extra as i64 - 1),
+                    _ => {
+                        let less = from_digits(&digits) - Big::from(1u128);
+                        (format!("{}{}9", less, "9".repeat(extra)), exp - extra as i64 - 1)
+                    }
+                };
+                let sign = if neg { "-" } else { "" };
+                Some(format!("{}{}e{}", sign, digits, exp))
+            }
+            Op::Format { .. } => None,
+        }
+    }
+}
+
+/// Parse `s` as a `T` both with `FromStr` and the model, panicking if they
+/// disagree
+fn check_parse<T: Float>(s: &str) {
+    match (std_parse::<T>(s), parse::<T>(s)) {
+        (Ok(a), Ok(b)) => check!(same(a, b), "parsing {:?} gave {:?}, not {:?}", s, a, b),
+        (sut, model) => check_eq!(sut.err(), model.err(), "parsing {:?}", s),
+    }
+}
+
+/// Run `op` against `T` and the model, panicking if they disagree
+pub fn check_op<T: Float>(op: &Op<T>) {
+    let (x, precision) = match *op {
+        Op::Format { x, precision } => (x, precision as usize),
+        _ => {
+            let s = op.numeral().expect("every other op parses");
+            check_parse::<T>(&s);
+            return;
+        }
+    };
+    let (neg, value) = parts(x);
+    let sign = if neg { "-" } else { "" };
+    let (m, e) = match value {
+        Value::Nan => {
+            for s in &[x.to_string(), format!("{:?}", x), format!("{:e}", x), format!("{:E}", x)] {
+                check_eq!(s, "NaN");
+            }
+            check_eq!(format!("{:.*}", precision, x), "NaN");
+            return;
+        }
+        Value::Infinite => {
+            let inf = format!("{}inf", sign);
+            for s in &[x.to_string(), format!("{:?}", x), format!("{:e}", x), format!("{:E}", x)] {
+                check_eq!(s, &inf);
+            }
+            check_eq!(format!("{:.*}", precision, x), inf);
+            return;
+        }
+        Value::Finite { m, e } => (m, e),
+    };
+
+    // `Display` writes the shortest decimal in full, and all the others write
+    // the same one. Each of them parses back to `x`.
+    let display = x.to_string();
+    let shortest = shortest::<T>(m, e);
+    let decimal = shortest
+        .iter()
+        .find(|d| format!("{}{}", sign, d.positional(0)) == display)
+        .unwrap_or_else(|| panic!("{:?} is written {}, not as one of {:?}", x, display, shortest));
+    let threshold = |exp: i64| nearest::<T>(false, "1", exp).to_bits();
+    let abs = x.to_bits() & !(1 << (T::BITS - 1));
+    let debug = if m != 0 && (abs < threshold(-4) || abs >= threshold(16)) {
+        decimal.exponential('e')
+    } else {
+        decimal.positional(1)
+    };
+    check_eq!(format!("{:?}", x), format!("{}{}", sign, debug));
+    check_eq!(format!("{:e}", x), format!("{}{}", sign, decimal.exponential('e')));
+    check_eq!(format!("{:E}", x), format!("{}{}", sign, decimal.exponential('E')));
+    for s in &[display, format!("{:?}", x), format!("{:e}", x), format!("{:E}", x)] {
+        check_parse::<T>(s);
+        check!(same(parse(s).expect("formatted floats parse"), x));
+    }
+
+    // With a precision the digits are exact, and need not parse back to `x`.
+    let s = format!("{:.*}", precision, x);
+    check_eq!(s, format!("{}{}", sign, fixed(m, e, precision)));
+    check_parse::<T>(&s);
+}
+
+/// The type an input of the `num_float` fuzz target names, if it has a header
+pub fn input_type(data: &[u8]) -> Option<&'static str> {
+    let mut ring = FiniteBuffer::new(data, MAX_INPUT_LEN).ok()?;
+    let version: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    if version != VERSION {
+        return None;
+    }
+    let _profile: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    let ty: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    Some(TYPES[ty as usize % TYPES.len()])
+}
+
+/// Decode the input of the `num_float` fuzz target into a [`Trace`] of ops
+/// on `T`
+///
+/// The target reads the encoding version, a profile byte and a type byte
+/// before reading ops until the input is exhausted. The profile byte chooses
+/// from [`PROFILES`] how ops are decoded and the type byte chooses from
+/// [`TYPES`]. Returns `None` if the input is of some other version, is too
+/// short to hold its header or names a type other than `T`.
+pub fn decode_input<T: Float>(data: &[u8]) -> Option<Trace<Op<T>>> {
+    if input_type(data)? != T::NAME {
+        return None;
+    }
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |ring| {
+        let _ty: u8 = Arbitrary::arbitrary(ring).ok()?;
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec![T::NAME.to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `num_float` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input<T: Float>(trace: &Trace<Op<T>>) -> Vec<u8> {
+    let ty = TYPES.iter().position(|&name| name == T::NAME).expect("every Float is in TYPES");
+    op::encode_input(trace, |_, buf| (ty as u8).encode(buf))
+}
+
+/// Replay a [`Trace`] against `T` and the model
+///
+/// Every op is checked as it comes, panicking as soon as the two disagree.
+/// The run is counted in [`stats`], if collection is enabled.
+pub fn replay<T: Float>(trace: &Trace<Op<T>>) {
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        check_op(op);
+    }
+}
+
+/// Decode and replay an input of the `num_float` fuzz target, whatever type
+/// it names
+pub fn run(data: &[u8]) {
+    match input_type(data) {
+        Some("f32") => {
+            if let Some(trace) = decode_input::<f32>(data) {
+                replay(&trace);
+            }
+        }
+        Some(_) => {
+            if let Some(trace) = decode_input::<f64>(data) {
+                replay(&trace);
+            }
+        }
+        None => {}
+    }
+}
+
+/// Decode an input of the `num_float` fuzz target into a text trace
+pub fn record(data: &[u8]) -> Option<String> {
+    match input_type(data)? {
+        "f32" => decode_input::<f32>(data).map(|trace| trace.to_string()),
+        _ => decode_input::<f64>(data).map(|trace| trace.to_string()),
+    }
+}
+
+fn replay_input<T: Float>(text: &str) -> Result<usize, ParseError> {
+    let trace: Trace<Op<T>> = text.parse()?;
+    replay(&trace);
+    Ok(trace.ops.len())
+}
+
+/// Replay a text trace of the `num_float` target, of whichever type its
+/// header names, returning the number of ops replayed
+pub fn replay_text(text: &str) -> Result<usize, ParseError> {
+    let header = trace::header(text)?;
+    match header.types[..] {
+        [ref ty] if ty == "f32" => replay_input::<f32>(text),
+        [ref ty] if ty == "f64" => replay_input::<f64>(text),
+        _ => Err(ParseError::new("expected f32 or f64")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decimal(x: f64) -> Decimal {
+        match parts(x).1 {
+            Value::Finite { m, e } => shortest::<f64>(m, e).remove(0),
+            _ => panic!("{} is not finite", x),
+        }
+    }
+
+    #[test]
+    fn halfway_rounds_to_even() {
+        assert_eq!(Ok(9_007_199_254_740_992.0), parse::<f64>("9007199254740993"));
+        assert_eq!(Ok(9_007_199_254_740_994.0), parse::<f64>("9007199254740993.00000000000000000001"));
+        assert_eq!(Ok(9_007_199_254_740_996.0), parse::<f64>("9007199254740995"));
+        assert_eq!(Ok(16_777_216.0), parse::<f32>("16777217"));
+    }
+
+    #[test]
+    fn the_least_subnormal_is_half_way_from_zero() {
+        assert_eq!(Ok(0.0), parse::<f64>("2.4703282292062327e-324"));
+        assert_eq!(Ok(5e-324), parse::<f64>("2.4703282292062328e-324"));
+        assert_eq!(Ok(f64::MAX), parse::<f64>("1.7976931348623158e308"));
+        assert_eq!(Ok(f64::INFINITY), parse::<f64>("1.7976931348623159e308"));
+        assert_eq!(Ok(-0.0), parse::<f64>("-0e99999999999999999999"));
+    }
+
+    #[test]
+    fn specials_and_errors_are_spelled_out() {
+        assert_eq!(Err(FloatErrorKind::Empty), parse::<f64>(""));
+        for &s in &[".", "-", "e5", "1e", "1e+", "1_0", "0x1", "1.2.3", "infinit"] {
+            assert_eq!(Err(FloatErrorKind::Invalid), parse::<f64>(s), "{:?}", s);
+        }
+        assert_eq!(Ok(f64::NEG_INFINITY), parse::<f64>("-Infinity"));
+        assert_eq!(Ok(500.0), parse::<f64>("+.5e+3"));
+        assert_eq!(Ok(5.0), parse::<f64>("5."));
+    }
+
+    #[test]
+    fn shortest_decimals_read_back() {
+        assert_eq!(Decimal::new("1", -1), decimal(0.1));
+        assert_eq!(Decimal::new("5", -324), decimal(5e-324));
+        assert_eq!(Decimal::new("17976931348623157", 292), decimal(f64::MAX));
+        assert_eq!("100", decimal(100.0).positional(0));
+        assert_eq!("0.0001", decimal(1e-4).positional(0));
+        assert_eq!("1.2345678901234568e17", decimal(123_456_789_012_345_680.0).exponential('e'));
+    }
+
+    #[test]
+    fn fixed_precision_rounds_half_to_even() {
+        assert_eq!("0", fixed(1, -1, 0));
+        assert_eq!("2", fixed(3, -1, 0));
+        assert_eq!("2", fixed(5, -1, 0));
+        assert_eq!("0.12", fixed(1, -3, 2));
+        assert_eq!("0.000", fixed(1, -20, 3));
+        assert_eq!("1024.0", fixed(1, 10, 1));
+    }
+
+    quickcheck! {
+        fn f64_formatting_agrees(bits: u64, precision: u8) -> bool {
+            check_op(&Op::Format { x: f64::from_bits(bits), precision: precision % 32 });
+            true
+        }
+
+        fn f32_formatting_agrees(bits: u32, precision: u8) -> bool {
+            check_op(&Op::Format { x: f32::from_bits(bits), precision: precision % 32 });
+            true
+        }
+
+        fn halfway_parsing_agrees(bits: u64, nudge: u8) -> bool {
+            check_op(&Op::Halfway { x: f64::from_bits(bits), nudge });
+            check_op(&Op::Halfway { x: f32::from_bits(bits as u32), nudge });
+            true
+        }
+
+        fn digits_parsing_agrees(digits: Vec<u8>, point: u16, exp: i16) -> bool {
+            let digits = Digits(digits.iter().map(|d| (b'0' + d % 10) as char).collect());
+            let op = Op::Digits { digits, point, exp: exp % 400 };
+            check_op::<f64>(&op);
+            true
+        }
+    }
+}
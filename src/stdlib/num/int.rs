@@ -0,0 +1,1159 @@
+//! Tests for the arithmetic of the primitive integers
+//!
+//! Every integer type has the same few dozen methods: `checked_add`,
+//! `wrapping_pow`, `overflowing_rem_euclid` and so on. Each of them is some
+//! exact result -- a sum, a power, a remainder -- squeezed into the type in one
+//! of a handful of ways. The model works out the exact result as a [`Big`] and
+//! squeezes it the way the flavour of the method says: `checked_*` gives
+//! `None` if it doesn't fit, `wrapping_*` keeps its low bits, `saturating_*`
+//! clamps it to the type's bounds and `overflowing_*` is the wrapped result
+//! with a flag for whether it fit. The same ops run for all twelve types, the
+//! type being chosen by the input.
+use arbitrary::*;
+use encode::{Encode, VERSION};
+use op::{self, Profiles, TaggedOp};
+use stats;
+use std::fmt;
+use std::mem;
+use std::num::ParseIntError;
+use std::ops::{Add, Div, Mul, Rem, Shl, Shr, Sub};
+use std::str::FromStr;
+use stdlib::num::big::Big;
+use trace::{self, Header, ParseError, Trace};
+
+/// The name of the fuzz target driving the integer types
+pub const TARGET: &str = "num_int";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The integer types, in the order the type byte of an input chooses them
+pub const TYPES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+lazy_static! {
+    /// The workload profiles of the `num_int` target, see [`Profiles`]
+    ///
+    /// `arithmetic` sticks to the methods that can overflow. `bits` sticks to
+    /// the ones that shuffle bits about.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op<u8>>(&[
+        (
+            "arithmetic",
+            &[("abs_diff", 0), ("isqrt", 0), ("checked_isqrt", 0), ("ilog", 0), ("bits", 0), ("power_of_two", 0)],
+        ),
+        (
+            "bits",
+            &[("add", 0), ("sub", 0), ("mul", 0), ("div", 0), ("rem", 0), ("div_euclid", 0), ("rem_euclid", 0), ("neg", 0), ("saturating_neg", 0), ("abs", 0), ("pow", 0), ("abs_diff", 0), ("isqrt", 0), ("checked_isqrt", 0), ("ilog", 0)],
+        ),
+    ]);
+}
+
+/// A primitive integer type, the system under test of the `num_int` target
+///
+/// Each method is like to its namesake on the integer types, and mostly just
+/// calls it. Not every type has every method -- there is no `checked_abs` on
+/// `u8`, nor `is_power_of_two` on `i8` -- and the ops calling those a type
+/// lacks are named in [`Int::LACKS`] and skipped when they come up.
+pub trait Int:
+    Copy
+    + fmt::Debug
+    + fmt::Display
+    + fmt::Binary
+    + fmt::Octal
+    + fmt::LowerHex
+    + fmt::UpperHex
+    + FromStr<Err = ParseIntError>
+    + PartialEq
+    + Arbitrary
+    + Encode
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    /// The type of the same width and no sign, `Self` if it has none
+    type Unsigned: Int;
+
+    /// The name of the type, as written in trace headers
+    const NAME: &'static str;
+    /// The width of the type in bits
+    const BITS: u32;
+    /// Whether the type is signed
+    const SIGNED: bool;
+    /// The names of the ops this type lacks, as written in traces
+    const LACKS: &'static [&'static str];
+
+    /// The value of the integer
+    fn to_big(self) -> Big;
+    /// The integer whose two's complement is the lowest [`Int::BITS`] bits
+    /// of `bits`
+    fn from_bits(bits: u128) -> Self;
+
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn saturating_add(self, rhs: Self) -> Self;
+    fn overflowing_add(self, rhs: Self) -> (Self, bool);
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    fn saturating_sub(self, rhs: Self) -> Self;
+    fn overflowing_sub(self, rhs: Self) -> (Self, bool);
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn wrapping_mul(self, rhs: Self) -> Self;
+    fn saturating_mul(self, rhs: Self) -> Self;
+    fn overflowing_mul(self, rhs: Self) -> (Self, bool);
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn wrapping_div(self, rhs: Self) -> Self;
+    fn saturating_div(self, rhs: Self) -> Self;
+    fn overflowing_div(self, rhs: Self) -> (Self, bool);
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+    fn wrapping_rem(self, rhs: Self) -> Self;
+    fn overflowing_rem(self, rhs: Self) -> (Self, bool);
+    fn div_euclid(self, rhs: Self) -> Self;
+    fn checked_div_euclid(self, rhs: Self) -> Option<Self>;
+    fn wrapping_div_euclid(self, rhs: Self) -> Self;
+    fn overflowing_div_euclid(self, rhs: Self) -> (Self, bool);
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn checked_rem_euclid(self, rhs: Self) -> Option<Self>;
+    fn wrapping_rem_euclid(self, rhs: Self) -> Self;
+    fn overflowing_rem_euclid(self, rhs: Self) -> (Self, bool);
+    fn checked_neg(self) -> Option<Self>;
+    fn wrapping_neg(self) -> Self;
+    fn overflowing_neg(self) -> (Self, bool);
+    fn saturating_neg(self) -> Self;
+    fn abs(self) -> Self;
+    fn checked_abs(self) -> Option<Self>;
+    fn wrapping_abs(self) -> Self;
+    fn saturating_abs(self) -> Self;
+    fn overflowing_abs(self) -> (Self, bool);
+    fn unsigned_abs(self) -> Self::Unsigned;
+    fn checked_shl(self, rhs: u32) -> Option<Self>;
+    fn wrapping_shl(self, rhs: u32) -> Self;
+    fn overflowing_shl(self, rhs: u32) -> (Self, bool);
+    fn checked_shr(self, rhs: u32) -> Option<Self>;
+    fn wrapping_shr(self, rhs: u32) -> Self;
+    fn overflowing_shr(self, rhs: u32) -> (Self, bool);
+    fn pow(self, exp: u32) -> Self;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+    fn wrapping_pow(self, exp: u32) -> Self;
+    fn saturating_pow(self, exp: u32) -> Self;
+    fn overflowing_pow(self, exp: u32) -> (Self, bool);
+    fn abs_diff(self, other: Self) -> Self::Unsigned;
+    fn isqrt(self) -> Self;
+    fn checked_isqrt(self) -> Option<Self>;
+    fn ilog(self, base: Self) -> u32;
+    fn checked_ilog(self, base: Self) -> Option<u32>;
+    fn ilog2(self) -> u32;
+    fn checked_ilog2(self) -> Option<u32>;
+    fn ilog10(self) -> u32;
+    fn checked_ilog10(self) -> Option<u32>;
+    fn count_ones(self) -> u32;
+    fn count_zeros(self) -> u32;
+    fn leading_zeros(self) -> u32;
+    fn trailing_zeros(self) -> u32;
+    fn leading_ones(self) -> u32;
+    fn trailing_ones(self) -> u32;
+    fn rotate_left(self, n: u32) -> Self;
+    fn rotate_right(self, n: u32) -> Self;
+    fn swap_bytes(self) -> Self;
+    fn reverse_bits(self) -> Self;
+    /// The bytes of the integer, least significant first, as a `Vec`
+    fn to_le_bytes(self) -> Vec<u8>;
+    /// The bytes of the integer, most significant first, as a `Vec`
+    fn to_be_bytes(self) -> Vec<u8>;
+    fn is_power_of_two(self) -> bool;
+    fn next_power_of_two(self) -> Self;
+    fn checked_next_power_of_two(self) -> Option<Self>;
+}
+
+// The methods every integer type has, whatever its sign.
+macro_rules! impl_int_common {
+    ($ty:ident) => {
+        fn to_big(self) -> Big {
+            if Self::SIGNED {
+                Big::from(self as i128)
+            } else {
+                Big::from(self as u128)
+            }
+        }
+        fn from_bits(bits: u128) -> Self {
+            bits as $ty
+        }
+
+        fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError> {
+            $ty::from_str_radix(src, radix)
+        }
+        fn checked_add(self, rhs: Self) -> Option<Self> {
+            $ty::checked_add(self, rhs)
+        }
+        fn wrapping_add(self, rhs: Self) -> Self {
+            $ty::wrapping_add(self, rhs)
+        }
+        fn saturating_add(self, rhs: Self) -> Self {
+            $ty::saturating_add(self, rhs)
+        }
+        fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+            $ty::overflowing_add(self, rhs)
+        }
+        fn checked_sub(self, rhs: Self) -> Option<Self> {
+            $ty::checked_sub(self, rhs)
+        }
+        fn wrapping_sub(self, rhs: Self) -> Self {
+            $ty::wrapping_sub(self, rhs)
+        }
+        fn saturating_sub(self, rhs: Self) -> Self {
+            $ty::saturating_sub(self, rhs)
+        }
+        fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+            $ty::overflowing_sub(self, rhs)
+        }
+        fn checked_mul(self, rhs: Self) -> Option<Self> {
+            $ty::checked_mul(self, rhs)
+        }
+        fn wrapping_mul(self, rhs: Self) -> Self {
+            $ty::wrapping_mul(self, rhs)
+        }
+        fn saturating_mul(self, rhs: Self) -> Self {
+            $ty::saturating_mul(self, rhs)
+        }
+        fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+            $ty::overflowing_mul(self, rhs)
+        }
+        fn checked_div(self, rhs: Self) -> Option<Self> {
+            $ty::checked_div(self, rhs)
+        }
+        fn wrapping_div(self, rhs: Self) -> Self {
+            $ty::wrapping_div(self, rhs)
+        }
+        fn saturating_div(self, rhs: Self) -> Self {
+            $ty::saturating_div(self, rhs)
+        }
+        fn overflowing_div(self, rhs: Self) -> (Self, bool) {
+            $ty::overflowing_div(self, rhs)
+        }
+        fn checked_rem(self, rhs: Self) -> Option<Self> {
+            $ty::checked_rem(self, rhs)
+        }
+        fn wrapping_rem(self, rhs: Self) -> Self {
+            $ty::wrapping_rem(self, rhs)
+        }
+        fn overflowing_rem(self, rhs: Self) -> (Self, bool) {
+            $ty::overflowing_rem(self, rhs)
+        }
+        fn div_euclid(self, rhs: Self) -> Self {
+            $ty::div_euclid(self, rhs)
+        }
+        fn checked_div_euclid(self, rhs: Self) -> Option<Self> {
+            $ty::checked_div_euclid(self, rhs)
+        }
+        fn wrapping_div_euclid(self, rhs: Self) -> Self {
+            $ty::wrapping_div_euclid(self, rhs)
+        }
+        fn overflowing_div_euclid(self, rhs: Self) -> (Self, bool) {
+            $ty::overflowing_div_euclid(self, rhs)
+        }
+        fn rem_euclid(self, rhs: Self) -> Self {
+            $ty::rem_euclid(self, rhs)
+        }
+        fn checked_rem_euclid(self, rhs: Self) -> Option<Self> {
+            $ty::checked_rem_euclid(self, rhs)
+        }
+        fn wrapping_rem_euclid(self, rhs: Self) -> Self {
+            $ty::wrapping_rem_euclid(self, rhs)
+        }
+        fn overflowing_rem_euclid(self, rhs: Self) -> (Self, bool) {
+            $ty::overflowing_rem_euclid(self, rhs)
+        }
+        fn checked_neg(self) -> Option<Self> {
+            $ty::checked_neg(self)
+        }
+        fn wrapping_neg(self) -> Self {
+            $ty::wrapping_neg(self)
+        }
+        fn overflowing_neg(self) -> (Self, bool) {
+            $ty::overflowing_neg(self)
+        }
+        fn checked_shl(self, rhs: u32) -> Option<Self> {
+            $ty::checked_shl(self, rhs)
+        }
+        fn wrapping_shl(self, rhs: u32) -> Self {
+            $ty::wrapping_shl(self, rhs)
+        }
+        fn overflowing_shl(self, rhs: u32) -> (Self, bool) {
+            $ty::overflowing_shl(self, rhs)
+        }
+        fn checked_shr(self, rhs: u32) -> Option<Self> {
+            $ty::checked_shr(self, rhs)
+        }
+        fn wrapping_shr(self, rhs: u32) -> Self {
+            $ty::wrapping_shr(self, rhs)
+        }
+        fn overflowing_shr(self, rhs: u32) -> (Self, bool) {
+            $ty::overflowing_shr(self, rhs)
+        }
+        fn pow(self, exp: u32) -> Self {
+            $ty::pow(self, exp)
+        }
+        fn checked_pow(self, exp: u32) -> Option<Self> {
+            $ty::checked_pow(self, exp)
+        }
+        fn wrapping_pow(self, exp: u32) -> Self {
+            $ty::wrapping_pow(self, exp)
+        }
+        fn saturating_pow(self, exp: u32) -> Self {
+            $ty::saturating_pow(self, exp)
+        }
+        fn overflowing_pow(self, exp: u32) -> (Self, bool) {
+            $ty::overflowing_pow(self, exp)
+        }
+        fn abs_diff(self, other: Self) -> Self::Unsigned {
+            $ty::abs_diff(self, other)
+        }
+        fn isqrt(self) -> Self {
+            $ty::isqrt(self)
+        }
+        fn ilog(self, base: Self) -> u32 {
+            $ty::ilog(self, base)
+        }
+        fn checked_ilog(self, base: Self) -> Option<u32> {
+            $ty::checked_ilog(self, base)
+        }
+        fn ilog2(self) -> u32 {
+            $ty::ilog2(self)
+        }
+        fn checked_ilog2(self) -> Option<u32> {
+            $ty::checked_ilog2(self)
+        }
+        fn ilog10(self) -> u32 {
+            $ty::ilog10(self)
+        }
+        fn checked_ilog10(self) -> Option<u32> {
+            $ty::checked_ilog10(self)
+        }
+        fn count_ones(self) -> u32 {
+            $ty::count_ones(self)
+        }
+        fn count_zeros(self) -> u32 {
+            $ty::count_zeros(self)
+        }
+        fn leading_zeros(self) -> u32 {
+            $ty::leading_zeros(self)
+        }
+        fn trailing_zeros(self) -> u32 {
+            $ty::trailing_zeros(self)
+        }
+        fn leading_ones(self) -> u32 {
+            $ty::leading_ones(self)
+        }
+        fn trailing_ones(self) -> u32 {
+            $ty::trailing_ones(self)
+        }
+        fn rotate_left(self, n: u32) -> Self {
+            $ty::rotate_left(self, n)
+        }
+        fn rotate_right(self, n: u32) -> Self {
+            $ty::rotate_right(self, n)
+        }
+        fn swap_bytes(self) -> Self {
+            $ty::swap_bytes(self)
+        }
+        fn reverse_bits(self) -> Self {
+            $ty::reverse_bits(self)
+        }
+        fn to_le_bytes(self) -> Vec<u8> {
+            $ty::to_le_bytes(self).to_vec()
+        }
+        fn to_be_bytes(self) -> Vec<u8> {
+            $ty::to_be_bytes(self).to_vec()
+        }
+    };
+}
+
+macro_rules! impl_int_signed {
+    ( $( $ty:ident => $unsigned:ident ),* ) => {
+        $(
+            impl Int for $ty {
+                type Unsigned = $unsigned;
+
+                const NAME: &'static str = stringify!($ty);
+                const BITS: u32 = mem::size_of::<$ty>() as u32 * 8;
+                const SIGNED: bool = true;
+                const LACKS: &'static [&'static str] = &["power_of_two"];
+
+                impl_int_common!($ty);
+
+                fn saturating_neg(self) -> Self {
+                    $ty::saturating_neg(self)
+                }
+                fn abs(self) -> Self {
+                    $ty::abs(self)
+                }
+                fn checked_abs(self) -> Option<Self> {
+                    $ty::checked_abs(self)
+                }
+                fn wrapping_abs(self) -> Self {
+                    $ty::wrapping_abs(self)
+                }
+                fn saturating_abs(self) -> Self {
+                    $ty::saturating_abs(self)
+                }
+                fn overflowing_abs(self) -> (Self, bool) {
+                    $ty::overflowing_abs(self)
+                }
+                fn unsigned_abs(self) -> Self::Unsigned {
+                    $ty::unsigned_abs(self)
+                }
+                fn checked_isqrt(self) -> Option<Self> {
+                    $ty::checked_isqrt(self)
+                }
+                fn is_power_of_two(self) -> bool {
+                    unreachable!("{} lacks is_power_of_two", Self::NAME)
+                }
+                fn next_power_of_two(self) -> Self {
+                    unreachable!("{} lacks next_power_of_two", Self::NAME)
+                }
+                fn checked_next_power_of_two(self) -> Option<Self> {
+                    unreachable!("{} lacks checked_next_power_of_two", Self::NAME)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_int_unsigned {
+    ( $( $ty:ident ),* ) => {
+        $(
+            impl Int for $ty {
+                type Unsigned = $ty;
+
+                const NAME: &'static str = stringify!($ty);
+                const BITS: u32 = mem::size_of::<$ty>() as u32 * 8;
+                const SIGNED: bool = false;
+                const LACKS: &'static [&'static str] = &["saturating_neg", "abs", "checked_isqrt"];
+
+                impl_int_common!($ty);
+
+                fn saturating_neg(self) -> Self {
+                    unreachable!("{} lacks saturating_neg", Self::NAME)
+                }
+                fn abs(self) -> Self {
+                    unreachable!("{} lacks abs", Self::NAME)
+                }
+                fn checked_abs(self) -> Option<Self> {
+                    unreachable!("{} lacks checked_abs", Self::NAME)
+                }
+                fn wrapping_abs(self) -> Self {
+                    unreachable!("{} lacks wrapping_abs", Self::NAME)
+                }
+                fn saturating_abs(self) -> Self {
+                    unreachable!("{} lacks saturating_abs", Self::NAME)
+                }
+                fn overflowing_abs(self) -> (Self, bool) {
+                    unreachable!("{} lacks overflowing_abs", Self::NAME)
+                }
+                fn unsigned_abs(self) -> Self::Unsigned {
+                    unreachable!("{} lacks unsigned_abs", Self::NAME)
+                }
+                fn checked_isqrt(self) -> Option<Self> {
+                    unreachable!("{} lacks checked_isqrt", Self::NAME)
+                }
+                fn is_power_of_two(self) -> bool {
+                    $ty::is_power_of_two(self)
+                }
+                fn next_power_of_two(self) -> Self {
+                    $ty::next_power_of_two(self)
+                }
+                fn checked_next_power_of_two(self) -> Option<Self> {
+                    $ty::checked_next_power_of_two(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_int_unsigned!(u8, u16, u32, u64, u128, usize);
+impl_int_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+/// The least value of `T`
+pub fn min<T: Int>() -> Big {
+    if T::SIGNED {
+        -Big::pow2(T::BITS - 1)
+    } else {
+        Big::zero()
+    }
+}
+
+/// The greatest value of `T`
+pub fn max<T: Int>() -> Big {
+    let bits = if T::SIGNED { T::BITS - 1 } else { T::BITS };
+    Big::pow2(bits) - Big::from(1u128)
+}
+
+/// Whether `x` is a value of `T`
+pub fn fits<T: Int>(x: &Big) -> bool {
+    min::<T>() <= *x && *x <= max::<T>()
+}
+
+/// The value of `T` with the same lowest bits as `x`
+///
+/// This is how every `wrapping_*` method squeezes its result into `T`.
+pub fn wrap<T: Int>(x: &Big) -> T {
+    T::from_bits(x.low_bits(T::BITS))
+}
+
+/// The value of `T` nearest to `x`
+///
+/// This is how every `saturating_*` method squeezes its result into `T`.
+pub fn saturate<T: Int>(x: &Big) -> T {
+    if *x < min::<T>() {
+        wrap(&min::<T>())
+    } else if *x > max::<T>() {
+        wrap(&max::<T>())
+    } else {
+        wrap(x)
+    }
+}
+
+/// The bits of `x`, lowest first
+fn bits<T: Int>(x: T) -> Vec<bool> {
+    let raw = x.to_big().low_bits(T::BITS);
+    (0..T::BITS).map(|i| raw >> i & 1 == 1).collect()
+}
+
+/// The value of `T` with the bits `bits`, lowest first
+fn from_bits<T: Int>(bits: &[bool]) -> T {
+    let raw = bits
+        .iter()
+        .enumerate()
+        .fold(0u128, |raw, (i, &bit)| raw | u128::from(bit) << i);
+    T::from_bits(raw)
+}
+
+/// The length of the run of `bit` that `bits` starts with
+fn run_of<'a, I>(bits: I, bit: bool) -> u32
+where
+    I: Iterator<Item = &'a bool>,
+{
+    bits.take_while(|&&b| b == bit).count() as u32
+}
+
+/// The bytes of `x`, lowest first
+fn bytes<T: Int>(x: T) -> Vec<u8> {
+    let raw = x.to_big().low_bits(T::BITS);
+    (0..T::BITS / 8).map(|i| (raw >> (8 * i)) as u8).collect()
+}
+
+/// The `Op` defines the methods called on integers of the `num_int` target.
+/// Each op calls every flavour of a method on its operands and checks them
+/// against the model.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op<T> {
+    /// This operation adds, see `checked_add` and friends
+    #[op(tag = 0, weight = 2)]
+    Add {
+        /// The left operand
+        lhs: T,
+        /// The right operand
+        rhs: T,
+    },
+    /// This operation subtracts, see `checked_sub` and friends
+    #[op(tag = 1, weight = 2)]
+    Sub {
+        /// The left operand
+        lhs: T,
+        /// The right operand
+        rhs: T,
+    },
+    /// This operation multiplies, see `checked_mul` and friends
+    #[op(tag = 2, weight = 2)]
+    Mul {
+        /// The left operand
+        lhs: T,
+        /// The right operand
+        rhs: T,
+    },
+    /// This operation divides, see `checked_div` and friends
+    #[op(tag = 3, weight = 2)]
+    Div {
+        /// The dividend
+        lhs: T,
+        /// The divisor
+        rhs: T,
+    },
+    /// This operation takes a remainder, see `checked_rem` and friends
+    #[op(tag = 4, weight = 2)]
+    Rem {
+        /// The dividend
+        lhs: T,
+        /// The divisor
+        rhs: T,
+    },
+    /// This operation divides, see `div_euclid` and friends
+    #[op(tag = 5)]
+    DivEuclid {
+        /// The dividend
+        lhs: T,
+        /// The divisor
+        rhs: T,
+    },
+    /// This operation takes a remainder, see `rem_euclid` and friends
+    #[op(tag = 6)]
+    RemEuclid {
+        /// The dividend
+        lhs: T,
+        /// The divisor
+        rhs: T,
+    },
+    /// This operation negates, see `checked_neg` and friends
+    #[op(tag = 7)]
+    Neg {
+        /// The operand
+        x: T,
+    },
+    /// This operation negates, see `saturating_neg`
+    #[op(tag = 8)]
+    SaturatingNeg {
+        /// The operand
+        x: T,
+    },
+    /// This operation takes the absolute value, see `abs` and friends
+    #[op(tag = 9)]
+    Abs {
+        /// The operand
+        x: T,
+    },
+    /// This operation shifts left, see `checked_shl` and friends
+    #[op(tag = 10)]
+    Shl {
+        /// The operand
+        x: T,
+        /// The number of bits to shift by
+        n: u8,
+    },
+    /// This operation shifts right, see `checked_shr` and friends
+    #[op(tag = 11)]
+    Shr {
+        /// The operand
+        x: T,
+        /// The number of bits to shift by
+        n: u8,
+    },
+    /// This operation raises to a power, see `pow` and friends
+    #[op(tag = 12, weight = 2)]
+    Pow {
+        /// The base
+        x: T,
+        /// The exponent
+        exp: u8,
+    },
+    /// This operation takes the distance between two integers, see
+    /// `abs_diff`
+    #[op(tag = 13)]
+    AbsDiff {
+        /// The left operand
+        lhs: T,
+        /// The right operand
+        rhs: T,
+    },
+    /// This operation takes a square root, see `isqrt`
+    #[op(tag = 14)]
+    Isqrt {
+        /// The operand
+        x: T,
+    },
+    /// This operation takes a square root, see `checked_isqrt`
+    #[op(tag = 15)]
+    CheckedIsqrt {
+        /// The operand
+        x: T,
+    },
+    /// This operation takes logarithms, see `ilog`, `ilog2`, `ilog10` and
+    /// their checked flavours
+    #[op(tag = 16)]
+    Ilog {
+        /// The operand
+        x: T,
+        /// The base of `ilog`
+        base: T,
+    },
+    /// This operation counts, rotates, reverses and swaps bits, see
+    /// `count_ones` and friends
+    #[op(tag = 17, weight = 2)]
+    Bits {
+        /// The operand
+        x: T,
+        /// The number of bits to rotate by
+        n: u8,
+    },
+    /// This operation looks for powers of two, see `is_power_of_two` and
+    /// `next_power_of_two`
+    #[op(tag = 18)]
+    PowerOfTwo {
+        /// The operand
+        x: T,
+    },
+}
+
+/// Check the `checked`, `wrapping` and `overflowing` flavours of a method
+/// whose exact result is `exact`
+fn check_flavours<T: Int>(exact: &Big, checked: Option<T>, wrapping: T, overflowing: (T, bool)) {
+    let wrapped: T = wrap(exact);
+    let fits = fits::<T>(exact);
+    check_eq!(checked, if fits { Some(wrapped) } else { None });
+    check_eq!(wrapping, wrapped);
+    check_eq!(overflowing, (wrapped, !fits));
+}
+
+/// Run `op` against `T` and the model, panicking if they disagree
+pub fn check_op<T: Int>(op: &Op<T>) {
+    match *op {
+        Op::Add { lhs, rhs } => {
+            let exact = lhs.to_big() + rhs.to_big();
+            check_flavours(&exact, lhs.checked_add(rhs), lhs.wrapping_add(rhs), lhs.overflowing_add(rhs));
+            check_eq!(lhs.saturating_add(rhs), saturate(&exact));
+            if fits::<T>(&exact) {
+                check_eq!(lhs + rhs, wrap(&exact));
+            }
+        }
+        Op::Sub { lhs, rhs } => {
+            let exact = lhs.to_big() - rhs.to_big();
+            check_flavours(&exact, lhs.checked_sub(rhs), lhs.wrapping_sub(rhs), lhs.overflowing_sub(rhs));
+            check_eq!(lhs.saturating_sub(rhs), saturate(&exact));
+            if fits::<T>(&exact) {
+                check_eq!(lhs - rhs, wrap(&exact));
+            }
+        }
+        Op::Mul { lhs, rhs } => {
+            let exact = lhs.to_big() * rhs.to_big();
+            check_flavours(&exact, lhs.checked_mul(rhs), lhs.wrapping_mul(rhs), lhs.overflowing_mul(rhs));
+            check_eq!(lhs.saturating_mul(rhs), saturate(&exact));
+            if fits::<T>(&exact) {
+                check_eq!(lhs * rhs, wrap(&exact));
+            }
+        }
+        // Dividing by zero panics, bar the checked flavours. Only the quotient
+        // can overflow -- `MIN / -1` -- and then the remainder overflows with
+        // it, even though `MIN % -1` is a perfectly good zero.
+        Op::Div { lhs, rhs } => {
+            if rhs.to_big().is_zero() {
+                check_eq!(lhs.checked_div(rhs), None);
+                return;
+            }
+            let (exact, _) = lhs.to_big().div_rem(&rhs.to_big());
+            check_flavours(&exact, lhs.checked_div(rhs), lhs.wrapping_div(rhs), lhs.overflowing_div(rhs));
+            check_eq!(lhs.saturating_div(rhs), saturate(&exact));
+            if fits::<T>(&exact) {
+                check_eq!(lhs / rhs, wrap(&exact));
+            }
+        }
+        Op::Rem { lhs, rhs } => {
+            if rhs.to_big().is_zero() {
+                check_eq!(lhs.checked_rem(rhs), None);
+                return;
+            }
+            let (quot, rem) = lhs.to_big().div_rem(&rhs.to_big());
+            let overflow = !fits::<T>(&quot);
+            let rem: T = wrap(&rem);
+            check_eq!(lhs.checked_rem(rhs), if overflow { None } else { Some(rem) });
+            check_eq!(lhs.wrapping_rem(rhs), rem);
+            check_eq!(lhs.overflowing_rem(rhs), (rem, overflow));
+            if !overflow {
+                check_eq!(lhs % rhs, rem);
+            }
+        }
+        Op::DivEuclid { lhs, rhs } => {
+            if rhs.to_big().is_zero() {
+                check_eq!(lhs.checked_div_euclid(rhs), None);
+                return;
+            }
+            let (exact, _) = lhs.to_big().div_rem_euclid(&rhs.to_big());
+            check_flavours(
+                &exact,
+                lhs.checked_div_euclid(rhs),
+                lhs.wrapping_div_euclid(rhs),
+                lhs.overflowing_div_euclid(rhs),
+            );
+            if fits::<T>(&exact) {
+                check_eq!(lhs.div_euclid(rhs), wrap(&exact));
+            }
+        }
+        Op::RemEuclid { lhs, rhs } => {
+            if rhs.to_big().is_zero() {
+                check_eq!(lhs.checked_rem_euclid(rhs), None);
+                return;
+            }
+            let (quot, rem) = lhs.to_big().div_rem_euclid(&rhs.to_big());
+            let overflow = !fits::<T>(&quot);
+            let rem: T = wrap(&rem);
+            check_eq!(lhs.checked_rem_euclid(rhs), if overflow { None } else { Some(rem) });
+            check_eq!(lhs.wrapping_rem_euclid(rhs), rem);
+            check_eq!(lhs.overflowing_rem_euclid(rhs), (rem, overflow));
+            if !overflow {
+                check_eq!(lhs.rem_euclid(rhs), rem);
+            }
+        }
+        Op::Neg { x } => {
+            let exact = -x.to_big();
+            check_flavours(&exact, x.checked_neg(), x.wrapping_neg(), x.overflowing_neg());
+        }
+        Op::SaturatingNeg { x } => {
+            check_eq!(x.saturating_neg(), saturate(&-x.to_big()));
+        }
+        Op::Abs { x } => {
+            let exact = x.to_big().abs();
+            check_flavours(&exact, x.checked_abs(), x.wrapping_abs(), x.overflowing_abs());
+            check_eq!(x.saturating_abs(), saturate(&exact));
+            check_eq!(x.unsigned_abs(), wrap::<T::Unsigned>(&exact));
+            if fits::<T>(&exact) {
+                check_eq!(x.abs(), wrap(&exact));
+            }
+        }
+        // A shift by `n` is a shift by `n` modulo the width, overflowing if
+        // `n` is no less than the width. The bits shifted out are no concern
+        // of the overflow flag.
+        Op::Shl { x, n } => {
+            let n = u32::from(n);
+            let shifted: T = wrap(&(x.to_big() * Big::pow2(n % T::BITS)));
+            let overflow = n >= T::BITS;
+            check_eq!(x.checked_shl(n), if overflow { None } else { Some(shifted) });
+            check_eq!(x.wrapping_shl(n), shifted);
+            check_eq!(x.overflowing_shl(n), (shifted, overflow));
+            if !overflow {
+                check_eq!(x << n, shifted);
+            }
+        }
+        Op::Shr { x, n } => {
+            let n = u32::from(n);
+            let (quot, _) = x.to_big().div_rem_euclid(&Big::pow2(n % T::BITS));
+            let shifted: T = wrap(&quot);
+            let overflow = n >= T::BITS;
+            check_eq!(x.checked_shr(n), if overflow { None } else { Some(shifted) });
+            check_eq!(x.wrapping_shr(n), shifted);
+            check_eq!(x.overflowing_shr(n), (shifted, overflow));
+            if !overflow {
+                check_eq!(x >> n, shifted);
+            }
+        }
+        Op::Pow { x, exp } => {
+            let exp = u32::from(exp);
+            let exact = x.to_big().pow(exp);
+            check_flavours(&exact, x.checked_pow(exp), x.wrapping_pow(exp), x.overflowing_pow(exp));
+            check_eq!(x.saturating_pow(exp), saturate(&exact));
+            if fits::<T>(&exact) {
+                check_eq!(x.pow(exp), wrap(&exact));
+            }
+        }
+        Op::AbsDiff { lhs, rhs } => {
+            let exact = (lhs.to_big() - rhs.to_big()).abs();
+            check_eq!(lhs.abs_diff(rhs), wrap::<T::Unsigned>(&exact));
+        }
+        Op::Isqrt { x } => {
+            if !x.to_big().is_negative() {
+                check_eq!(x.isqrt(), wrap(&x.to_big().isqrt()));
+            }
+        }
+        Op::CheckedIsqrt { x } => {
+            let root = if x.to_big().is_negative() {
+                None
+            } else {
+                Some(wrap(&x.to_big().isqrt()))
+            };
+            check_eq!(x.checked_isqrt(), root);
+        }
+        // The logarithms are of positive integers only, and `ilog` only to
+        // bases of two or more.
+        Op::Ilog { x, base } => {
+            let positive = x.to_big() > Big::zero();
+            let ilog = if positive && base.to_big() > Big::from(1u128) {
+                Some(x.to_big().ilog(&base.to_big()))
+            } else {
+                None
+            };
+            check_eq!(x.checked_ilog(base), ilog);
+            if let Some(ilog) = ilog {
+                check_eq!(x.ilog(base), ilog);
+            }
+            let ilog2 = if positive { Some(x.to_big().ilog(&Big::from(2u128))) } else { None };
+            check_eq!(x.checked_ilog2(), ilog2);
+            let ilog10 = if positive { Some(x.to_big().ilog(&Big::from(10u128))) } else { None };
+            check_eq!(x.checked_ilog10(), ilog10);
+            if positive {
+                check_eq!(Some(x.ilog2()), ilog2);
+                check_eq!(Some(x.ilog10()), ilog10);
+            }
+        }
+        Op::Bits { x, n } => {
+            let bits = bits(x);
+            let width = T::BITS as usize;
+            let ones = bits.iter().filter(|&&bit| bit).count() as u32;
+            check_eq!(x.count_ones(), ones);
+            check_eq!(x.count_zeros(), T::BITS - ones);
+            check_eq!(x.leading_zeros(), run_of(bits.iter().rev(), false));
+            check_eq!(x.trailing_zeros(), run_of(bits.iter(), false));
+            check_eq!(x.leading_ones(), run_of(bits.iter().rev(), true));
+            check_eq!(x.trailing_ones(), run_of(bits.iter(), true));
+
+            let n = u32::from(n);
+            let k = (n % T::BITS) as usize;
+            let left: Vec<bool> = (0..width).map(|i| bits[(i + width - k) % width]).collect();
+            let right: Vec<bool> = (0..width).map(|i| bits[(i + k) % width]).collect();
+            check_eq!(x.rotate_left(n), from_bits(&left));
+            check_eq!(x.rotate_right(n), from_bits(&right));
+            let reversed: Vec<bool> = bits.iter().rev().cloned().collect();
+            check_eq!(x.reverse_bits(), from_bits(&reversed));
+
+            let le = bytes(x);
+            let be: Vec<u8> = le.iter().rev().cloned().collect();
+            check_eq!(x.to_le_bytes(), le);
+            check_eq!(x.to_be_bytes(), be);
+            let swapped: Vec<bool> = (0..width).map(|i| bits[(width / 8 - 1 - i / 8) * 8 + i % 8]).collect();
+            check_eq!(x.swap_bytes(), from_bits(&swapped));
+        }
+        // Zero is no power of two, and the next one up from it is one.
+        Op::PowerOfTwo { x } => {
+            let one = Big::from(1u128);
+            let next = if x.to_big() <= one {
+                one
+            } else {
+                Big::pow2((x.to_big() - one).bit_len())
+            };
+            check_eq!(x.is_power_of_two(), next == x.to_big());
+            let fits = fits::<T>(&next);
+            check_eq!(x.checked_next_power_of_two(), if fits { Some(wrap(&next)) } else { None });
+            if fits {
+                check_eq!(x.next_power_of_two(), wrap(&next));
+            }
+        }
+    }
+}
+
+/// The type an input of the `num_int` fuzz target names, if it has a header
+pub fn input_type(data: &[u8]) -> Option<&'static str> {
+    let mut ring = FiniteBuffer::new(data, MAX_INPUT_LEN).ok()?;
+    let version: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    if version != VERSION {
+        return None;
+    }
+    let _profile: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    let ty: u8 = Arbitrary::arbitrary(&mut ring).ok()?;
+    Some(TYPES[ty as usize % TYPES.len()])
+}
+
+/// Decode the input of the `num_int` fuzz target into a [`Trace`] of ops on
+/// `T`
+///
+/// The target reads the encoding version, a profile byte and a type byte
+/// before reading ops until the input is exhausted. The profile byte chooses
+/// from [`PROFILES`] how ops are decoded and the type byte chooses from
+/// [`TYPES`]. Returns `None` if the input is of some other version, is too
+/// short to hold its header or names a type other than `T`.
+pub fn decode_input<T: Int>(data: &[u8]) -> Option<Trace<Op<T>>> {
+    if input_type(data)? != T::NAME {
+        return None;
+    }
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |ring| {
+        let _ty: u8 = Arbitrary::arbitrary(ring).ok()?;
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec![T::NAME.to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `num_int` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input<T: Int>(trace: &Trace<Op<T>>) -> Vec<u8> {
+    let ty = TYPES.iter().position(|&name| name == T::NAME).expect("every Int is in TYPES");
+    op::encode_input(trace, |_, buf| (ty as u8).encode(buf))
+}
+
+/// Replay a [`Trace`] against `T` and the model
+///
+/// Every op is checked as it comes, panicking as soon as the two disagree. Ops
+/// `T` lacks are skipped. The run is counted in [`stats`], if collection is
+/// enabled.
+pub fn replay<T: Int>(trace: &Trace<Op<T>>) {
+    let caps = op::capabilities::<Op<T>>(T::NAME, T::LACKS);
+    stats::input(TARGET);
+    for op in &trace.ops {
+        if caps & (1 << op.tag()) == 0 {
+            continue;
+        }
+        stats::op(TARGET, op);
+        check_op(op);
+    }
+}
+
+// Calls `$f::<T>($args)` for the integer type `T` named by `$name`, or
+// evaluates to `$other` if no type has that name.
+macro_rules! with_int_type {
+    ($name:expr, $f:ident($($arg:expr),*), $other:expr) => {
+        match $name {
+            "u8" => $f::<u8>($($arg),*),
+            "u16" => $f::<u16>($($arg),*),
+            "u32" => $f::<u32>($($arg),*),
+            "u64" => $f::<u64>($($arg),*),
+            "u128" => $f::<u128>($($arg),*),
+            "usize" => $f::<usize>($($arg),*),
+            "i8" => $f::<i8>($($arg),*),
+            "i16" => $f::<i16>($($arg),*),
+            "i32" => $f::<i32>($($arg),*),
+            "i64" => $f::<i64>($($arg),*),
+            "i128" => $f::<i128>($($arg),*),
+            "isize" => $f::<isize>($($arg),*),
+            _ => $other,
+        }
+    };
+}
+
+fn run_input<T: Int>(data: &[u8]) {
+    if let Some(trace) = decode_input::<T>(data) {
+        replay(&trace);
+    }
+}
+
+/// Decode and replay an input of the `num_int` fuzz target, whatever type it
+/// names
+pub fn run(data: &[u8]) {
+    if let Some(ty) = input_type(data) {
+        with_int_type!(ty, run_input(data), ())
+    }
+}
+
+fn record_input<T: Int>(data: &[u8]) -> Option<String> {
+    decode_input::<T>(data).map(|trace| trace.to_string())
+}
+
+/// Decode an input of the `num_int` fuzz target into a text trace
+pub fn record(data: &[u8]) -> Option<String> {
+    with_int_type!(input_type(data)?, record_input(data), None)
+}
+
+fn replay_input<T: Int>(text: &str) -> Result<usize, ParseError> {
+    let trace: Trace<Op<T>> = text.parse()?;
+    replay(&trace);
+    Ok(trace.ops.len())
+}
+
+/// Replay a text trace of the `num_int` target, of whatever type its header
+/// names, returning the number of ops replayed
+pub fn replay_text(text: &str) -> Result<usize, ParseError> {
+    let header = trace::header(text)?;
+    let ty = match header.types[..] {
+        [ref ty] => ty.as_str(),
+        _ => return Err(ParseError::new("expected a single integer type")),
+    };
+    with_int_type!(
+        ty,
+        replay_input(text),
+        Err(ParseError::new(format!("'{}' is not an integer type", ty)))
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bounds_are_those_of_the_type() {
+        assert_eq!(Big::from(i128::from(i8::MIN)), min::<i8>());
+        assert_eq!(Big::from(u128::from(u16::MAX)), max::<u16>());
+        assert_eq!(Big::from(i128::MAX), max::<i128>());
+        assert_eq!(Big::from(u128::MAX), max::<u128>());
+    }
+
+    #[test]
+    fn wrapping_keeps_the_low_bits() {
+        assert_eq!(-128i8, wrap(&Big::from(128u128)));
+        assert_eq!(255u8, wrap(&Big::from(-1i128)));
+        assert_eq!(127i8, saturate(&Big::from(1000u128)));
+        assert_eq!(0u8, saturate(&Big::from(-1i128)));
+    }
+
+    #[test]
+    fn edges_agree() {
+        for &x in &[i8::MIN, -1, 0, 1, i8::MAX] {
+            for &y in &[i8::MIN, -1, 0, 1, i8::MAX] {
+                for op in &[
+                    Op::Add { lhs: x, rhs: y },
+                    Op::Mul { lhs: x, rhs: y },
+                    Op::Div { lhs: x, rhs: y },
+                    Op::Rem { lhs: x, rhs: y },
+                    Op::DivEuclid { lhs: x, rhs: y },
+                    Op::RemEuclid { lhs: x, rhs: y },
+                    Op::Ilog { x, base: y },
+                ] {
+                    check_op(op);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lacking_ops_are_masked() {
+        let signed = op::capabilities::<Op<i32>>("i32", i32::LACKS);
+        let unsigned = op::capabilities::<Op<u32>>("u32", u32::LACKS);
+        assert_eq!(0, signed & 1 << 18);
+        assert_eq!(0, unsigned & 1 << 9);
+        assert_ne!(0, unsigned & 1 << 18);
+    }
+
+    quickcheck! {
+        fn every_op_agrees_on_i16(x: i16, y: i16, n: u8) -> bool {
+            for op in &[
+                Op::Sub { lhs: x, rhs: y },
+                Op::Neg { x },
+                Op::SaturatingNeg { x },
+                Op::Abs { x },
+                Op::Shl { x, n },
+                Op::Shr { x, n },
+                Op::Pow { x, exp: n % 20 },
+                Op::AbsDiff { lhs: x, rhs: y },
+                Op::Isqrt { x },
+                Op::CheckedIsqrt { x },
+                Op::Bits { x, n },
+            ] {
+                check_op(op);
+            }
+            true
+        }
+
+        fn every_op_agrees_on_u64(x: u64, y: u64, n: u8) -> bool {
+            for op in &[
+                Op::Add { lhs: x, rhs: y },
+                Op::Mul { lhs: x, rhs: y },
+                Op::Div { lhs: x, rhs: y },
+                Op::Shl { x, n },
+                Op::Pow { x, exp: n },
+                Op::Ilog { x, base: y },
+                Op::Bits { x, n },
+                Op::PowerOfTwo { x },
+            ] {
+                check_op(op);
+            }
+            true
+        }
+
+        fn traces_round_trip(ops: Vec<(i32, i32)>) -> bool {
+            let mut trace = Trace::new(Header {
+                target: TARGET.to_string(),
+                types: vec!["i32".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops.into_iter().map(|(lhs, rhs)| Op::Mul { lhs, rhs }).collect();
+            let decoded = decode_input::<i32>(&encode_input(&trace)).expect("an i32 input");
+            decoded.ops.iter().zip(&trace.ops).all(|(a, b)| a.to_string() == b.to_string())
+        }
+    }
+}
@@ -0,0 +1,743 @@
+//! Tests for the parsing and formatting of the primitive integers
+//!
+//! `from_str_radix` reads an optional sign and then digits, most significant
+//! first, failing on the first digit that is no digit or that takes the value
+//! out of range. Formatting writes the digits back out, in one of five bases,
+//! and pads them to a width with a fill char or with zeros. We check both
+//! against [`from_str_radix`] and [`format`], which do the same a digit at a
+//! time over a [`Big`].
+use arbitrary::*;
+use encode::Encode;
+use op::{self, Profiles};
+use stats;
+use std::fmt;
+use std::num::IntErrorKind;
+use std::str::FromStr;
+use stdlib::num::big::{Big, DIGITS};
+use stdlib::num::int::{self, Int};
+use trace::{self, Header, ParseError, Trace};
+
+/// The name of the fuzz target parsing and formatting the integer types
+pub const TARGET: &str = "num_int_fmt";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most chars in a [`Numeral`]
+pub const MAX_NUMERAL_LEN: usize = 64;
+
+/// The chars a [`Numeral`] is made of
+///
+/// These are the signs, the digits of every radix in both cases and a few
+/// chars that are no digit at all, among them a digit of another script.
+pub const NUMERAL_CHARS: &str =
+    "+-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_\u{e9}\u{663}";
+
+/// The chars a formatted integer may be padded with
+///
+/// The space is the default. The others are written out in format specs,
+/// and `é` takes two bytes but counts as one char of the width.
+pub const FILLS: [char; 3] = [' ', '*', '\u{e9}'];
+
+/// The radix a byte of fuzz input chooses, in `2..=36`
+pub fn radix(byte: u8) -> u32 {
+    2 + u32::from(byte) % 35
+}
+
+lazy_static! {
+    /// The workload profiles of the `num_int_fmt` target, see [`Profiles`]
+    ///
+    /// `parse` sticks to parsing and `format` to formatting.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op<u8>>(&[
+        ("parse", &[("format", 0)]),
+        ("format", &[("parse", 0), ("reparse", 0), ("overflow", 0)]),
+    ]);
+}
+
+/// A string to parse, decoded from fuzz input
+///
+/// A `u8` length, taken modulo one more than [`MAX_NUMERAL_LEN`], is followed
+/// by one byte per char, taken modulo the number of [`NUMERAL_CHARS`]. In
+/// traces a `Numeral` is written out as itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Numeral(pub String);
+
+impl Arbitrary for Numeral {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let chars: Vec<char> = NUMERAL_CHARS.chars().collect();
+        let len: u8 = Arbitrary::arbitrary(u)?;
+        let mut s = String::new();
+        for _ in 0..(len as usize % (MAX_NUMERAL_LEN + 1)) {
+            let c: u8 = Arbitrary::arbitrary(u)?;
+            s.push(chars[c as usize % chars.len()]);
+        }
+        Ok(Numeral(s))
+    }
+}
+
+impl Encode for Numeral {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        // Decoding reads at most `MAX_NUMERAL_LEN` chars, so we write no more.
+        let len = self.0.chars().count().min(MAX_NUMERAL_LEN);
+        (len as u8).encode(buf);
+        for c in self.0.chars().take(len) {
+            let idx = NUMERAL_CHARS
+                .chars()
+                .position(|n| n == c)
+                .expect("a Numeral is made of NUMERAL_CHARS");
+            (idx as u8).encode(buf);
+        }
+    }
+}
+
+impl fmt::Display for Numeral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Numeral {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.chars().find(|&c| !NUMERAL_CHARS.contains(c)) {
+            Some(c) => Err(ParseError::new(format!("'{}' is not a numeral char", c))),
+            None => Ok(Numeral(s.to_string())),
+        }
+    }
+}
+
+/// The base an integer is formatted in, the type of a format spec
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base {
+    /// `{}`, through `Display`
+    Decimal,
+    /// `{:b}`, through `Binary`
+    Binary,
+    /// `{:o}`, through `Octal`
+    Octal,
+    /// `{:x}`, through `LowerHex`
+    LowerHex,
+    /// `{:X}`, through `UpperHex`
+    UpperHex,
+}
+
+/// The bases, in the order a byte of fuzz input chooses them
+const BASES: [Base; 5] = [Base::Decimal, Base::Binary, Base::Octal, Base::LowerHex, Base::UpperHex];
+
+impl Base {
+    /// The radix of the base
+    pub fn radix(self) -> u32 {
+        match self {
+            Base::Decimal => 10,
+            Base::Binary => 2,
+            Base::Octal => 8,
+            Base::LowerHex | Base::UpperHex => 16,
+        }
+    }
+
+    /// The prefix the `#` flag adds
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Base::Decimal => "",
+            Base::Binary => "0b",
+            Base::Octal => "0o",
+            Base::LowerHex | Base::UpperHex => "0x",
+        }
+    }
+
+    /// The type of the format spec
+    fn ty(self) -> &'static str {
+        match self {
+            Base::Decimal => "",
+            Base::Binary => "b",
+            Base::Octal => "o",
+            Base::LowerHex => "x",
+            Base::UpperHex => "X",
+        }
+    }
+}
+
+/// Where a formatted integer goes in its width
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    /// No alignment in the spec, which is to the right for integers
+    Default,
+    /// `<`
+    Left,
+    /// `^`
+    Center,
+    /// `>`
+    Right,
+}
+
+/// The alignments, in the order a byte of fuzz input chooses them
+const ALIGNS: [Align; 4] = [Align::Default, Align::Left, Align::Center, Align::Right];
+
+/// A format spec for an integer, such as `{:*^+#012.3x}`
+///
+/// A spec is decoded from a `u32`, from which the base, alignment, fill and
+/// flags are taken in turn by modulo and division. The width is less than 64
+/// and the precision less than 16. In traces a `Spec` is written as a format
+/// string with its width and precision always given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spec {
+    /// The base, chosen by the type of the spec
+    pub base: Base,
+    /// The alignment
+    pub align: Align,
+    /// The fill, one of [`FILLS`] and a space unless the alignment is given
+    pub fill: char,
+    /// Whether to write a `+` on integers no less than zero, the `+` flag
+    pub plus: bool,
+    /// Whether to write the prefix of the base, the `#` flag
+    pub alternate: bool,
+    /// Whether to pad with zeros after the sign and prefix, the `0` flag
+    pub zero: bool,
+    /// The least number of chars to write
+    pub width: usize,
+    /// The precision, which integers ignore
+    pub precision: usize,
+}
+
+impl Arbitrary for Spec {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let mut x: u32 = Arbitrary::arbitrary(u)?;
+        let mut take = |n: u32| {
+            let digit = x % n;
+            x /= n;
+            digit as usize
+        };
+        let base = BASES[take(5)];
+        let align = ALIGNS[take(4)];
+        let fill = FILLS[take(3)];
+        let flags = take(8);
+        let width = take(64);
+        let precision = take(16);
+        Ok(Spec {
+            base,
+            align,
+            fill: if align == Align::Default { ' ' } else { fill },
+            plus: flags & 1 != 0,
+            alternate: flags & 2 != 0,
+            zero: flags & 4 != 0,
+            width,
+            precision,
+        })
+    }
+}
+
+impl Encode for Spec {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let index = |found: Option<usize>| found.expect("specs are built from BASES, ALIGNS and FILLS") as u32;
+        let flags = self.plus as u32 | (self.alternate as u32) << 1 | (self.zero as u32) << 2;
+        let mut x = self.precision as u32 % 16;
+        x = x * 64 + self.width as u32 % 64;
+        x = x * 8 + flags;
+        x = x * 3 + index(FILLS.iter().position(|&f| f == self.fill));
+        x = x * 4 + index(ALIGNS.iter().position(|&a| a == self.align));
+        x = x * 5 + index(BASES.iter().position(|&b| b == self.base));
+        x.encode(buf)
+    }
+}
+
+impl fmt::Display for Spec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("{:")?;
+        let align = match self.align {
+            Align::Default => "",
+            Align::Left => "<",
+            Align::Center => "^",
+            Align::Right => ">",
+        };
+        if self.align != Align::Default && self.fill != ' ' {
+            write!(f, "{}", self.fill)?;
+        }
+        f.write_str(align)?;
+        if self.plus {
+            f.write_str("+")?;
+        }
+        if self.alternate {
+            f.write_str("#")?;
+        }
+        if self.zero {
+            f.write_str("0")?;
+        }
+        write!(f, "{}.{}{}}}", self.width, self.precision, self.base.ty())
+    }
+}
+
+impl FromStr for Spec {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || ParseError::new(format!("'{}' is not a format spec", s));
+        if !s.starts_with("{:") || !s.ends_with('}') {
+            return Err(bad());
+        }
+        let mut rest: Vec<char> = s[2..s.len() - 1].chars().collect();
+        let align_of = |c: char| match c {
+            '<' => Some(Align::Left),
+            '^' => Some(Align::Center),
+            '>' => Some(Align::Right),
+            _ => None,
+        };
+        let (align, fill) = match (rest.first().cloned(), rest.get(1).cloned().and_then(align_of)) {
+            (Some(fill), Some(align)) if FILLS.contains(&fill) => {
+                rest.drain(..2);
+                (align, fill)
+            }
+            (Some(c), _) => match align_of(c) {
+                Some(align) => {
+                    rest.remove(0);
+                    (align, ' ')
+                }
+                None => (Align::Default, ' '),
+            },
+            (None, _) => return Err(bad()),
+        };
+        let mut flag = |c: char| {
+            let found = rest.first() == Some(&c);
+            if found {
+                rest.remove(0);
+            }
+            found
+        };
+        let plus = flag('+');
+        let alternate = flag('#');
+        let rest: String = rest.into_iter().collect();
+        let dot = rest.find('.').ok_or_else(bad)?;
+        let (width, rest) = (&rest[..dot], &rest[dot + 1..]);
+        // The width is written without leading zeros, so one there is the
+        // `0` flag.
+        let zero = width.len() > 1 && width.starts_with('0');
+        let width = if zero { &width[1..] } else { width };
+        let ty = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+        let precision = &rest[..rest.len() - ty.len()];
+        let base = BASES.iter().cloned().find(|b| b.ty() == ty).ok_or_else(bad)?;
+        Ok(Spec {
+            base,
+            align,
+            fill,
+            plus,
+            alternate,
+            zero,
+            width: width.parse().map_err(|_| bad())?,
+            precision: precision.parse().map_err(|_| bad())?,
+        })
+    }
+}
+
+/// The value of `c` as a digit of base 36, in either case
+fn digit(c: char) -> Option<u32> {
+    match c {
+        '0'..='9' => Some(c as u32 - '0' as u32),
+        'a'..='z' => Some(c as u32 - 'a' as u32 + 10),
+        'A'..='Z' => Some(c as u32 - 'A' as u32 + 10),
+        _ => None,
+    }
+}
+
+/// Parse `src` as a `T` in base `radix`, which must be in `2..=36`
+///
+/// This is like to `T::from_str_radix`. A `+` may lead, or a `-` if `T` is
+/// signed, but not on its own. Digits are read left to right, and the first
+/// that is no digit of `radix`, or that takes the value out of the bounds of
+/// `T`, is the error.
+pub fn from_str_radix<T: Int>(src: &str, radix: u32) -> Result<T, IntErrorKind> {
+    let (negative, digits) = match src {
+        "" => return Err(IntErrorKind::Empty),
+        "+" | "-" => return Err(IntErrorKind::InvalidDigit),
+        _ if src.starts_with('+') => (false, &src[1..]),
+        _ if src.starts_with('-') && T::SIGNED => (true, &src[1..]),
+        _ => (false, src),
+    };
+    let radix_big = Big::from(u128::from(radix));
+    let mut magnitude = Big::zero();
+    for c in digits.chars() {
+        let d = match digit(c) {
+            Some(d) if d < radix => d,
+            _ => return Err(IntErrorKind::InvalidDigit),
+        };
+        magnitude = magnitude * radix_big.clone() + Big::from(u128::from(d));
+        if negative && -magnitude.clone() < int::min::<T>() {
+            return Err(IntErrorKind::NegOverflow);
+        }
+        if !negative && magnitude > int::max::<T>() {
+            return Err(IntErrorKind::PosOverflow);
+        }
+    }
+    let value = if negative { -magnitude } else { magnitude };
+    Ok(int::wrap(&value))
+}
+
+/// Write `x` in base `radix`, with a `-` if it is negative
+pub fn to_str_radix<T: Int>(x: T, radix: u32) -> String {
+    x.to_big().to_str_radix(radix)
+}
+
+/// Format `x` by `spec`
+///
+/// This is like to `format!` with the spec. Decimal integers are written as
+/// a sign and magnitude, all other bases write the bits of the two's
+/// complement. The sign comes first, then the prefix, then the digits. The
+/// `0` flag pads between prefix and digits and overrides the alignment.
+pub fn format<T: Int>(x: T, spec: &Spec) -> String {
+    let (negative, mut digits) = match spec.base {
+        Base::Decimal => (x.to_big().is_negative(), x.to_big().abs().to_str_radix(10)),
+        base => (false, Big::from(x.to_big().low_bits(T::BITS)).to_str_radix(base.radix())),
+    };
+    if spec.base == Base::UpperHex {
+        digits = digits.to_uppercase();
+    }
+    let sign = match (negative, spec.plus) {
+        (true, _) => "-",
+        (false, true) => "+",
+        (false, false) => "",
+    };
+    let prefix = if spec.alternate { spec.base.prefix() } else { "" };
+    let len = sign.len() + prefix.len() + digits.len();
+    if len >= spec.width {
+        return format!("{}{}{}", sign, prefix, digits);
+    }
+    let pad = spec.width - len;
+    if spec.zero {
+        return format!("{}{}{}{}", sign, prefix, "0".repeat(pad), digits);
+    }
+    let (before, after) = match spec.align {
+        Align::Left => (0, pad),
+        Align::Center => (pad / 2, pad - pad / 2),
+        Align::Right | Align::Default => (pad, 0),
+    };
+    let fill = spec.fill.to_string();
+    format!("{}{}{}{}{}", fill.repeat(before), sign, prefix, digits, fill.repeat(after))
+}
+
+// Format specs are string literals, so `std_format` builds one for each
+// combination of alignment, fill and flags, a level at a time, and leaves
+// width and precision to arguments.
+macro_rules! format_spec {
+    (@base $x:expr, $spec:expr, $($lit:tt)*) => {
+        match $spec.base {
+            Base::Decimal => format!(concat!("{:", $($lit,)* "w$.p$}"), $x, w = $spec.width, p = $spec.precision),
+            Base::Binary => format!(concat!("{:", $($lit,)* "w$.p$b}"), $x, w = $spec.width, p = $spec.precision),
+            Base::Octal => format!(concat!("{:", $($lit,)* "w$.p$o}"), $x, w = $spec.width, p = $spec.precision),
+            Base::LowerHex => format!(concat!("{:", $($lit,)* "w$.p$x}"), $x, w = $spec.width, p = $spec.precision),
+            Base::UpperHex => format!(concat!("{:", $($lit,)* "w$.p$X}"), $x, w = $spec.width, p = $spec.precision),
+        }
+    };
+    (@zero $x:expr, $spec:expr, $($lit:tt)*) => {
+        if $spec.zero {
+            format_spec!(@base $x, $spec, $($lit)* "0")
+        } else {
+            format_spec!(@base $x, $spec, $($lit)*)
+        }
+    };
+    (@alternate $x:expr, $spec:expr, $($lit:tt)*) => {
+        if $spec.alternate {
+            format_spec!(@zero $x, $spec, $($lit)* "#")
+        } else {
+            format_spec!(@zero $x, $spec, $($lit)*)
+        }
+    };
+    (@plus $x:expr, $spec:expr, $($lit:tt)*) => {
+        if $spec.plus {
+            format_spec!(@alternate $x, $spec, $($lit)* "+")
+        } else {
+            format_spec!(@alternate $x, $spec, $($lit)*)
+        }
+    };
+    ($x:expr, $spec:expr) => {
+        match ($spec.align, $spec.fill) {
+            (Align::Default, _) => format_spec!(@plus $x, $spec,),
+            (Align::Left, ' ') => format_spec!(@plus $x, $spec, "<"),
+            (Align::Left, '*') => format_spec!(@plus $x, $spec, "*<"),
+            (Align::Left, _) => format_spec!(@plus $x, $spec, "\u{e9}<"),
+            (Align::Center, ' ') => format_spec!(@plus $x, $spec, "^"),
+            (Align::Center, '*') => format_spec!(@plus $x, $spec, "*^"),
+            (Align::Center, _) => format_spec!(@plus $x, $spec, "\u{e9}^"),
+            (Align::Right, ' ') => format_spec!(@plus $x, $spec, ">"),
+            (Align::Right, '*') => format_spec!(@plus $x, $spec, "*>"),
+            (Align::Right, _) => format_spec!(@plus $x, $spec, "\u{e9}>"),
+        }
+    };
+}
+
+/// Format `x` by `spec` with `format!`
+///
+/// Fills other than those of [`FILLS`] are taken for `é`.
+pub fn std_format<T: Int>(x: T, spec: &Spec) -> String {
+    format_spec!(x, spec)
+}
+
+/// The `Op` defines the ways integers of the `num_int_fmt` target are parsed
+/// and formatted.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op<T> {
+    /// This operation parses a string, see `from_str_radix` and `FromStr`
+    #[op(tag = 0, weight = 2)]
+    Parse {
+        /// The string to parse
+        s: Numeral,
+        /// The radix, see [`radix`]
+        radix: u8,
+    },
+    /// This operation writes out an integer and parses it back, see
+    /// `from_str_radix`
+    #[op(tag = 1, weight = 2)]
+    Reparse {
+        /// The integer
+        x: T,
+        /// The radix, see [`radix`]
+        radix: u8,
+        /// Put `zeros % 8` zeros after the sign, and a `+` if there is none
+        /// and `zeros & 8` is set
+        zeros: u8,
+    },
+    /// This operation writes out an integer and a digit more and parses
+    /// that, see `from_str_radix`
+    #[op(tag = 2, weight = 2)]
+    Overflow {
+        /// The integer
+        x: T,
+        /// The radix, see [`radix`]
+        radix: u8,
+        /// The digit to append, modulo the radix
+        digit: u8,
+    },
+    /// This operation formats an integer, see `format!`
+    #[op(tag = 3, weight = 4)]
+    Format {
+        /// The integer
+        x: T,
+        /// The format spec
+        spec: Spec,
+    },
+}
+
+/// Parse `src` with `T::from_str_radix`, keeping only the kind of error
+fn std_from_str_radix<T: Int>(src: &str, radix: u32) -> Result<T, IntErrorKind> {
+    T::from_str_radix(src, radix).map_err(|e| *e.kind())
+}
+
+/// Run `op` against `T` and the model, panicking if they disagree
+pub fn check_op<T: Int>(op: &Op<T>) {
+    match *op {
+        Op::Parse { ref s, radix: byte } => {
+            let s = &s.0;
+            check_eq!(std_from_str_radix::<T>(s, radix(byte)), from_str_radix(s, radix(byte)));
+            check_eq!(s.parse::<T>().map_err(|e| *e.kind()), from_str_radix(s, 10));
+        }
+        Op::Reparse { x, radix: byte, zeros } => {
+            let radix = radix(byte);
+            let written = to_str_radix(x, radix);
+            if radix == 10 {
+                check_eq!(x.to_string(), written);
+            }
+            let (sign, magnitude) = match written.starts_with('-') {
+                true => ("-", &written[1..]),
+                false if zeros & 8 != 0 => ("+", &written[..]),
+                false => ("", &written[..]),
+            };
+            let s = format!("{}{}{}", sign, "0".repeat(zeros as usize % 8), magnitude);
+            check_eq!(from_str_radix(&s, radix), Ok(x));
+            check_eq!(std_from_str_radix(&s, radix), Ok(x));
+            check_eq!(std_from_str_radix(&s.to_uppercase(), radix), Ok(x));
+        }
+        Op::Overflow { x, radix: byte, digit } => {
+            let radix = radix(byte);
+            let d = DIGITS.as_bytes()[(u32::from(digit) % radix) as usize] as char;
+            let s = format!("{}{}", to_str_radix(x, radix), d);
+            check_eq!(std_from_str_radix::<T>(&s, radix), from_str_radix(&s, radix));
+        }
+        Op::Format { x, ref spec } => {
+            let s = std_format(x, spec);
+            check_eq!(s, format(x, spec));
+            // What was written parses back, once the padding, sign and prefix
+            // are taken off. Bases other than ten write the bits of the two's
+            // complement, which is what the unsigned type parses.
+            let body = s.trim_matches(spec.fill);
+            let (sign, rest) = match body.chars().next() {
+                Some(c @ '+') | Some(c @ '-') => (c.to_string(), &body[1..]),
+                _ => (String::new(), body),
+            };
+            let prefix = if spec.alternate { spec.base.prefix() } else { "" };
+            check!(rest.starts_with(prefix));
+            let digits = format!("{}{}", sign, &rest[prefix.len()..]);
+            let radix = spec.base.radix();
+            if spec.base == Base::Decimal {
+                check_eq!(std_from_str_radix(&digits, radix), Ok(x));
+            } else {
+                let bits: T::Unsigned = int::wrap(&Big::from(x.to_big().low_bits(T::BITS)));
+                check_eq!(std_from_str_radix(&digits, radix), Ok(bits));
+            }
+        }
+    }
+}
+
+/// Decode the input of the `num_int_fmt` fuzz target into a [`Trace`] of ops
+/// on `T`
+///
+/// The input has the header of the `num_int` target, see
+/// [`int::decode_input`], and is followed by ops until it is exhausted.
+/// Returns `None` if the input is of some other version, is too short to hold
+/// its header or names a type other than `T`.
+pub fn decode_input<T: Int>(data: &[u8]) -> Option<Trace<Op<T>>> {
+    if int::input_type(data)? != T::NAME {
+        return None;
+    }
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |ring| {
+        let _ty: u8 = Arbitrary::arbitrary(ring).ok()?;
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec![T::NAME.to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `num_int_fmt` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input<T: Int>(trace: &Trace<Op<T>>) -> Vec<u8> {
+    let ty = int::TYPES.iter().position(|&name| name == T::NAME).expect("every Int is in TYPES");
+    op::encode_input(trace, |_, buf| (ty as u8).encode(buf))
+}
+
+/// Replay a [`Trace`] against `T` and the model
+///
+/// Every op is checked as it comes, panicking as soon as the two disagree.
+/// The run is counted in [`stats`], if collection is enabled.
+pub fn replay<T: Int>(trace: &Trace<Op<T>>) {
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        check_op(op);
+    }
+}
+
+fn run_input<T: Int>(data: &[u8]) {
+    if let Some(trace) = decode_input::<T>(data) {
+        replay(&trace);
+    }
+}
+
+/// Decode and replay an input of the `num_int_fmt` fuzz target, whatever
+/// type it names
+pub fn run(data: &[u8]) {
+    if let Some(ty) = int::input_type(data) {
+        with_int_type!(ty, run_input(data), ())
+    }
+}
+
+fn record_input<T: Int>(data: &[u8]) -> Option<String> {
+    decode_input::<T>(data).map(|trace| trace.to_string())
+}
+
+/// Decode an input of the `num_int_fmt` fuzz target into a text trace
+pub fn record(data: &[u8]) -> Option<String> {
+    with_int_type!(int::input_type(data)?, record_input(data), None)
+}
+
+fn replay_input<T: Int>(text: &str) -> Result<usize, ParseError> {
+    let trace: Trace<Op<T>> = text.parse()?;
+    replay(&trace);
+    Ok(trace.ops.len())
+}
+
+/// Replay a text trace of the `num_int_fmt` target, of whatever type its
+/// header names, returning the number of ops replayed
+pub fn replay_text(text: &str) -> Result<usize, ParseError> {
+    let header = trace::header(text)?;
+    let ty = match header.types[..] {
+        [ref ty] => ty.as_str(),
+        _ => return Err(ParseError::new("expected a single integer type")),
+    };
+    with_int_type!(
+        ty,
+        replay_input(text),
+        Err(ParseError::new(format!("'{}' is not an integer type", ty)))
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn spec(s: &str) -> Spec {
+        s.parse().expect("a valid spec")
+    }
+
+    fn spec_of(x: u32) -> Spec {
+        let mut buf = Vec::new();
+        x.encode(&mut buf);
+        let mut ring = FiniteBuffer::new(&buf, 4).expect("four bytes");
+        Arbitrary::arbitrary(&mut ring).expect("four bytes are enough")
+    }
+
+    #[test]
+    fn signs_alone_are_invalid() {
+        assert_eq!(Err(IntErrorKind::Empty), from_str_radix::<i8>("", 10));
+        assert_eq!(Err(IntErrorKind::InvalidDigit), from_str_radix::<i8>("-", 10));
+        assert_eq!(Err(IntErrorKind::InvalidDigit), from_str_radix::<u8>("+", 10));
+        assert_eq!(Err(IntErrorKind::InvalidDigit), from_str_radix::<u8>("-0", 10));
+        assert_eq!(Ok(0), from_str_radix::<i8>("-0", 10));
+    }
+
+    #[test]
+    fn the_first_bad_digit_decides() {
+        assert_eq!(Err(IntErrorKind::PosOverflow), from_str_radix::<u8>("256x", 10));
+        assert_eq!(Err(IntErrorKind::InvalidDigit), from_str_radix::<u8>("25x6", 10));
+        assert_eq!(Err(IntErrorKind::NegOverflow), from_str_radix::<i8>("-129", 10));
+        assert_eq!(Ok(-128), from_str_radix::<i8>("-80", 16));
+        assert_eq!(Err(IntErrorKind::InvalidDigit), from_str_radix::<i8>("\u{663}", 10));
+    }
+
+    #[test]
+    fn zeros_override_the_alignment() {
+        assert_eq!("+0x0000000ff", format(255u8, &spec("{:*^+#012.3x}")));
+        assert_eq!("***+0xff****", format(255u8, &spec("{:*^+#12.3x}")));
+        assert_eq!("\u{e9}\u{e9}-3\u{e9}\u{e9}\u{e9}", format(-3i8, &spec("{:\u{e9}^7.0}")));
+        assert_eq!("+ff", format(-1i8, &spec("{:+0.0x}")));
+        assert_eq!("0xFF", format(255u8, &spec("{:#0.0X}")));
+    }
+
+    #[test]
+    fn specs_are_written_as_format_strings() {
+        for &s in &["{:0.0}", "{:00.0b}", "{:<+#010.15o}", "{:\u{e9}>7.0X}", "{:*^0.0x}"] {
+            assert_eq!(s, spec(s).to_string());
+        }
+    }
+
+    quickcheck! {
+        fn specs_round_trip(x: u32) -> bool {
+            let spec = spec_of(x);
+            let mut buf = Vec::new();
+            spec.encode(&mut buf);
+            let mut ring = FiniteBuffer::new(&buf, 4).expect("four bytes");
+            let decoded: Spec = Arbitrary::arbitrary(&mut ring).expect("four bytes are enough");
+            decoded == spec && spec.to_string().parse::<Spec>().ok() == Some(spec)
+        }
+
+        fn formatting_agrees_on_i32(x: i32, y: u32) -> bool {
+            check_op(&Op::Format { x, spec: spec_of(y) });
+            true
+        }
+
+        fn parsing_agrees_on_i64(x: i64, radix: u8, digit: u8) -> bool {
+            check_op(&Op::Reparse { x, radix, zeros: digit });
+            check_op(&Op::Overflow { x, radix, digit });
+            true
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! Tests for `std::num`
+pub mod big;
+pub mod float;
+#[macro_use]
+pub mod int;
+pub mod int_fmt;
@@ -0,0 +1,771 @@
+//! Tests for `std::rc::Rc` and `std::rc::Weak`
+//!
+//! Ops keep a pool of `Rc` and `Weak` handles to any number of allocations,
+//! cloning, dropping, downgrading and upgrading them and reaching through
+//! them with `get_mut`, `make_mut`, `try_unwrap` and `into_inner`. The model,
+//! a [`PropRc`], keeps a pool alike, of handles naming the allocation they
+//! point to, and the counts of an allocation follow from it: its strong count
+//! is the number of `Rc` handles to it and its weak count the number of
+//! `Weak`.
+//!
+//! Every value is a [`Tracked`], which logs when it is dropped. A value must
+//! be dropped just when the last `Rc` to its allocation goes, unless it was
+//! moved out first, and the log of the system under test must match the
+//! model's after every op.
+use op::{self, Profiles};
+use stats;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `Rc` and `Weak`
+pub const TARGET: &str = "rc";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most handles held at once; ops that would hold more do nothing
+pub const MAX_HANDLES: usize = 64;
+
+lazy_static! {
+    /// The workload profiles of the `rc` target, see [`Profiles`]
+    ///
+    /// `unique` drops handles about as often as it makes them, so handles
+    /// are often the only ones to their allocation, which is when `get_mut`,
+    /// `try_unwrap` and `into_inner` succeed and `make_mut` doesn't clone.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "unique",
+        &[("drop", 6), ("clone", 1), ("get_mut", 3), ("try_unwrap", 2), ("into_inner", 2)],
+    )]);
+}
+
+/// The log of the [`Tracked`] values made and dropped
+#[derive(Debug, Default)]
+pub struct Log {
+    made: Cell<usize>,
+    dropped: RefCell<Vec<usize>>,
+}
+
+impl Log {
+    /// Construct a new, empty `Log`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The ids of the values dropped, in the order they were
+    pub fn dropped(&self) -> Vec<usize> {
+        self.dropped.borrow().clone()
+    }
+}
+
+/// A value that logs when it is dropped
+///
+/// Each value made, by [`Tracked::new`] or by cloning, takes the next id
+/// of its log, so every value is told apart.
+#[derive(Debug)]
+pub struct Tracked<'a> {
+    /// The byte the value holds
+    pub byte: u8,
+    id: usize,
+    log: &'a Log,
+}
+
+impl<'a> Tracked<'a> {
+    /// Construct a new `Tracked` holding `byte`, logged in `log`
+    pub fn new(byte: u8, log: &'a Log) -> Self {
+        let id = log.made.get();
+        log.made.set(id + 1);
+        Tracked { byte, id, log }
+    }
+
+    /// The id of the value, the number of values its log had made before it
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<'a> Clone for Tracked<'a> {
+    fn clone(&self) -> Self {
+        Tracked::new(self.byte, self.log)
+    }
+}
+
+impl<'a> Drop for Tracked<'a> {
+    fn drop(&mut self) {
+        self.log.dropped.borrow_mut().push(self.id);
+    }
+}
+
+/// The kind of a handle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// An `Rc`
+    Strong,
+    /// A `Weak`
+    Weak,
+}
+
+/// A model of a handle, pointing to an allocation of a [`PropRc`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PropHandle {
+    /// The kind of the handle
+    pub kind: Kind,
+    /// The index of the allocation the handle points to
+    pub alloc: usize,
+}
+
+/// A model of a value, made by [`Tracked::new`] or cloned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PropValue {
+    /// The byte the value holds
+    pub byte: u8,
+    /// The id of the value
+    pub id: usize,
+}
+
+/// A model of a pool of `Rc<Tracked>` and `Weak<Tracked>` handles
+///
+/// Allocations are never forgotten, only left without a value when their
+/// last `Rc` goes or their value is moved out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PropRc {
+    handles: Vec<PropHandle>,
+    allocs: Vec<Option<PropValue>>,
+    made: usize,
+    dropped: Vec<usize>,
+}
+
+impl PropRc {
+    /// Construct a new `PropRc` with no handles
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The handles held
+    pub fn handles(&self) -> &[PropHandle] {
+        &self.handles
+    }
+
+    /// The ids of the values dropped, in the order they were
+    pub fn dropped(&self) -> &[usize] {
+        &self.dropped
+    }
+
+    /// The value of `alloc`, if it has one yet
+    pub fn value(&self, alloc: usize) -> Option<PropValue> {
+        self.allocs[alloc]
+    }
+
+    fn count(&self, alloc: usize, kind: Kind) -> usize {
+        self.handles
+            .iter()
+            .filter(|h| h.alloc == alloc && h.kind == kind)
+            .count()
+    }
+
+    /// The number of `Rc` handles to `alloc`
+    ///
+    /// This is like to [`Rc::strong_count`] and [`Weak::strong_count`]
+    pub fn strong_count(&self, alloc: usize) -> usize {
+        self.count(alloc, Kind::Strong)
+    }
+
+    /// The number of `Weak` handles to `alloc`
+    ///
+    /// This is like to [`Rc::weak_count`]. Note that [`Weak::weak_count`] is
+    /// zero once the strong count is.
+    pub fn weak_count(&self, alloc: usize) -> usize {
+        self.count(alloc, Kind::Weak)
+    }
+
+    fn make(&mut self, byte: u8) -> PropValue {
+        let id = self.made;
+        self.made += 1;
+        PropValue { byte, id }
+    }
+
+    fn alloc(&mut self, value: PropValue) -> usize {
+        self.allocs.push(Some(value));
+        self.allocs.len() - 1
+    }
+
+    fn hold(&mut self, kind: Kind, alloc: usize) {
+        self.handles.push(PropHandle { kind, alloc });
+    }
+
+    /// Drop the value of `alloc` if no `Rc` to it is left
+    fn release(&mut self, alloc: usize) {
+        if self.strong_count(alloc) == 0 {
+            if let Some(value) = self.allocs[alloc].take() {
+                self.dropped.push(value.id);
+            }
+        }
+    }
+
+    /// Hold an `Rc` to a new allocation of `byte`
+    ///
+    /// This is like to [`Rc::new`]
+    pub fn new_rc(&mut self, byte: u8) {
+        let value = self.make(byte);
+        let alloc = self.alloc(value);
+        self.hold(Kind::Strong, alloc);
+    }
+
+    /// Hold an `Rc` to a new allocation of `byte`, and after it a `Weak` to
+    /// the same
+    ///
+    /// This is like to [`Rc::new_cyclic`], keeping a clone of the `Weak` the
+    /// value is made with.
+    pub fn new_cyclic(&mut self, byte: u8) {
+        self.new_rc(byte);
+        let alloc = self.allocs.len() - 1;
+        self.hold(Kind::Weak, alloc);
+    }
+
+    /// Hold a clone of the handle at `idx`, last
+    ///
+    /// This is like to [`Rc::clone`] and [`Weak::clone`]
+    pub fn clone_handle(&mut self, idx: usize) {
+        let handle = self.handles[idx];
+        self.handles.push(handle);
+    }
+
+    /// Drop the handle at `idx`
+    pub fn drop_handle(&mut self, idx: usize) {
+        let handle = self.handles.remove(idx);
+        self.release(handle.alloc);
+    }
+
+    /// Hold a `Weak` to the allocation of the `Rc` at `idx`, last
+    ///
+    /// This is like to [`Rc::downgrade`]
+    pub fn downgrade(&mut self, idx: usize) {
+        let alloc = self.handles[idx].alloc;
+        self.hold(Kind::Weak, alloc);
+    }
+
+    /// Hold an `Rc` to the allocation of the `Weak` at `idx`, last, if it
+    /// still has a value, giving whether it does
+    ///
+    /// This is like to [`Weak::upgrade`]
+    pub fn upgrade(&mut self, idx: usize) -> bool {
+        let alloc = self.handles[idx].alloc;
+        let live = self.strong_count(alloc) > 0;
+        if live {
+            self.hold(Kind::Strong, alloc);
+        }
+        live
+    }
+
+    /// Write `byte` to the value of the `Rc` at `idx` if that is the only
+    /// handle to its allocation, giving whether it is
+    ///
+    /// This is like to [`Rc::get_mut`]
+    pub fn get_mut(&mut self, idx: usize, byte: u8) -> bool {
+        let alloc = self.handles[idx].alloc;
+        let unique = self.strong_count(alloc) == 1 && self.weak_count(alloc) == 0;
+        if unique {
+            self.write(alloc, byte);
+        }
+        unique
+    }
+
+    fn write(&mut self, alloc: usize, byte: u8) {
+        if let Some(ref mut value) = self.allocs[alloc] {
+            value.byte = byte;
+        }
+    }
+
+    /// Write `byte` to the value of the `Rc` at `idx`, first making the
+    /// value the handle's alone
+    ///
+    /// This is like to [`Rc::make_mut`]. If other `Rc`s share the
+    /// allocation the value is cloned into a new one. If only `Weak`s do,
+    /// the value is moved into a new one, and they are left without it.
+    pub fn make_mut(&mut self, idx: usize, byte: u8) {
+        let alloc = self.handles[idx].alloc;
+        let value = self.allocs[alloc].expect("an Rc has a value");
+        let fresh = if self.strong_count(alloc) > 1 {
+            let clone = self.make(value.byte);
+            Some(clone)
+        } else if self.weak_count(alloc) > 0 {
+            self.allocs[alloc] = None;
+            Some(value)
+        } else {
+            None
+        };
+        let alloc = match fresh {
+            Some(value) => {
+                let alloc = self.alloc(value);
+                self.handles[idx].alloc = alloc;
+                alloc
+            }
+            None => alloc,
+        };
+        self.write(alloc, byte);
+    }
+
+    /// Move the value out of the allocation of the `Rc` at `idx` if it is the
+    /// only `Rc` to it, giving the value, and drop the handle either way
+    ///
+    /// This is like to [`Rc::into_inner`]. The value given is not yet
+    /// dropped.
+    pub fn into_inner(&mut self, idx: usize) -> Option<PropValue> {
+        let handle = self.handles.remove(idx);
+        if self.strong_count(handle.alloc) == 0 {
+            self.allocs[handle.alloc].take()
+        } else {
+            None
+        }
+    }
+
+    /// Move the value out of the allocation of the `Rc` at `idx` if it is the
+    /// only `Rc` to it, giving the value, and otherwise keep the handle
+    ///
+    /// This is like to [`Rc::try_unwrap`]. The value given is not yet
+    /// dropped.
+    pub fn try_unwrap(&mut self, idx: usize) -> Option<PropValue> {
+        let alloc = self.handles[idx].alloc;
+        if self.strong_count(alloc) == 1 {
+            self.into_inner(idx)
+        } else {
+            None
+        }
+    }
+
+    /// Drop `value`, moved out of its allocation
+    pub fn drop_value(&mut self, value: PropValue) {
+        self.dropped.push(value.id);
+    }
+}
+
+/// A handle of the pool of the `rc` target
+pub enum Handle<'a> {
+    /// An `Rc`
+    Strong(Rc<Tracked<'a>>),
+    /// A `Weak`
+    Weak(Weak<Tracked<'a>>),
+}
+
+impl<'a> Handle<'a> {
+    /// The kind of the handle
+    pub fn kind(&self) -> Kind {
+        match *self {
+            Handle::Strong(_) => Kind::Strong,
+            Handle::Weak(_) => Kind::Weak,
+        }
+    }
+
+    /// A pointer to the value of the allocation of the handle
+    pub fn as_ptr(&self) -> *const Tracked<'a> {
+        match *self {
+            Handle::Strong(ref rc) => Rc::as_ptr(rc),
+            Handle::Weak(ref weak) => weak.as_ptr(),
+        }
+    }
+}
+
+/// The `Op` defines the ways the pool of handles of the `rc` target is
+/// changed.
+///
+/// Handles are picked by `idx` modulo the number held, and ops on a handle
+/// do nothing while none are held. Ops that don't apply to the handle picked
+/// -- upgrading an `Rc`, say -- do nothing either.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `Rc::new`
+    #[op(tag = 0, weight = 2)]
+    New {
+        /// The byte of the new value
+        byte: u8,
+    },
+    /// This operation triggers `Rc::new_cyclic`
+    #[op(tag = 1)]
+    NewCyclic {
+        /// The byte of the new value
+        byte: u8,
+    },
+    /// This operation triggers `Rc::clone` or `Weak::clone`
+    #[op(tag = 2, weight = 3)]
+    Clone {
+        /// The handle to clone
+        idx: u8,
+    },
+    /// This operation drops a handle
+    #[op(tag = 3, weight = 3)]
+    Drop {
+        /// The handle to drop
+        idx: u8,
+    },
+    /// This operation triggers `Rc::downgrade`
+    #[op(tag = 4, weight = 2)]
+    Downgrade {
+        /// The `Rc` to downgrade
+        idx: u8,
+    },
+    /// This operation triggers `Weak::upgrade`
+    #[op(tag = 5, weight = 2)]
+    Upgrade {
+        /// The `Weak` to upgrade
+        idx: u8,
+    },
+    /// This operation triggers `Rc::get_mut`
+    #[op(tag = 6)]
+    GetMut {
+        /// The `Rc` to write through
+        idx: u8,
+        /// The byte to write
+        byte: u8,
+    },
+    /// This operation triggers `Rc::make_mut`
+    #[op(tag = 7)]
+    MakeMut {
+        /// The `Rc` to write through
+        idx: u8,
+        /// The byte to write
+        byte: u8,
+    },
+    /// This operation triggers `Rc::try_unwrap`
+    #[op(tag = 8)]
+    TryUnwrap {
+        /// The `Rc` to unwrap
+        idx: u8,
+    },
+    /// This operation triggers `Rc::into_inner`
+    #[op(tag = 9)]
+    IntoInner {
+        /// The `Rc` to unwrap
+        idx: u8,
+    },
+    /// This operation triggers `Rc::ptr_eq` and `Weak::ptr_eq`
+    #[op(tag = 10)]
+    PtrEq {
+        /// One handle
+        a: u8,
+        /// The other handle
+        b: u8,
+    },
+}
+
+/// Check `handles` and `log` against `model`
+///
+/// Every handle must be of the kind of its model handle, and count and see
+/// as it does, and the values dropped since the first `since` must be the
+/// model's.
+fn check_state(handles: &[Handle], log: &Log, model: &PropRc, since: usize) {
+    check_eq!(handles.len(), model.handles().len());
+    let mut counts = HashMap::new();
+    for prop in model.handles() {
+        let count = counts.entry(prop.alloc).or_insert((0, 0));
+        match prop.kind {
+            Kind::Strong => count.0 += 1,
+            Kind::Weak => count.1 += 1,
+        }
+    }
+    for (handle, prop) in handles.iter().zip(model.handles()) {
+        let (strong, weak) = counts[&prop.alloc];
+        check_eq!(handle.kind(), prop.kind);
+        match *handle {
+            Handle::Strong(ref rc) => {
+                check_eq!(Rc::strong_count(rc), strong);
+                check_eq!(Rc::weak_count(rc), weak);
+                let value = model.value(prop.alloc).expect("an Rc has a value");
+                check_eq!((rc.byte, rc.id()), (value.byte, value.id));
+            }
+            Handle::Weak(ref w) => {
+                check_eq!(w.strong_count(), strong);
+                check_eq!(w.weak_count(), if strong > 0 { weak } else { 0 });
+            }
+        }
+    }
+    let dropped = log.dropped.borrow();
+    check_eq!(dropped.len(), model.dropped().len());
+    check_eq!(&dropped[since..], &model.dropped()[since..]);
+}
+
+/// Run `op` against `handles`, whose values are logged in `log`, and
+/// `model`, panicking if they disagree
+pub fn check_op<'a>(op: &Op, log: &'a Log, handles: &mut Vec<Handle<'a>>, model: &mut PropRc) {
+    let held = handles.len();
+    let since = model.dropped().len();
+    match *op {
+        Op::New { .. }
+        | Op::NewCyclic { .. }
+        | Op::Clone { .. }
+        | Op::Downgrade { .. }
+        | Op::Upgrade { .. }
+            if held >= MAX_HANDLES => {}
+        Op::New { byte } => {
+            handles.push(Handle::Strong(Rc::new(Tracked::new(byte, log))));
+            model.new_rc(byte);
+        }
+        Op::NewCyclic { byte } => {
+            let mut inner = None;
+            let rc = Rc::new_cyclic(|weak| {
+                check!(weak.upgrade().is_none());
+                check_eq!(weak.strong_count(), 0);
+                inner = Some(weak.clone());
+                Tracked::new(byte, log)
+            });
+            handles.push(Handle::Strong(rc));
+            handles.push(Handle::Weak(inner.expect("new_cyclic calls its closure")));
+            model.new_cyclic(byte);
+        }
+        _ if held == 0 => {}
+        Op::Clone { idx } => {
+            let idx = idx as usize % held;
+            let clone = match handles[idx] {
+                Handle::Strong(ref rc) => Handle::Strong(Rc::clone(rc)),
+                Handle::Weak(ref weak) => Handle::Weak(Weak::clone(weak)),
+            };
+            handles.push(clone);
+            model.clone_handle(idx);
+        }
+        Op::Drop { idx } => {
+            let idx = idx as usize % held;
+            drop(handles.remove(idx));
+            model.drop_handle(idx);
+        }
+        Op::Downgrade { idx } => {
+            let idx = idx as usize % held;
+            if let Handle::Strong(ref rc) = handles[idx] {
+                let weak = Rc::downgrade(rc);
+                handles.push(Handle::Weak(weak));
+                model.downgrade(idx);
+            }
+        }
+        Op::Upgrade { idx } => {
+            let idx = idx as usize % held;
+            if let Handle::Weak(ref weak) = handles[idx] {
+                let sut = weak.upgrade();
+                check_eq!(sut.is_some(), model.upgrade(idx));
+                if let Some(rc) = sut {
+                    handles.push(Handle::Strong(rc));
+                }
+            }
+        }
+        Op::GetMut { idx, byte } => {
+            let idx = idx as usize % held;
+            if let Handle::Strong(ref mut rc) = handles[idx] {
+                let sut = Rc::get_mut(rc).map(|value| value.byte = byte);
+                check_eq!(sut.is_some(), model.get_mut(idx, byte));
+            }
+        }
+        Op::MakeMut { idx, byte } => {
+            let idx = idx as usize % held;
+            if let Handle::Strong(ref mut rc) = handles[idx] {
+                Rc::make_mut(rc).byte = byte;
+                model.make_mut(idx, byte);
+            }
+        }
+        Op::TryUnwrap { idx } => {
+            let idx = idx as usize % held;
+            if let Handle::Strong(_) = handles[idx] {
+                let rc = match handles.remove(idx) {
+                    Handle::Strong(rc) => rc,
+                    Handle::Weak(_) => unreachable!(),
+                };
+                let expected = model.try_unwrap(idx);
+                match Rc::try_unwrap(rc) {
+                    Ok(value) => {
+                        let expected = expected.expect("the value is moved out");
+                        check_eq!((value.byte, value.id()), (expected.byte, expected.id));
+                        drop(value);
+                        model.drop_value(expected);
+                    }
+                    Err(rc) => {
+                        check!(expected.is_none());
+                        handles.insert(idx, Handle::Strong(rc));
+                    }
+                }
+            }
+        }
+        Op::IntoInner { idx } => {
+            let idx = idx as usize % held;
+            if let Handle::Strong(_) = handles[idx] {
+                let rc = match handles.remove(idx) {
+                    Handle::Strong(rc) => rc,
+                    Handle::Weak(_) => unreachable!(),
+                };
+                let expected = model.into_inner(idx);
+                let sut = Rc::into_inner(rc);
+                check_eq!(
+                    sut.as_ref().map(|v| (v.byte, v.id())),
+                    expected.map(|v| (v.byte, v.id))
+                );
+                drop(sut);
+                if let Some(value) = expected {
+                    model.drop_value(value);
+                }
+            }
+        }
+        Op::PtrEq { a, b } => {
+            let (a, b) = (a as usize % held, b as usize % held);
+            let expected = model.handles()[a].alloc == model.handles()[b].alloc;
+            let sut = match (&handles[a], &handles[b]) {
+                (Handle::Strong(a), Handle::Strong(b)) => Rc::ptr_eq(a, b),
+                (Handle::Weak(a), Handle::Weak(b)) => Weak::ptr_eq(a, b),
+                (a, b) => a.as_ptr() == b.as_ptr(),
+            };
+            check_eq!(sut, expected);
+        }
+    }
+    check_state(handles, log, model, since);
+}
+
+/// Decode the input of the `rc` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `rc` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against a pool of `Rc<Tracked>` and `Weak<Tracked>`
+/// handles and [`PropRc`]
+///
+/// Every op is applied to both the model and the system under test, panicking
+/// as soon as the two disagree. Once the handles are all dropped every value
+/// must have been. The run is counted in [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let log = Log::new();
+    let mut handles = Vec::new();
+    let mut model = PropRc::new();
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        check_op(op, &log, &mut handles, &mut model);
+        stats::size(TARGET, handles.len());
+    }
+    drop(handles);
+    let mut dropped = log.dropped();
+    dropped.sort_unstable();
+    check_eq!(dropped, (0..log.made.get()).collect::<Vec<_>>());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(ops: &[Op]) -> PropRc {
+        let log = Log::new();
+        let mut handles = Vec::new();
+        let mut model = PropRc::new();
+        for op in ops {
+            check_op(op, &log, &mut handles, &mut model);
+        }
+        model
+    }
+
+    #[test]
+    fn value_drops_with_the_last_rc() {
+        let model = run(&[
+            Op::New { byte: 1 },
+            Op::Downgrade { idx: 0 },
+            Op::Clone { idx: 0 },
+            Op::Drop { idx: 0 },
+            Op::Upgrade { idx: 0 },
+        ]);
+        assert!(model.dropped().is_empty());
+        assert_eq!(2, model.strong_count(0));
+        let model = run(&[
+            Op::New { byte: 1 },
+            Op::Downgrade { idx: 0 },
+            Op::Drop { idx: 0 },
+            Op::Upgrade { idx: 0 },
+        ]);
+        assert_eq!(&[0], model.dropped());
+        assert_eq!((0, 1), (model.strong_count(0), model.weak_count(0)));
+    }
+
+    #[test]
+    fn make_mut_clones_when_shared_and_moves_when_only_weak() {
+        let mut model = PropRc::new();
+        model.new_rc(1);
+        model.clone_handle(0);
+        model.make_mut(0, 2);
+        assert_eq!(Some(PropValue { byte: 2, id: 1 }), model.value(1));
+        assert_eq!(Some(PropValue { byte: 1, id: 0 }), model.value(0));
+        model.downgrade(1);
+        model.make_mut(1, 3);
+        assert_eq!(None, model.value(0));
+        assert_eq!(Some(PropValue { byte: 3, id: 0 }), model.value(2));
+        assert!(model.dropped().is_empty());
+        run(&[
+            Op::New { byte: 1 },
+            Op::Clone { idx: 0 },
+            Op::MakeMut { idx: 0, byte: 2 },
+            Op::Downgrade { idx: 1 },
+            Op::MakeMut { idx: 1, byte: 3 },
+        ]);
+    }
+
+    #[test]
+    fn unwrapping_needs_the_only_rc() {
+        let model = run(&[
+            Op::NewCyclic { byte: 4 },
+            Op::Clone { idx: 0 },
+            Op::TryUnwrap { idx: 0 },
+            Op::IntoInner { idx: 0 },
+            Op::TryUnwrap { idx: 1 },
+        ]);
+        assert_eq!(&[0], model.dropped());
+        assert_eq!(
+            vec![PropHandle {
+                kind: Kind::Weak,
+                alloc: 0
+            }],
+            model.handles()
+        );
+    }
+
+    quickcheck! {
+        fn pool_agrees(ops: Vec<(u8, u8, u8)>) -> bool {
+            let ops: Vec<Op> = ops
+                .into_iter()
+                .map(|(tag, a, b)| match tag % 11 {
+                    0 => Op::New { byte: a },
+                    1 => Op::NewCyclic { byte: a },
+                    2 => Op::Clone { idx: a },
+                    3 => Op::Drop { idx: a },
+                    4 => Op::Downgrade { idx: a },
+                    5 => Op::Upgrade { idx: a },
+                    6 => Op::GetMut { idx: a, byte: b },
+                    7 => Op::MakeMut { idx: a, byte: b },
+                    8 => Op::TryUnwrap { idx: a },
+                    9 => Op::IntoInner { idx: a },
+                    _ => Op::PtrEq { a, b },
+                })
+                .collect();
+            let mut trace = Trace::new(Header {
+                target: TARGET.to_string(),
+                types: vec!["u8".to_string()],
+                capacity: 0,
+                hash_seed: None,
+            });
+            trace.ops = ops;
+            replay(&trace);
+            true
+        }
+    }
+}
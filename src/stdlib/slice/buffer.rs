@@ -0,0 +1,849 @@
+//! Tests for the methods that rearrange and view slices
+//!
+//! Ops grow, shrink and rearrange a `Vec<u8>` in place -- `rotate_left`,
+//! `copy_within`, `fill` and so on -- alongside a [`PropSlice`] that does the
+//! same with index loops. Other ops view the buffer through the iterators of
+//! slices, `chunks`, `windows`, `split` and their kin, each driven from both
+//! ends with `next`, `next_back`, `nth` and `nth_back` and checked against the
+//! list of views the model works out up front.
+use op::{self, Profiles};
+use stats;
+use std::collections::VecDeque;
+use std::mem;
+use std::ops::Range;
+use stdlib::util::Bytes;
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving the methods of `[u8]`
+pub const TARGET: &str = "slice_buffer";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+lazy_static! {
+    /// The workload profiles of the `slice_buffer` target, see [`Profiles`]
+    ///
+    /// `rearrange` sticks to the methods that change the buffer and `view` to
+    /// the ones that look at it.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[
+        (
+            "rearrange",
+            &[("chunks", 0), ("chunks_exact", 0), ("rchunks", 0), ("windows", 0), ("split", 0), ("rsplit", 0), ("splitn", 0), ("join", 0), ("contains", 0), ("starts_with", 0), ("ends_with", 0)],
+        ),
+        (
+            "view",
+            &[("rotate_left", 0), ("rotate_right", 0), ("reverse", 0), ("swap", 0), ("copy_within", 0), ("fill", 0), ("split_at_mut", 0)],
+        ),
+    ]);
+}
+
+/// A `[u8]` model
+///
+/// Every method is a loop over indices, moving a byte at a time, and every
+/// method taking an index or range panics out of bounds as the slice does.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PropSlice {
+    data: Vec<u8>,
+}
+
+impl PropSlice {
+    /// Construct a new, empty `PropSlice`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bytes of the `PropSlice`
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The number of bytes
+    ///
+    /// This is like to [`slice::len`]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Whether there are no bytes
+    ///
+    /// This is like to [`slice::is_empty`]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Append `bytes`
+    ///
+    /// This is like to [`Vec::extend_from_slice`]
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.data.push(byte);
+        }
+    }
+
+    /// Keep the first `len` bytes
+    ///
+    /// This is like to [`Vec::truncate`]
+    pub fn truncate(&mut self, len: usize) {
+        while self.data.len() > len {
+            self.data.pop();
+        }
+    }
+
+    /// Move the first `mid` bytes to the end
+    ///
+    /// This is like to [`slice::rotate_left`]
+    pub fn rotate_left(&mut self, mid: usize) {
+        assert!(mid <= self.len(), "mid is past the end");
+        let len = self.len();
+        let old = self.data.clone();
+        for i in 0..len {
+            self.data[i] = old[(i + mid) % len];
+        }
+    }
+
+    /// Move the last `k` bytes to the start
+    ///
+    /// This is like to [`slice::rotate_right`]
+    pub fn rotate_right(&mut self, k: usize) {
+        assert!(k <= self.len(), "k is past the end");
+        let len = self.len();
+        self.rotate_left(len - k);
+    }
+
+    /// Reverse the bytes in `range`
+    ///
+    /// This is like to [`slice::reverse`] on a subslice
+    pub fn reverse(&mut self, range: Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "range out of bounds"
+        );
+        let old = self.data.clone();
+        for i in range.clone() {
+            self.data[i] = old[range.start + range.end - 1 - i];
+        }
+    }
+
+    /// Swap the bytes at `a` and `b`
+    ///
+    /// This is like to [`slice::swap`]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        let (x, y) = (self.data[a], self.data[b]);
+        self.data[a] = y;
+        self.data[b] = x;
+    }
+
+    /// Copy the bytes in `src` to `dest` onwards, which may overlap
+    ///
+    /// This is like to [`slice::copy_within`]
+    pub fn copy_within(&mut self, src: Range<usize>, dest: usize) {
+        assert!(
+            src.start <= src.end && src.end <= self.len(),
+            "src out of bounds"
+        );
+        assert!(
+            dest + (src.end - src.start) <= self.len(),
+            "dest out of bounds"
+        );
+        let copied: Vec<u8> = src.map(|i| self.data[i]).collect();
+        for (i, byte) in copied.into_iter().enumerate() {
+            self.data[dest + i] = byte;
+        }
+    }
+
+    /// Set every byte in `range` to `value`
+    ///
+    /// This is like to [`slice::fill`] on a subslice
+    pub fn fill(&mut self, range: Range<usize>, value: u8) {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "range out of bounds"
+        );
+        for i in range {
+            self.data[i] = value;
+        }
+    }
+}
+
+/// The chunks of `size` bytes of `v`, the last of them maybe shorter
+///
+/// This is like to [`slice::chunks`]
+pub fn chunks(v: &[u8], size: usize) -> Vec<&[u8]> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start < v.len() {
+        out.push(&v[start..(start + size).min(v.len())]);
+        start += size;
+    }
+    out
+}
+
+/// The chunks of exactly `size` bytes of `v`, and the bytes left over
+///
+/// This is like to [`slice::chunks_exact`] and `ChunksExact::remainder`
+pub fn chunks_exact(v: &[u8], size: usize) -> (Vec<&[u8]>, &[u8]) {
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start + size <= v.len() {
+        out.push(&v[start..start + size]);
+        start += size;
+    }
+    (out, &v[start..])
+}
+
+/// The chunks of `size` bytes of `v` from its end, the last of them maybe
+/// shorter
+///
+/// This is like to [`slice::rchunks`]
+pub fn rchunks(v: &[u8], size: usize) -> Vec<&[u8]> {
+    let mut out = Vec::new();
+    let mut end = v.len();
+    while end > 0 {
+        let start = end.saturating_sub(size);
+        out.push(&v[start..end]);
+        end = start;
+    }
+    out
+}
+
+/// Every run of `size` bytes of `v`, overlapping
+///
+/// This is like to [`slice::windows`]
+pub fn windows(v: &[u8], size: usize) -> Vec<&[u8]> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    while start + size <= v.len() {
+        out.push(&v[start..start + size]);
+        start += 1;
+    }
+    out
+}
+
+/// The runs of `v` between the bytes `pred` holds for
+///
+/// This is like to [`slice::split`]. There is one more run than there are
+/// such bytes, and runs may be empty.
+pub fn split<F: Fn(u8) -> bool>(v: &[u8], pred: F) -> Vec<&[u8]> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    for i in 0..v.len() {
+        if pred(v[i]) {
+            out.push(&v[start..i]);
+            start = i + 1;
+        }
+    }
+    out.push(&v[start..]);
+    out
+}
+
+/// The first `n - 1` runs of `v` between the bytes `pred` holds for, and
+/// then the rest
+///
+/// This is like to [`slice::splitn`]
+pub fn splitn<F: Fn(u8) -> bool>(v: &[u8], n: usize, pred: F) -> Vec<&[u8]> {
+    let mut out = Vec::new();
+    if n == 0 {
+        return out;
+    }
+    let mut start = 0;
+    for i in 0..v.len() {
+        if out.len() + 1 == n {
+            break;
+        }
+        if pred(v[i]) {
+            out.push(&v[start..i]);
+            start = i + 1;
+        }
+    }
+    out.push(&v[start..]);
+    out
+}
+
+/// The last `n - 1` runs of `v` between the bytes `pred` holds for, last
+/// first, and then the rest
+///
+/// This is like to [`slice::rsplitn`]
+pub fn rsplitn<F: Fn(u8) -> bool>(v: &[u8], n: usize, pred: F) -> Vec<&[u8]> {
+    let mut out = Vec::new();
+    if n == 0 {
+        return out;
+    }
+    let mut end = v.len();
+    for i in (0..v.len()).rev() {
+        if out.len() + 1 == n {
+            break;
+        }
+        if pred(v[i]) {
+            out.push(&v[i + 1..end]);
+            end = i;
+        }
+    }
+    out.push(&v[..end]);
+    out
+}
+
+/// The `pieces` one after another, with `sep` between each two
+///
+/// This is like to `join` on a slice of slices
+pub fn join(pieces: &[&[u8]], sep: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        if i > 0 {
+            out.extend_from_slice(sep);
+        }
+        out.extend_from_slice(piece);
+    }
+    out
+}
+
+/// Whether `needle` is at the start of `v`
+///
+/// This is like to [`slice::starts_with`]
+pub fn starts_with(v: &[u8], needle: &[u8]) -> bool {
+    needle.len() <= v.len() && (0..needle.len()).all(|i| v[i] == needle[i])
+}
+
+/// Whether `needle` is at the end of `v`
+///
+/// This is like to [`slice::ends_with`]
+pub fn ends_with(v: &[u8], needle: &[u8]) -> bool {
+    let offset = match v.len().checked_sub(needle.len()) {
+        Some(offset) => offset,
+        None => return false,
+    };
+    (0..needle.len()).all(|i| v[offset + i] == needle[i])
+}
+
+/// Whether `v` holds `x`
+///
+/// This is like to [`slice::contains`]
+pub fn contains(v: &[u8], x: u8) -> bool {
+    for &byte in v {
+        if byte == x {
+            return true;
+        }
+    }
+    false
+}
+
+/// The `Op` defines the ways the buffer of the `slice_buffer` target is
+/// changed and viewed.
+///
+/// Indices are `u16` and taken modulo one more than the length of the buffer,
+/// see [`scale`]. Chunk and window sizes are `u8` and taken, see [`size`], to
+/// be at least one. The `steps` of the ops driving an iterator say how to
+/// drive it, see [`drive`].
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `Vec::extend_from_slice`
+    #[op(tag = 0, weight = 3)]
+    Extend {
+        /// The bytes to append
+        bytes: Bytes,
+    },
+    /// This operation triggers `Vec::truncate`
+    #[op(tag = 1)]
+    Truncate {
+        /// The length to keep
+        len: u16,
+    },
+    /// This operation triggers `slice::rotate_left`
+    #[op(tag = 2)]
+    RotateLeft {
+        /// The number of bytes to move to the end
+        mid: u16,
+    },
+    /// This operation triggers `slice::rotate_right`
+    #[op(tag = 3)]
+    RotateRight {
+        /// The number of bytes to move to the start
+        k: u16,
+    },
+    /// This operation triggers `slice::reverse` on a subslice
+    #[op(tag = 4)]
+    Reverse {
+        /// One end of the subslice
+        start: u16,
+        /// The other end of the subslice
+        end: u16,
+    },
+    /// This operation triggers `slice::swap`
+    #[op(tag = 5)]
+    Swap {
+        /// One index, modulo the length
+        a: u16,
+        /// The other index, modulo the length
+        b: u16,
+    },
+    /// This operation triggers `slice::copy_within`
+    #[op(tag = 6)]
+    CopyWithin {
+        /// One end of the bytes to copy
+        start: u16,
+        /// The other end of the bytes to copy
+        end: u16,
+        /// Where to copy to, modulo one more than the room there is
+        dest: u16,
+    },
+    /// This operation triggers `slice::fill` on a subslice
+    #[op(tag = 7)]
+    Fill {
+        /// One end of the subslice
+        start: u16,
+        /// The other end of the subslice
+        end: u16,
+        /// The byte to fill with
+        value: u8,
+    },
+    /// This operation triggers `slice::split_at_mut` and swaps the first
+    /// byte of the left half with the last of the right
+    #[op(tag = 8)]
+    SplitAtMut {
+        /// Where to split
+        mid: u16,
+    },
+    /// This operation triggers `slice::chunks`
+    #[op(tag = 9)]
+    Chunks {
+        /// The size of the chunks
+        size: u8,
+        /// How to drive the iterator
+        steps: Bytes,
+    },
+    /// This operation triggers `slice::chunks_exact`
+    #[op(tag = 10)]
+    ChunksExact {
+        /// The size of the chunks
+        size: u8,
+        /// How to drive the iterator
+        steps: Bytes,
+    },
+    /// This operation triggers `slice::rchunks`
+    #[op(tag = 11)]
+    Rchunks {
+        /// The size of the chunks
+        size: u8,
+        /// How to drive the iterator
+        steps: Bytes,
+    },
+    /// This operation triggers `slice::windows`
+    #[op(tag = 12)]
+    Windows {
+        /// The size of the windows
+        size: u8,
+        /// How to drive the iterator
+        steps: Bytes,
+    },
+    /// This operation triggers `slice::split`
+    #[op(tag = 13)]
+    Split {
+        /// Split at the multiples of `modulus % 8 + 2`
+        modulus: u8,
+        /// How to drive the iterator
+        steps: Bytes,
+    },
+    /// This operation triggers `slice::rsplit`
+    #[op(tag = 14)]
+    Rsplit {
+        /// Split at the multiples of `modulus % 8 + 2`
+        modulus: u8,
+        /// How to drive the iterator
+        steps: Bytes,
+    },
+    /// This operation triggers `slice::splitn` and `slice::rsplitn`
+    #[op(tag = 15)]
+    Splitn {
+        /// Split at the multiples of `modulus % 8 + 2`
+        modulus: u8,
+        /// The most pieces to split into
+        n: u8,
+    },
+    /// This operation triggers `concat` and `join` on the chunks of the buffer
+    #[op(tag = 16)]
+    Join {
+        /// The size of the chunks
+        size: u8,
+        /// The separator to join with
+        sep: Bytes,
+    },
+    /// This operation triggers `slice::contains`
+    #[op(tag = 17)]
+    Contains {
+        /// The byte to look for
+        x: u8,
+    },
+    /// This operation triggers `slice::starts_with`
+    #[op(tag = 18)]
+    StartsWith {
+        /// Look for the first `len` bytes, see [`needle`]
+        len: u16,
+        /// The change to make to them, see [`needle`]
+        flip: u8,
+    },
+    /// This operation triggers `slice::ends_with`
+    #[op(tag = 19)]
+    EndsWith {
+        /// Look for the last `len` bytes, see [`needle`]
+        len: u16,
+        /// The change to make to them, see [`needle`]
+        flip: u8,
+    },
+}
+
+/// Scale `idx` into `0..=len`
+fn scale(idx: u16, len: usize) -> usize {
+    idx as usize % (len + 1)
+}
+
+/// The range between two scaled indices, whichever way round they are
+fn range(a: u16, b: u16, len: usize) -> Range<usize> {
+    let (a, b) = (scale(a, len), scale(b, len));
+    a.min(b)..a.max(b)
+}
+
+/// A chunk or window size of at least one and at most one past `len`
+fn size(size: u8, len: usize) -> usize {
+    1 + size as usize % (len + 1)
+}
+
+/// A predicate holding for the multiples of `modulus % 8 + 2`
+fn multiple_of(modulus: u8) -> impl Fn(u8) -> bool {
+    move |byte| byte % (modulus % 8 + 2) == 0
+}
+
+/// Bytes to look for at one end of `v`
+///
+/// These are the `len` bytes at that end, as `take` takes them from `v`, with
+/// `len` taken modulo two more than the length of `v`. One past the length
+/// is all of `v` and a zero besides. If `flip` isn't zero, one byte is
+/// changed by it, so the needle is no longer at that end.
+fn needle<F>(v: &[u8], len: u16, flip: u8, take: F) -> Vec<u8>
+where
+    F: Fn(&[u8], usize) -> Vec<u8>,
+{
+    let len = len as usize % (v.len() + 2);
+    let mut needle = if len > v.len() {
+        let mut needle = take(v, v.len());
+        needle.push(0);
+        needle
+    } else {
+        take(v, len)
+    };
+    if flip != 0 && !needle.is_empty() {
+        let idx = flip as usize % needle.len();
+        needle[idx] ^= flip;
+    }
+    needle
+}
+
+/// Drive `iter` by `steps`, checking it against the items `model` has left
+///
+/// Each step is a byte. Its low two bits say whether to call `next`,
+/// `next_back`, `nth` or `nth_back`, and its high six bits are the argument
+/// of `nth` and `nth_back`. When the steps run out the iterator is drained
+/// from the front. Before every step the size hint must bound the items left,
+/// and if `exact` be exactly their number.
+fn drive<'a, I>(mut iter: I, items: Vec<&'a [u8]>, steps: &[u8], exact: bool)
+where
+    I: DoubleEndedIterator<Item = &'a [u8]>,
+{
+    let mut model: VecDeque<&[u8]> = items.into_iter().collect();
+    let steps = steps.iter().cloned().chain(::std::iter::repeat(0));
+    for step in steps {
+        let left = model.len();
+        let (lower, upper) = iter.size_hint();
+        if exact {
+            check_eq!((lower, upper), (left, Some(left)));
+        } else {
+            check!(
+                lower <= left && upper.into_iter().all(|upper| left <= upper),
+                "size_hint {:?} does not bound {}",
+                (lower, upper),
+                left
+            );
+        }
+        let n = (step >> 2) as usize;
+        let (sut, expected) = match step & 0b11 {
+            0 => (iter.next(), model.pop_front()),
+            1 => (iter.next_back(), model.pop_back()),
+            2 => {
+                model.drain(..n.min(left));
+                (iter.nth(n), model.pop_front())
+            }
+            _ => {
+                model.drain(left - n.min(left)..);
+                (iter.nth_back(n), model.pop_back())
+            }
+        };
+        check_eq!(sut, expected);
+        if left == 0 {
+            check!(iter.next().is_none());
+            check!(iter.next_back().is_none());
+            break;
+        }
+    }
+}
+
+/// Run `op` against `sut` and `model`, panicking if they disagree
+pub fn check_op(op: &Op, sut: &mut Vec<u8>, model: &mut PropSlice) {
+    let len = model.len();
+    match *op {
+        Op::Extend { ref bytes } => {
+            sut.extend_from_slice(&bytes.0);
+            model.extend_from_slice(&bytes.0);
+        }
+        Op::Truncate { len: keep } => {
+            let keep = scale(keep, len);
+            sut.truncate(keep);
+            model.truncate(keep);
+        }
+        Op::RotateLeft { mid } => {
+            let mid = scale(mid, len);
+            sut.rotate_left(mid);
+            model.rotate_left(mid);
+        }
+        Op::RotateRight { k } => {
+            let k = scale(k, len);
+            sut.rotate_right(k);
+            model.rotate_right(k);
+        }
+        Op::Reverse { start, end } => {
+            let range = range(start, end, len);
+            sut[range.clone()].reverse();
+            model.reverse(range);
+        }
+        Op::Swap { a, b } => {
+            if len > 0 {
+                let (a, b) = (a as usize % len, b as usize % len);
+                sut.swap(a, b);
+                model.swap(a, b);
+            }
+        }
+        Op::CopyWithin { start, end, dest } => {
+            let src = range(start, end, len);
+            let dest = dest as usize % (len - src.len() + 1);
+            sut.copy_within(src.clone(), dest);
+            model.copy_within(src, dest);
+        }
+        Op::Fill { start, end, value } => {
+            let range = range(start, end, len);
+            sut[range.clone()].fill(value);
+            model.fill(range, value);
+        }
+        Op::SplitAtMut { mid } => {
+            let mid = scale(mid, len);
+            {
+                let (left, right) = sut.split_at_mut(mid);
+                check_eq!(&left[..], &model.as_slice()[..mid]);
+                check_eq!(&right[..], &model.as_slice()[mid..]);
+                if let (Some(first), Some(last)) = (left.first_mut(), right.last_mut()) {
+                    mem::swap(first, last);
+                }
+            }
+            if 0 < mid && mid < len {
+                model.swap(0, len - 1);
+            }
+        }
+        Op::Chunks { size: n, ref steps } => {
+            let n = size(n, len);
+            drive(sut.chunks(n), chunks(model.as_slice(), n), &steps.0, true);
+            check_eq!(sut.chunks(n).len(), chunks(model.as_slice(), n).len());
+        }
+        Op::ChunksExact { size: n, ref steps } => {
+            let n = size(n, len);
+            let (items, remainder) = chunks_exact(model.as_slice(), n);
+            check_eq!(sut.chunks_exact(n).remainder(), remainder);
+            check_eq!(sut.chunks_exact(n).len(), items.len());
+            drive(sut.chunks_exact(n), items, &steps.0, true);
+        }
+        Op::Rchunks { size: n, ref steps } => {
+            let n = size(n, len);
+            check_eq!(sut.rchunks(n).len(), rchunks(model.as_slice(), n).len());
+            drive(sut.rchunks(n), rchunks(model.as_slice(), n), &steps.0, true);
+        }
+        Op::Windows { size: n, ref steps } => {
+            let n = size(n, len);
+            check_eq!(sut.windows(n).len(), windows(model.as_slice(), n).len());
+            drive(sut.windows(n), windows(model.as_slice(), n), &steps.0, true);
+        }
+        Op::Split { modulus, ref steps } => {
+            let pred = multiple_of(modulus);
+            drive(
+                sut.split(|&b| pred(b)),
+                split(model.as_slice(), &pred),
+                &steps.0,
+                false,
+            );
+        }
+        Op::Rsplit { modulus, ref steps } => {
+            let pred = multiple_of(modulus);
+            let mut items = split(model.as_slice(), &pred);
+            items.reverse();
+            drive(sut.rsplit(|&b| pred(b)), items, &steps.0, false);
+        }
+        Op::Splitn { modulus, n } => {
+            let pred = multiple_of(modulus);
+            let n = n as usize;
+            let pieces: Vec<&[u8]> = sut.splitn(n, |&b| pred(b)).collect();
+            check_eq!(pieces, splitn(model.as_slice(), n, &pred));
+            let pieces: Vec<&[u8]> = sut.rsplitn(n, |&b| pred(b)).collect();
+            check_eq!(pieces, rsplitn(model.as_slice(), n, &pred));
+        }
+        Op::Join { size: n, ref sep } => {
+            let n = size(n, len);
+            let pieces: Vec<&[u8]> = sut.chunks(n).collect();
+            check_eq!(pieces.concat(), model.as_slice());
+            check_eq!(pieces.join(&sep.0[..]), join(&pieces, &sep.0));
+            if let Some(byte) = sep.0.first() {
+                check_eq!(pieces.join(byte), join(&pieces, &[*byte]));
+            }
+        }
+        Op::Contains { x } => {
+            check_eq!(sut.contains(&x), contains(model.as_slice(), x));
+        }
+        Op::StartsWith { len: n, flip } => {
+            let needle = needle(model.as_slice(), n, flip, |v, n| v[..n].to_vec());
+            check_eq!(
+                sut.starts_with(&needle),
+                starts_with(model.as_slice(), &needle)
+            );
+        }
+        Op::EndsWith { len: n, flip } => {
+            let needle = needle(model.as_slice(), n, flip, |v, n| v[v.len() - n..].to_vec());
+            check_eq!(sut.ends_with(&needle), ends_with(model.as_slice(), &needle));
+        }
+    }
+    check_eq!(&sut[..], model.as_slice());
+}
+
+/// Decode the input of the `slice_buffer` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `slice_buffer` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against `Vec<u8>` and [`PropSlice`]
+///
+/// Every op is applied to both the model and the system under test, panicking
+/// as soon as the two disagree. The run is counted in [`stats`], if
+/// collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let mut model = PropSlice::new();
+    let mut sut = Vec::new();
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        check_op(op, &mut sut, &mut model);
+        stats::size(TARGET, model.len());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn model_of(bytes: &[u8]) -> PropSlice {
+        let mut model = PropSlice::new();
+        model.extend_from_slice(bytes);
+        model
+    }
+
+    #[test]
+    fn rotations_wrap_around() {
+        let mut model = model_of(&[1, 2, 3, 4, 5]);
+        model.rotate_left(2);
+        assert_eq!(&[3, 4, 5, 1, 2], model.as_slice());
+        model.rotate_right(2);
+        assert_eq!(&[1, 2, 3, 4, 5], model.as_slice());
+        model.rotate_left(5);
+        assert_eq!(&[1, 2, 3, 4, 5], model.as_slice());
+    }
+
+    #[test]
+    fn copies_may_overlap() {
+        let mut model = model_of(&[1, 2, 3, 4, 5]);
+        model.copy_within(0..3, 2);
+        assert_eq!(&[1, 2, 1, 2, 3], model.as_slice());
+        model.copy_within(2..5, 0);
+        assert_eq!(&[1, 2, 3, 2, 3], model.as_slice());
+    }
+
+    #[test]
+    fn chunks_come_short_at_the_far_end() {
+        let v = [1, 2, 3, 4, 5];
+        assert_eq!(vec![&[1, 2][..], &[3, 4], &[5]], chunks(&v, 2));
+        assert_eq!(vec![&[4, 5][..], &[2, 3], &[1]], rchunks(&v, 2));
+        assert_eq!((vec![&[1, 2][..], &[3, 4]], &[5][..]), chunks_exact(&v, 2));
+        assert_eq!(Vec::<&[u8]>::new(), windows(&v, 6));
+    }
+
+    #[test]
+    fn splits_keep_empty_runs() {
+        let even = |b: u8| b & 1 == 0;
+        assert_eq!(vec![&[][..], &[1], &[]], split(&[0, 1, 2], even));
+        assert_eq!(vec![&[0u8; 0][..]], split(&[], even));
+        assert_eq!(vec![&[][..], &[1, 2]], splitn(&[0, 1, 2], 2, even));
+        assert_eq!(vec![&[][..], &[0, 1]], rsplitn(&[0, 1, 2], 2, even));
+        assert_eq!(Vec::<&[u8]>::new(), splitn(&[0, 1, 2], 0, even));
+    }
+
+    quickcheck! {
+        fn views_agree(bytes: Vec<u8>, size: u8, steps: Vec<u8>) -> bool {
+            let mut sut = bytes.clone();
+            let mut model = model_of(&bytes);
+            let steps = Bytes(steps);
+            for op in &[
+                Op::Chunks { size, steps: steps.clone() },
+                Op::ChunksExact { size, steps: steps.clone() },
+                Op::Rchunks { size, steps: steps.clone() },
+                Op::Windows { size, steps: steps.clone() },
+                Op::Split { modulus: size, steps: steps.clone() },
+                Op::Rsplit { modulus: size, steps },
+                Op::Splitn { modulus: size, n: size % 4 },
+            ] {
+                check_op(op, &mut sut, &mut model);
+            }
+            true
+        }
+
+        fn rearrangements_agree(bytes: Vec<u8>, a: u16, b: u16, c: u16) -> bool {
+            let mut sut = bytes.clone();
+            let mut model = model_of(&bytes);
+            for op in &[
+                Op::RotateLeft { mid: a },
+                Op::RotateRight { k: b },
+                Op::Reverse { start: a, end: b },
+                Op::Swap { a, b },
+                Op::CopyWithin { start: a, end: b, dest: c },
+                Op::Fill { start: b, end: c, value: a as u8 },
+                Op::SplitAtMut { mid: c },
+                Op::StartsWith { len: a, flip: b as u8 },
+                Op::EndsWith { len: a, flip: 0 },
+            ] {
+                check_op(op, &mut sut, &mut model);
+            }
+            true
+        }
+    }
+}
@@ -0,0 +1,3 @@
+//! Tests for `std::slice`
+pub mod buffer;
+pub mod sort;
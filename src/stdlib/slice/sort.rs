@@ -0,0 +1,484 @@
+//! Tests for the sorts of slices
+//!
+//! Every element to sort is an [`Item`], a key to compare by and a tag of
+//! where it started which comparisons never see. With the tags the output of a
+//! stable sort is fixed, so `sort`, `sort_by_key` and `sort_by_cached_key` are
+//! checked item for item against [`insertion_sort`]. The unstable sorts and
+//! `select_nth_unstable` are checked on keys alone, and `binary_search` on a
+//! slice sorted by the model.
+//!
+//! The `Chaos*` ops sort by a comparison that answers whatever the fuzz input
+//! says, which is no order at all. The sorts may panic then, as their docs
+//! say, but whether they do or not the slice must come out a permutation of
+//! the slice that went in, and every comparison must be between items of it.
+use op::{self, Profiles};
+use stats;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use stdlib::util::{panics, Bytes};
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving the sorts of slices
+pub const TARGET: &str = "slice_sort";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+lazy_static! {
+    /// The workload profiles of the `slice_sort` target, see [`Profiles`]
+    ///
+    /// `consistent` sticks to sorting by a total order and `chaos` to sorting
+    /// by no order at all.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[
+        (
+            "consistent",
+            &[("chaos_sort", 0), ("chaos_sort_unstable", 0), ("chaos_select_nth", 0), ("chaos_binary_search", 0)],
+        ),
+        (
+            "chaos",
+            &[("sort", 0), ("sort_by_key", 0), ("sort_by_cached_key", 0), ("sort_unstable", 0), ("select_nth", 0), ("binary_search", 0)],
+        ),
+    ]);
+}
+
+/// An element to sort
+///
+/// Items compare by key alone. The tag is where the item started, and tells
+/// apart items of the same key.
+#[derive(Clone, Copy, Debug)]
+pub struct Item {
+    /// The key items are compared by
+    pub key: u8,
+    /// The index of the item before sorting
+    pub tag: usize,
+}
+
+impl PartialEq for Item {
+    fn eq(&self, other: &Item) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Item {}
+
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Item) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Item {
+    fn cmp(&self, other: &Item) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// The items of `keys`, each tagged with its index
+pub fn items(keys: &[u8]) -> Vec<Item> {
+    keys.iter().enumerate().map(|(tag, &key)| Item { key, tag }).collect()
+}
+
+/// The keys and tags of `items`, which unlike the items compare by both
+fn identities(items: &[Item]) -> Vec<(u8, usize)> {
+    items.iter().map(|item| (item.key, item.tag)).collect()
+}
+
+/// Sort `v` by `compare`, stably
+///
+/// Each element in turn moves back past the elements greater than it, so
+/// elements that compare equal keep their order.
+pub fn insertion_sort<T, F>(v: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in 1..v.len() {
+        let mut j = i;
+        while j > 0 && compare(&v[j - 1], &v[j]) == Ordering::Greater {
+            v.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+/// The index of `key` in the sorted `items`, or where it would go
+///
+/// This is like to [`slice::binary_search`] but gives the first of equal
+/// keys, where `binary_search` may give any.
+pub fn binary_search(items: &[Item], key: u8) -> Result<usize, usize> {
+    let point = items.iter().take_while(|item| item.key < key).count();
+    match items.get(point) {
+        Some(item) if item.key == key => Ok(point),
+        _ => Err(point),
+    }
+}
+
+/// Whether `items` holds each tag of `keys` once, with its key
+fn is_permutation(items: &[Item], keys: &[u8]) -> bool {
+    let mut seen = vec![false; keys.len()];
+    for item in items {
+        if item.tag >= keys.len() || seen[item.tag] || keys[item.tag] != item.key {
+            return false;
+        }
+        seen[item.tag] = true;
+    }
+    items.len() == keys.len()
+}
+
+/// A comparison by no order at all
+///
+/// Each call answers with the next of the answers, over and over: `Less`,
+/// `Equal` or `Greater` as the answer is 0, 1 or 2 modulo three. With no
+/// answers every call answers `Equal`. Calls are checked to compare items of
+/// the slice being sorted, but a failed check would only be a panic of the
+/// sort, and so is recorded to be asserted after.
+pub struct Chaos<'a> {
+    keys: &'a [u8],
+    answers: &'a [u8],
+    calls: Cell<usize>,
+    stranger: RefCell<Option<(Item, Item)>>,
+}
+
+impl<'a> Chaos<'a> {
+    /// A comparison of the items of `keys` by `answers`
+    pub fn new(keys: &'a [u8], answers: &'a [u8]) -> Self {
+        Chaos {
+            keys,
+            answers,
+            calls: Cell::new(0),
+            stranger: RefCell::new(None),
+        }
+    }
+
+    /// Compare `a` with `b`
+    pub fn compare(&self, a: &Item, b: &Item) -> Ordering {
+        let known = |item: &Item| self.keys.get(item.tag) == Some(&item.key);
+        if !(known(a) && known(b)) {
+            *self.stranger.borrow_mut() = Some((*a, *b));
+        }
+        let call = self.calls.get();
+        self.calls.set(call + 1);
+        match self.answers.get(call % self.answers.len().max(1)).map(|answer| answer % 3) {
+            Some(0) => Ordering::Less,
+            Some(2) => Ordering::Greater,
+            _ => Ordering::Equal,
+        }
+    }
+
+    /// Assert every call compared items of the slice
+    pub fn check(&self) {
+        check_eq!(*self.stranger.borrow(), None, "compared items that are not in the slice");
+    }
+}
+
+/// The `Op` defines the ways a slice of [`Item`] is sorted or searched.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `slice::sort`
+    #[op(tag = 0, weight = 2)]
+    Sort {
+        /// The keys of the items
+        keys: Bytes,
+    },
+    /// This operation triggers `slice::sort_by_key`
+    #[op(tag = 1)]
+    SortByKey {
+        /// The keys of the items
+        keys: Bytes,
+        /// Sort by the key shifted right `shift % 8` bits, making ties
+        shift: u8,
+    },
+    /// This operation triggers `slice::sort_by_cached_key`
+    #[op(tag = 2)]
+    SortByCachedKey {
+        /// The keys of the items
+        keys: Bytes,
+        /// Sort by the key shifted right `shift % 8` bits, making ties
+        shift: u8,
+    },
+    /// This operation triggers `slice::sort_unstable` and
+    /// `slice::sort_unstable_by_key`
+    #[op(tag = 3, weight = 2)]
+    SortUnstable {
+        /// The keys of the items
+        keys: Bytes,
+    },
+    /// This operation triggers `slice::select_nth_unstable`
+    #[op(tag = 4)]
+    SelectNth {
+        /// The keys of the items
+        keys: Bytes,
+        /// The index to select, modulo the length
+        index: u8,
+    },
+    /// This operation triggers `slice::binary_search`,
+    /// `slice::binary_search_by_key` and `slice::partition_point` on the sorted
+    /// items
+    #[op(tag = 5)]
+    BinarySearch {
+        /// The keys of the items
+        keys: Bytes,
+        /// The key to search for
+        key: u8,
+    },
+    /// This operation triggers `slice::sort_by` with a [`Chaos`] comparison
+    #[op(tag = 6)]
+    ChaosSort {
+        /// The keys of the items
+        keys: Bytes,
+        /// The answers of the comparison
+        answers: Bytes,
+    },
+    /// This operation triggers `slice::sort_unstable_by` with a [`Chaos`]
+    /// comparison
+    #[op(tag = 7)]
+    ChaosSortUnstable {
+        /// The keys of the items
+        keys: Bytes,
+        /// The answers of the comparison
+        answers: Bytes,
+    },
+    /// This operation triggers `slice::select_nth_unstable_by` with a
+    /// [`Chaos`] comparison
+    #[op(tag = 8)]
+    ChaosSelectNth {
+        /// The keys of the items
+        keys: Bytes,
+        /// The answers of the comparison
+        answers: Bytes,
+        /// The index to select, modulo the length
+        index: u8,
+    },
+    /// This operation triggers `slice::binary_search_by` with a [`Chaos`]
+    /// comparison
+    #[op(tag = 9)]
+    ChaosBinarySearch {
+        /// The keys of the items
+        keys: Bytes,
+        /// The answers of the comparison
+        answers: Bytes,
+    },
+}
+
+impl Op {
+    /// The keys of the items the op sorts
+    pub fn keys(&self) -> &[u8] {
+        match *self {
+            Op::Sort { ref keys }
+            | Op::SortByKey { ref keys, .. }
+            | Op::SortByCachedKey { ref keys, .. }
+            | Op::SortUnstable { ref keys }
+            | Op::SelectNth { ref keys, .. }
+            | Op::BinarySearch { ref keys, .. }
+            | Op::ChaosSort { ref keys, .. }
+            | Op::ChaosSortUnstable { ref keys, .. }
+            | Op::ChaosSelectNth { ref keys, .. }
+            | Op::ChaosBinarySearch { ref keys, .. } => &keys.0,
+        }
+    }
+}
+
+/// Run `op`, panicking if the slice and the model disagree
+pub fn check_op(op: &Op) {
+    let keys = op.keys();
+    let mut sut = items(keys);
+    let mut model = items(keys);
+    insertion_sort(&mut model, Item::cmp);
+    match *op {
+        Op::Sort { .. } => {
+            sut.sort();
+            check_eq!(identities(&sut), identities(&model));
+        }
+        Op::SortByKey { shift, .. } => {
+            let key = |item: &Item| item.key >> (shift % 8);
+            let mut model = items(keys);
+            insertion_sort(&mut model, |a, b| key(a).cmp(&key(b)));
+            sut.sort_by_key(key);
+            check_eq!(identities(&sut), identities(&model));
+        }
+        Op::SortByCachedKey { shift, .. } => {
+            let key = |item: &Item| item.key >> (shift % 8);
+            let mut model = items(keys);
+            insertion_sort(&mut model, |a, b| key(a).cmp(&key(b)));
+            // The key of each item is worked out at most once, however the
+            // sort goes.
+            let calls = Cell::new(0);
+            sut.sort_by_cached_key(|item| {
+                calls.set(calls.get() + 1);
+                key(item)
+            });
+            check_eq!(identities(&sut), identities(&model));
+            check!(calls.get() <= keys.len());
+        }
+        Op::SortUnstable { .. } => {
+            sut.sort_unstable();
+            check_eq!(sut, model);
+            check!(is_permutation(&sut, keys));
+            let mut sut = items(keys);
+            sut.sort_unstable_by_key(|item| u8::MAX - item.key);
+            model.reverse();
+            check_eq!(sut, model);
+            check!(is_permutation(&sut, keys));
+        }
+        Op::SelectNth { index, .. } => {
+            if keys.is_empty() {
+                return;
+            }
+            let index = index as usize % keys.len();
+            {
+                let (left, nth, right) = sut.select_nth_unstable(index);
+                check_eq!(left.len(), index);
+                check_eq!(nth.key, model[index].key);
+                check!(left.iter().all(|item| item <= nth));
+                check!(right.iter().all(|item| item >= nth));
+            }
+            check!(is_permutation(&sut, keys));
+        }
+        Op::BinarySearch { key, .. } => {
+            let expected = binary_search(&model, key);
+            let found = model.binary_search(&Item { key, tag: 0 });
+            match (found, expected) {
+                // Any of the items of the key will do.
+                (Ok(idx), Ok(_)) => check_eq!(model[idx].key, key),
+                (found, expected) => check_eq!(found, expected),
+            }
+            let by_key = model.binary_search_by_key(&key, |item| item.key);
+            check_eq!(by_key.is_ok(), expected.is_ok());
+            if let Ok(idx) = by_key {
+                check_eq!(model[idx].key, key);
+            }
+            let point = match expected {
+                Ok(idx) | Err(idx) => idx,
+            };
+            check_eq!(model.partition_point(|item| item.key < key), point);
+        }
+        Op::ChaosSort { ref answers, .. } => {
+            let chaos = Chaos::new(keys, &answers.0);
+            panics(|| sut.sort_by(|a, b| chaos.compare(a, b)));
+            chaos.check();
+            check!(is_permutation(&sut, keys));
+        }
+        Op::ChaosSortUnstable { ref answers, .. } => {
+            let chaos = Chaos::new(keys, &answers.0);
+            panics(|| sut.sort_unstable_by(|a, b| chaos.compare(a, b)));
+            chaos.check();
+            check!(is_permutation(&sut, keys));
+        }
+        Op::ChaosSelectNth { ref answers, index, .. } => {
+            if keys.is_empty() {
+                return;
+            }
+            let index = index as usize % keys.len();
+            let chaos = Chaos::new(keys, &answers.0);
+            let mut left = None;
+            panics(|| left = Some(sut.select_nth_unstable_by(index, |a, b| chaos.compare(a, b)).0.len()));
+            chaos.check();
+            if let Some(left) = left {
+                check_eq!(left, index);
+            }
+            check!(is_permutation(&sut, keys));
+        }
+        Op::ChaosBinarySearch { ref answers, .. } => {
+            // Searching never panics, whatever the comparison says, and lands
+            // in bounds.
+            let chaos = Chaos::new(keys, &answers.0);
+            match sut.binary_search_by(|item| chaos.compare(item, item)) {
+                Ok(idx) => check!(idx < keys.len()),
+                Err(idx) => check!(idx <= keys.len()),
+            }
+            chaos.check();
+        }
+    }
+}
+
+/// Decode the input of the `slice_sort` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `slice_sort` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against the sorts and the model
+///
+/// Every op is checked as it comes, panicking as soon as the two disagree.
+/// The run is counted in [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        stats::size(TARGET, op.keys().len());
+        check_op(op);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn insertion_sort_is_stable() {
+        let mut model = items(&[3, 1, 3, 0, 1]);
+        insertion_sort(&mut model, Item::cmp);
+        assert_eq!(vec![(0, 3), (1, 1), (1, 4), (3, 0), (3, 2)], identities(&model));
+    }
+
+    #[test]
+    fn binary_search_finds_the_first_of_equal_keys() {
+        let model = items(&[1, 2, 2, 2, 5]);
+        assert_eq!(Ok(1), binary_search(&model, 2));
+        assert_eq!(Err(4), binary_search(&model, 3));
+        assert_eq!(Err(5), binary_search(&model, 9));
+        assert_eq!(Err(0), binary_search(&[], 9));
+    }
+
+    #[test]
+    fn permutations_keep_every_tag_once() {
+        let keys = [4, 4, 7];
+        assert!(is_permutation(&items(&keys), &keys));
+        let mut twice = items(&keys);
+        twice[1] = twice[0];
+        assert!(!is_permutation(&twice, &keys));
+        assert!(!is_permutation(&items(&keys[..2]), &keys));
+    }
+
+    quickcheck! {
+        fn sorts_agree(keys: Vec<u8>, shift: u8, index: u8, key: u8) -> bool {
+            let keys = Bytes(keys);
+            check_op(&Op::Sort { keys: keys.clone() });
+            check_op(&Op::SortByKey { keys: keys.clone(), shift });
+            check_op(&Op::SortByCachedKey { keys: keys.clone(), shift });
+            check_op(&Op::SortUnstable { keys: keys.clone() });
+            check_op(&Op::SelectNth { keys: keys.clone(), index });
+            check_op(&Op::BinarySearch { keys, key });
+            true
+        }
+
+        fn chaos_only_permutes(keys: Vec<u8>, answers: Vec<u8>, index: u8) -> bool {
+            let (keys, answers) = (Bytes(keys), Bytes(answers));
+            check_op(&Op::ChaosSort { keys: keys.clone(), answers: answers.clone() });
+            check_op(&Op::ChaosSortUnstable { keys: keys.clone(), answers: answers.clone() });
+            check_op(&Op::ChaosSelectNth { keys: keys.clone(), answers: answers.clone(), index });
+            check_op(&Op::ChaosBinarySearch { keys, answers });
+            true
+        }
+    }
+}
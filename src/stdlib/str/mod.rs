@@ -0,0 +1,3 @@
+//! Tests for `std::str`
+pub mod search;
+pub mod utf8;
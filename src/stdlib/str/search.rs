@@ -0,0 +1,982 @@
+//! Tests for the searching and splitting methods of `str`
+//!
+//! Every method of `str` taking a pattern -- `find`, `split`, `replace` and
+//! the rest -- is built on a searcher. A `char` is searched for with `memchr`, a
+//! `&str` with the Two-Way algorithm and a closure by trying each char in turn.
+//! We check them all against naive implementations which try the pattern at
+//! every char boundary of the haystack, in order.
+use arbitrary::*;
+use encode::Encode;
+use op::{self, Profiles};
+use stats;
+use std::fmt;
+use std::str::FromStr;
+use trace::{Header, ParseError, Trace};
+
+/// The name of the fuzz target driving the searchers of `str`
+pub const TARGET: &str = "str_search";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most chars in a [`Word`]
+pub const MAX_WORD_LEN: usize = 64;
+
+/// The chars haystacks and needles are made of
+///
+/// A needle drawn from the whole of Unicode would almost never turn up in a
+/// haystack. Over a small alphabet matches, near misses and periodic needles
+/// -- the hard cases for Two-Way -- are common. The wider chars share lead or
+/// continuation bytes with one another, so a searcher comparing bytes must
+/// take care to match only at char boundaries.
+pub const ALPHABET: [char; 8] = ['a', 'b', 'c', '\u{e9}', '\u{a9}', '\u{20ac}', '\u{2603}', '\u{1f41b}'];
+
+lazy_static! {
+    /// The workload profiles of the `str_search` target, see [`Profiles`]
+    ///
+    /// `forward` only searches from the front of the haystack and `reverse`
+    /// only from the back.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[
+        (
+            "forward",
+            &[("rfind", 0), ("rmatch_indices", 0), ("rsplit", 0), ("rsplitn", 0), ("rsplit_once", 0), ("strip_suffix", 0)],
+        ),
+        (
+            "reverse",
+            &[("find", 0), ("match_indices", 0), ("split", 0), ("splitn", 0), ("split_terminator", 0), ("split_once", 0), ("strip_prefix", 0)],
+        ),
+    ]);
+}
+
+/// A short string over [`ALPHABET`] decoded from fuzz input
+///
+/// A `u8` length, taken modulo one more than [`MAX_WORD_LEN`], is followed by
+/// one byte per char, taken modulo the size of the alphabet. In traces a
+/// `Word` is written out as itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Word(pub String);
+
+/// The index into [`ALPHABET`] of `c`
+fn letter(c: char) -> Option<u8> {
+    ALPHABET.iter().position(|&l| l == c).map(|i| i as u8)
+}
+
+impl Arbitrary for Word {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let len: u8 = Arbitrary::arbitrary(u)?;
+        let mut s = String::new();
+        for _ in 0..(len as usize % (MAX_WORD_LEN + 1)) {
+            let l: u8 = Arbitrary::arbitrary(u)?;
+            s.push(ALPHABET[l as usize % ALPHABET.len()]);
+        }
+        Ok(Word(s))
+    }
+}
+
+impl Encode for Word {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        // Decoding reads at most `MAX_WORD_LEN` chars, so we write no more.
+        let len = self.0.chars().count().min(MAX_WORD_LEN);
+        (len as u8).encode(buf);
+        for c in self.0.chars().take(len) {
+            letter(c)
+                .expect("a Word is made of the ALPHABET")
+                .encode(buf);
+        }
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Word {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.chars().find(|&c| letter(c).is_none()) {
+            Some(c) => Err(ParseError::new(format!("'{}' is not in the alphabet", c))),
+            None => Ok(Word(s.to_string())),
+        }
+    }
+}
+
+/// A pattern to search for
+///
+/// In traces a `Pattern` is written as its kind and its value, such as
+/// `char:a`, `str:ab` or `set:a€`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// A `char` pattern
+    Char(char),
+    /// A `&str` pattern
+    Str(Word),
+    /// A closure pattern, matching the chars of [`ALPHABET`] whose bits are set
+    Set(u8),
+}
+
+impl Pattern {
+    /// Determine if the closure of a `Pattern::Set` matches `c`
+    pub fn in_set(set: u8, c: char) -> bool {
+        match letter(c) {
+            Some(l) => set & (1 << l) != 0,
+            None => false,
+        }
+    }
+
+    /// Determine if searches for the pattern run from both ends
+    ///
+    /// This is so if the searcher of the pattern is a
+    /// [`DoubleEndedSearcher`], as those of chars and closures are. The
+    /// iterators of such patterns are double-ended, and only those patterns
+    /// may be trimmed from both ends at once.
+    ///
+    /// [`DoubleEndedSearcher`]: https://doc.rust-lang.org/std/str/pattern/trait.DoubleEndedSearcher.html
+    pub fn is_double_ended(&self) -> bool {
+        match *self {
+            Pattern::Char(_) | Pattern::Set(_) => true,
+            Pattern::Str(_) => false,
+        }
+    }
+
+    /// The length in bytes of the match of the pattern starting at `idx`
+    ///
+    /// Returns `None` if there is no match there. `idx` must be a char
+    /// boundary of `hay`.
+    pub fn match_at(&self, hay: &str, idx: usize) -> Option<usize> {
+        match *self {
+            Pattern::Char(c) => hay[idx..].chars().next().filter(|&h| h == c).map(char::len_utf8),
+            Pattern::Str(ref w) => {
+                if hay.as_bytes()[idx..].starts_with(w.0.as_bytes()) {
+                    Some(w.0.len())
+                } else {
+                    None
+                }
+            }
+            Pattern::Set(set) => hay[idx..]
+                .chars()
+                .next()
+                .filter(|&h| Pattern::in_set(set, h))
+                .map(char::len_utf8),
+        }
+    }
+
+    /// The length in bytes of the match of the pattern ending at `idx`
+    ///
+    /// Returns `None` if there is no match there. `idx` must be a char
+    /// boundary of `hay`.
+    pub fn match_ending_at(&self, hay: &str, idx: usize) -> Option<usize> {
+        match *self {
+            Pattern::Char(c) => hay[..idx].chars().next_back().filter(|&h| h == c).map(char::len_utf8),
+            Pattern::Str(ref w) => {
+                if hay.as_bytes()[..idx].ends_with(w.0.as_bytes()) {
+                    Some(w.0.len())
+                } else {
+                    None
+                }
+            }
+            Pattern::Set(set) => hay[..idx]
+                .chars()
+                .next_back()
+                .filter(|&h| Pattern::in_set(set, h))
+                .map(char::len_utf8),
+        }
+    }
+}
+
+impl Arbitrary for Pattern {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let kind: u8 = Arbitrary::arbitrary(u)?;
+        match kind % 3 {
+            0 => {
+                let l: u8 = Arbitrary::arbitrary(u)?;
+                Ok(Pattern::Char(ALPHABET[l as usize % ALPHABET.len()]))
+            }
+            1 => Ok(Pattern::Str(Arbitrary::arbitrary(u)?)),
+            _ => Ok(Pattern::Set(Arbitrary::arbitrary(u)?)),
+        }
+    }
+}
+
+impl Encode for Pattern {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match *self {
+            Pattern::Char(c) => {
+                0u8.encode(buf);
+                letter(c).expect("a Pattern is made of the ALPHABET").encode(buf);
+            }
+            Pattern::Str(ref w) => {
+                1u8.encode(buf);
+                w.encode(buf);
+            }
+            Pattern::Set(set) => {
+                2u8.encode(buf);
+                set.encode(buf);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Pattern::Char(c) => write!(f, "char:{}", c),
+            Pattern::Str(ref w) => write!(f, "str:{}", w),
+            Pattern::Set(set) => {
+                f.write_str("set:")?;
+                for c in ALPHABET.iter().filter(|&&c| Pattern::in_set(set, c)) {
+                    write!(f, "{}", c)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let kind = parts.next().expect("split yields at least once");
+        let value: Word = parts
+            .next()
+            .ok_or_else(|| ParseError::new(format!("expected kind:value, got '{}'", s)))?
+            .parse()?;
+        match kind {
+            "char" => {
+                let mut chars = value.0.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(Pattern::Char(c)),
+                    _ => Err(ParseError::new(format!("'{}' is not a single char", value))),
+                }
+            }
+            "str" => Ok(Pattern::Str(value)),
+            "set" => Ok(Pattern::Set(value.0.chars().fold(0, |set, c| {
+                set | 1 << letter(c).expect("a Word is made of the ALPHABET")
+            }))),
+            _ => Err(ParseError::new(format!("unknown pattern kind '{}'", kind))),
+        }
+    }
+}
+
+/// The char boundary of `hay` following `idx`, or one past its end
+fn next_boundary(hay: &str, idx: usize) -> usize {
+    let mut next = idx + 1;
+    while next < hay.len() && !hay.is_char_boundary(next) {
+        next += 1;
+    }
+    next
+}
+
+/// The char boundary of `hay` preceding `idx`, which must not be 0
+fn prev_boundary(hay: &str, idx: usize) -> usize {
+    let mut prev = idx - 1;
+    while !hay.is_char_boundary(prev) {
+        prev -= 1;
+    }
+    prev
+}
+
+/// The matches of `pat` in `hay`, searching from the front
+///
+/// This is like to [`str::match_indices`]. A match starts at the first char
+/// boundary where `pat` matches at or after the end of the last one. An empty
+/// `&str` pattern matches at every boundary, end of `hay` included.
+pub fn match_indices<'a>(hay: &'a str, pat: &Pattern) -> Vec<(usize, &'a str)> {
+    let mut matches = Vec::new();
+    let mut idx = 0;
+    while idx <= hay.len() {
+        match pat.match_at(hay, idx) {
+            Some(len) => {
+                matches.push((idx, &hay[idx..idx + len]));
+                idx = if len == 0 { next_boundary(hay, idx) } else { idx + len };
+            }
+            None => idx = next_boundary(hay, idx),
+        }
+    }
+    matches
+}
+
+/// The matches of `pat` in `hay`, searching from the back
+///
+/// This is like to [`str::rmatch_indices`]. A `&str` pattern may overlap
+/// itself, so these are not always the matches of [`match_indices`]
+/// reversed.
+pub fn rmatch_indices<'a>(hay: &'a str, pat: &Pattern) -> Vec<(usize, &'a str)> {
+    let mut matches = Vec::new();
+    let mut idx = hay.len();
+    loop {
+        match pat.match_ending_at(hay, idx) {
+            Some(len) if len > 0 => {
+                matches.push((idx - len, &hay[idx - len..idx]));
+                idx -= len;
+                continue;
+            }
+            Some(_) => matches.push((idx, &hay[idx..idx])),
+            None => {}
+        }
+        if idx == 0 {
+            return matches;
+        }
+        idx = prev_boundary(hay, idx);
+    }
+}
+
+/// The byte offset of the first match of `pat` in `hay`
+///
+/// This is like to [`str::find`]
+pub fn find(hay: &str, pat: &Pattern) -> Option<usize> {
+    match_indices(hay, pat).first().map(|&(idx, _)| idx)
+}
+
+/// The byte offset of the last match of `pat` in `hay`
+///
+/// This is like to [`str::rfind`]
+pub fn rfind(hay: &str, pat: &Pattern) -> Option<usize> {
+    rmatch_indices(hay, pat).first().map(|&(idx, _)| idx)
+}
+
+/// The pieces of `hay` between `matches`, taken from the front
+fn pieces<'a>(hay: &'a str, matches: &[(usize, &'a str)]) -> Vec<&'a str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for &(idx, m) in matches {
+        pieces.push(&hay[start..idx]);
+        start = idx + m.len();
+    }
+    pieces.push(&hay[start..]);
+    pieces
+}
+
+/// The pieces of `hay` between `matches`, taken from the back
+fn rpieces<'a>(hay: &'a str, matches: &[(usize, &'a str)]) -> Vec<&'a str> {
+    let mut pieces = Vec::new();
+    let mut end = hay.len();
+    for &(idx, m) in matches {
+        pieces.push(&hay[idx + m.len()..end]);
+        end = idx;
+    }
+    pieces.push(&hay[..end]);
+    pieces
+}
+
+/// Split `hay` by `pat`
+///
+/// This is like to [`str::split`]
+pub fn split<'a>(hay: &'a str, pat: &Pattern) -> Vec<&'a str> {
+    pieces(hay, &match_indices(hay, pat))
+}
+
+/// Split `hay` by `pat`, from the back
+///
+/// This is like to [`str::rsplit`]
+pub fn rsplit<'a>(hay: &'a str, pat: &Pattern) -> Vec<&'a str> {
+    rpieces(hay, &rmatch_indices(hay, pat))
+}
+
+/// Split `hay` by `pat` into at most `n` pieces
+///
+/// This is like to [`str::splitn`]
+pub fn splitn<'a>(hay: &'a str, n: usize, pat: &Pattern) -> Vec<&'a str> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut matches = match_indices(hay, pat);
+    matches.truncate(n - 1);
+    pieces(hay, &matches)
+}
+
+/// Split `hay` by `pat` into at most `n` pieces, from the back
+///
+/// This is like to [`str::rsplitn`]
+pub fn rsplitn<'a>(hay: &'a str, n: usize, pat: &Pattern) -> Vec<&'a str> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut matches = rmatch_indices(hay, pat);
+    matches.truncate(n - 1);
+    rpieces(hay, &matches)
+}
+
+/// Split `hay` by `pat`, leaving off an empty last piece
+///
+/// This is like to [`str::split_terminator`]
+pub fn split_terminator<'a>(hay: &'a str, pat: &Pattern) -> Vec<&'a str> {
+    let mut pieces = split(hay, pat);
+    if pieces.last() == Some(&"") {
+        pieces.pop();
+    }
+    pieces
+}
+
+/// Split `hay` around the first match of `pat`
+///
+/// This is like to [`str::split_once`]
+pub fn split_once<'a>(hay: &'a str, pat: &Pattern) -> Option<(&'a str, &'a str)> {
+    match_indices(hay, pat)
+        .first()
+        .map(|&(idx, m)| (&hay[..idx], &hay[idx + m.len()..]))
+}
+
+/// Split `hay` around the last match of `pat`
+///
+/// This is like to [`str::rsplit_once`]
+pub fn rsplit_once<'a>(hay: &'a str, pat: &Pattern) -> Option<(&'a str, &'a str)> {
+    rmatch_indices(hay, pat)
+        .first()
+        .map(|&(idx, m)| (&hay[..idx], &hay[idx + m.len()..]))
+}
+
+/// Replace the first `n` matches of `pat` in `hay` with `to`
+///
+/// This is like to [`str::replacen`], and to [`str::replace`] when `n` is at
+/// least the number of matches.
+pub fn replacen(hay: &str, pat: &Pattern, to: &str, n: usize) -> String {
+    let mut out = String::new();
+    let mut start = 0;
+    for (idx, m) in match_indices(hay, pat).into_iter().take(n) {
+        out.push_str(&hay[start..idx]);
+        out.push_str(to);
+        start = idx + m.len();
+    }
+    out.push_str(&hay[start..]);
+    out
+}
+
+/// Remove a match of `pat` from the front of `hay`
+///
+/// This is like to [`str::strip_prefix`]
+pub fn strip_prefix<'a>(hay: &'a str, pat: &Pattern) -> Option<&'a str> {
+    pat.match_at(hay, 0).map(|len| &hay[len..])
+}
+
+/// Remove a match of `pat` from the back of `hay`
+///
+/// This is like to [`str::strip_suffix`]
+pub fn strip_suffix<'a>(hay: &'a str, pat: &Pattern) -> Option<&'a str> {
+    pat.match_ending_at(hay, hay.len())
+        .map(|len| &hay[..hay.len() - len])
+}
+
+/// Remove every match of `pat` from the front of `hay`
+///
+/// This is like to [`str::trim_start_matches`]
+pub fn trim_start_matches<'a>(mut hay: &'a str, pat: &Pattern) -> &'a str {
+    // An empty match would strip nothing, forever.
+    while let Some(len) = pat.match_at(hay, 0).filter(|&len| len > 0) {
+        hay = &hay[len..];
+    }
+    hay
+}
+
+/// Remove every match of `pat` from the back of `hay`
+///
+/// This is like to [`str::trim_end_matches`]
+pub fn trim_end_matches<'a>(mut hay: &'a str, pat: &Pattern) -> &'a str {
+    while let Some(len) = pat.match_ending_at(hay, hay.len()).filter(|&len| len > 0) {
+        hay = &hay[..hay.len() - len];
+    }
+    hay
+}
+
+/// Remove every match of `pat` from both ends of `hay`
+///
+/// This is like to [`str::trim_matches`], which only takes patterns that are
+/// [`Pattern::is_double_ended`].
+pub fn trim_matches<'a>(hay: &'a str, pat: &Pattern) -> &'a str {
+    trim_end_matches(trim_start_matches(hay, pat), pat)
+}
+
+/// The `Op` defines the set of searches made of `str` and of the naive
+/// implementations in this module.
+///
+/// Each op carries its own haystack and pattern. Iterators are drained from
+/// the front or the back as the bits of `dirs` say, lowest bit first, when
+/// the pattern allows it. Otherwise they are drained from the front.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `str::find` and `str::contains`
+    #[op(tag = 0)]
+    Find {
+        /// The haystack
+        hay: Word,
+        /// The pattern to search for
+        pat: Pattern,
+    },
+    /// This operation triggers `str::rfind`
+    #[op(tag = 1)]
+    Rfind {
+        /// The haystack
+        hay: Word,
+        /// The pattern to search for
+        pat: Pattern,
+    },
+    /// This operation triggers `str::match_indices` and `str::matches`
+    #[op(tag = 2)]
+    MatchIndices {
+        /// The haystack
+        hay: Word,
+        /// The pattern to search for
+        pat: Pattern,
+        /// The ends to drain the iterators from
+        dirs: u16,
+    },
+    /// This operation triggers `str::rmatch_indices` and `str::rmatches`
+    #[op(tag = 3)]
+    RmatchIndices {
+        /// The haystack
+        hay: Word,
+        /// The pattern to search for
+        pat: Pattern,
+        /// The ends to drain the iterators from
+        dirs: u16,
+    },
+    /// This operation triggers `str::split`
+    #[op(tag = 4)]
+    Split {
+        /// The haystack
+        hay: Word,
+        /// The pattern to split by
+        pat: Pattern,
+        /// The ends to drain the iterator from
+        dirs: u16,
+    },
+    /// This operation triggers `str::rsplit`
+    #[op(tag = 5)]
+    Rsplit {
+        /// The haystack
+        hay: Word,
+        /// The pattern to split by
+        pat: Pattern,
+        /// The ends to drain the iterator from
+        dirs: u16,
+    },
+    /// This operation triggers `str::splitn`
+    #[op(tag = 6)]
+    Splitn {
+        /// The haystack
+        hay: Word,
+        /// The pattern to split by
+        pat: Pattern,
+        /// The most pieces to split into
+        n: u8,
+    },
+    /// This operation triggers `str::rsplitn`
+    #[op(tag = 7)]
+    Rsplitn {
+        /// The haystack
+        hay: Word,
+        /// The pattern to split by
+        pat: Pattern,
+        /// The most pieces to split into
+        n: u8,
+    },
+    /// This operation triggers `str::split_terminator`
+    #[op(tag = 8)]
+    SplitTerminator {
+        /// The haystack
+        hay: Word,
+        /// The pattern to split by
+        pat: Pattern,
+        /// The ends to drain the iterator from
+        dirs: u16,
+    },
+    /// This operation triggers `str::split_once`
+    #[op(tag = 9)]
+    SplitOnce {
+        /// The haystack
+        hay: Word,
+        /// The pattern to split by
+        pat: Pattern,
+    },
+    /// This operation triggers `str::rsplit_once`
+    #[op(tag = 10)]
+    RsplitOnce {
+        /// The haystack
+        hay: Word,
+        /// The pattern to split by
+        pat: Pattern,
+    },
+    /// This operation triggers `str::replace`
+    #[op(tag = 11)]
+    Replace {
+        /// The haystack
+        hay: Word,
+        /// The pattern to replace
+        pat: Pattern,
+        /// The replacement
+        to: Word,
+    },
+    /// This operation triggers `str::replacen`
+    #[op(tag = 12)]
+    Replacen {
+        /// The haystack
+        hay: Word,
+        /// The pattern to replace
+        pat: Pattern,
+        /// The replacement
+        to: Word,
+        /// The most matches to replace
+        n: u8,
+    },
+    /// This operation triggers `str::strip_prefix` and `str::starts_with`
+    #[op(tag = 13)]
+    StripPrefix {
+        /// The haystack
+        hay: Word,
+        /// The pattern to strip
+        pat: Pattern,
+    },
+    /// This operation triggers `str::strip_suffix` and `str::ends_with`
+    #[op(tag = 14)]
+    StripSuffix {
+        /// The haystack
+        hay: Word,
+        /// The pattern to strip
+        pat: Pattern,
+    },
+    /// This operation triggers `str::trim_start_matches`,
+    /// `str::trim_end_matches` and, when the pattern allows it,
+    /// `str::trim_matches`
+    #[op(tag = 15)]
+    TrimMatches {
+        /// The haystack
+        hay: Word,
+        /// The pattern to trim
+        pat: Pattern,
+    },
+}
+
+/// Decode the input of the `str_search` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["char".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `str_search` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Call `$body` with `$p` bound to `$pat` as a pattern `str` understands
+macro_rules! with_pattern {
+    ($pat:expr, |$p:ident| $body:expr) => {
+        match *$pat {
+            Pattern::Char(c) => {
+                let $p = c;
+                $body
+            }
+            Pattern::Str(ref w) => {
+                let $p = &w.0[..];
+                $body
+            }
+            Pattern::Set(set) => {
+                let $p = move |c: char| Pattern::in_set(set, c);
+                $body
+            }
+        }
+    };
+}
+
+/// Drain the iterator `$iter` made with `$p` bound to `$pat`
+///
+/// The iterator is drained from both ends, as `$dirs` says, if the pattern is
+/// double-ended and else from the front.
+macro_rules! drain_with_pattern {
+    ($pat:expr, $dirs:expr, |$p:ident| $iter:expr) => {
+        match *$pat {
+            Pattern::Char(c) => {
+                let $p = c;
+                drain_both_ends($iter, $dirs)
+            }
+            Pattern::Str(ref w) => {
+                let $p = &w.0[..];
+                $iter.collect::<Vec<_>>()
+            }
+            Pattern::Set(set) => {
+                let $p = move |c: char| Pattern::in_set(set, c);
+                drain_both_ends($iter, $dirs)
+            }
+        }
+    };
+}
+
+/// Drain `iter` from the front or the back as the bits of `dirs` say
+///
+/// The items are returned in the order they were taken. Once one end is
+/// exhausted both must be.
+fn drain_both_ends<I>(mut iter: I, dirs: u16) -> Vec<I::Item>
+where
+    I: DoubleEndedIterator,
+{
+    let mut items = Vec::new();
+    for i in 0.. {
+        let item = if dirs.rotate_right(i % 16) & 1 == 0 {
+            iter.next()
+        } else {
+            iter.next_back()
+        };
+        match item {
+            Some(item) => items.push(item),
+            None => break,
+        }
+    }
+    check!(iter.next().is_none());
+    check!(iter.next_back().is_none());
+    items
+}
+
+/// Drain the model's `items` the way [`drain_with_pattern`] drains `str`'s
+fn drain_model<T>(items: Vec<T>, pat: &Pattern, dirs: u16) -> Vec<T> {
+    if pat.is_double_ended() {
+        drain_both_ends(items.into_iter(), dirs)
+    } else {
+        items
+    }
+}
+
+/// Replay a [`Trace`] against `str` and the naive implementations
+///
+/// Every op is applied to both, panicking as soon as the two disagree. The
+/// run is counted in [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        match *op {
+            Op::Find { ref hay, ref pat } => {
+                let hay = &hay.0[..];
+                let model = find(hay, pat);
+                check_eq!(with_pattern!(pat, |p| hay.find(p)), model);
+                check_eq!(with_pattern!(pat, |p| hay.contains(p)), model.is_some());
+                stats::size(TARGET, hay.len());
+            }
+            Op::Rfind { ref hay, ref pat } => {
+                let hay = &hay.0[..];
+                check_eq!(with_pattern!(pat, |p| hay.rfind(p)), rfind(hay, pat));
+                stats::size(TARGET, hay.len());
+            }
+            Op::MatchIndices { ref hay, ref pat, dirs } => {
+                let hay = &hay.0[..];
+                let model = drain_model(match_indices(hay, pat), pat, dirs);
+                let sut = drain_with_pattern!(pat, dirs, |p| hay.match_indices(p));
+                check_eq!(sut, model);
+                let model: Vec<&str> = model.into_iter().map(|(_, m)| m).collect();
+                let sut = drain_with_pattern!(pat, dirs, |p| hay.matches(p));
+                check_eq!(sut, model);
+                stats::size(TARGET, hay.len());
+            }
+            Op::RmatchIndices { ref hay, ref pat, dirs } => {
+                let hay = &hay.0[..];
+                let model = drain_model(rmatch_indices(hay, pat), pat, dirs);
+                let sut = drain_with_pattern!(pat, dirs, |p| hay.rmatch_indices(p));
+                check_eq!(sut, model);
+                let model: Vec<&str> = model.into_iter().map(|(_, m)| m).collect();
+                let sut = drain_with_pattern!(pat, dirs, |p| hay.rmatches(p));
+                check_eq!(sut, model);
+                stats::size(TARGET, hay.len());
+            }
+            Op::Split { ref hay, ref pat, dirs } => {
+                let hay = &hay.0[..];
+                let sut = drain_with_pattern!(pat, dirs, |p| hay.split(p));
+                check_eq!(sut, drain_model(split(hay, pat), pat, dirs));
+                stats::size(TARGET, hay.len());
+            }
+            Op::Rsplit { ref hay, ref pat, dirs } => {
+                let hay = &hay.0[..];
+                let sut = drain_with_pattern!(pat, dirs, |p| hay.rsplit(p));
+                check_eq!(sut, drain_model(rsplit(hay, pat), pat, dirs));
+                stats::size(TARGET, hay.len());
+            }
+            Op::Splitn { ref hay, ref pat, n } => {
+                let hay = &hay.0[..];
+                let n = n as usize;
+                let sut: Vec<&str> = with_pattern!(pat, |p| hay.splitn(n, p).collect());
+                check_eq!(sut, splitn(hay, n, pat));
+                stats::size(TARGET, hay.len());
+            }
+            Op::Rsplitn { ref hay, ref pat, n } => {
+                let hay = &hay.0[..];
+                let n = n as usize;
+                let sut: Vec<&str> = with_pattern!(pat, |p| hay.rsplitn(n, p).collect());
+                check_eq!(sut, rsplitn(hay, n, pat));
+                stats::size(TARGET, hay.len());
+            }
+            Op::SplitTerminator { ref hay, ref pat, dirs } => {
+                let hay = &hay.0[..];
+                let sut = drain_with_pattern!(pat, dirs, |p| hay.split_terminator(p));
+                check_eq!(sut, drain_model(split_terminator(hay, pat), pat, dirs));
+                stats::size(TARGET, hay.len());
+            }
+            Op::SplitOnce { ref hay, ref pat } => {
+                let hay = &hay.0[..];
+                check_eq!(with_pattern!(pat, |p| hay.split_once(p)), split_once(hay, pat));
+                stats::size(TARGET, hay.len());
+            }
+            Op::RsplitOnce { ref hay, ref pat } => {
+                let hay = &hay.0[..];
+                check_eq!(with_pattern!(pat, |p| hay.rsplit_once(p)), rsplit_once(hay, pat));
+                stats::size(TARGET, hay.len());
+            }
+            Op::Replace { ref hay, ref pat, ref to } => {
+                let hay = &hay.0[..];
+                let sut = with_pattern!(pat, |p| hay.replace(p, &to.0));
+                check_eq!(sut, replacen(hay, pat, &to.0, usize::MAX));
+                stats::size(TARGET, hay.len());
+            }
+            Op::Replacen { ref hay, ref pat, ref to, n } => {
+                let hay = &hay.0[..];
+                let n = n as usize;
+                let sut = with_pattern!(pat, |p| hay.replacen(p, &to.0, n));
+                check_eq!(sut, replacen(hay, pat, &to.0, n));
+                stats::size(TARGET, hay.len());
+            }
+            Op::StripPrefix { ref hay, ref pat } => {
+                let hay = &hay.0[..];
+                let model = strip_prefix(hay, pat);
+                check_eq!(with_pattern!(pat, |p| hay.strip_prefix(p)), model);
+                check_eq!(with_pattern!(pat, |p| hay.starts_with(p)), model.is_some());
+                stats::size(TARGET, hay.len());
+            }
+            Op::StripSuffix { ref hay, ref pat } => {
+                let hay = &hay.0[..];
+                let model = strip_suffix(hay, pat);
+                check_eq!(with_pattern!(pat, |p| hay.strip_suffix(p)), model);
+                check_eq!(with_pattern!(pat, |p| hay.ends_with(p)), model.is_some());
+                stats::size(TARGET, hay.len());
+            }
+            Op::TrimMatches { ref hay, ref pat } => {
+                let hay = &hay.0[..];
+                let sut = with_pattern!(pat, |p| hay.trim_start_matches(p));
+                check_eq!(sut, trim_start_matches(hay, pat));
+                let sut = with_pattern!(pat, |p| hay.trim_end_matches(p));
+                check_eq!(sut, trim_end_matches(hay, pat));
+                // Trimming both ends at once takes a double-ended searcher,
+                // which a `&str` does not have.
+                match *pat {
+                    Pattern::Char(c) => check_eq!(hay.trim_matches(c), trim_matches(hay, pat)),
+                    Pattern::Set(set) => {
+                        let sut = hay.trim_matches(|c| Pattern::in_set(set, c));
+                        check_eq!(sut, trim_matches(hay, pat));
+                    }
+                    Pattern::Str(_) => {}
+                }
+                stats::size(TARGET, hay.len());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn word(bytes: &[u8]) -> String {
+        bytes
+            .iter()
+            .map(|&b| ALPHABET[b as usize % ALPHABET.len()])
+            .collect()
+    }
+
+    fn needle(s: &str) -> Pattern {
+        Pattern::Str(Word(s.to_string()))
+    }
+
+    #[test]
+    fn empty_needle_matches_every_boundary() {
+        let matches: Vec<usize> = match_indices("a\u{e9}b", &needle(""))
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(vec![0, 1, 3, 4], matches);
+        let rmatches: Vec<usize> = rmatch_indices("a\u{e9}b", &needle(""))
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(vec![4, 3, 1, 0], rmatches);
+        assert_eq!(vec!["", "a", "\u{e9}", "b", ""], split("a\u{e9}b", &needle("")));
+    }
+
+    #[test]
+    fn overlapping_needles_depend_on_direction() {
+        assert_eq!(vec![(0, "aa")], match_indices("aaa", &needle("aa")));
+        assert_eq!(vec![(1, "aa")], rmatch_indices("aaa", &needle("aa")));
+        assert_eq!(vec!["", "a"], split("aaa", &needle("aa")));
+        assert_eq!(vec!["", "a"], rsplit("aaa", &needle("aa")));
+        assert_eq!("a", trim_end_matches("aaa", &needle("aa")));
+    }
+
+    #[test]
+    fn splits_are_limited() {
+        let comma = Pattern::Char('a');
+        assert!(splitn("bab", 0, &comma).is_empty());
+        assert_eq!(vec!["bab"], splitn("bab", 1, &comma));
+        assert_eq!(vec!["b", "b"], rsplitn("bab", 5, &comma));
+        assert_eq!(vec!["b", "b"], split_terminator("bab", &comma));
+        assert_eq!(vec!["b"], split_terminator("ba", &comma));
+        assert!(split_terminator("", &comma).is_empty());
+    }
+
+    #[test]
+    fn patterns_parse_as_written() {
+        for pat in &[Pattern::Char('\u{20ac}'), needle("ab\u{1f41b}"), needle(""), Pattern::Set(0b1000_0101)] {
+            assert_eq!(Ok(pat.clone()), pat.to_string().parse());
+        }
+        assert!("char:ab".parse::<Pattern>().is_err());
+        assert!("str:xyz".parse::<Pattern>().is_err());
+    }
+
+    quickcheck! {
+        fn split_then_join_restores(hay: Vec<u8>, pat: Vec<u8>) -> bool {
+            let (hay, pat) = (word(&hay), word(&pat));
+            pat.is_empty() || split(&hay, &needle(&pat)).join(&pat) == hay
+        }
+
+        fn char_matches_do_not_overlap(hay: Vec<u8>, set: u8) -> bool {
+            let hay = word(&hay);
+            let pat = Pattern::Set(set);
+            let mut rmatches = rmatch_indices(&hay, &pat);
+            rmatches.reverse();
+            let mut rpieces = rsplit(&hay, &pat);
+            rpieces.reverse();
+            rmatches == match_indices(&hay, &pat) && rpieces == split(&hay, &pat)
+        }
+
+        fn replace_joins_the_split(hay: Vec<u8>, pat: Vec<u8>, to: Vec<u8>) -> bool {
+            let (hay, pat, to) = (word(&hay), needle(&word(&pat)), word(&to));
+            replacen(&hay, &pat, &to, usize::MAX) == split(&hay, &pat).join(&to)
+        }
+
+        fn trim_leaves_no_match_at_the_ends(hay: Vec<u8>, set: u8) -> bool {
+            let hay = word(&hay);
+            let pat = Pattern::Set(set);
+            let trimmed = trim_matches(&hay, &pat);
+            strip_prefix(trimmed, &pat).is_none() && strip_suffix(trimmed, &pat).is_none()
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! Tests for the UTF-8 decoding of `str`
+//!
+//! `str::from_utf8` decides whether bytes are UTF-8 and, when they are not,
+//! where they stop being so. `String::from_utf8_lossy` decides where to put
+//! the replacement chars, and `str::chars` and friends decode the bytes once
+//! they've been let through. We check all of them against [`Utf8Decoder`], a
+//! state machine written from the well-formed byte sequences of the Unicode
+//! Standard, chapter 3, table 3-7.
+use op::{self, Profiles};
+use stats;
+use std::str;
+use stdlib::string::Char;
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `str::from_utf8`
+pub const TARGET: &str = "str_from_utf8";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 4_096;
+
+/// The replacement char `String::from_utf8_lossy` puts in for bad bytes
+pub const REPLACEMENT: char = '\u{fffd}';
+
+lazy_static! {
+    /// The workload profiles of the `str_from_utf8` target, see [`Profiles`]
+    ///
+    /// `valid` only ever appends chars. `broken` appends bad sequences as
+    /// often as good.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[
+        (
+            "valid",
+            &[("byte", 0), ("truncated", 0), ("overlong", 0), ("surrogate", 0), ("too_large", 0)],
+        ),
+        ("broken", &[("char", 1)]),
+    ]);
+}
+
+/// A step of a [`Utf8Decoder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// The byte completed a char
+    Char(char),
+    /// The byte continued a char, which is not yet complete
+    Pending,
+    /// The byte ended a sequence of `len` bytes which is no prefix of a char
+    ///
+    /// If `retry` is true the byte itself is not part of the sequence and
+    /// must be given to the decoder again.
+    Invalid {
+        /// The length of the invalid sequence, in bytes
+        len: usize,
+        /// Whether the byte must be given to the decoder again
+        retry: bool,
+    },
+}
+
+/// A byte-by-byte UTF-8 decoder
+///
+/// The decoder knows, from the first byte of a sequence, how many bytes are to
+/// follow and which values the second byte may take -- that's where overlong
+/// encodings, surrogates and code points past `U+10FFFF` are ruled out. Every
+/// byte after the second is a plain continuation byte, `80..=BF`.
+///
+/// An invalid sequence is the longest prefix of a char's encoding that could
+/// be found, or a single byte if there is none. This is the "maximal subpart"
+/// of the Unicode Standard, which is what both `Utf8Error::error_len` and
+/// `String::from_utf8_lossy` work in.
+#[derive(Debug, Clone, Default)]
+pub struct Utf8Decoder {
+    /// The bits of the code point decoded so far
+    point: u32,
+    /// The bytes of the current sequence seen so far
+    seen: usize,
+    /// The bytes still to come in the current sequence
+    need: usize,
+    /// The least value the next byte may take
+    lower: u8,
+    /// The greatest value the next byte may take
+    upper: u8,
+}
+
+impl Utf8Decoder {
+    /// Construct a new `Utf8Decoder`, at the start of a sequence
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The bytes of an unfinished sequence the decoder holds
+    pub fn pending(&self) -> usize {
+        self.seen
+    }
+
+    /// Feed a byte to the decoder
+    pub fn push(&mut self, byte: u8) -> Step {
+        if self.need == 0 {
+            return self.start(byte);
+        }
+        if byte < self.lower || byte > self.upper {
+            let len = self.seen;
+            *self = Self::new();
+            return Step::Invalid { len, retry: true };
+        }
+        self.point = self.point << 6 | u32::from(byte & 0x3F);
+        self.seen += 1;
+        self.need -= 1;
+        self.lower = 0x80;
+        self.upper = 0xBF;
+        if self.need > 0 {
+            return Step::Pending;
+        }
+        let point = self.point;
+        *self = Self::new();
+        Step::Char(::std::char::from_u32(point).expect("well-formed sequences encode chars"))
+    }
+
+    /// Begin a sequence with `byte`
+    fn start(&mut self, byte: u8) -> Step {
+        let (need, lower, upper, bits) = match byte {
+            0x00..=0x7F => return Step::Char(char::from(byte)),
+            0xC2..=0xDF => (1, 0x80, 0xBF, byte & 0x1F),
+            0xE0 => (2, 0xA0, 0xBF, byte & 0x0F),
+            0xE1..=0xEC | 0xEE..=0xEF => (2, 0x80, 0xBF, byte & 0x0F),
+            0xED => (2, 0x80, 0x9F, byte & 0x0F),
+            0xF0 => (3, 0x90, 0xBF, byte & 0x07),
+            0xF1..=0xF3 => (3, 0x80, 0xBF, byte & 0x07),
+            0xF4 => (3, 0x80, 0x8F, byte & 0x07),
+            // Continuation bytes, the lead bytes of overlong two byte
+            // sequences and those of sequences past `U+10FFFF`.
+            _ => return Step::Invalid { len: 1, retry: false },
+        };
+        *self = Self {
+            point: u32::from(bits),
+            seen: 1,
+            need,
+            lower,
+            upper,
+        };
+        Step::Pending
+    }
+}
+
+/// A piece of decoded bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Piece {
+    /// A char and the byte offset it starts at
+    Char(usize, char),
+    /// An invalid sequence
+    Invalid {
+        /// The byte offset the sequence starts at
+        offset: usize,
+        /// The length of the sequence, in bytes
+        len: usize,
+        /// Whether the sequence was cut short by the end of the bytes
+        truncated: bool,
+    },
+}
+
+/// Decode `bytes` into chars and invalid sequences
+pub fn decode(bytes: &[u8]) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut decoder = Utf8Decoder::new();
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match decoder.push(bytes[idx]) {
+            Step::Char(c) => {
+                let offset = idx + 1 - c.len_utf8();
+                pieces.push(Piece::Char(offset, c));
+                idx += 1;
+            }
+            Step::Pending => idx += 1,
+            Step::Invalid { len, retry } => {
+                let end = if retry { idx } else { idx + 1 };
+                pieces.push(Piece::Invalid {
+                    offset: end - len,
+                    len,
+                    truncated: false,
+                });
+                idx = end;
+            }
+        }
+    }
+    if decoder.pending() > 0 {
+        pieces.push(Piece::Invalid {
+            offset: bytes.len() - decoder.pending(),
+            len: decoder.pending(),
+            truncated: true,
+        });
+    }
+    pieces
+}
+
+/// The error of decoding `bytes`, if they are not UTF-8
+///
+/// This is like to [`str::from_utf8`]. The error is given as the `valid_up_to`
+/// and `error_len` of `Utf8Error`.
+pub fn from_utf8(bytes: &[u8]) -> Result<(), (usize, Option<usize>)> {
+    for piece in decode(bytes) {
+        if let Piece::Invalid {
+            offset,
+            len,
+            truncated,
+        } = piece
+        {
+            return Err((offset, if truncated { None } else { Some(len) }));
+        }
+    }
+    Ok(())
+}
+
+/// Decode `bytes`, replacing each invalid sequence with [`REPLACEMENT`]
+///
+/// This is like to [`String::from_utf8_lossy`]
+pub fn from_utf8_lossy(bytes: &[u8]) -> String {
+    decode(bytes)
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Char(_, c) => c,
+            Piece::Invalid { .. } => REPLACEMENT,
+        })
+        .collect()
+}
+
+/// Encode `point` in `width` bytes, the way UTF-8 would if it allowed it
+///
+/// Nothing is checked: `point` may be a surrogate, or past `U+10FFFF`, and
+/// `width` may be more than `point` needs.
+pub fn encode_raw(point: u32, width: usize) -> Vec<u8> {
+    let cont = |shift: u32| 0x80 | (point >> shift) as u8 & 0x3F;
+    match width {
+        1 => vec![point as u8 & 0x7F],
+        2 => vec![0xC0 | (point >> 6) as u8 & 0x1F, cont(0)],
+        3 => vec![0xE0 | (point >> 12) as u8 & 0x0F, cont(6), cont(0)],
+        _ => vec![0xF0 | (point >> 18) as u8 & 0x07, cont(12), cont(6), cont(0)],
+    }
+}
+
+/// The `Op` defines the ways bytes are appended to the input of
+/// `str::from_utf8` and [`decode`].
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation appends the encoding of a char
+    #[op(tag = 0, weight = 4)]
+    Char {
+        /// The char to append
+        c: Char,
+    },
+    /// This operation appends a byte
+    #[op(tag = 1)]
+    Byte {
+        /// The byte to append
+        b: u8,
+    },
+    /// This operation appends the start of the encoding of a char
+    #[op(tag = 2)]
+    Truncated {
+        /// The char to append part of
+        c: Char,
+        /// Append `1 + len % (w - 1)` of its `w` bytes, all if `w` is 1
+        len: u8,
+    },
+    /// This operation appends an overlong encoding of a char
+    #[op(tag = 3)]
+    Overlong {
+        /// The char to encode, which is appended as is if it takes 4 bytes
+        c: Char,
+        /// Encode it in up to `width % 3` bytes more than it takes
+        width: u8,
+    },
+    /// This operation appends the encoding of a surrogate
+    #[op(tag = 4)]
+    Surrogate {
+        /// Encode `U+D800` plus `n % 0x800`
+        n: u16,
+    },
+    /// This operation appends the encoding of a code point past `U+10FFFF`
+    #[op(tag = 5)]
+    TooLarge {
+        /// Encode `U+110000` plus `n % 0xF0000`, in 4 bytes
+        n: u32,
+    },
+}
+
+impl Op {
+    /// The bytes this op appends
+    pub fn bytes(&self) -> Vec<u8> {
+        match *self {
+            Op::Char { c } => encode_raw(c.0 as u32, c.0.len_utf8()),
+            Op::Byte { b } => vec![b],
+            Op::Truncated { c, len } => {
+                let width = c.0.len_utf8();
+                let mut bytes = encode_raw(c.0 as u32, width);
+                if width > 1 {
+                    bytes.truncate(1 + len as usize % (width - 1));
+                }
+                bytes
+            }
+            Op::Overlong { c, width } => {
+                let least = c.0.len_utf8();
+                let width = (least + 1 + width as usize % 3).min(4);
+                encode_raw(c.0 as u32, width)
+            }
+            Op::Surrogate { n } => encode_raw(0xD800 + u32::from(n) % 0x800, 3),
+            Op::TooLarge { n } => encode_raw(0x11_0000 + n % 0xF_0000, 4),
+        }
+    }
+}
+
+/// Decode the input of the `str_from_utf8` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `str_from_utf8` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against `str::from_utf8` and [`Utf8Decoder`]
+///
+/// Each op appends to a buffer of bytes which is decoded afresh, by both, and
+/// checked, panicking as soon as the two disagree. The run is counted in
+/// [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let mut bytes = Vec::new();
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        bytes.extend(op.bytes());
+        let pieces = decode(&bytes);
+        stats::size(TARGET, bytes.len());
+
+        let model = from_utf8(&bytes);
+        let sut = str::from_utf8(&bytes).map(|_| ()).map_err(|e| (e.valid_up_to(), e.error_len()));
+        check_eq!(sut, model);
+        let sut = String::from_utf8(bytes.clone())
+            .map(|_| ())
+            .map_err(|e| (e.utf8_error().valid_up_to(), e.utf8_error().error_len()));
+        check_eq!(sut, model);
+        if let Err((valid_up_to, _)) = model {
+            check!(str::from_utf8(&bytes[..valid_up_to]).is_ok());
+        }
+        check_eq!(String::from_utf8_lossy(&bytes), from_utf8_lossy(&bytes));
+
+        if let Ok(s) = str::from_utf8(&bytes) {
+            let model: Vec<(usize, char)> = pieces
+                .iter()
+                .map(|piece| match *piece {
+                    Piece::Char(offset, c) => (offset, c),
+                    Piece::Invalid { .. } => unreachable!("the bytes are UTF-8"),
+                })
+                .collect();
+            check_eq!(s.char_indices().collect::<Vec<_>>(), model);
+            let rev: Vec<char> = model.iter().rev().map(|&(_, c)| c).collect();
+            check_eq!(s.chars().rev().collect::<Vec<_>>(), rev);
+            let rev: Vec<(usize, char)> = model.iter().rev().cloned().collect();
+            check_eq!(s.char_indices().rev().collect::<Vec<_>>(), rev);
+            check_eq!(s.chars().count(), model.len());
+            let mut boundaries = vec![false; s.len() + 1];
+            boundaries[s.len()] = true;
+            for &(offset, _) in &model {
+                boundaries[offset] = true;
+            }
+            for (idx, &boundary) in boundaries.iter().enumerate() {
+                check_eq!(s.is_char_boundary(idx), boundary);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lossy(bytes: &[u8]) -> Vec<char> {
+        from_utf8_lossy(bytes).chars().collect()
+    }
+
+    #[test]
+    fn maximal_subparts_are_replaced() {
+        // The example of the Unicode Standard, chapter 3, table 3-8.
+        let bytes = [0x61, 0xF1, 0x80, 0x80, 0xE1, 0x80, 0xC2, 0x62, 0x80, 0x63, 0x80, 0xBF, 0x64];
+        let r = REPLACEMENT;
+        assert_eq!(vec!['a', r, r, r, 'b', r, 'c', r, r, 'd'], lossy(&bytes));
+    }
+
+    #[test]
+    fn second_bytes_are_restricted() {
+        // Overlong, surrogate and past U+10FFFF: each lead byte is a
+        // sequence of one and its continuation bytes are on their own.
+        assert_eq!(Err((0, Some(1))), from_utf8(&[0xE0, 0x80, 0x80]));
+        assert_eq!(Err((0, Some(1))), from_utf8(&[0xED, 0xA0, 0x80]));
+        assert_eq!(Err((0, Some(1))), from_utf8(&[0xF4, 0x90, 0x80, 0x80]));
+        assert_eq!(Err((0, Some(1))), from_utf8(&[0xC0, 0x80]));
+        assert_eq!(Err((1, Some(2))), from_utf8(&[0x61, 0xE2, 0x82, 0x61]));
+        assert_eq!(Err((1, None)), from_utf8(&[0x61, 0xF0, 0x9F, 0x90]));
+    }
+
+    #[test]
+    fn raw_encoding_matches_utf8() {
+        for &c in &['a', '\u{e9}', '\u{2603}', '\u{1f41b}'] {
+            let mut buf = [0; 4];
+            assert_eq!(c.encode_utf8(&mut buf).as_bytes(), &encode_raw(c as u32, c.len_utf8())[..]);
+        }
+    }
+
+    quickcheck! {
+        fn strings_decode_to_their_chars(s: String) -> bool {
+            let model: Vec<(usize, char)> = decode(s.as_bytes())
+                .into_iter()
+                .filter_map(|piece| match piece {
+                    Piece::Char(offset, c) => Some((offset, c)),
+                    Piece::Invalid { .. } => None,
+                })
+                .collect();
+            model == s.char_indices().collect::<Vec<_>>()
+        }
+
+        fn pieces_cover_the_bytes(bytes: Vec<u8>) -> bool {
+            let mut end = 0;
+            for piece in decode(&bytes) {
+                let (offset, len) = match piece {
+                    Piece::Char(offset, c) => (offset, c.len_utf8()),
+                    Piece::Invalid { offset, len, .. } => (offset, len),
+                };
+                if offset != end || len == 0 {
+                    return false;
+                }
+                end = offset + len;
+            }
+            end == bytes.len()
+        }
+
+        fn only_the_last_piece_is_truncated(bytes: Vec<u8>) -> bool {
+            let pieces = decode(&bytes);
+            pieces.iter().rev().skip(1).all(|piece| match *piece {
+                Piece::Invalid { truncated, .. } => !truncated,
+                Piece::Char(..) => true,
+            })
+        }
+    }
+}
@@ -0,0 +1,686 @@
+//! Tests for `std::string::String`
+use arbitrary::*;
+use encode::Encode;
+use op::{self, Profiles};
+use stats;
+use std::fmt;
+use std::ops::Range;
+use std::str::{self, FromStr};
+use trace::{Header, ParseError, Trace};
+
+/// The name of the fuzz target driving `String`
+pub const TARGET: &str = "string";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+lazy_static! {
+    /// The workload profiles of the `string` target, see [`Profiles`]
+    ///
+    /// `grow` mostly pushes and inserts. `shrink` mostly pops, removes and
+    /// drains, working its way through whatever the string has.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[
+        (
+            "grow",
+            &[("push", 4), ("push_str", 4), ("insert", 2), ("insert_str", 2), ("clear", 0)],
+        ),
+        (
+            "shrink",
+            &[("pop", 3), ("remove", 3), ("truncate", 2), ("drain", 2), ("retain", 2), ("split_off", 2)],
+        ),
+    ]);
+}
+
+/// A `String` model
+///
+/// This type mimics the semantics of `String` while being 'obviously correct'
+/// enough to serve as a `QuickCheck` model. A `String` is a UTF-8 encoded
+/// buffer and it's the encoding that makes the real thing tricky. We keep the
+/// chars themselves, decoded, and only encode them when asked.
+///
+/// Indices are byte offsets into the encoding, as they are for `String`, and
+/// every method taking one panics if it does not fall on a char boundary.
+pub struct PropString {
+    data: Vec<char>,
+}
+
+impl Default for PropString {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PropString {
+    /// Construct a new, empty `PropString`
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    /// The index into `data` of the char starting at byte offset `idx`
+    ///
+    /// The byte offset just past the last char maps to `data.len()`. Returns
+    /// `None` if `idx` is not a char boundary.
+    fn char_index(&self, idx: usize) -> Option<usize> {
+        let mut offset = 0;
+        for (i, c) in self.data.iter().enumerate() {
+            if offset == idx {
+                return Some(i);
+            }
+            offset += c.len_utf8();
+        }
+        if offset == idx {
+            Some(self.data.len())
+        } else {
+            None
+        }
+    }
+
+    /// Like `char_index` but panicking, as `String` does, off a boundary
+    fn expect_char_index(&self, idx: usize) -> usize {
+        self.char_index(idx)
+            .unwrap_or_else(|| panic!("byte index {} is not a char boundary", idx))
+    }
+
+    /// Like `expect_char_index` over both ends of a range
+    fn expect_char_range(&self, range: Range<usize>) -> Range<usize> {
+        assert!(range.start <= range.end, "range start is past its end");
+        self.expect_char_index(range.start)..self.expect_char_index(range.end)
+    }
+
+    /// Determine if byte offset `idx` falls on a char boundary
+    ///
+    /// This is like to [`str::is_char_boundary`]
+    pub fn is_char_boundary(&self, idx: usize) -> bool {
+        self.char_index(idx).is_some()
+    }
+
+    /// Return the length of the `PropString` in bytes
+    ///
+    /// This is like to [`String::len`]
+    pub fn len(&self) -> usize {
+        self.data.iter().map(|c| c.len_utf8()).sum()
+    }
+
+    /// Determine if the `PropString` is empty
+    ///
+    /// This is like to [`String::is_empty`]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The UTF-8 encoding of the `PropString`
+    ///
+    /// This is like to [`String::as_bytes`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for c in &self.data {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        }
+        bytes
+    }
+
+    /// Append a char to the end
+    ///
+    /// This is like to [`String::push`]
+    pub fn push(&mut self, c: char) {
+        self.data.push(c)
+    }
+
+    /// Append a string slice to the end
+    ///
+    /// This is like to [`String::push_str`]
+    pub fn push_str(&mut self, s: &str) {
+        self.data.extend(s.chars())
+    }
+
+    /// Insert a char at byte offset `idx`
+    ///
+    /// This is like to [`String::insert`]
+    pub fn insert(&mut self, idx: usize, c: char) {
+        let i = self.expect_char_index(idx);
+        self.data.insert(i, c)
+    }
+
+    /// Insert a string slice at byte offset `idx`
+    ///
+    /// This is like to [`String::insert_str`]
+    pub fn insert_str(&mut self, idx: usize, s: &str) {
+        let i = self.expect_char_index(idx);
+        let tail = self.data.split_off(i);
+        self.data.extend(s.chars());
+        self.data.extend(tail);
+    }
+
+    /// Remove the last char, if one exists
+    ///
+    /// This is like to [`String::pop`]
+    pub fn pop(&mut self) -> Option<char> {
+        self.data.pop()
+    }
+
+    /// Remove the char at byte offset `idx`
+    ///
+    /// This is like to [`String::remove`]
+    pub fn remove(&mut self, idx: usize) -> char {
+        let i = self.expect_char_index(idx);
+        assert!(i < self.data.len(), "cannot remove a char from the end");
+        self.data.remove(i)
+    }
+
+    /// Shorten to `new_len` bytes, doing nothing if already shorter
+    ///
+    /// This is like to [`String::truncate`]
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len <= self.len() {
+            let i = self.expect_char_index(new_len);
+            self.data.truncate(i)
+        }
+    }
+
+    /// Keep only the chars for which `f` returns true
+    ///
+    /// This is like to [`String::retain`]
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&char) -> bool,
+    {
+        self.data.retain(f)
+    }
+
+    /// Remove the chars in the byte range `range`, returning them
+    ///
+    /// This is like to [`String::drain`]
+    pub fn drain(&mut self, range: Range<usize>) -> String {
+        let range = self.expect_char_range(range);
+        self.data.drain(range).collect()
+    }
+
+    /// Split in two at byte offset `at`, returning everything after it
+    ///
+    /// This is like to [`String::split_off`]
+    pub fn split_off(&mut self, at: usize) -> PropString {
+        let i = self.expect_char_index(at);
+        PropString {
+            data: self.data.split_off(i),
+        }
+    }
+
+    /// Replace the chars in the byte range `range` with `s`
+    ///
+    /// This is like to [`String::replace_range`]
+    pub fn replace_range(&mut self, range: Range<usize>, s: &str) {
+        let range = self.expect_char_range(range);
+        let tail = self.data.split_off(range.end);
+        self.data.truncate(range.start);
+        self.data.extend(s.chars());
+        self.data.extend(tail);
+    }
+
+    /// Clear all contents of `PropString`
+    ///
+    /// This is like to [`String::clear`]
+    pub fn clear(&mut self) {
+        self.data.clear()
+    }
+}
+
+/// A char decoded from fuzz input
+///
+/// Drawing a `u32` and keeping the valid chars would give us four byte chars
+/// nearly every time. Instead the low two bits of the `u32` choose the length
+/// of the char's encoding and the rest choose a char of that length. In traces
+/// a `Char` is written as its code point in hex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Char(pub char);
+
+/// The code points encoded in 1, 2, 3 and 4 bytes
+const WIDTHS: [Range<u32>; 4] = [0..0x80, 0x80..0x800, 0x800..0x1_0000, 0x1_0000..0x11_0000];
+
+/// The surrogates, which are not chars
+const SURROGATES: Range<u32> = 0xD800..0xE000;
+
+impl Arbitrary for Char {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let x: u32 = Arbitrary::arbitrary(u)?;
+        let width = &WIDTHS[(x & 0b11) as usize];
+        let mut point = width.start + (x >> 2) % (width.end - width.start);
+        if SURROGATES.contains(&point) {
+            // Moving past the surrogates stays within the three byte chars.
+            point += SURROGATES.end - SURROGATES.start;
+        }
+        Ok(Char(
+            ::std::char::from_u32(point).expect("surrogates were skipped"),
+        ))
+    }
+}
+
+impl Encode for Char {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let point = self.0 as u32;
+        let width = self.0.len_utf8() - 1;
+        ((point - WIDTHS[width].start) << 2 | width as u32).encode(buf)
+    }
+}
+
+impl fmt::Display for Char {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:x}", self.0 as u32)
+    }
+}
+
+impl FromStr for Char {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u32::from_str_radix(s, 16)
+            .ok()
+            .and_then(::std::char::from_u32)
+            .map(Char)
+            .ok_or_else(|| ParseError::new(format!("'{}' is not a code point in hex", s)))
+    }
+}
+
+/// A short string decoded from fuzz input
+///
+/// A `u8` length is followed by that many [`Char`]. In traces a `Text` is
+/// written as the hex code points of its chars separated by commas.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Text(pub String);
+
+impl Arbitrary for Text {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let len: u8 = Arbitrary::arbitrary(u)?;
+        let mut s = String::new();
+        for _ in 0..len {
+            let c: Char = Arbitrary::arbitrary(u)?;
+            s.push(c.0);
+        }
+        Ok(Text(s))
+    }
+}
+
+impl Encode for Text {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        // Decoding reads at most `u8::MAX` chars, so we write no more.
+        let len = self.0.chars().count().min(usize::from(u8::MAX));
+        (len as u8).encode(buf);
+        for c in self.0.chars().take(len) {
+            Char(c).encode(buf);
+        }
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, c) in self.0.chars().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", Char(c))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Text {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Ok(Text(String::new()));
+        }
+        let mut text = String::new();
+        for point in s.split(',') {
+            text.push(point.parse::<Char>()?.0);
+        }
+        Ok(Text(text))
+    }
+}
+
+/// The `Op` defines the set of operations that are available against `String`
+/// and [`PropString`].
+///
+/// Byte offsets are `u16` and taken modulo one more than the length of the
+/// string, so that most fall within it. Whether they fall on a char boundary
+/// is up to the fuzzer.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `String::push`
+    #[op(tag = 0)]
+    Push {
+        /// The char to push
+        c: Char,
+    },
+    /// This operation triggers `String::push_str`
+    #[op(tag = 1)]
+    PushStr {
+        /// The string to push
+        s: Text,
+    },
+    /// This operation triggers `String::insert`
+    #[op(tag = 2)]
+    Insert {
+        /// The byte offset to insert at
+        idx: u16,
+        /// The char to insert
+        c: Char,
+    },
+    /// This operation triggers `String::insert_str`
+    #[op(tag = 3)]
+    InsertStr {
+        /// The byte offset to insert at
+        idx: u16,
+        /// The string to insert
+        s: Text,
+    },
+    /// This operation triggers `String::pop`
+    #[op(tag = 4)]
+    Pop,
+    /// This operation triggers `String::remove`
+    #[op(tag = 5)]
+    Remove {
+        /// The byte offset of the char to remove
+        idx: u16,
+    },
+    /// This operation triggers `String::truncate`
+    #[op(tag = 6)]
+    Truncate {
+        /// The new length, in bytes
+        len: u16,
+    },
+    /// This operation triggers `String::retain`
+    #[op(tag = 7)]
+    Retain {
+        /// Remove the chars encoded in `width % 4 + 1` bytes
+        width: u8,
+    },
+    /// This operation triggers `String::drain`
+    #[op(tag = 8)]
+    Drain {
+        /// One end of the byte range to drain
+        start: u16,
+        /// The other end of the byte range to drain
+        end: u16,
+    },
+    /// This operation triggers `String::split_off`
+    #[op(tag = 9)]
+    SplitOff {
+        /// The byte offset to split at
+        at: u16,
+    },
+    /// This operation triggers `String::replace_range`
+    #[op(tag = 10)]
+    ReplaceRange {
+        /// One end of the byte range to replace
+        start: u16,
+        /// The other end of the byte range to replace
+        end: u16,
+        /// The replacement
+        s: Text,
+    },
+    /// This operation triggers `String::reserve`
+    #[op(tag = 11)]
+    Reserve {
+        /// Reserve `n` more bytes
+        n: u16,
+    },
+    /// This operation triggers `String::shrink_to_fit`
+    #[op(tag = 12)]
+    ShrinkToFit,
+    /// This operation triggers `String::clear`
+    #[op(tag = 13)]
+    Clear,
+}
+
+/// Decode the input of the `string` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version, a profile byte and an initial
+/// capacity before reading ops until the input is exhausted. The profile byte
+/// chooses from [`PROFILES`] how ops are decoded. Returns `None` if the input
+/// is of some other version or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |ring| {
+        let capacity: u8 = Arbitrary::arbitrary(ring).ok()?;
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["char".to_string()],
+            capacity: capacity as usize,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `string` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile. The capacity is truncated to a `u8`.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |header, buf| (header.capacity as u8).encode(buf))
+}
+
+/// Scale a fuzz-chosen byte offset into `0..=len`
+fn scale(idx: u16, len: usize) -> usize {
+    idx as usize % (len + 1)
+}
+
+/// Check that `idx` is a char boundary of both model and SUT, or of neither
+///
+/// Returns whether it is one. Ops given an offset off a boundary would panic,
+/// so are skipped.
+fn boundary(model: &PropString, sut: &str, idx: usize) -> bool {
+    let is_boundary = model.is_char_boundary(idx);
+    check_eq!(is_boundary, sut.is_char_boundary(idx));
+    is_boundary
+}
+
+/// Replay a [`Trace`] against `String` and [`PropString`]
+///
+/// Every op is applied to both the model and the system under test, panicking
+/// as soon as the two disagree. The run is counted in [`stats`], if collection
+/// is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let mut model = PropString::new();
+    let mut sut = String::with_capacity(trace.header.capacity);
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        let cap_before = sut.capacity();
+        match *op {
+            Op::Push { c } => {
+                sut.push(c.0);
+                model.push(c.0);
+            }
+            Op::PushStr { ref s } => {
+                sut.push_str(&s.0);
+                model.push_str(&s.0);
+            }
+            Op::Insert { idx, c } => {
+                let idx = scale(idx, model.len());
+                if boundary(&model, &sut, idx) {
+                    sut.insert(idx, c.0);
+                    model.insert(idx, c.0);
+                }
+            }
+            Op::InsertStr { idx, ref s } => {
+                let idx = scale(idx, model.len());
+                if boundary(&model, &sut, idx) {
+                    sut.insert_str(idx, &s.0);
+                    model.insert_str(idx, &s.0);
+                }
+            }
+            Op::Pop => {
+                let sut_res = sut.pop();
+                let model_res = model.pop();
+                check_eq!(sut_res, model_res);
+            }
+            Op::Remove { idx } => {
+                // There is no char to remove at the end of the string.
+                if !model.is_empty() {
+                    let idx = idx as usize % model.len();
+                    if boundary(&model, &sut, idx) {
+                        let sut_res = sut.remove(idx);
+                        let model_res = model.remove(idx);
+                        check_eq!(sut_res, model_res);
+                    }
+                }
+            }
+            Op::Truncate { len } => {
+                // Truncating past the end does nothing, so we let the new
+                // length run a little past it.
+                let len = len as usize % (model.len() + 2);
+                if len > model.len() || boundary(&model, &sut, len) {
+                    sut.truncate(len);
+                    model.truncate(len);
+                }
+            }
+            Op::Retain { width } => {
+                let width = width as usize % 4 + 1;
+                sut.retain(|c| c.len_utf8() != width);
+                model.retain(|c| c.len_utf8() != width);
+            }
+            Op::Drain { start, end } => {
+                let (a, b) = (scale(start, model.len()), scale(end, model.len()));
+                let range = a.min(b)..a.max(b);
+                if boundary(&model, &sut, range.start) && boundary(&model, &sut, range.end) {
+                    let sut_res: String = sut.drain(range.clone()).collect();
+                    let model_res = model.drain(range);
+                    check_eq!(sut_res, model_res);
+                }
+            }
+            Op::SplitOff { at } => {
+                let at = scale(at, model.len());
+                if boundary(&model, &sut, at) {
+                    let sut_res = sut.split_off(at);
+                    let model_res = model.split_off(at);
+                    check_eq!(sut_res.as_bytes(), &model_res.to_bytes()[..]);
+                }
+            }
+            Op::ReplaceRange { start, end, ref s } => {
+                let (a, b) = (scale(start, model.len()), scale(end, model.len()));
+                let range = a.min(b)..a.max(b);
+                if boundary(&model, &sut, range.start) && boundary(&model, &sut, range.end) {
+                    sut.replace_range(range.clone(), &s.0);
+                    model.replace_range(range, &s.0);
+                }
+            }
+            Op::Reserve { n } => {
+                // NOTE There is no model behaviour here
+                let prev_cap = sut.capacity();
+                sut.reserve(n as usize);
+                check!(sut.capacity() >= sut.len() + n as usize);
+                check!(sut.capacity() >= prev_cap);
+            }
+            Op::ShrinkToFit => {
+                // NOTE There is no model behaviour here
+                let prev_cap = sut.capacity();
+                sut.shrink_to_fit();
+                check!(sut.capacity() <= prev_cap);
+            }
+            Op::Clear => {
+                // Clearing a String removes all bytes but keeps the memory
+                // around for reuse.
+                let prev_cap = sut.capacity();
+                sut.clear();
+                model.clear();
+                check_eq!(prev_cap, sut.capacity());
+            }
+        }
+        stats::size(TARGET, model.len());
+        stats::capacity(TARGET, cap_before, sut.capacity());
+        // Check invariants
+        //
+        // The bytes of the SUT must always be valid UTF-8, else every `&str`
+        // taken of it is undefined behaviour waiting to happen.
+        check!(str::from_utf8(sut.as_bytes()).is_ok());
+        // The bytes of the SUT must always be exactly the encoding of the
+        // model's chars.
+        check_eq!(sut.as_bytes(), &model.to_bytes()[..]);
+        // The capacity of the SUT must always be at least its length.
+        check!(sut.capacity() >= sut.len());
+        check_eq!(sut.len(), model.len());
+        check_eq!(sut.is_empty(), model.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn from_str(s: &str) -> PropString {
+        let mut model = PropString::new();
+        model.push_str(s);
+        model
+    }
+
+    #[test]
+    fn boundaries_are_byte_offsets() {
+        let model = from_str("aé€😀");
+        let boundaries: Vec<usize> = (0..=model.len())
+            .filter(|&i| model.is_char_boundary(i))
+            .collect();
+        assert_eq!(vec![0, 1, 3, 6, 10], boundaries);
+        assert_eq!(10, model.len());
+    }
+
+    #[test]
+    fn edits_work_on_byte_offsets() {
+        let mut model = from_str("aé€");
+        model.insert(1, 'x');
+        assert_eq!("axé€".as_bytes(), &model.to_bytes()[..]);
+        assert_eq!('é', model.remove(2));
+        model.replace_range(1..2, "yz");
+        assert_eq!("ayz€".as_bytes(), &model.to_bytes()[..]);
+        assert_eq!("z€", model.drain(2..6));
+        assert_eq!(b"ay", &model.to_bytes()[..]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_off_boundary_panics() {
+        from_str("é").insert(1, 'x');
+    }
+
+    quickcheck! {
+        fn encoding_matches_string(s: String) -> bool {
+            from_str(&s).to_bytes() == s.as_bytes()
+        }
+
+        fn boundaries_match_str(s: String) -> bool {
+            let model = from_str(&s);
+            (0..s.len() + 2).all(|i| model.is_char_boundary(i) == s.is_char_boundary(i))
+        }
+
+        fn split_off_then_push_str_restores(s: String, at: usize) -> bool {
+            let mut model = from_str(&s);
+            let at = at % (s.len() + 1);
+            if !s.is_char_boundary(at) {
+                return true;
+            }
+            let tail = model.split_off(at);
+            let tail = String::from_utf8(tail.to_bytes()).expect("model encodes UTF-8");
+            model.push_str(&tail);
+            model.to_bytes() == s.as_bytes()
+        }
+
+        fn char_round_trips_through_encoding(x: u32) -> bool {
+            let mut buf = x.to_le_bytes().to_vec();
+            buf.extend_from_slice(&[0; 4]);
+            let mut ring = FiniteBuffer::new(&buf, 8).expect("buffer is not empty");
+            let c: Char = Arbitrary::arbitrary(&mut ring).expect("four bytes are enough");
+            let mut enc = Vec::new();
+            c.encode(&mut enc);
+            let mut ring = FiniteBuffer::new(&enc, 4).expect("buffer is not empty");
+            let d: Char = Arbitrary::arbitrary(&mut ring).expect("four bytes are enough");
+            c == d && d.to_string().parse::<Char>().ok() == Some(c)
+        }
+    }
+}
@@ -0,0 +1,884 @@
+//! Tests for `std::sync::Arc` and `std::sync::Weak`
+//!
+//! This is the `rc` target taken across threads. Each of [`THREADS`] threads
+//! keeps its own pool of `Arc` and `Weak` handles, and ops name the thread
+//! that runs them, cloning, dropping, downgrading and upgrading its handles,
+//! reaching through them with `make_mut` and giving them to other threads.
+//! In a round the threads run their ops a window at a time, see
+//! [`Turns`], so their effects on shared allocations cross from thread to
+//! thread.
+//!
+//! The model, a [`PropArc`], isn't told the order, so it is given what each
+//! thread saw -- which ids its values took, whether its upgrades succeeded
+//! and what `make_mut` did -- and checks that this could have happened in
+//! any order, given what the other threads held. Once the threads are joined
+//! the counts of every handle, its value, and the values dropped in the round
+//! must be the model's exactly.
+use op::{self, Profiles};
+use stats;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use stdlib::rc::{Kind, PropHandle, PropValue};
+use stdlib::sync::{by_thread, run_on_threads, Round, Turns, THREADS};
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `Arc` and `Weak`
+pub const TARGET: &str = "sync_arc";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most handles a thread holds at once; ops that would hold more do
+/// nothing
+pub const MAX_HANDLES: usize = 16;
+
+lazy_static! {
+    /// The workload profiles of the `sync_arc` target, see [`Profiles`]
+    ///
+    /// `contended` gives handles away often and syncs seldom, so threads
+    /// share allocations and race to upgrade, drop and `make_mut` them.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "contended",
+        &[("give", 4), ("upgrade", 3), ("make_mut", 3), ("drop", 3), ("sync", 1)],
+    )]);
+}
+
+/// The log of the [`Tracked`] values made and dropped, by any thread
+#[derive(Debug, Default)]
+pub struct Log {
+    made: AtomicUsize,
+    dropped: Mutex<Vec<usize>>,
+}
+
+impl Log {
+    /// Construct a new, empty `Log`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of values made
+    pub fn made(&self) -> usize {
+        self.made.load(Ordering::SeqCst)
+    }
+
+    /// The ids of the values dropped, in the order they were
+    pub fn dropped(&self) -> Vec<usize> {
+        self.dropped
+            .lock()
+            .expect("no thread panics holding the log")
+            .clone()
+    }
+}
+
+/// A value that logs when it is dropped
+///
+/// Each value made, by [`Tracked::new`] or by cloning, takes the next id
+/// of its log, in the order they are made.
+#[derive(Debug)]
+pub struct Tracked<'a> {
+    /// The byte the value holds
+    pub byte: u8,
+    id: usize,
+    log: &'a Log,
+}
+
+impl<'a> Tracked<'a> {
+    /// Construct a new `Tracked` holding `byte`, logged in `log`
+    pub fn new(byte: u8, log: &'a Log) -> Self {
+        let id = log.made.fetch_add(1, Ordering::SeqCst);
+        Tracked { byte, id, log }
+    }
+
+    /// The id of the value
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl<'a> Clone for Tracked<'a> {
+    fn clone(&self) -> Self {
+        Tracked::new(self.byte, self.log)
+    }
+}
+
+impl<'a> Drop for Tracked<'a> {
+    fn drop(&mut self) {
+        if let Ok(mut dropped) = self.log.dropped.lock() {
+            dropped.push(self.id);
+        }
+    }
+}
+
+/// What a thread saw running an op
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The op did nothing
+    Skipped,
+    /// The op did what it always does
+    Done,
+    /// `Arc::new` made the value of this id
+    Made(usize),
+    /// `Weak::upgrade` gave an `Arc`, or didn't
+    Upgraded(bool),
+    /// `Arc::make_mut` wrote to the allocation in place
+    InPlace,
+    /// `Arc::make_mut` moved the value to a new allocation
+    Moved,
+    /// `Arc::make_mut` cloned the value, as this id, into a new allocation
+    Cloned(usize),
+}
+
+/// What the model learns of a round as it goes through the threads' ops
+#[derive(Debug, Default)]
+struct RoundState {
+    /// For each allocation, the threads holding handles to it at the start,
+    /// as a mask
+    holders: Vec<usize>,
+    /// For each allocation, whether it had lost its value at the start
+    dead: Vec<bool>,
+    /// The handles given, with the threads giving and taking them
+    given: Vec<(usize, usize, PropHandle)>,
+    /// The allocations a thread wrote to in place
+    exclusive: Vec<(usize, usize)>,
+    /// The allocations seen without an `Arc`
+    emptied: Vec<usize>,
+    /// The allocations whose value was moved out
+    moved: HashSet<usize>,
+}
+
+impl RoundState {
+    /// Whether a thread but `thread` held a handle to `alloc` at the start
+    fn shared(&self, alloc: usize, thread: usize) -> bool {
+        self.holders
+            .get(alloc)
+            .map_or(0, |&mask| mask & !(1 << thread))
+            != 0
+    }
+}
+
+/// A model of the pools of `Arc<Tracked>` and `Weak<Tracked>` handles of
+/// [`THREADS`] threads
+///
+/// Allocations are never forgotten, only left without a value when their
+/// last `Arc` goes or their value is moved out. Values are only dropped when
+/// a round ends, as the model can't tell when in the round their last `Arc`
+/// went.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropArc {
+    pools: Vec<Vec<PropHandle>>,
+    allocs: Vec<Option<PropValue>>,
+    ids: HashSet<usize>,
+    dropped: Vec<usize>,
+}
+
+impl Default for PropArc {
+    fn default() -> Self {
+        PropArc {
+            pools: vec![Vec::new(); THREADS],
+            allocs: Vec::new(),
+            ids: HashSet::new(),
+            dropped: Vec::new(),
+        }
+    }
+}
+
+impl PropArc {
+    /// Construct a new `PropArc` with no handles
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The handles held by `thread`
+    pub fn pool(&self, thread: usize) -> &[PropHandle] {
+        &self.pools[thread]
+    }
+
+    /// The ids of the values dropped, round by round
+    pub fn dropped(&self) -> &[usize] {
+        &self.dropped
+    }
+
+    /// The value of `alloc`, if it has one yet
+    pub fn value(&self, alloc: usize) -> Option<PropValue> {
+        self.allocs[alloc]
+    }
+
+    /// The number of `Arc` and `Weak` handles to each allocation, among the
+    /// pools of all threads
+    ///
+    /// This is like to [`Arc::strong_count`] and [`Arc::weak_count`]
+    pub fn counts(&self) -> HashMap<usize, (usize, usize)> {
+        let mut counts = HashMap::new();
+        for handle in self.pools.iter().flat_map(|pool| pool.iter()) {
+            let count = counts.entry(handle.alloc).or_insert((0, 0));
+            match handle.kind {
+                Kind::Strong => count.0 += 1,
+                Kind::Weak => count.1 += 1,
+            }
+        }
+        counts
+    }
+
+    /// The number of `Arc` and `Weak` handles `thread` holds to `alloc`, or
+    /// gave away in `round`, which are kept until the round ends
+    fn held(&self, round: &RoundState, thread: usize, alloc: usize) -> (usize, usize) {
+        let given = round.given.iter().filter(|g| g.0 == thread).map(|g| &g.2);
+        let pool = self.pools[thread].iter().chain(given);
+        pool.filter(|h| h.alloc == alloc)
+            .fold((0, 0), |(strong, weak), h| match h.kind {
+                Kind::Strong => (strong + 1, weak),
+                Kind::Weak => (strong, weak + 1),
+            })
+    }
+
+    /// Take `id` as the id of a value, checking no value had it before
+    fn take_id(&mut self, id: usize) {
+        check!(self.ids.insert(id));
+    }
+
+    fn alloc(&mut self, value: PropValue) -> usize {
+        self.allocs.push(Some(value));
+        self.allocs.len() - 1
+    }
+
+    /// Run `script`, the ops of each thread in a round, given the outcomes
+    /// each thread saw, panicking if they could not have been
+    ///
+    /// Once all ops are run the handles given are taken, in the order of the
+    /// threads giving them, and the values of allocations left without an
+    /// `Arc` are dropped.
+    pub fn round(&mut self, script: &[Vec<&Op>], outcomes: &[Vec<Outcome>]) {
+        let mut round = RoundState {
+            holders: vec![0; self.allocs.len()],
+            dead: self.allocs.iter().map(Option::is_none).collect(),
+            ..RoundState::default()
+        };
+        for (thread, pool) in self.pools.iter().enumerate() {
+            for handle in pool {
+                round.holders[handle.alloc] |= 1 << thread;
+            }
+        }
+        for (thread, (ops, outcomes)) in script.iter().zip(outcomes).enumerate() {
+            check_eq!(ops.len(), outcomes.len());
+            for (op, &outcome) in ops.iter().zip(outcomes) {
+                self.step(&mut round, thread, op, outcome);
+            }
+        }
+        for &(alloc, thread) in &round.exclusive {
+            for (other, pool) in self.pools.iter().enumerate() {
+                check!(other == thread || pool.iter().all(|h| h.alloc != alloc));
+            }
+            for &(from, _, handle) in &round.given {
+                check!(from == thread || handle.alloc != alloc);
+            }
+        }
+        for (_, to, handle) in mem::take(&mut round.given) {
+            self.pools[to].push(handle);
+        }
+        let counts = self.counts();
+        for &alloc in &round.emptied {
+            check!(counts.get(&alloc).map_or(0, |count| count.0) == 0);
+        }
+        for (alloc, value) in self.allocs.iter_mut().enumerate() {
+            if counts.get(&alloc).map_or(0, |count| count.0) == 0 {
+                if let Some(value) = value.take() {
+                    if !round.moved.contains(&alloc) {
+                        self.dropped.push(value.id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run `op` for `thread`, given it saw `outcome`
+    fn step(&mut self, round: &mut RoundState, thread: usize, op: &Op, outcome: Outcome) {
+        let held = self.pools[thread].len();
+        let full = held >= MAX_HANDLES;
+        let pick = |idx: u8| {
+            if held == 0 {
+                None
+            } else {
+                Some(idx as usize % held)
+            }
+        };
+        let expected = match *op {
+            Op::New { .. } | Op::Clone { .. } | Op::Downgrade { .. } | Op::Upgrade { .. }
+                if full =>
+            {
+                None
+            }
+            Op::New { byte, .. } => {
+                let id = match outcome {
+                    Outcome::Made(id) => id,
+                    _ => panic!("Arc::new saw {:?}", outcome),
+                };
+                self.take_id(id);
+                let alloc = self.alloc(PropValue { byte, id });
+                self.pools[thread].push(PropHandle {
+                    kind: Kind::Strong,
+                    alloc,
+                });
+                Some(outcome)
+            }
+            Op::Sync => None,
+            Op::Clone { idx, .. } => pick(idx).map(|idx| {
+                let handle = self.pools[thread][idx];
+                self.pools[thread].push(handle);
+                Outcome::Done
+            }),
+            Op::Drop { idx, .. } => pick(idx).map(|idx| {
+                self.pools[thread].remove(idx);
+                Outcome::Done
+            }),
+            Op::Give { idx, to, .. } => pick(idx).map(|idx| {
+                let handle = self.pools[thread].remove(idx);
+                round.given.push((thread, to as usize % THREADS, handle));
+                Outcome::Done
+            }),
+            Op::Downgrade { idx, .. } => match pick(idx).map(|idx| self.pools[thread][idx]) {
+                Some(PropHandle {
+                    kind: Kind::Strong,
+                    alloc,
+                }) => {
+                    self.pools[thread].push(PropHandle {
+                        kind: Kind::Weak,
+                        alloc,
+                    });
+                    Some(Outcome::Done)
+                }
+                _ => None,
+            },
+            Op::Upgrade { idx, .. } => match pick(idx).map(|idx| self.pools[thread][idx]) {
+                Some(PropHandle {
+                    kind: Kind::Weak,
+                    alloc,
+                }) => {
+                    let live = outcome == Outcome::Upgraded(true);
+                    self.upgrade(round, thread, alloc, live);
+                    Some(Outcome::Upgraded(live))
+                }
+                _ => None,
+            },
+            Op::MakeMut { idx, byte, .. } => match pick(idx) {
+                Some(idx) if self.pools[thread][idx].kind == Kind::Strong => {
+                    self.make_mut(round, thread, idx, byte, outcome);
+                    Some(outcome)
+                }
+                _ => None,
+            },
+        };
+        check_eq!(outcome, expected.unwrap_or(Outcome::Skipped));
+    }
+
+    /// Hold an `Arc` to `alloc` for `thread` if it was `live` when upgraded
+    ///
+    /// This is like to [`Weak::upgrade`]. The upgrade must have succeeded if
+    /// the thread holds an `Arc` to the allocation, and failed if it had
+    /// lost its value before the round. If no other thread held a handle to
+    /// it when the round started no other thread could have changed the
+    /// outcome of the upgrade.
+    /// Handles the thread gave away count as held, as they are until the
+    /// round ends.
+    fn upgrade(&mut self, round: &mut RoundState, thread: usize, alloc: usize, live: bool) {
+        let (strong, _) = self.held(round, thread, alloc);
+        check!(live || strong == 0);
+        check!(!live || round.dead.get(alloc) != Some(&true));
+        if !round.shared(alloc, thread) {
+            check_eq!(live, strong > 0);
+        }
+        if live {
+            self.pools[thread].push(PropHandle {
+                kind: Kind::Strong,
+                alloc,
+            });
+        } else {
+            round.emptied.push(alloc);
+        }
+    }
+
+    /// Write `byte` to the value of the `Arc` of `thread` at `idx`, first
+    /// making the value the handle's alone as `outcome` says it was
+    ///
+    /// This is like to [`Arc::make_mut`]. The value must have been cloned if
+    /// the thread holds another `Arc` to the allocation, and can't have been
+    /// written in place if the thread holds a `Weak` to it. If no other
+    /// thread held a handle to it when the round started the outcome is
+    /// that of `Rc::make_mut`.
+    fn make_mut(
+        &mut self,
+        round: &mut RoundState,
+        thread: usize,
+        idx: usize,
+        byte: u8,
+        outcome: Outcome,
+    ) {
+        let alloc = self.pools[thread][idx].alloc;
+        let value = self.allocs[alloc].expect("an Arc has a value");
+        let (strong, weak) = self.held(round, thread, alloc);
+        let alone = match (strong, weak) {
+            (1, 0) => Outcome::InPlace,
+            (1, _) => Outcome::Moved,
+            _ => Outcome::Cloned(0),
+        };
+        let seen = match outcome {
+            Outcome::Cloned(_) => Outcome::Cloned(0),
+            _ => outcome,
+        };
+        check!(strong == 1 || seen == Outcome::Cloned(0));
+        check!(weak == 0 || seen != Outcome::InPlace);
+        if !round.shared(alloc, thread) {
+            check_eq!(seen, alone);
+        }
+        let fresh = match outcome {
+            Outcome::InPlace => {
+                round.exclusive.push((alloc, thread));
+                alloc
+            }
+            Outcome::Moved => {
+                round.emptied.push(alloc);
+                round.moved.insert(alloc);
+                self.alloc(value)
+            }
+            Outcome::Cloned(id) => {
+                self.take_id(id);
+                self.alloc(PropValue {
+                    byte: value.byte,
+                    id,
+                })
+            }
+            _ => panic!("Arc::make_mut saw {:?}", outcome),
+        };
+        self.pools[thread][idx].alloc = fresh;
+        if let Some(ref mut value) = self.allocs[fresh] {
+            value.byte = byte;
+        }
+    }
+}
+
+/// A handle of a pool of the `sync_arc` target
+pub enum Handle<'a> {
+    /// An `Arc`
+    Strong(Arc<Tracked<'a>>),
+    /// A `Weak`
+    Weak(Weak<Tracked<'a>>),
+}
+
+impl<'a> Handle<'a> {
+    /// The kind of the handle
+    pub fn kind(&self) -> Kind {
+        match *self {
+            Handle::Strong(_) => Kind::Strong,
+            Handle::Weak(_) => Kind::Weak,
+        }
+    }
+}
+
+/// The `Op` defines the ways the pools of handles of the `sync_arc` target
+/// are changed.
+///
+/// Ops but `Sync` are run by the thread `thread` modulo [`THREADS`], in the
+/// round that the next `Sync` ends. Handles are picked by `idx` modulo the
+/// number the thread holds, and ops on a handle do nothing while it holds
+/// none. Ops that don't apply to the handle picked -- upgrading an `Arc`,
+/// say -- do nothing either.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `Arc::new`
+    #[op(tag = 0, weight = 2)]
+    New {
+        /// The thread to run the op
+        thread: u8,
+        /// The byte of the new value
+        byte: u8,
+    },
+    /// This operation triggers `Arc::clone` or `Weak::clone`
+    #[op(tag = 1, weight = 3)]
+    Clone {
+        /// The thread to run the op
+        thread: u8,
+        /// The handle to clone
+        idx: u8,
+    },
+    /// This operation drops a handle
+    #[op(tag = 2, weight = 3)]
+    Drop {
+        /// The thread to run the op
+        thread: u8,
+        /// The handle to drop
+        idx: u8,
+    },
+    /// This operation triggers `Arc::downgrade`
+    #[op(tag = 3, weight = 2)]
+    Downgrade {
+        /// The thread to run the op
+        thread: u8,
+        /// The `Arc` to downgrade
+        idx: u8,
+    },
+    /// This operation triggers `Weak::upgrade`
+    #[op(tag = 4, weight = 2)]
+    Upgrade {
+        /// The thread to run the op
+        thread: u8,
+        /// The `Weak` to upgrade
+        idx: u8,
+    },
+    /// This operation triggers `Arc::make_mut`
+    #[op(tag = 5, weight = 2)]
+    MakeMut {
+        /// The thread to run the op
+        thread: u8,
+        /// The `Arc` to write through
+        idx: u8,
+        /// The byte to write
+        byte: u8,
+    },
+    /// This operation sends a handle to another thread, which takes it
+    /// when the round ends
+    #[op(tag = 6, weight = 2)]
+    Give {
+        /// The thread to run the op
+        thread: u8,
+        /// The handle to give
+        idx: u8,
+        /// The thread to take the handle
+        to: u8,
+    },
+    /// This operation ends a round, joining the threads
+    #[op(tag = 7)]
+    Sync,
+}
+
+impl Op {
+    /// The thread to run the op, or `None` for `Sync`
+    pub fn thread(&self) -> Option<usize> {
+        match *self {
+            Op::New { thread, .. }
+            | Op::Clone { thread, .. }
+            | Op::Drop { thread, .. }
+            | Op::Downgrade { thread, .. }
+            | Op::Upgrade { thread, .. }
+            | Op::MakeMut { thread, .. }
+            | Op::Give { thread, .. } => Some(thread as usize % THREADS),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// Run `op` against `pool`, whose values are logged in `log`, putting the
+/// handles given away in `given` and saying what was seen
+fn run_op<'a>(
+    op: &Op,
+    log: &'a Log,
+    pool: &mut Vec<Handle<'a>>,
+    given: &mut Vec<(usize, Handle<'a>)>,
+) -> Outcome {
+    let held = pool.len();
+    match *op {
+        Op::New { .. } | Op::Clone { .. } | Op::Downgrade { .. } | Op::Upgrade { .. }
+            if held >= MAX_HANDLES =>
+        {
+            Outcome::Skipped
+        }
+        Op::New { byte, .. } => {
+            let value = Tracked::new(byte, log);
+            let id = value.id();
+            pool.push(Handle::Strong(Arc::new(value)));
+            Outcome::Made(id)
+        }
+        Op::Sync => Outcome::Skipped,
+        _ if held == 0 => Outcome::Skipped,
+        Op::Clone { idx, .. } => {
+            let clone = match pool[idx as usize % held] {
+                Handle::Strong(ref arc) => Handle::Strong(Arc::clone(arc)),
+                Handle::Weak(ref weak) => Handle::Weak(Weak::clone(weak)),
+            };
+            pool.push(clone);
+            Outcome::Done
+        }
+        Op::Drop { idx, .. } => {
+            drop(pool.remove(idx as usize % held));
+            Outcome::Done
+        }
+        Op::Give { idx, to, .. } => {
+            given.push((to as usize % THREADS, pool.remove(idx as usize % held)));
+            Outcome::Done
+        }
+        Op::Downgrade { idx, .. } => {
+            let weak = match pool[idx as usize % held] {
+                Handle::Strong(ref arc) => Arc::downgrade(arc),
+                Handle::Weak(_) => return Outcome::Skipped,
+            };
+            pool.push(Handle::Weak(weak));
+            Outcome::Done
+        }
+        Op::Upgrade { idx, .. } => {
+            let upgraded = match pool[idx as usize % held] {
+                Handle::Weak(ref weak) => weak.upgrade(),
+                Handle::Strong(_) => return Outcome::Skipped,
+            };
+            let live = upgraded.is_some();
+            pool.extend(upgraded.map(Handle::Strong));
+            Outcome::Upgraded(live)
+        }
+        Op::MakeMut { idx, byte, .. } => match pool[idx as usize % held] {
+            Handle::Strong(ref mut arc) => {
+                let (ptr, id) = (Arc::as_ptr(arc), arc.id());
+                Arc::make_mut(arc).byte = byte;
+                if Arc::as_ptr(arc) == ptr {
+                    Outcome::InPlace
+                } else if arc.id() == id {
+                    Outcome::Moved
+                } else {
+                    Outcome::Cloned(arc.id())
+                }
+            }
+            Handle::Weak(_) => Outcome::Skipped,
+        },
+    }
+}
+
+/// Run the ops of `round` on the threads whose pools are `pools` and give
+/// what each thread saw
+///
+/// The threads run the ops a window at a time, in the order of `round`, see
+/// [`Turns`]. Once all are joined they take the handles given to them, in
+/// the order of the threads giving them.
+pub fn run_round<'a>(
+    round: &Round<Op>,
+    log: &'a Log,
+    pools: &mut [Vec<Handle<'a>>],
+) -> Vec<Vec<Outcome>> {
+    let turns = Turns::new(round.iter().map(|&(thread, _)| thread).collect());
+    let jobs: Vec<_> = by_thread(round)
+        .into_iter()
+        .zip(pools.iter_mut())
+        .enumerate()
+        .filter(|(_, (ops, _))| !ops.is_empty())
+        .collect();
+    let ran = run_on_threads(jobs, |(thread, (ops, pool))| {
+        let mut given = Vec::new();
+        let mut seen = Vec::with_capacity(ops.len());
+        for op in ops {
+            match turns.take(thread, || run_op(op, log, pool, &mut given)) {
+                Some(outcome) => seen.push(outcome),
+                None => break,
+            }
+        }
+        (thread, seen, given)
+    });
+    let mut outcomes = vec![Vec::new(); THREADS];
+    for (thread, seen, given) in ran {
+        outcomes[thread] = seen;
+        for (to, handle) in given {
+            pools[to].push(handle);
+        }
+    }
+    outcomes
+}
+
+/// Check `pools` and `log` against `model`
+///
+/// Every handle must be of the kind of its model handle, and count and see
+/// as it does, and the values dropped since the first `since` must be the
+/// model's, in any order.
+fn check_state(pools: &[Vec<Handle>], log: &Log, model: &PropArc, since: usize) {
+    let counts = model.counts();
+    for (thread, pool) in pools.iter().enumerate() {
+        check_eq!(pool.len(), model.pool(thread).len());
+        for (handle, prop) in pool.iter().zip(model.pool(thread)) {
+            let (strong, weak) = counts[&prop.alloc];
+            check_eq!(handle.kind(), prop.kind);
+            match *handle {
+                Handle::Strong(ref arc) => {
+                    check_eq!(Arc::strong_count(arc), strong);
+                    check_eq!(Arc::weak_count(arc), weak);
+                    let value = model.value(prop.alloc).expect("an Arc has a value");
+                    check_eq!((arc.byte, arc.id()), (value.byte, value.id));
+                }
+                Handle::Weak(ref w) => {
+                    check_eq!(w.strong_count(), strong);
+                    check_eq!(w.weak_count(), if strong > 0 { weak } else { 0 });
+                }
+            }
+        }
+    }
+    let mut dropped = log.dropped();
+    check_eq!(dropped.len(), model.dropped().len());
+    let mut expected = model.dropped()[since..].to_vec();
+    dropped[since..].sort_unstable();
+    expected.sort_unstable();
+    check_eq!(&dropped[since..], &expected[..]);
+}
+
+/// Run `round` against `pools`, whose values are logged in `log`, and
+/// `model`, panicking if they disagree
+pub fn check_round<'a>(
+    round: &Round<Op>,
+    log: &'a Log,
+    pools: &mut [Vec<Handle<'a>>],
+    model: &mut PropArc,
+) {
+    let since = model.dropped().len();
+    let outcomes = run_round(round, log, pools);
+    model.round(&by_thread(round), &outcomes);
+    check_state(pools, log, model, since);
+}
+
+/// Decode the input of the `sync_arc` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `sync_arc` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against the pools of `Arc<Tracked>` and
+/// `Weak<Tracked>` handles of [`THREADS`] threads and [`PropArc`]
+///
+/// Ops are gathered until a `Sync`, or the end of the trace, and then run as
+/// a round, see [`Turns`], panicking as soon as the model can't explain what
+/// the threads saw. Once the handles are all dropped every value must have
+/// been. The run is counted in [`stats`], if collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let log = Log::new();
+    let mut pools: Vec<Vec<Handle>> = (0..THREADS).map(|_| Vec::new()).collect();
+    let mut model = PropArc::new();
+    let mut round: Vec<(usize, &Op)> = Vec::new();
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        match op.thread() {
+            Some(thread) => round.push((thread, op)),
+            None => {
+                check_round(&round, &log, &mut pools, &mut model);
+                stats::size(TARGET, pools.iter().map(Vec::len).sum());
+                round.clear();
+            }
+        }
+    }
+    check_round(&round, &log, &mut pools, &mut model);
+    drop(pools);
+    let mut dropped = log.dropped();
+    dropped.sort_unstable();
+    check_eq!(dropped, (0..log.made()).collect::<Vec<_>>());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stdlib::sync::testing::{ops, trace};
+    use stdlib::util::panics;
+
+    #[test]
+    fn given_handles_are_taken_at_the_sync() {
+        let log = Log::new();
+        let mut pools: Vec<Vec<Handle>> = (0..THREADS).map(|_| Vec::new()).collect();
+        let mut model = PropArc::new();
+        let ops = [
+            Op::New { thread: 0, byte: 1 },
+            Op::Clone { thread: 0, idx: 0 },
+            Op::Give {
+                thread: 0,
+                idx: 0,
+                to: 2,
+            },
+        ];
+        let round: Vec<_> = ops.iter().map(|op| (0, op)).collect();
+        check_round(&round, &log, &mut pools, &mut model);
+        assert_eq!(1, model.pool(0).len());
+        assert_eq!(model.pool(0), model.pool(2));
+        assert_eq!(Some(&(2, 0)), model.counts().get(&0));
+        assert!(model.dropped().is_empty());
+    }
+
+    #[test]
+    fn outcomes_the_threads_could_not_see_are_caught() {
+        let new = Op::New { thread: 0, byte: 1 };
+        let make_mut = Op::MakeMut {
+            thread: 0,
+            idx: 0,
+            byte: 2,
+        };
+        let alone = |op| vec![vec![op], vec![], vec![], vec![]];
+        let saw = |outcome| vec![vec![outcome], vec![], vec![], vec![]];
+        let mut model = PropArc::new();
+        model.round(&alone(&new), &saw(Outcome::Made(0)));
+        let mut written = model.clone();
+        written.round(&alone(&make_mut), &saw(Outcome::InPlace));
+        assert_eq!(Some(PropValue { byte: 2, id: 0 }), written.value(0));
+        for &outcome in &[Outcome::Moved, Outcome::Cloned(1), Outcome::Skipped] {
+            let mut model = model.clone();
+            assert!(panics(|| model.round(&alone(&make_mut), &saw(outcome))));
+        }
+        let mut model = model.clone();
+        assert!(panics(|| model.round(&alone(&new), &saw(Outcome::Made(0)))));
+    }
+
+    #[test]
+    fn racing_upgrades_and_drops_agree() {
+        let mut ops = vec![
+            Op::New { thread: 0, byte: 1 },
+            Op::Downgrade { thread: 0, idx: 0 },
+            Op::Downgrade { thread: 0, idx: 0 },
+            Op::Give {
+                thread: 0,
+                idx: 1,
+                to: 1,
+            },
+            Op::Give {
+                thread: 0,
+                idx: 1,
+                to: 2,
+            },
+            Op::Sync,
+        ];
+        for _ in 0..8 {
+            ops.extend(vec![
+                Op::Drop { thread: 0, idx: 0 },
+                Op::Upgrade { thread: 1, idx: 0 },
+                Op::MakeMut {
+                    thread: 1,
+                    idx: 1,
+                    byte: 3,
+                },
+                Op::Upgrade { thread: 2, idx: 0 },
+                Op::Give {
+                    thread: 1,
+                    idx: 1,
+                    to: 0,
+                },
+                Op::Sync,
+            ]);
+        }
+        replay(&trace(TARGET, "u8", ops));
+    }
+
+    quickcheck! {
+        fn replays_are_explained(data: Vec<u8>) -> bool {
+            replay(&trace(TARGET, "u8", ops(&data)));
+            true
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Tests for `std::sync::atomic::AtomicUsize`
+//!
+//! Ops name the one of [`THREADS`] threads that runs them on a shared
+//! `AtomicUsize`, and in a round the threads run their ops a window at a
+//! time, see [`Turns`](super::Turns), keeping what each returned. Each op is
+//! atomic, so what they returned must be what some order of them returns,
+//! run against a [`PropAtomicUsize`] from the value the round started with,
+//! see [`linearize`]. The value the round ends with must be the order's too, and
+//! the next round starts from it.
+use op::{self, Profiles};
+use stats;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use stdlib::sync::linearizability::{linearize, record, Sequential};
+use stdlib::sync::{Round, THREADS};
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `AtomicUsize`
+pub const TARGET: &str = "sync_atomic";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most ops a thread runs in a round; ops past them do nothing
+pub const MAX_ROUND_OPS: usize = 12;
+
+lazy_static! {
+    /// The workload profiles of the `sync_atomic` target, see [`Profiles`]
+    ///
+    /// `exchange` runs mostly `compare_exchange` and `swap`, which store
+    /// what they are given, so the values exchanges expect come up often and
+    /// threads compete to succeed.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "exchange",
+        &[("compare_exchange", 6), ("swap", 2), ("fetch_add", 1), ("sync", 1)],
+    )]);
+}
+
+/// A model of an `AtomicUsize`
+///
+/// Every op returns a `Result`, `Err` only for a `compare_exchange` that
+/// failed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PropAtomicUsize {
+    value: usize,
+}
+
+impl PropAtomicUsize {
+    /// Construct a new `PropAtomicUsize` holding `value`
+    pub fn new(value: usize) -> Self {
+        PropAtomicUsize { value }
+    }
+
+    /// The value held
+    ///
+    /// This is like to [`AtomicUsize::load`]
+    pub fn load(&self) -> usize {
+        self.value
+    }
+
+    /// Add `val` to the value, wrapping around, and give the value before
+    ///
+    /// This is like to [`AtomicUsize::fetch_add`]
+    pub fn fetch_add(&mut self, val: usize) -> usize {
+        let prev = self.value;
+        self.value = prev.wrapping_add(val);
+        prev
+    }
+
+    /// Store `val` and give the value before
+    ///
+    /// This is like to [`AtomicUsize::swap`]
+    pub fn swap(&mut self, val: usize) -> usize {
+        let prev = self.value;
+        self.value = val;
+        prev
+    }
+
+    /// Store `new` if the value is `current`, and give the value before,
+    /// as `Ok` if it was stored
+    ///
+    /// This is like to [`AtomicUsize::compare_exchange`]
+    pub fn compare_exchange(&mut self, current: usize, new: usize) -> Result<usize, usize> {
+        if self.value == current {
+            self.value = new;
+            Ok(current)
+        } else {
+            Err(self.value)
+        }
+    }
+
+    /// Run `op`, giving what it returns
+    ///
+    /// Loads and all ops but `compare_exchange` return `Ok`.
+    pub fn apply(&mut self, op: &Op) -> Result<usize, usize> {
+        match *op {
+            Op::FetchAdd { val, .. } => Ok(self.fetch_add(val as usize)),
+            Op::CompareExchange { current, new, .. } => {
+                self.compare_exchange(current as usize, new as usize)
+            }
+            Op::Swap { val, .. } => Ok(self.swap(val as usize)),
+            Op::Load { .. } | Op::Sync => Ok(self.load()),
+        }
+    }
+}
+
+impl Sequential<Op> for PropAtomicUsize {
+    type Ret = Result<usize, usize>;
+
+    fn apply(&mut self, op: &Op) -> Result<usize, usize> {
+        PropAtomicUsize::apply(self, op)
+    }
+}
+
+/// The `Op` defines the ways the `AtomicUsize` of the `sync_atomic` target
+/// is changed.
+///
+/// Ops but `Sync` are run by the thread `thread` modulo [`THREADS`], in the
+/// round that the next `Sync` ends. All orderings are `SeqCst`.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `AtomicUsize::fetch_add`
+    #[op(tag = 0, weight = 2)]
+    FetchAdd {
+        /// The thread to run the op
+        thread: u8,
+        /// The value to add
+        val: u8,
+    },
+    /// This operation triggers `AtomicUsize::compare_exchange`
+    #[op(tag = 1, weight = 3)]
+    CompareExchange {
+        /// The thread to run the op
+        thread: u8,
+        /// The value expected
+        current: u8,
+        /// The value to store
+        new: u8,
+    },
+    /// This operation triggers `AtomicUsize::swap`
+    #[op(tag = 2, weight = 2)]
+    Swap {
+        /// The thread to run the op
+        thread: u8,
+        /// The value to store
+        val: u8,
+    },
+    /// This operation triggers `AtomicUsize::load`
+    #[op(tag = 3)]
+    Load {
+        /// The thread to run the op
+        thread: u8,
+    },
+    /// This operation ends a round, joining the threads
+    #[op(tag = 4)]
+    Sync,
+}
+
+impl Op {
+    /// The thread to run the op, or `None` for `Sync`
+    pub fn thread(&self) -> Option<usize> {
+        match *self {
+            Op::FetchAdd { thread, .. }
+            | Op::CompareExchange { thread, .. }
+            | Op::Swap { thread, .. }
+            | Op::Load { thread } => Some(thread as usize % THREADS),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// Run `op` against `atomic`, giving what it returns as
+/// [`PropAtomicUsize::apply`] does
+fn run_op(op: &Op, atomic: &AtomicUsize) -> Result<usize, usize> {
+    let order = Ordering::SeqCst;
+    match *op {
+        Op::FetchAdd { val, .. } => Ok(atomic.fetch_add(val as usize, order)),
+        Op::CompareExchange { current, new, .. } => {
+            atomic.compare_exchange(current as usize, new as usize, order, order)
+        }
+        Op::Swap { val, .. } => Ok(atomic.swap(val as usize, order)),
+        Op::Load { .. } | Op::Sync => Ok(atomic.load(order)),
+    }
+}
+
+/// Run `round` against `atomic`, which `model` holds the value of, panicking
+/// if no order of the ops explains what they returned
+///
+/// The order must be one [`linearize`] finds and leave the value the round
+/// ended with, which the model is left holding.
+pub fn check_round(round: &Round<Op>, atomic: &AtomicUsize, model: &mut PropAtomicUsize) {
+    let history = record(round, |_, op| run_op(op, atomic));
+    let end = PropAtomicUsize::new(atomic.load(Ordering::SeqCst));
+    let order = linearize(model, &history, |model| *model == end);
+    check!(
+        order.is_some(),
+        "no order explains {:?} from {:?}",
+        history,
+        model
+    );
+    *model = end;
+}
+
+/// Decode the input of the `sync_atomic` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["usize".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `sync_atomic` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against an `AtomicUsize` shared by [`THREADS`]
+/// threads and [`PropAtomicUsize`]
+///
+/// Ops are gathered until a `Sync`, or the end of the trace, and then run as
+/// a round, see [`Turns`](super::Turns), panicking as soon as no sequential
+/// order explains what the threads saw. The run is counted in [`stats`], if
+/// collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let atomic = AtomicUsize::new(0);
+    let mut model = PropAtomicUsize::default();
+    let mut round: Vec<(usize, &Op)> = Vec::new();
+    let mut ran = [0; THREADS];
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        match op.thread() {
+            Some(thread) if ran[thread] < MAX_ROUND_OPS => {
+                ran[thread] += 1;
+                round.push((thread, op));
+            }
+            Some(_) => {}
+            None => {
+                check_round(&round, &atomic, &mut model);
+                stats::size(TARGET, model.load());
+                round.clear();
+                ran = [0; THREADS];
+            }
+        }
+    }
+    check_round(&round, &atomic, &mut model);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stdlib::sync::linearizability::Call;
+    use stdlib::sync::testing::{ops, rounds, trace};
+
+    #[test]
+    fn an_order_is_found_only_if_one_explains_the_history() {
+        let swap = Op::Swap { thread: 0, val: 5 };
+        let cas = Op::CompareExchange {
+            thread: 1,
+            current: 5,
+            new: 7,
+        };
+        let load = Op::Load { thread: 1 };
+        let call = |op: &Op, ret, invoked| Call {
+            thread: op.thread().unwrap(),
+            op: op.clone(),
+            ret,
+            invoked,
+            responded: invoked + 2,
+        };
+        let start = PropAtomicUsize::new(0);
+        let seven = |model: &PropAtomicUsize| model.load() == 7;
+        let history = vec![
+            call(&swap, Ok(0), 0),
+            call(&cas, Ok(5), 1),
+            call(&load, Ok(7), 4),
+        ];
+        assert_eq!(Some(vec![0, 1, 2]), linearize(&start, &history, seven));
+        assert_eq!(None, linearize(&start, &history, |m| m.load() == 5));
+        let history = vec![
+            call(&swap, Ok(7), 0),
+            call(&cas, Ok(5), 1),
+            call(&load, Ok(7), 4),
+        ];
+        assert_eq!(None, linearize(&start, &history, seven));
+        let history = vec![
+            call(&swap, Ok(0), 0),
+            call(&load, Ok(7), 1),
+            call(&cas, Ok(5), 4),
+        ];
+        assert_eq!(None, linearize(&start, &history, seven));
+    }
+
+    #[test]
+    fn racing_exchanges_are_explained() {
+        let mut ops = Vec::new();
+        for round in 0..16 {
+            for step in 0..3 {
+                for thread in 0..THREADS as u8 {
+                    ops.push(match step {
+                        0 => Op::CompareExchange {
+                            thread,
+                            current: round,
+                            new: round + 1,
+                        },
+                        1 => Op::FetchAdd { thread, val: 1 },
+                        _ => Op::Swap { thread, val: round },
+                    });
+                }
+            }
+            ops.push(Op::Sync);
+        }
+        replay(&trace(TARGET, "usize", ops));
+    }
+
+    quickcheck! {
+        fn atomic_agrees(data: Vec<u8>) -> bool {
+            let ops: Vec<Op> = ops(&data);
+            let (atomic, alone) = (AtomicUsize::new(0), AtomicUsize::new(0));
+            let (mut model, mut alone_model) = (PropAtomicUsize::new(0), PropAtomicUsize::new(0));
+            let mut in_turn = PropAtomicUsize::new(0);
+            rounds(&ops, Op::thread).iter().all(|round| {
+                check_round(round, &atomic, &mut model);
+                let one_thread: Vec<_> = round.iter().map(|&(_, op)| (0, op)).collect();
+                check_round(&one_thread, &alone, &mut alone_model);
+                for &(_, op) in round {
+                    let _ = in_turn.apply(op);
+                }
+                alone_model == in_turn
+            })
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! A checker of histories of concurrent ops against a sequential model
+//!
+//! Threads that share an object run ops on it, and each op takes effect at
+//! some instant between its invocation and its response. If the object is
+//! linearizable there is an order of the ops that keeps the order of any two
+//! of them that didn't overlap, and that, run one after the other against a
+//! sequential model, returns just what the ops returned. This module records
+//! the [`Call`]s threads make, with the instants they were invoked and
+//! responded at, and [`linearize`] searches for such an order.
+//!
+//! The threads of [`record`] run their calls a window at a time, see
+//! [`Turns`], so the calls of a window overlap and the search has to find
+//! their order, while a window's calls all go before the next window's.
+//!
+//! The search is that of Wing and Gong: it tries each call that may go next
+//! -- any not yet ordered that was invoked before every call not yet ordered
+//! responded -- and backs out when the model returns something else.
+//! Orderings that reach a state already found a dead end, the same calls
+//! ordered and the model the same, are cut short, so the model must be
+//! `Hash` and `Eq`.
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use stdlib::sync::{by_thread, run_on_threads, Round, Turns};
+
+/// A sequential model of an object shared by threads
+///
+/// A model may be run with ops of several kinds, each kind `O` returning
+/// a `Ret`.
+pub trait Sequential<O>: Clone + Eq + Hash {
+    /// What an op returns
+    type Ret: PartialEq;
+
+    /// Run `op`, giving what it returns
+    fn apply(&mut self, op: &O) -> Self::Ret;
+}
+
+/// An op a thread ran, with what it returned and the instants it was
+/// invoked and responded at
+///
+/// Instants are ticks of a [`Clock`]. A call whose response is before the
+/// invocation of another must take effect first.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Call<O, R> {
+    /// The thread that ran the op
+    pub thread: usize,
+    /// The op
+    pub op: O,
+    /// What the op returned
+    pub ret: R,
+    /// The tick the op was invoked at
+    pub invoked: usize,
+    /// The tick the op responded at
+    pub responded: usize,
+}
+
+impl<O, R> fmt::Debug for Call<O, R>
+where
+    O: fmt::Debug,
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}@{}..{}: {:?} -> {:?}",
+            self.thread, self.invoked, self.responded, self.op, self.ret
+        )
+    }
+}
+
+/// A clock shared by threads, ticking once for every invocation and every
+/// response
+///
+/// Ticks are taken by `SeqCst` read-modify-writes, so a call that responded
+/// at an earlier tick than another was invoked at happened before it.
+#[derive(Debug, Default)]
+pub struct Clock {
+    ticks: AtomicUsize,
+}
+
+impl Clock {
+    /// Construct a new `Clock`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `op` with `run` for `thread`, recording the call
+    pub fn call<O, R, F>(&self, thread: usize, op: O, run: F) -> Call<O, R>
+    where
+        F: FnOnce(&O) -> R,
+    {
+        let invoked = self.ticks.fetch_add(1, Ordering::SeqCst);
+        let ret = run(&op);
+        let responded = self.ticks.fetch_add(1, Ordering::SeqCst);
+        Call {
+            thread,
+            op,
+            ret,
+            invoked,
+            responded,
+        }
+    }
+}
+
+/// Run the ops of `round` with `run`, each thread's ops on a thread of its
+/// own, and give the history of their calls
+///
+/// The threads run the ops a window at a time, in the order of `round`, see
+/// [`Turns`]. Threads with no ops aren't started, see [`run_on_threads`].
+/// Calls are given thread by thread.
+pub fn record<O, R, F>(round: &Round<O>, run: F) -> Vec<Call<O, R>>
+where
+    O: Clone + Send + Sync,
+    R: Send,
+    F: Fn(usize, &O) -> R + Sync,
+{
+    let clock = Clock::new();
+    let turns = Turns::new(round.iter().map(|&(thread, _)| thread).collect());
+    let jobs: Vec<_> = by_thread(round)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, ops)| !ops.is_empty())
+        .collect();
+    let calls = run_on_threads(jobs, |(thread, ops)| {
+        let mut calls = Vec::with_capacity(ops.len());
+        for op in ops {
+            let call = turns.take(thread, || {
+                clock.call(thread, op.clone(), |op| run(thread, op))
+            });
+            match call {
+                Some(call) => calls.push(call),
+                None => break,
+            }
+        }
+        calls
+    });
+    calls.into_iter().flat_map(Vec::into_iter).collect()
+}
+
+/// Find an order of the calls of `history` that explains them, run against
+/// `start`, leaving a model `done` accepts
+///
+/// Gives the indices of the calls in that order, or `None` if no order does.
+pub fn linearize<M, O, F>(start: &M, history: &[Call<O, M::Ret>], done: F) -> Option<Vec<usize>>
+where
+    M: Sequential<O>,
+    F: Fn(&M) -> bool,
+{
+    let mut search = Search {
+        history,
+        done,
+        ordered: vec![false; history.len()],
+        order: Vec::with_capacity(history.len()),
+        failed: HashSet::new(),
+    };
+    if search.run(start.clone()) {
+        Some(search.order)
+    } else {
+        None
+    }
+}
+
+/// The state of [`linearize`]
+struct Search<'a, M, O: 'a, F>
+where
+    M: Sequential<O>,
+    M::Ret: 'a,
+{
+    history: &'a [Call<O, M::Ret>],
+    done: F,
+    /// Whether each call is ordered yet
+    ordered: Vec<bool>,
+    /// The calls ordered, in order
+    order: Vec<usize>,
+    /// The dead ends found
+    failed: HashSet<(Vec<bool>, M)>,
+}
+
+impl<'a, M, O, F> Search<'a, M, O, F>
+where
+    M: Sequential<O>,
+    F: Fn(&M) -> bool,
+{
+    /// Order the calls left after those ordered, run against `model`,
+    /// giving whether they could be
+    fn run(&mut self, model: M) -> bool {
+        if self.order.len() == self.history.len() {
+            return (self.done)(&model);
+        }
+        let state = (self.ordered.clone(), model);
+        if self.failed.contains(&state) {
+            return false;
+        }
+        let model = state.1;
+        let left = || {
+            self.history
+                .iter()
+                .zip(&self.ordered)
+                .enumerate()
+                .filter(|&(_, (_, &ordered))| !ordered)
+                .map(|(idx, (call, _))| (idx, call))
+        };
+        let horizon = left().map(|(_, call)| call.responded).min();
+        let horizon = horizon.unwrap_or(usize::MAX);
+        let next: Vec<usize> = left()
+            .filter(|&(_, call)| call.invoked < horizon)
+            .map(|(idx, _)| idx)
+            .collect();
+        for idx in next {
+            let mut after = model.clone();
+            if after.apply(&self.history[idx].op) != self.history[idx].ret {
+                continue;
+            }
+            self.ordered[idx] = true;
+            self.order.push(idx);
+            if self.run(after) {
+                return true;
+            }
+            self.order.pop();
+            self.ordered[idx] = false;
+        }
+        self.failed.insert((self.ordered.clone(), model));
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A register holding a byte, written by `Some` and read by `None`
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+    struct Register(u8);
+
+    impl Sequential<Option<u8>> for Register {
+        type Ret = u8;
+
+        fn apply(&mut self, op: &Option<u8>) -> u8 {
+            if let Some(byte) = *op {
+                self.0 = byte;
+            }
+            self.0
+        }
+    }
+
+    fn call(thread: usize, op: Option<u8>, ret: u8, at: (usize, usize)) -> Call<Option<u8>, u8> {
+        Call {
+            thread,
+            op,
+            ret,
+            invoked: at.0,
+            responded: at.1,
+        }
+    }
+
+    #[test]
+    fn overlapping_calls_go_in_either_order() {
+        let history = vec![
+            call(0, Some(1), 1, (0, 3)),
+            call(1, None, 0, (1, 2)),
+            call(1, None, 1, (4, 5)),
+        ];
+        let start = Register::default();
+        assert_eq!(Some(vec![1, 0, 2]), linearize(&start, &history, |_| true));
+        assert_eq!(None, linearize(&start, &history, |r| r.0 == 0));
+    }
+
+    #[test]
+    fn calls_that_dont_overlap_keep_their_order() {
+        let history = vec![call(0, Some(1), 1, (0, 1)), call(1, None, 0, (2, 3))];
+        assert_eq!(None, linearize(&Register::default(), &history, |_| true));
+        let history = vec![call(0, Some(1), 1, (0, 2)), call(1, None, 0, (1, 3))];
+        assert_eq!(
+            Some(vec![1, 0]),
+            linearize(&Register::default(), &history, |_| true)
+        );
+    }
+
+    #[test]
+    fn recorded_windows_overlap_and_go_one_after_another() {
+        let ops = [Some(1), None, Some(2), Some(3)];
+        let round: Vec<(usize, &Option<u8>)> = ops
+            .iter()
+            .flat_map(|op| vec![(0, op), (1, op)])
+            .collect();
+        let register = ::std::sync::Mutex::new(Register::default());
+        let inside = ::std::sync::Barrier::new(2);
+        let history = record(&round, |_, op| {
+            inside.wait();
+            register.lock().unwrap().apply(op)
+        });
+        assert_eq!(8, history.len());
+        let window = |idx: usize| -> Vec<_> {
+            history.iter().filter(|call| call.op == ops[idx]).collect()
+        };
+        for idx in 0..ops.len() {
+            for (a, b) in window(idx).into_iter().zip(window(idx).into_iter().rev()) {
+                assert!(a.invoked < b.responded);
+            }
+        }
+        for idx in 1..ops.len() {
+            for before in window(idx - 1) {
+                for after in window(idx) {
+                    assert!(before.responded < after.invoked);
+                }
+            }
+        }
+        let end = register.lock().unwrap().clone();
+        assert!(linearize(&Register::default(), &history, |r| *r == end).is_some());
+    }
+
+    quickcheck! {
+        fn sequential_histories_linearize(ops: Vec<(bool, u8)>) -> bool {
+            let mut register = Register::default();
+            let history: Vec<_> = ops
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (write, byte))| {
+                    let op = if write { Some(byte) } else { None };
+                    let ret = register.apply(&op);
+                    call(idx % 3, op, ret, (2 * idx, 2 * idx + 1))
+                })
+                .collect();
+            let order = linearize(&Register::default(), &history, |r| *r == register);
+            order == Some((0..history.len()).collect())
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Tests for `std::sync`
+//!
+//! The ops of these targets are scripted for a number of threads at once.
+//! A script is cut into rounds by `sync` ops, and in each round every thread
+//! runs its own ops on a thread of its own. The ops are released a window at
+//! a time, see [`Turns`], so the input fixes which ops may overlap and a
+//! replay lets the same ops overlap again. Between rounds the threads are
+//! joined, and that is when the system under test is checked.
+pub mod arc;
+pub mod atomic;
+pub mod linearizability;
+pub mod mpsc;
+pub mod mutex;
+pub mod rwlock;
+#[cfg(test)]
+pub mod testing;
+
+use std::panic;
+use std::sync::{Barrier, Condvar, Mutex};
+use std::thread;
+
+/// The number of threads a script is run by
+pub const THREADS: usize = 4;
+
+/// The ops of a round, each with the thread to run it, in the order they take
+/// turns
+pub type Round<'a, O> = [(usize, &'a O)];
+
+/// The ops of each of [`THREADS`] threads in `round`, in the order each runs
+/// them
+pub fn by_thread<'a, O>(round: &Round<'a, O>) -> Vec<Vec<&'a O>> {
+    let mut script = vec![Vec::new(); THREADS];
+    for &(thread, op) in round {
+        script[thread].push(op);
+    }
+    script
+}
+
+/// The turns of the threads of a round
+///
+/// The ops of a round are cut into windows, in order, each window as many
+/// ops as follow one another on different threads. The threads of a window
+/// wait for one another at a [`Barrier`] and then run their ops at once, and
+/// a window is only released once the one before it is done. The input so
+/// fixes which ops overlap and which finish before others begin, but not
+/// how the ops of a window interleave, so what the threads saw need only be
+/// explained by some order of their ops. The targets panic as soon as none
+/// does.
+///
+/// Threads wait for their turn in [`Turns::take`]. If a thread panics in its
+/// turn the round is abandoned, and the threads still waiting give up rather
+/// than wait for turns that will never come.
+#[derive(Debug)]
+pub struct Turns {
+    /// For each thread, the windows it has a turn in, in order
+    windows: Vec<Vec<usize>>,
+    /// For each window, its number of turns and the barrier its threads
+    /// wait at
+    barriers: Vec<(usize, Barrier)>,
+    at: Mutex<At>,
+    next: Condvar,
+}
+
+/// The progress of a round through its [`Turns`]
+#[derive(Debug, Default)]
+struct At {
+    /// The window being run
+    window: usize,
+    /// The turns of the window done
+    done: usize,
+    /// The turns each thread took
+    taken: Vec<usize>,
+    /// Whether the round was abandoned
+    abandoned: bool,
+}
+
+/// Passes the turn on when dropped, abandoning the round if the turn panicked
+struct Pass<'a>(&'a Turns);
+
+impl<'a> Drop for Pass<'a> {
+    fn drop(&mut self) {
+        let mut at = self.0.at.lock().unwrap_or_else(|e| e.into_inner());
+        at.done += 1;
+        at.abandoned |= thread::panicking();
+        if self.0.barriers.get(at.window).map(|b| b.0) == Some(at.done) {
+            at.window += 1;
+            at.done = 0;
+        }
+        self.0.next.notify_all();
+    }
+}
+
+impl Turns {
+    /// Construct the `Turns` of the threads of `order`, one turn each, in
+    /// windows of turns on different threads
+    pub fn new(order: Vec<usize>) -> Self {
+        let threads = order.iter().max().map_or(0, |&thread| thread + 1);
+        let mut windows = vec![Vec::new(); threads];
+        let mut sizes: Vec<usize> = Vec::new();
+        let mut start = 0;
+        for (idx, &thread) in order.iter().enumerate() {
+            if sizes.is_empty() || order[start..idx].contains(&thread) {
+                start = idx;
+                sizes.push(0);
+            }
+            windows[thread].push(sizes.len() - 1);
+            *sizes.last_mut().expect("a window was started") += 1;
+        }
+        Turns {
+            windows,
+            barriers: sizes.into_iter().map(|n| (n, Barrier::new(n))).collect(),
+            at: Mutex::new(At {
+                taken: vec![0; threads],
+                ..At::default()
+            }),
+            next: Condvar::new(),
+        }
+    }
+
+    /// Wait for the next turn of `thread` and run `f` in it, once the other
+    /// threads of its window are ready to take theirs, giving what it gave,
+    /// or `None` if the round was abandoned
+    pub fn take<T, F>(&self, thread: usize, f: F) -> Option<T>
+    where
+        F: FnOnce() -> T,
+    {
+        let mut at = self.at.lock().unwrap_or_else(|e| e.into_inner());
+        let window = *self.windows.get(thread)?.get(at.taken[thread])?;
+        while !at.abandoned && at.window != window {
+            at = self.next.wait(at).unwrap_or_else(|e| e.into_inner());
+        }
+        if at.abandoned {
+            return None;
+        }
+        at.taken[thread] += 1;
+        drop(at);
+        self.barriers[window].1.wait();
+        let _pass = Pass(self);
+        Some(f())
+    }
+}
+
+/// Run `work` on each of `jobs`, each on a thread of its own, and give what
+/// each gave, in the order of `jobs`
+///
+/// A single job runs on the calling thread. If a job panics, the panic is
+/// passed on to the caller as it was.
+pub fn run_on_threads<J, T, F>(jobs: Vec<J>, work: F) -> Vec<T>
+where
+    J: Send,
+    T: Send,
+    F: Fn(J) -> T + Sync,
+{
+    if jobs.len() <= 1 {
+        return jobs.into_iter().map(work).collect();
+    }
+    let work = &work;
+    thread::scope(|scope| {
+        let threads: Vec<_> = jobs
+            .into_iter()
+            .map(|job| scope.spawn(move || work(job)))
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stdlib::util::panics;
+
+    #[test]
+    fn windows_take_turns_in_order() {
+        let round = [(2, &'a'), (0, &'b'), (2, &'c'), (1, &'d'), (0, &'e')];
+        let turns = Turns::new(round.iter().map(|&(thread, _)| thread).collect());
+        let seen = Mutex::new(String::new());
+        let jobs: Vec<_> = by_thread(&round).into_iter().enumerate().collect();
+        run_on_threads(jobs, |(thread, ops)| {
+            for op in ops {
+                turns.take(thread, || seen.lock().unwrap().push(*op));
+            }
+        });
+        let seen = seen.into_inner().unwrap();
+        let mut first: Vec<char> = seen[..2].chars().collect();
+        let mut second: Vec<char> = seen[2..].chars().collect();
+        first.sort_unstable();
+        second.sort_unstable();
+        assert_eq!((vec!['a', 'b'], vec!['c', 'd', 'e']), (first, second));
+    }
+
+    #[test]
+    fn the_turns_of_a_window_overlap() {
+        let turns = Turns::new(vec![0, 1, 2, 0]);
+        let inside = Barrier::new(3);
+        let jobs: Vec<_> = (0..3).collect();
+        run_on_threads(jobs, |thread| {
+            turns.take(thread, || inside.wait());
+            if thread == 0 {
+                turns.take(thread, || ());
+            }
+        });
+    }
+
+    #[test]
+    fn a_panic_abandons_the_round() {
+        let turns = Turns::new(vec![0, 1, 0, 1, 0, 1]);
+        let taken = Mutex::new(Vec::new());
+        let jobs = vec![(0, true), (1, false)];
+        assert!(panics(|| {
+            run_on_threads(jobs, |(thread, fail)| {
+                for turn in 0..3 {
+                    turns.take(thread, || {
+                        taken.lock().unwrap().push(thread);
+                        assert!(!fail || turn < 1);
+                    });
+                }
+            });
+        }));
+        let mut taken = taken.into_inner().unwrap();
+        taken.sort_unstable();
+        assert_eq!(vec![0, 0, 1, 1], taken);
+    }
+}
@@ -0,0 +1,319 @@
+//! Tests for `std::sync::mpsc`
+//!
+//! Ops name the one of [`THREADS`] threads that sends a value, each thread
+//! through a `Sender` of its own, or have the thread holding the `Receiver`,
+//! the first, try to receive one. In a round the threads run their ops a
+//! window at a time, see [`Turns`](super::Turns), so what they returned must
+//! be what some order of them returns, run against a [`PropVecDeque`] of the
+//! values in flight when the round started, see [`linearize`]. The values in
+//! flight when the round ends must be the order's too, and the next round
+//! starts from them.
+//!
+//! At the end of a round the values are received and sent again, in order,
+//! to be seen. Nothing else runs then, so this leaves the channel as it was.
+use op::{self, Profiles};
+use stats;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Mutex;
+use stdlib::collections::vec_deque::PropVecDeque;
+use stdlib::sync::linearizability::{linearize, record, Sequential};
+use stdlib::sync::{Round, THREADS};
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `mpsc` channels
+pub const TARGET: &str = "sync_mpsc";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most ops a thread runs in a round; ops past them do nothing
+pub const MAX_ROUND_OPS: usize = 12;
+
+lazy_static! {
+    /// The workload profiles of the `sync_mpsc` target, see [`Profiles`]
+    ///
+    /// `drain` receives about as often as all others send, so the channel
+    /// is often empty when the receiver takes its turn.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "drain",
+        &[("send", 1), ("try_recv", 3), ("sync", 1)],
+    )]);
+}
+
+/// The `Op` defines the ways the channel of the `sync_mpsc` target is
+/// changed.
+///
+/// `Send` is run by the thread `thread` modulo [`THREADS`], and `TryRecv` by
+/// the first thread, in the round that the next `Sync` ends.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `Sender::send`
+    #[op(tag = 0, weight = 2)]
+    Send {
+        /// The thread to run the op
+        thread: u8,
+        /// The value to send
+        value: u8,
+    },
+    /// This operation triggers `Receiver::try_recv`
+    #[op(tag = 1, weight = 2)]
+    TryRecv,
+    /// This operation ends a round, joining the threads
+    #[op(tag = 2)]
+    Sync,
+}
+
+impl Op {
+    /// The thread to run the op, or `None` for `Sync`
+    pub fn thread(&self) -> Option<usize> {
+        match *self {
+            Op::Send { thread, .. } => Some(thread as usize % THREADS),
+            Op::TryRecv => Some(0),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// Sends return `None`, and receives the value received, if any
+impl Sequential<Op> for PropVecDeque<u8> {
+    type Ret = Option<u8>;
+
+    fn apply(&mut self, op: &Op) -> Option<u8> {
+        match *op {
+            Op::Send { value, .. } => {
+                self.push_back(value);
+                None
+            }
+            Op::TryRecv => self.pop_front(),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// A channel, with a `Sender` for each thread
+///
+/// Only the first thread receives, but the `Receiver` is behind a `Mutex`
+/// so that the threads can share the channel.
+pub struct Channel {
+    senders: Vec<Sender<u8>>,
+    receiver: Mutex<Receiver<u8>>,
+}
+
+impl Channel {
+    /// Construct a new, empty `Channel`
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Channel {
+            senders: (0..THREADS).map(|_| sender.clone()).collect(),
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    /// Run `op` for `thread`, giving what it returns as the model does
+    fn run_op(&self, thread: usize, op: &Op) -> Option<u8> {
+        match *op {
+            Op::Send { value, .. } => {
+                check!(self.senders[thread].send(value).is_ok());
+                None
+            }
+            Op::TryRecv => self.try_recv(),
+            Op::Sync => None,
+        }
+    }
+
+    fn try_recv(&self) -> Option<u8> {
+        let receiver = self.receiver.lock().expect("no thread panics receiving");
+        match receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(err) => {
+                check_eq!(err, TryRecvError::Empty);
+                None
+            }
+        }
+    }
+
+    /// The values in flight, received and sent again
+    fn in_flight(&self) -> PropVecDeque<u8> {
+        let mut values = PropVecDeque::new();
+        while let Some(value) = self.try_recv() {
+            values.push_back(value);
+        }
+        let mut resent = values.clone();
+        while let Some(value) = resent.pop_front() {
+            check!(self.senders[0].send(value).is_ok());
+        }
+        values
+    }
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Run `round` against `channel`, which `model` holds the values in flight
+/// of, panicking if no order of the ops explains what they returned
+///
+/// The order must be one [`linearize`] finds and leave the values in flight
+/// when the round ended, which the model is left holding.
+pub fn check_round(round: &Round<Op>, channel: &Channel, model: &mut PropVecDeque<u8>) {
+    let history = record(round, |thread, op| channel.run_op(thread, op));
+    let end = channel.in_flight();
+    let order = linearize(model, &history, |model| *model == end);
+    check!(
+        order.is_some(),
+        "no order explains {:?} from {:?}",
+        history,
+        model
+    );
+    *model = end;
+}
+
+/// Decode the input of the `sync_mpsc` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `sync_mpsc` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against a channel shared by [`THREADS`] threads and
+/// [`PropVecDeque`]
+///
+/// Ops are gathered until a `Sync`, or the end of the trace, and then run as
+/// a round, see [`Turns`](super::Turns), panicking as soon as no order
+/// explains what the threads saw. The run is counted in [`stats`], if
+/// collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let channel = Channel::new();
+    let mut model = PropVecDeque::new();
+    let mut round: Vec<(usize, &Op)> = Vec::new();
+    let mut ran = [0; THREADS];
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        match op.thread() {
+            Some(thread) if ran[thread] < MAX_ROUND_OPS => {
+                ran[thread] += 1;
+                round.push((thread, op));
+            }
+            Some(_) => {}
+            None => {
+                check_round(&round, &channel, &mut model);
+                stats::size(TARGET, model.len());
+                round.clear();
+                ran = [0; THREADS];
+            }
+        }
+    }
+    check_round(&round, &channel, &mut model);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stdlib::sync::linearizability::Call;
+    use stdlib::sync::testing::{ops, rounds, trace};
+
+    #[test]
+    fn values_of_a_sender_arrive_in_order() {
+        let call = |op: Op, ret, invoked| Call {
+            thread: op.thread().unwrap(),
+            op,
+            ret,
+            invoked,
+            responded: invoked + 2,
+        };
+        let send = |thread, value, invoked| call(Op::Send { thread, value }, None, invoked);
+        let recv = |ret, invoked| call(Op::TryRecv, ret, invoked);
+        let start = PropVecDeque::new();
+        let empty = PropVecDeque::is_empty;
+        let history = vec![
+            send(1, 1, 0),
+            send(2, 2, 1),
+            recv(Some(2), 2),
+            recv(Some(1), 4),
+        ];
+        assert!(linearize(&start, &history, empty).is_some());
+        let history = vec![
+            send(1, 1, 0),
+            send(1, 2, 2),
+            recv(Some(2), 3),
+            recv(Some(1), 5),
+        ];
+        assert!(linearize(&start, &history, empty).is_none());
+        let history = vec![send(1, 1, 0), recv(None, 3)];
+        assert!(linearize(&start, &history, |_| true).is_none());
+    }
+
+    #[test]
+    fn in_flight_values_are_kept() {
+        let channel = Channel::new();
+        let send = Op::Send {
+            thread: 1,
+            value: 1,
+        };
+        let mut model = PropVecDeque::new();
+        check_round(&[(1, &send)], &channel, &mut model);
+        check_round(&[(1, &send)], &channel, &mut model);
+        assert_eq!(2, model.len());
+        check_round(&[(0, &Op::TryRecv); 3], &channel, &mut model);
+        assert!(model.is_empty());
+    }
+
+    #[test]
+    fn racing_sends_and_receives_agree() {
+        let mut ops = Vec::new();
+        for round in 0..8 {
+            for step in 0..4 {
+                ops.push(Op::TryRecv);
+                for thread in 1..THREADS as u8 {
+                    ops.push(Op::Send {
+                        thread,
+                        value: round ^ thread ^ step,
+                    });
+                }
+            }
+            ops.push(Op::Sync);
+        }
+        replay(&trace(TARGET, "u8", ops));
+    }
+
+    quickcheck! {
+        fn channel_agrees(data: Vec<u8>) -> bool {
+            let ops: Vec<Op> = ops(&data);
+            let (channel, alone) = (Channel::new(), Channel::new());
+            let (mut model, mut alone_model) = (PropVecDeque::new(), PropVecDeque::new());
+            let mut in_turn = PropVecDeque::new();
+            rounds(&ops, Op::thread).iter().all(|round| {
+                check_round(round, &channel, &mut model);
+                let one_thread: Vec<_> = round.iter().map(|&(_, op)| (0, op)).collect();
+                check_round(&one_thread, &alone, &mut alone_model);
+                for &(_, op) in round {
+                    in_turn.apply(op);
+                }
+                alone_model == in_turn
+            })
+        }
+    }
+}
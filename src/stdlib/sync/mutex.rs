@@ -0,0 +1,315 @@
+//! Tests for `std::sync::Mutex`, guarding a `VecDeque`
+//!
+//! Ops name the one of [`THREADS`] threads that runs them, each locking the
+//! `Mutex` to push or pop at either end of the queue it guards or to read
+//! its length. In a round the threads run their ops a window at a time, see
+//! [`Turns`](super::Turns), and since each op holds the lock throughout,
+//! what they returned must be what some order of them returns, run against
+//! a [`PropVecDeque`] from the queue the round started with, see
+//! [`linearize`]. The queue the round ends with must be the order's too, and
+//! the next round starts from it.
+use op::{self, Profiles};
+use stats;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use stdlib::collections::vec_deque::PropVecDeque;
+use stdlib::sync::linearizability::{linearize, record, Sequential};
+use stdlib::sync::{Round, THREADS};
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `Mutex<VecDeque>`
+pub const TARGET: &str = "sync_mutex";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most ops a thread runs in a round; ops past them do nothing
+pub const MAX_ROUND_OPS: usize = 12;
+
+lazy_static! {
+    /// The workload profiles of the `sync_mutex` target, see [`Profiles`]
+    ///
+    /// `queue` pushes at the back and pops at the front, so threads hand
+    /// values to one another first in, first out, and compete for them.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "queue",
+        &[("push_back", 4), ("pop_front", 4), ("push_front", 0), ("pop_back", 0), ("len", 1), ("sync", 1)],
+    )]);
+}
+
+/// The `Op` defines the ways the `Mutex<VecDeque<u8>>` of the `sync_mutex`
+/// target is changed.
+///
+/// Ops but `Sync` are run by the thread `thread` modulo [`THREADS`], in the
+/// round that the next `Sync` ends, each under a lock of its own.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `VecDeque::push_back`
+    #[op(tag = 0, weight = 2)]
+    PushBack {
+        /// The thread to run the op
+        thread: u8,
+        /// The value to push
+        value: u8,
+    },
+    /// This operation triggers `VecDeque::push_front`
+    #[op(tag = 1)]
+    PushFront {
+        /// The thread to run the op
+        thread: u8,
+        /// The value to push
+        value: u8,
+    },
+    /// This operation triggers `VecDeque::pop_back`
+    #[op(tag = 2)]
+    PopBack {
+        /// The thread to run the op
+        thread: u8,
+    },
+    /// This operation triggers `VecDeque::pop_front`
+    #[op(tag = 3, weight = 2)]
+    PopFront {
+        /// The thread to run the op
+        thread: u8,
+    },
+    /// This operation triggers `VecDeque::len`
+    #[op(tag = 4)]
+    Len {
+        /// The thread to run the op
+        thread: u8,
+    },
+    /// This operation ends a round, joining the threads
+    #[op(tag = 5)]
+    Sync,
+}
+
+impl Op {
+    /// The thread to run the op, or `None` for `Sync`
+    pub fn thread(&self) -> Option<usize> {
+        match *self {
+            Op::PushBack { thread, .. }
+            | Op::PushFront { thread, .. }
+            | Op::PopBack { thread }
+            | Op::PopFront { thread }
+            | Op::Len { thread } => Some(thread as usize % THREADS),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// Ops return the value popped or the length, or `None` if they push
+impl Sequential<Op> for PropVecDeque<u8> {
+    type Ret = Option<usize>;
+
+    fn apply(&mut self, op: &Op) -> Option<usize> {
+        match *op {
+            Op::PushBack { value, .. } => {
+                self.push_back(value);
+                None
+            }
+            Op::PushFront { value, .. } => {
+                self.push_front(value);
+                None
+            }
+            Op::PopBack { .. } => self.pop_back().map(usize::from),
+            Op::PopFront { .. } => self.pop_front().map(usize::from),
+            Op::Len { .. } => Some(self.len()),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// Run `op` against `deque`, giving what it returns as the model does
+fn run_op(op: &Op, deque: &Mutex<VecDeque<u8>>) -> Option<usize> {
+    let mut deque = deque.lock().expect("no thread panics holding the lock");
+    match *op {
+        Op::PushBack { value, .. } => {
+            deque.push_back(value);
+            None
+        }
+        Op::PushFront { value, .. } => {
+            deque.push_front(value);
+            None
+        }
+        Op::PopBack { .. } => deque.pop_back().map(usize::from),
+        Op::PopFront { .. } => deque.pop_front().map(usize::from),
+        Op::Len { .. } => Some(deque.len()),
+        Op::Sync => None,
+    }
+}
+
+/// Run `round` against `deque`, which `model` holds the queue of, panicking
+/// if no order of the ops explains what they returned
+///
+/// The order must be one [`linearize`] finds and leave the queue the round
+/// ended with, which the model is left holding.
+pub fn check_round(round: &Round<Op>, deque: &Mutex<VecDeque<u8>>, model: &mut PropVecDeque<u8>) {
+    let history = record(round, |_, op| run_op(op, deque));
+    let deque = deque.lock().expect("no thread panics holding the lock");
+    let mut end = PropVecDeque::new();
+    for &value in deque.iter() {
+        end.push_back(value);
+    }
+    let order = linearize(model, &history, |model| *model == end);
+    check!(
+        order.is_some(),
+        "no order explains {:?} from {:?}",
+        history,
+        model
+    );
+    *model = end;
+}
+
+/// Decode the input of the `sync_mutex` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `sync_mutex` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against a `Mutex<VecDeque<u8>>` shared by
+/// [`THREADS`] threads and [`PropVecDeque`]
+///
+/// Ops are gathered until a `Sync`, or the end of the trace, and then run as
+/// a round, see [`Turns`](super::Turns), panicking as soon as no order
+/// explains what the threads saw. The run is counted in [`stats`], if
+/// collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let deque = Mutex::new(VecDeque::new());
+    let mut model = PropVecDeque::new();
+    let mut round: Vec<(usize, &Op)> = Vec::new();
+    let mut ran = [0; THREADS];
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        match op.thread() {
+            Some(thread) if ran[thread] < MAX_ROUND_OPS => {
+                ran[thread] += 1;
+                round.push((thread, op));
+            }
+            Some(_) => {}
+            None => {
+                check_round(&round, &deque, &mut model);
+                stats::size(TARGET, model.len());
+                round.clear();
+                ran = [0; THREADS];
+            }
+        }
+    }
+    check_round(&round, &deque, &mut model);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stdlib::sync::linearizability::Call;
+    use stdlib::sync::testing::{ops, rounds, trace};
+
+    #[test]
+    fn pops_must_find_what_was_pushed_first() {
+        let call = |op: Op, ret, invoked| Call {
+            thread: op.thread().unwrap(),
+            op,
+            ret,
+            invoked,
+            responded: invoked + 2,
+        };
+        let pushes = || {
+            vec![
+                call(
+                    Op::PushBack {
+                        thread: 0,
+                        value: 1,
+                    },
+                    None,
+                    0,
+                ),
+                call(
+                    Op::PushBack {
+                        thread: 1,
+                        value: 2,
+                    },
+                    None,
+                    1,
+                ),
+            ]
+        };
+        let start = PropVecDeque::new();
+        for &(first, second) in &[(1, 2), (2, 1)] {
+            let mut history = pushes();
+            history.push(call(Op::PopFront { thread: 2 }, Some(first), 4));
+            history.push(call(Op::PopFront { thread: 2 }, Some(second), 6));
+            assert!(linearize(&start, &history, PropVecDeque::is_empty).is_some());
+        }
+        let mut history = pushes();
+        history.push(call(Op::PopFront { thread: 2 }, None, 4));
+        assert!(linearize(&start, &history, |_| true).is_none());
+        let mut history = pushes();
+        history.push(call(Op::Len { thread: 3 }, Some(1), 1));
+        assert!(linearize(&start, &history, |model| model.len() == 2).is_some());
+    }
+
+    #[test]
+    fn racing_pops_take_each_value_once() {
+        let mut ops = Vec::new();
+        for round in 0..8 {
+            for step in 0..5 {
+                for thread in 0..THREADS as u8 {
+                    ops.push(match step {
+                        0 => Op::PushBack {
+                            thread,
+                            value: round,
+                        },
+                        1 => Op::PopFront { thread },
+                        2 => Op::PushFront {
+                            thread,
+                            value: thread,
+                        },
+                        3 => Op::PopBack { thread },
+                        _ => Op::Len { thread },
+                    });
+                }
+            }
+            ops.push(Op::Sync);
+        }
+        replay(&trace(TARGET, "u8", ops));
+    }
+
+    quickcheck! {
+        fn deque_agrees(data: Vec<u8>) -> bool {
+            let ops: Vec<Op> = ops(&data);
+            let (deque, alone) = (Mutex::new(VecDeque::new()), Mutex::new(VecDeque::new()));
+            let (mut model, mut alone_model) = (PropVecDeque::new(), PropVecDeque::new());
+            let mut in_turn = PropVecDeque::new();
+            rounds(&ops, Op::thread).iter().all(|round| {
+                check_round(round, &deque, &mut model);
+                let one_thread: Vec<_> = round.iter().map(|&(_, op)| (0, op)).collect();
+                check_round(&one_thread, &alone, &mut alone_model);
+                for &(_, op) in round {
+                    in_turn.apply(op);
+                }
+                alone_model == in_turn
+            })
+        }
+    }
+}
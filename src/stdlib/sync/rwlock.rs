@@ -0,0 +1,314 @@
+//! Tests for `std::sync::RwLock`, guarding a `HashMap`
+//!
+//! Ops name the one of [`THREADS`] threads that runs them, each taking the
+//! `RwLock` to write, inserting or removing a key, or to read, getting a key
+//! or the length. In a round the threads run their ops a window at a time,
+//! see [`Turns`](super::Turns), and since each op holds the lock throughout,
+//! what they returned must be what some order of them returns, run against
+//! a [`PropHashMap`] from the map the round started with, see
+//! [`linearize`]. The map the round ends with must be the order's too, and
+//! the next round starts from it.
+use op::{self, Profiles};
+use stats;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use stdlib::collections::hash_map::PropHashMap;
+use stdlib::sync::linearizability::{linearize, record, Sequential};
+use stdlib::sync::{Round, THREADS};
+use trace::{Header, Trace};
+
+/// The name of the fuzz target driving `RwLock<HashMap>`
+pub const TARGET: &str = "sync_rwlock";
+
+/// The most bytes of fuzz input the target will decode
+pub const MAX_INPUT_LEN: usize = 16_384;
+
+/// The most ops a thread runs in a round; ops past them do nothing
+pub const MAX_ROUND_OPS: usize = 12;
+
+lazy_static! {
+    /// The workload profiles of the `sync_rwlock` target, see [`Profiles`]
+    ///
+    /// `read_mostly` gets keys far more often than it writes them, so that
+    /// most turns read what a few writes left.
+    pub static ref PROFILES: Profiles = Profiles::new::<Op>(&[(
+        "read_mostly",
+        &[("get", 8), ("len", 2), ("insert", 2), ("remove", 1), ("sync", 1)],
+    )]);
+}
+
+/// The `Op` defines the ways the `RwLock<HashMap<u8, u8>>` of the
+/// `sync_rwlock` target is changed.
+///
+/// Ops but `Sync` are run by the thread `thread` modulo [`THREADS`], in the
+/// round that the next `Sync` ends, each under a lock of its own: a write
+/// lock to insert or remove, and a read lock otherwise.
+///
+/// The tag of a variant must never change, see [`TaggedOp`].
+#[derive(Clone, Debug, TaggedOp)]
+pub enum Op {
+    /// This operation triggers `HashMap::insert`
+    #[op(tag = 0, weight = 2)]
+    Insert {
+        /// The thread to run the op
+        thread: u8,
+        /// The key to insert
+        key: u8,
+        /// The value to insert
+        value: u8,
+    },
+    /// This operation triggers `HashMap::remove`
+    #[op(tag = 1)]
+    Remove {
+        /// The thread to run the op
+        thread: u8,
+        /// The key to remove
+        key: u8,
+    },
+    /// This operation triggers `HashMap::get`
+    #[op(tag = 2, weight = 2)]
+    Get {
+        /// The thread to run the op
+        thread: u8,
+        /// The key to get
+        key: u8,
+    },
+    /// This operation triggers `HashMap::len`
+    #[op(tag = 3)]
+    Len {
+        /// The thread to run the op
+        thread: u8,
+    },
+    /// This operation ends a round, joining the threads
+    #[op(tag = 4)]
+    Sync,
+}
+
+impl Op {
+    /// The thread to run the op, or `None` for `Sync`
+    pub fn thread(&self) -> Option<usize> {
+        match *self {
+            Op::Insert { thread, .. }
+            | Op::Remove { thread, .. }
+            | Op::Get { thread, .. }
+            | Op::Len { thread } => Some(thread as usize % THREADS),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// Ops return the value replaced, removed or got, or the length
+///
+/// Two models are the same map only if they hold their entries in the same
+/// order, so [`check_round`] compares their entries sorted.
+impl Sequential<Op> for PropHashMap<u8, u8> {
+    type Ret = Option<usize>;
+
+    fn apply(&mut self, op: &Op) -> Option<usize> {
+        match *op {
+            Op::Insert { key, value, .. } => self.insert(key, value).map(usize::from),
+            Op::Remove { key, .. } => self.remove(&key).map(usize::from),
+            Op::Get { key, .. } => self.get(&key).map(|&value| usize::from(value)),
+            Op::Len { .. } => Some(self.len()),
+            Op::Sync => None,
+        }
+    }
+}
+
+/// Run `op` against `map`, giving what it returns as the model does
+fn run_op(op: &Op, map: &RwLock<HashMap<u8, u8>>) -> Option<usize> {
+    let poisoned = "no thread panics holding the lock";
+    match *op {
+        Op::Insert { key, value, .. } => {
+            let mut map = map.write().expect(poisoned);
+            map.insert(key, value).map(usize::from)
+        }
+        Op::Remove { key, .. } => map.write().expect(poisoned).remove(&key).map(usize::from),
+        Op::Get { key, .. } => {
+            let map = map.read().expect(poisoned);
+            map.get(&key).map(|&value| usize::from(value))
+        }
+        Op::Len { .. } => Some(map.read().expect(poisoned).len()),
+        Op::Sync => None,
+    }
+}
+
+/// The entries of `model`, by key
+fn sorted(model: &PropHashMap<u8, u8>) -> Vec<(u8, u8)> {
+    let mut entries = model.entries().to_vec();
+    entries.sort_unstable();
+    entries
+}
+
+/// Run `round` against `map`, which `model` holds the entries of, panicking
+/// if no order of the ops explains what they returned
+///
+/// The order must be one [`linearize`] finds and leave the entries the round
+/// ended with, which the model is left holding.
+pub fn check_round(
+    round: &Round<Op>,
+    map: &RwLock<HashMap<u8, u8>>,
+    model: &mut PropHashMap<u8, u8>,
+) {
+    let history = record(round, |_, op| run_op(op, map));
+    let mut end = PropHashMap::new();
+    let mut entries: Vec<(u8, u8)> = map
+        .read()
+        .expect("no thread panics holding the lock")
+        .iter()
+        .map(|(&key, &value)| (key, value))
+        .collect();
+    entries.sort_unstable();
+    for &(key, value) in &entries {
+        end.insert(key, value);
+    }
+    let order = linearize(model, &history, |model| sorted(model) == entries);
+    check!(
+        order.is_some(),
+        "no order explains {:?} from {:?}",
+        history,
+        model
+    );
+    *model = end;
+}
+
+/// Decode the input of the `sync_rwlock` fuzz target into a [`Trace`]
+///
+/// The target reads the encoding version and a profile byte before reading ops
+/// until the input is exhausted. The profile byte chooses from [`PROFILES`]
+/// how ops are decoded. Returns `None` if the input is of some other version
+/// or is too short to hold its header.
+pub fn decode_input(data: &[u8]) -> Option<Trace<Op>> {
+    op::decode_input(data, MAX_INPUT_LEN, &PROFILES, |_| {
+        Some(Header {
+            target: TARGET.to_string(),
+            types: vec!["u8".to_string()],
+            capacity: 0,
+            hash_seed: None,
+        })
+    })
+}
+
+/// Encode a [`Trace`] as input for the `sync_rwlock` fuzz target
+///
+/// This is the inverse of [`decode_input`]. Ops are encoded by tag, under the
+/// `tagged` profile.
+pub fn encode_input(trace: &Trace<Op>) -> Vec<u8> {
+    op::encode_input(trace, |_, _| ())
+}
+
+/// Replay a [`Trace`] against an `RwLock<HashMap<u8, u8>>` shared by
+/// [`THREADS`] threads and [`PropHashMap`]
+///
+/// Ops are gathered until a `Sync`, or the end of the trace, and then run as
+/// a round, see [`Turns`](super::Turns), panicking as soon as no order
+/// explains what the threads saw. The run is counted in [`stats`], if
+/// collection is enabled.
+pub fn replay(trace: &Trace<Op>) {
+    let map = RwLock::new(HashMap::new());
+    let mut model = PropHashMap::new();
+    let mut round: Vec<(usize, &Op)> = Vec::new();
+    let mut ran = [0; THREADS];
+    stats::input(TARGET);
+    for op in &trace.ops {
+        stats::op(TARGET, op);
+        match op.thread() {
+            Some(thread) if ran[thread] < MAX_ROUND_OPS => {
+                ran[thread] += 1;
+                round.push((thread, op));
+            }
+            Some(_) => {}
+            None => {
+                check_round(&round, &map, &mut model);
+                stats::size(TARGET, model.len());
+                round.clear();
+                ran = [0; THREADS];
+            }
+        }
+    }
+    check_round(&round, &map, &mut model);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stdlib::sync::linearizability::Call;
+    use stdlib::sync::testing::{ops, rounds, trace};
+
+    #[test]
+    fn reads_see_the_writes_before_them() {
+        let call = |op: Op, ret, invoked| Call {
+            thread: op.thread().unwrap(),
+            op,
+            ret,
+            invoked,
+            responded: invoked + 2,
+        };
+        let insert = |thread, value, invoked| {
+            call(
+                Op::Insert {
+                    thread,
+                    key: 1,
+                    value,
+                },
+                None,
+                invoked,
+            )
+        };
+        let get = |ret, invoked| call(Op::Get { thread: 2, key: 1 }, ret, invoked);
+        let start = PropHashMap::new();
+        let one = |model: &PropHashMap<u8, u8>| model.len() == 1;
+        let history = vec![insert(0, 5, 0), get(None, 1), get(Some(5), 4)];
+        assert!(linearize(&start, &history, one).is_some());
+        let history = vec![insert(0, 5, 0), get(None, 3)];
+        assert!(linearize(&start, &history, one).is_none());
+        let mut history = vec![insert(0, 5, 0), insert(1, 6, 1)];
+        history[1].ret = Some(5);
+        history.push(get(Some(6), 4));
+        assert!(linearize(&start, &history, one).is_some());
+        history[0].ret = Some(6);
+        assert!(linearize(&start, &history, one).is_none());
+    }
+
+    #[test]
+    fn racing_writers_and_readers_agree() {
+        let mut ops = Vec::new();
+        for round in 0..8 {
+            for step in 0..5 {
+                for thread in 0..THREADS as u8 {
+                    ops.push(match step {
+                        0 => Op::Insert {
+                            thread,
+                            key: thread & 1,
+                            value: round,
+                        },
+                        1 => Op::Get { thread, key: 0 },
+                        2 => Op::Remove { thread, key: 1 },
+                        3 => Op::Get { thread, key: 1 },
+                        _ => Op::Len { thread },
+                    });
+                }
+            }
+            ops.push(Op::Sync);
+        }
+        replay(&trace(TARGET, "u8", ops));
+    }
+
+    quickcheck! {
+        fn map_agrees(data: Vec<u8>) -> bool {
+            let ops: Vec<Op> = ops(&data);
+            let (map, alone) = (RwLock::new(HashMap::new()), RwLock::new(HashMap::new()));
+            let (mut model, mut alone_model) = (PropHashMap::new(), PropHashMap::new());
+            let mut in_turn = PropHashMap::new();
+            rounds(&ops, Op::thread).iter().all(|round| {
+                check_round(round, &map, &mut model);
+                let one_thread: Vec<_> = round.iter().map(|&(_, op)| (0, op)).collect();
+                check_round(&one_thread, &alone, &mut alone_model);
+                for &(_, op) in round {
+                    in_turn.apply(op);
+                }
+                sorted(&alone_model) == sorted(&in_turn)
+            })
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Helpers shared by the tests of the `sync` targets
+use arbitrary::{Arbitrary, FiniteBuffer};
+use trace::{Header, Trace};
+
+/// The ops held by `data`, decoded by tag until it is exhausted
+///
+/// Unlike the fuzz targets, which read on past the end of their input, this
+/// reads no more than `data` holds.
+pub fn ops<O: Arbitrary>(data: &[u8]) -> Vec<O> {
+    let mut ops = Vec::new();
+    if let Ok(mut ring) = FiniteBuffer::new(data, data.len()) {
+        while let Ok(op) = Arbitrary::arbitrary(&mut ring) {
+            ops.push(op);
+        }
+    }
+    ops
+}
+
+/// A trace of `ops` for `target`, whose values are of type `ty`
+pub fn trace<O>(target: &str, ty: &str, ops: Vec<O>) -> Trace<O> {
+    let mut trace = Trace::new(Header {
+        target: target.to_string(),
+        types: vec![ty.to_string()],
+        capacity: 0,
+        hash_seed: None,
+    });
+    trace.ops = ops;
+    trace
+}
+
+/// The rounds of `ops`, cut at the ops `thread_of` gives no thread
+pub fn rounds<O>(ops: &[O], thread_of: fn(&O) -> Option<usize>) -> Vec<Vec<(usize, &O)>> {
+    let mut rounds = vec![Vec::new()];
+    for op in ops {
+        match thread_of(op) {
+            Some(thread) => rounds
+                .last_mut()
+                .expect("there is always a round")
+                .push((thread, op)),
+            None => rounds.push(Vec::new()),
+        }
+    }
+    rounds
+}
@@ -0,0 +1,88 @@
+//! Helpers shared by the targets of `std`
+use arbitrary::*;
+use encode::Encode;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use trace::ParseError;
+
+/// Bytes decoded from fuzz input
+///
+/// A `u8` length is followed by that many bytes. In traces `Bytes` are
+/// written as two hex digits a byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Arbitrary for Bytes {
+    fn arbitrary<U>(u: &mut U) -> Result<Self, U::Error>
+    where
+        U: Unstructured + ?Sized,
+    {
+        let len: u8 = Arbitrary::arbitrary(u)?;
+        let mut bytes = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            bytes.push(Arbitrary::arbitrary(u)?);
+        }
+        Ok(Bytes(bytes))
+    }
+}
+
+impl Encode for Bytes {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        // Decoding reads at most `u8::MAX` bytes, so we write no more.
+        let len = self.0.len().min(usize::from(u8::MAX));
+        (len as u8).encode(buf);
+        buf.extend_from_slice(&self.0[..len]);
+    }
+}
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Bytes {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() % 2 == 1 || !s.is_ascii() {
+            return Err(ParseError::new(format!("'{}' is not bytes in hex", s)));
+        }
+        (0..s.len())
+            .step_by(2)
+            .map(|idx| {
+                u8::from_str_radix(&s[idx..idx + 2], 16)
+                    .map_err(|_| ParseError::new(format!("'{}' is not bytes in hex", s)))
+            })
+            .collect::<Result<Vec<u8>, ParseError>>()
+            .map(Bytes)
+    }
+}
+
+/// Run `f`, giving whether it panicked
+///
+/// The fuzz harness aborts from its panic hook, before any panic can be
+/// caught, so the hook is set aside while `f` runs.
+pub fn panics<F: FnOnce()>(f: F) -> bool {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(hook);
+    result.is_err()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bytes_are_written_in_hex() {
+        assert_eq!("00ff1a", Bytes(vec![0, 255, 26]).to_string());
+        assert_eq!(Ok(Bytes(vec![0, 255, 26])), "00ff1a".parse());
+        assert!("0".parse::<Bytes>().is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! A human-readable format for op histories
+//!
+//! The fuzz targets consume raw bytes, which is fine for libFuzzer but no good
+//! for people. A crash input is opaque and, worse, its meaning shifts whenever
+//! the decoding of an `Op` changes. A [`Trace`] is the same history written out
+//! as text: a header line naming the target and its parameters, followed by one
+//! op per line.
+//!
+//! ```text
+//! # target=hash_map types=u16,u16 capacity=12 hash_seed=3
+//! insert k=3 v=7
+//! get k=3
+//! clear
+//! ```
+//!
+//! Blank lines are ignored, as are lines starting with `#` after the header, so
+//! a trace can be annotated and edited by hand. Each `Op` type defines its own
+//! line format through `Display` and `FromStr`, with [`OpLine`] doing the
+//! common parsing.
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+/// An error encountered while parsing a [`Trace`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line: Option<usize>,
+    msg: String,
+}
+
+impl ParseError {
+    /// Construct a new `ParseError` with the given message
+    pub fn new<S>(msg: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            line: None,
+            msg: msg.into(),
+        }
+    }
+
+    /// Note the line, counting from 1, at which the error occurred
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.msg),
+            None => write!(f, "{}", self.msg),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// The parameters a fuzz target reads before its ops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    /// The name of the fuzz target, as in `fuzz/Cargo.toml`
+    pub target: String,
+    /// The element types of the collection under test, by name
+    pub types: Vec<String>,
+    /// The initial capacity of the collection under test
+    pub capacity: usize,
+    /// The seed of [`BuildTrulyAwfulHasher`], for targets that hash
+    ///
+    /// [`BuildTrulyAwfulHasher`]: ../stdlib/collections/hash_map/struct.BuildTrulyAwfulHasher.html
+    pub hash_seed: Option<u8>,
+}
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "# target={} types={} capacity={}",
+            self.target,
+            self.types.join(","),
+            self.capacity
+        )?;
+        if let Some(hash_seed) = self.hash_seed {
+            write!(f, " hash_seed={}", hash_seed)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Header {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The header is read as though it were an op named 'header'.
+        let line = format!("header {}", s.trim().trim_start_matches('#'));
+        let line = OpLine::parse(&line)?;
+        let types = line.get::<String>("types")?;
+        Ok(Header {
+            target: line.get("target")?,
+            types: types.split(',').map(|t| t.trim().to_string()).collect(),
+            capacity: line.get("capacity")?,
+            hash_seed: line.get_opt("hash_seed")?,
+        })
+    }
+}
+
+/// A recorded history of ops against a single fuzz target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<O> {
+    /// The parameters the target read before its ops
+    pub header: Header,
+    /// The ops, in the order they were performed
+    pub ops: Vec<O>,
+}
+
+impl<O> Trace<O> {
+    /// Construct a new `Trace` with no ops
+    pub fn new(header: Header) -> Self {
+        Self {
+            header,
+            ops: Vec::new(),
+        }
+    }
+}
+
+impl<O> fmt::Display for Trace<O>
+where
+    O: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.header)?;
+        for op in &self.ops {
+            writeln!(f, "{}", op)?;
+        }
+        Ok(())
+    }
+}
+
+impl<O> FromStr for Trace<O>
+where
+    O: FromStr<Err = ParseError>,
+{
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|&(_, line)| !line.is_empty());
+        let mut trace = match lines.next() {
+            Some((idx, line)) if line.starts_with('#') => {
+                Trace::new(line.parse().map_err(|e: ParseError| e.at_line(idx))?)
+            }
+            Some((idx, _)) => {
+                return Err(ParseError::new("expected a header").at_line(idx));
+            }
+            None => return Err(ParseError::new("empty trace")),
+        };
+        for (idx, line) in lines.filter(|&(_, line)| !line.starts_with('#')) {
+            trace
+                .ops
+                .push(line.parse().map_err(|e: ParseError| e.at_line(idx))?);
+        }
+        Ok(trace)
+    }
+}
+
+/// Read only the [`Header`] of a trace
+///
+/// This is useful for finding out which target a trace belongs to, and so
+/// which `Op` type to parse the rest of it with.
+pub fn header(s: &str) -> Result<Header, ParseError> {
+    match s
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .find(|&(_, line)| !line.is_empty())
+    {
+        Some((idx, line)) => line.parse().map_err(|e: ParseError| e.at_line(idx)),
+        None => Err(ParseError::new("empty trace")),
+    }
+}
+
+/// A single line of a trace, split into an op name and its fields
+///
+/// Lines look like `insert k=3 v=7`: a name followed by whitespace separated
+/// `key=value` pairs. The values are parsed with `FromStr` when asked for.
+pub struct OpLine<'a> {
+    name: &'a str,
+    fields: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> OpLine<'a> {
+    /// Split `line` into an op name and its fields
+    pub fn parse(line: &'a str) -> Result<Self, ParseError> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or_else(|| ParseError::new("empty op"))?;
+        let mut fields = Vec::new();
+        for word in words {
+            let mut kv = word.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) if !k.is_empty() => fields.push((k, v)),
+                _ => {
+                    return Err(ParseError::new(format!(
+                        "expected key=value, found '{}'",
+                        word
+                    )));
+                }
+            }
+        }
+        Ok(Self { name, fields })
+    }
+
+    /// The name of the op
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Parse the field `key`, which must be present
+    pub fn get<T>(&self, key: &str) -> Result<T, ParseError>
+    where
+        T: FromStr,
+    {
+        self.get_opt(key)?.ok_or_else(|| {
+            ParseError::new(format!("{}: missing field '{}'", self.name, key))
+        })
+    }
+
+    /// Parse the field `key`, if it is present
+    pub fn get_opt<T>(&self, key: &str) -> Result<Option<T>, ParseError>
+    where
+        T: FromStr,
+    {
+        match self.fields.iter().find(|&&(k, _)| k == key) {
+            Some(&(_, v)) => v.parse().map(Some).map_err(|_| {
+                ParseError::new(format!(
+                    "{}: could not parse field '{}' from '{}'",
+                    self.name, key, v
+                ))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Fail with an error naming the op as unknown
+    pub fn unknown<T>(&self) -> Result<T, ParseError> {
+        Err(ParseError::new(format!("unknown op '{}'", self.name)))
+    }
+}